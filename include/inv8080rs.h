@@ -0,0 +1,87 @@
+#ifndef INV8080RS_H
+#define INV8080RS_H
+
+/* Generated with cbindgen, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The CPU-model including memory etc.
+typedef struct Cpu Cpu;
+
+// Create a Space Invaders machine with a rom. Returns null if the rom is empty or too large.
+//
+// # Safety
+// `rom` must point to `len` readable bytes.
+Cpu *inv8080_create(const uint8_t *rom, uintptr_t len);
+
+// Destroy a machine created with `inv8080_create`
+//
+// # Safety
+// `cpu` must come from `inv8080_create` (or be null) and not be used afterwards.
+void inv8080_destroy(Cpu *cpu);
+
+// Replace the rom, the rest of the machine state is kept. Returns false if the rom is too large.
+//
+// # Safety
+// `cpu` must be a valid machine and `rom` must point to `len` readable bytes.
+bool inv8080_load_rom(Cpu *cpu, const uint8_t *rom, uintptr_t len);
+
+// Reset (program counter to 0 and interrupts disabled, RAM is kept)
+//
+// # Safety
+// `cpu` must be a valid machine.
+void inv8080_reset(Cpu *cpu);
+
+// Power cycle (like reset, but RAM, registers and I/O are cleared)
+//
+// # Safety
+// `cpu` must be a valid machine.
+void inv8080_power_cycle(Cpu *cpu);
+
+// Run one frame (1/60 s) including the two interrupts
+//
+// # Safety
+// `cpu` must be a valid machine.
+void inv8080_run_frame(Cpu *cpu);
+
+// Pointer to the framebuffer, 7168 bytes of 32 bytes per column from the bottom-left of the display with the lowest bit of each byte at the bottom.
+// Valid until the machine is destroyed.
+//
+// # Safety
+// `cpu` must be a valid machine.
+const uint8_t *inv8080_framebuffer(const Cpu *cpu);
+
+// Save the machine state into `buf` if it has room for it. Returns the size of the state, so a call with a null
+// `buf` (or too small `len`) tells how much room is needed.
+//
+// # Safety
+// `cpu` must be a valid machine and `buf` must be null or point to `len` writable bytes.
+uintptr_t inv8080_save_state(const Cpu *cpu,
+                             uint8_t *buf,
+                             uintptr_t len);
+
+// Load a state saved with `inv8080_save_state`. Returns false, keeping the machine as it was, if it is not a state
+// of this machine.
+//
+// # Safety
+// `cpu` must be a valid machine and `state` must point to `len` readable bytes.
+bool inv8080_load_state(Cpu *cpu,
+                        const uint8_t *state,
+                        uintptr_t len);
+
+// Set the value of an input port (0-7), e.g. port 1 for coin, start and player 1 controls
+//
+// # Safety
+// `cpu` must be a valid machine.
+void inv8080_set_input(Cpu *cpu, uintptr_t port, uint8_t value);
+
+// Value of an output port (0-7), e.g. ports 3 and 5 for sounds
+//
+// # Safety
+// `cpu` must be a valid machine.
+uint8_t inv8080_get_output(const Cpu *cpu, uintptr_t port);
+
+#endif  /* INV8080RS_H */
@@ -0,0 +1,163 @@
+//! Locating the rom, sounds and other assets, so the emulator can be started from any directory. Files can also be
+//! given in memory, overriding the directories, for embedding without a file system (wasm, fuzzing, tests).
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Name of the application directories
+const APP: &str = "inv8080rs";
+
+/// Directories searched for assets, in order, and files given in memory
+#[derive(Clone, Debug, PartialEq)]
+pub struct Locator {
+    pub dirs: Vec<PathBuf>,
+    /// Contents by name, found before the directories are searched
+    files: Vec<(String, Vec<u8>)>,
+    /// Files not in memory are read from the file system
+    disk: bool,
+}
+
+impl Locator {
+    /// Search the given directories first, then `assets` in the current directory, next to the executable and the
+    /// data and config directories of the platform
+    pub fn new(mut dirs: Vec<PathBuf>) -> Self {
+        let exe = env::current_exe().ok();
+        let exe_dir = exe.as_deref().and_then(Path::parent);
+        dirs.extend(search_dirs(env::consts::OS, |v| env::var(v).ok(), exe_dir));
+        Locator {
+            dirs,
+            files: vec![],
+            disk: true,
+        }
+    }
+
+    /// Only files in memory, nothing is read from the file system
+    pub fn memory(files: Vec<(String, Vec<u8>)>) -> Self {
+        Locator {
+            dirs: vec![],
+            files,
+            disk: false,
+        }
+    }
+
+    /// Add a file in memory, replacing one with the same name
+    pub fn insert(&mut self, name: &str, contents: Vec<u8>) {
+        self.files.retain(|(n, _)| n != name);
+        self.files.push((name.to_string(), contents));
+    }
+
+    /// File in memory with the name
+    fn in_memory(&self, name: &str) -> Option<Vec<u8>> {
+        let file = self.files.iter().find(|(n, _)| n == name);
+        file.map(|(_, contents)| contents.clone())
+    }
+
+    /// Contents of the asset with the name, in memory or in the search directories
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, String> {
+        if let Some(contents) = self.in_memory(name) {
+            return Ok(contents);
+        }
+        if !self.disk {
+            return Err(format!("{name}: not found in memory"));
+        }
+        let path = self.resolve(name);
+        fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Contents of a file given by path (e.g. on the command line), in memory under the path or read from it
+    pub fn read_path(&self, path: &str) -> Result<Vec<u8>, String> {
+        match self.in_memory(path) {
+            Some(contents) => Ok(contents),
+            None if !self.disk => Err(format!("{path}: not found in memory")),
+            None => fs::read(path).map_err(|e| format!("{path}: {e}")),
+        }
+    }
+
+    /// First existing file with the name in the search directories
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.dirs.iter().map(|d| d.join(name)).find(|p| p.is_file())
+    }
+
+    /// Like find, but falls back to `assets/<name>` so errors name a sensible path
+    pub fn resolve(&self, name: &str) -> PathBuf {
+        self.find(name)
+            .unwrap_or_else(|| Path::new("assets").join(name))
+    }
+}
+
+impl Default for Locator {
+    fn default() -> Self {
+        Locator::new(vec![])
+    }
+}
+
+/// Directory for settings of an operating system, given environment variables: `$XDG_CONFIG_HOME/inv8080rs` (default
+/// `~/.config/inv8080rs`) on Linux, `~/Library/Application Support/inv8080rs` on macOS and `%APPDATA%\inv8080rs` on
+/// Windows
+pub fn config_dir(os: &str, var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let home = var("HOME").map(PathBuf::from);
+    match os {
+        "windows" => var("APPDATA").map(|d| Path::new(&d).join(APP)),
+        "macos" => home.map(|h| h.join("Library/Application Support").join(APP)),
+        _ => var("XDG_CONFIG_HOME")
+            .filter(|d| Path::new(d).is_absolute())
+            .map(PathBuf::from)
+            .or_else(|| home.map(|h| h.join(".config")))
+            .map(|d| d.join(APP)),
+    }
+}
+
+/// Default search directories for an operating system (as in `std::env::consts::OS`), given environment variables and
+/// the directory of the executable
+pub fn search_dirs(
+    os: &str,
+    var: impl Fn(&str) -> Option<String>,
+    exe_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("assets")];
+    if let Some(exe_dir) = exe_dir {
+        dirs.push(exe_dir.join("assets"));
+        dirs.push(exe_dir.to_path_buf());
+    }
+    let home = var("HOME").map(PathBuf::from);
+    match os {
+        "windows" => {
+            for v in ["APPDATA", "LOCALAPPDATA"] {
+                if let Some(dir) = var(v) {
+                    dirs.push(Path::new(&dir).join(APP));
+                }
+            }
+        }
+        "macos" => {
+            if let Some(home) = home {
+                dirs.push(home.join("Library/Application Support").join(APP));
+            }
+        }
+        _ => {
+            // XDG base directories, with their defaults
+            let xdg = |v: &str, default: &str| {
+                var(v)
+                    .filter(|d| Path::new(d).is_absolute())
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|h| h.join(default)))
+            };
+            dirs.extend(xdg("XDG_DATA_HOME", ".local/share").map(|d| d.join(APP)));
+            dirs.extend(xdg("XDG_CONFIG_HOME", ".config").map(|d| d.join(APP)));
+            let data_dirs = var("XDG_DATA_DIRS")
+                .filter(|d| !d.is_empty())
+                .unwrap_or("/usr/local/share:/usr/share".into());
+            dirs.extend(
+                data_dirs
+                    .split(':')
+                    .filter(|d| Path::new(d).is_absolute())
+                    .map(|d| Path::new(d).join(APP)),
+            );
+        }
+    }
+    dirs
+}
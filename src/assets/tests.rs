@@ -0,0 +1,149 @@
+use super::*;
+
+fn vars<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |v| {
+        vars.iter()
+            .find(|(name, _)| *name == v)
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn linux_defaults() {
+    let dirs = search_dirs(
+        "linux",
+        vars(&[("HOME", "/home/me")]),
+        Some(Path::new("/opt/inv")),
+    );
+    assert_eq!(
+        dirs,
+        paths(&[
+            "assets",
+            "/opt/inv/assets",
+            "/opt/inv",
+            "/home/me/.local/share/inv8080rs",
+            "/home/me/.config/inv8080rs",
+            "/usr/local/share/inv8080rs",
+            "/usr/share/inv8080rs",
+        ])
+    );
+}
+
+#[test]
+fn linux_xdg() {
+    let dirs = search_dirs(
+        "linux",
+        vars(&[
+            ("HOME", "/home/me"),
+            ("XDG_DATA_HOME", "/data"),
+            ("XDG_CONFIG_HOME", "relative/is/ignored"),
+            ("XDG_DATA_DIRS", "/a:relative:/b"),
+        ]),
+        None,
+    );
+    assert_eq!(
+        dirs,
+        paths(&[
+            "assets",
+            "/data/inv8080rs",
+            "/home/me/.config/inv8080rs",
+            "/a/inv8080rs",
+            "/b/inv8080rs",
+        ])
+    );
+}
+
+#[test]
+fn windows_and_macos() {
+    let dirs = search_dirs(
+        "windows",
+        vars(&[("APPDATA", "C:/Users/me/AppData/Roaming")]),
+        None,
+    );
+    assert_eq!(
+        dirs,
+        paths(&["assets", "C:/Users/me/AppData/Roaming/inv8080rs"])
+    );
+    let dirs = search_dirs("macos", vars(&[("HOME", "/Users/me")]), None);
+    assert_eq!(
+        dirs,
+        paths(&["assets", "/Users/me/Library/Application Support/inv8080rs"])
+    );
+}
+
+#[test]
+fn given_dirs_first() {
+    let locator = Locator::new(paths(&["src", "src/assets"]));
+    assert_eq!(&locator.dirs[..3], &paths(&["src", "src/assets", "assets"]));
+    assert_eq!(locator.find("lib.rs"), Some(PathBuf::from("src/lib.rs")));
+    assert_eq!(
+        locator.find("tests.rs"),
+        Some(PathBuf::from("src/assets/tests.rs"))
+    );
+    assert_eq!(locator.find("no-such-file"), None);
+    assert_eq!(
+        locator.resolve("no-such-file"),
+        PathBuf::from("assets/no-such-file")
+    );
+}
+
+#[test]
+fn config_dirs() {
+    let linux = |v| config_dir("linux", vars(v));
+    assert_eq!(
+        linux(&[("HOME", "/home/me")]),
+        Some(PathBuf::from("/home/me/.config/inv8080rs"))
+    );
+    assert_eq!(
+        linux(&[("HOME", "/home/me"), ("XDG_CONFIG_HOME", "/cfg")]),
+        Some(PathBuf::from("/cfg/inv8080rs"))
+    );
+    assert_eq!(linux(&[]), None);
+    assert_eq!(
+        config_dir("windows", vars(&[("APPDATA", "C:/AppData")])),
+        Some(PathBuf::from("C:/AppData/inv8080rs"))
+    );
+    assert_eq!(
+        config_dir("macos", vars(&[("HOME", "/Users/me")])),
+        Some(PathBuf::from(
+            "/Users/me/Library/Application Support/inv8080rs"
+        ))
+    );
+}
+
+#[test]
+fn files_in_memory() {
+    let mut locator = Locator::memory(vec![("invaders.rom".into(), vec![1, 2])]);
+    assert_eq!(locator.read("invaders.rom"), Ok(vec![1, 2]));
+    locator.insert("invaders.rom", vec![3]);
+    locator.insert("bezel.bmp", vec![4]);
+    assert_eq!(locator.read("invaders.rom"), Ok(vec![3]));
+    assert_eq!(locator.read_path("bezel.bmp"), Ok(vec![4]));
+    assert_eq!(
+        locator.read("no-such.wav"),
+        Err("no-such.wav: not found in memory".into())
+    );
+    // Never falls back to the file system, even for files that exist
+    assert_eq!(
+        locator.read_path("Cargo.toml"),
+        Err("Cargo.toml: not found in memory".into())
+    );
+}
+
+#[test]
+fn files_on_disk() {
+    let locator = Locator::new(vec![]);
+    assert!(locator.read_path("Cargo.toml").is_ok());
+    assert!(locator
+        .read("no-such.wav")
+        .unwrap_err()
+        .starts_with("assets/no-such.wav: "));
+    assert!(locator
+        .read_path("no/such/bezel.bmp")
+        .unwrap_err()
+        .starts_with("no/such/bezel.bmp: "));
+}
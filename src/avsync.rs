@@ -0,0 +1,102 @@
+//! Audio-video sync: frames are paced by sleeping, which drifts from the clock of the sound card over long sessions. A
+//! silent stream gets one frame of samples each frame, so the samples still queued tell how far the video is ahead of
+//! the audio. The frame time is corrected by up to ±0.5% to keep the queue at its target.
+
+#[cfg(test)]
+mod tests;
+
+/// Largest correction of the frame time, in per mille
+pub const MAX_CORRECTION: i64 = 5;
+/// Frames of samples kept queued, enough that the device never runs dry between frames
+pub const TARGET_FRAMES: u32 = 4;
+/// Frames the drift is averaged over, the device consumes samples in chunks
+const SMOOTHING: i64 = 16;
+
+/// Drift measurement and correction
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvSync {
+    /// Samples per second of the device
+    rate: u32,
+    fps: u32,
+    /// Fraction of a sample carried over to the next frame, in 1/fps samples
+    remainder: u32,
+    /// The queue is filled to the target on the next frame
+    primed: bool,
+    /// Average drift in samples, times `SMOOTHING`
+    average: i64,
+    /// Frames measured and the largest drift seen, in samples
+    frames: u64,
+    max_drift: i64,
+}
+
+impl AvSync {
+    pub fn new(rate: u32, fps: u32) -> Self {
+        AvSync {
+            rate,
+            fps,
+            remainder: 0,
+            primed: false,
+            average: 0,
+            frames: 0,
+            max_drift: 0,
+        }
+    }
+
+    /// Start over after a pause or off normal speed, the queue should be cleared
+    pub fn restart(&mut self) {
+        self.remainder = 0;
+        self.primed = false;
+        self.average = 0;
+    }
+
+    /// Samples to queue this frame, with fractions of a sample carried over. The first frame also fills the queue up
+    /// to the target.
+    pub fn frame_samples(&mut self) -> usize {
+        let total = self.rate + self.remainder;
+        self.remainder = total % self.fps;
+        let mut samples = total / self.fps;
+        if !self.primed {
+            self.primed = true;
+            samples += self.target();
+        }
+        samples as usize
+    }
+
+    /// Samples kept queued
+    pub fn target(&self) -> u32 {
+        self.rate * TARGET_FRAMES / self.fps
+    }
+
+    /// Measure with the samples still queued after this frame's were added
+    pub fn update(&mut self, queued: u32) {
+        let drift = queued as i64 - (self.target() + self.rate / self.fps) as i64;
+        self.average += drift - self.average / SMOOTHING;
+        self.frames += 1;
+        if drift.abs() > self.max_drift.abs() {
+            self.max_drift = drift;
+        }
+    }
+
+    /// Average drift in samples, positive when the video is ahead of the audio
+    pub fn drift(&self) -> i64 {
+        self.average / SMOOTHING
+    }
+
+    /// Correction of the frame time in per mille, proportional to the drift and full at a frame of drift
+    pub fn correction(&self) -> i64 {
+        let frame = (self.rate / self.fps) as i64;
+        (self.average * MAX_CORRECTION / (frame * SMOOTHING)).clamp(-MAX_CORRECTION, MAX_CORRECTION)
+    }
+
+    /// Summary of the measurement, printed on quit
+    pub fn report(&self) -> String {
+        let ms = |samples: i64| samples as f64 * 1000.0 / self.rate as f64;
+        format!(
+            "A/V sync: {} frames measured, drift {:.1} ms (largest {:.1} ms), correction {:+.1}%\n",
+            self.frames,
+            ms(self.drift()),
+            ms(self.max_drift),
+            self.correction() as f64 / 10.0
+        )
+    }
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+#[test]
+fn samples_per_frame() {
+    let mut sync = AvSync::new(11025, 60);
+    assert_eq!(sync.target(), 735);
+    // The first frame fills the queue
+    assert_eq!(sync.frame_samples(), 183 + 735);
+    let samples: usize = (1..60).map(|_| sync.frame_samples()).sum();
+    assert_eq!(samples + 183, 11025);
+    sync.restart();
+    assert_eq!(sync.frame_samples(), 183 + 735);
+}
+
+#[test]
+fn in_sync() {
+    let mut sync = AvSync::new(11025, 60);
+    for _ in 0..100 {
+        sync.update(735 + 183);
+    }
+    assert_eq!((sync.drift(), sync.correction()), (0, 0));
+}
+
+#[test]
+fn corrects_drift() {
+    let mut sync = AvSync::new(11025, 60);
+    // Video ahead, the queue grows, frames get longer
+    for _ in 0..200 {
+        sync.update(735 + 183 + 400);
+    }
+    assert_eq!(sync.correction(), MAX_CORRECTION);
+    // Video behind, the queue runs dry, frames get shorter
+    for _ in 0..200 {
+        sync.update(735 + 183 - 91);
+    }
+    assert!((-2..=-1).contains(&sync.correction()));
+    for _ in 0..200 {
+        sync.update(0);
+    }
+    assert_eq!(sync.correction(), -MAX_CORRECTION);
+    assert!(sync.report().contains("-0.5%"));
+}
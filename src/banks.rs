@@ -0,0 +1,95 @@
+//! Bank switching: a window of the address space shows one of several banks of memory, selected by writing the bank
+//! number to an output port. For boards and homebrew programs that outgrow the flat 16 KB.
+
+use std::ops::RangeInclusive;
+
+#[cfg(test)]
+mod tests;
+
+/// Banked memory. The selected bank lives in the memory of the machine, the others are kept here.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Banks {
+    /// Addresses switched
+    pub window: RangeInclusive<usize>,
+    /// Output port selecting the bank
+    pub port: usize,
+    /// Contents of the banks, the one of the selected bank is stale until switched away from
+    banks: Vec<Vec<u8>>,
+    selected: usize,
+}
+
+impl Banks {
+    /// `count` empty banks over the window, bank 0 selected
+    pub fn new(window: RangeInclusive<usize>, port: usize, count: usize) -> Self {
+        let size = window.end() - window.start() + 1;
+        Banks {
+            window,
+            port,
+            banks: vec![vec![0; size]; count.max(1)],
+            selected: 0,
+        }
+    }
+
+    /// Number of banks
+    pub fn count(&self) -> usize {
+        self.banks.len()
+    }
+
+    /// Bank shown in the window
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Bytes in each bank
+    pub fn size(&self) -> usize {
+        self.banks[0].len()
+    }
+
+    /// Fill banks from data laid out bank after bank, e.g. a rom larger than the window
+    pub fn load(&mut self, memory: &mut [u8], data: &[u8]) {
+        let size = self.size();
+        for (bank, chunk) in self.banks.iter_mut().zip(data.chunks(size)) {
+            bank[..chunk.len()].copy_from_slice(chunk);
+        }
+        memory[self.window.clone()].copy_from_slice(&self.banks[self.selected]);
+    }
+
+    /// Show a bank in the window, the bank number wraps around the number of banks like unused select lines
+    pub fn select(&mut self, memory: &mut [u8], bank: usize) {
+        let bank = bank % self.count();
+        if bank == self.selected {
+            return;
+        }
+        self.banks[self.selected].copy_from_slice(&memory[self.window.clone()]);
+        memory[self.window.clone()].copy_from_slice(&self.banks[bank]);
+        self.selected = bank;
+    }
+
+    /// Contents of all banks, current as of the memory given
+    pub fn contents(&self, memory: &[u8]) -> Vec<u8> {
+        let mut contents = vec![];
+        for (i, bank) in self.banks.iter().enumerate() {
+            if i == self.selected {
+                contents.extend_from_slice(&memory[self.window.clone()]);
+            } else {
+                contents.extend_from_slice(bank);
+            }
+        }
+        contents
+    }
+
+    /// Restore contents of all banks as returned by `contents`, with a bank selected
+    pub fn restore(
+        &mut self,
+        memory: &mut [u8],
+        contents: &[u8],
+        selected: usize,
+    ) -> Result<(), String> {
+        if contents.len() != self.count() * self.size() || selected >= self.count() {
+            return Err("Banks do not match".to_string());
+        }
+        self.selected = selected;
+        self.load(memory, contents);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn switch_keeps_contents() {
+    let mut memory = vec![0; 16];
+    let mut banks = Banks::new(4..=7, 7, 3);
+    assert_eq!((banks.count(), banks.size(), banks.selected()), (3, 4, 0));
+    banks.load(&mut memory, &[1, 1, 1, 1, 2, 2, 2, 2, 3, 3]);
+    assert_eq!(memory[4..8], [1, 1, 1, 1]);
+    memory[4] = 9;
+    banks.select(&mut memory, 2);
+    assert_eq!(memory[4..8], [3, 3, 0, 0]);
+    // Wraps around
+    banks.select(&mut memory, 3);
+    assert_eq!(memory[4..8], [9, 1, 1, 1]);
+    assert_eq!(memory[..4], [0; 4]);
+    assert_eq!(
+        banks.contents(&memory),
+        [9, 1, 1, 1, 2, 2, 2, 2, 3, 3, 0, 0]
+    );
+}
+
+#[test]
+fn restore() {
+    let mut memory = vec![0; 8];
+    let mut banks = Banks::new(0..=3, 7, 2);
+    let contents = [1, 2, 3, 4, 5, 6, 7, 8];
+    assert!(banks.restore(&mut memory, &contents[1..], 0).is_err());
+    assert!(banks.restore(&mut memory, &contents, 2).is_err());
+    banks.restore(&mut memory, &contents, 1).unwrap();
+    assert_eq!(memory[..4], [5, 6, 7, 8]);
+    assert_eq!(banks.contents(&memory), contents);
+}
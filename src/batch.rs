@@ -0,0 +1,250 @@
+//! Headless simulation of many independent games in parallel, e.g. for collecting statistics on bots
+
+use std::thread;
+
+use crate::{
+    cpu::{Cpu, RamInit},
+    ram_map,
+    utils::splitmix64,
+    FPS, FREQ,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Player 1 controls (and coin slot) for one frame
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Input {
+    pub credit: bool,
+    pub start: bool,
+    pub fire: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Input {
+    /// Set the bits of input port 1
+    pub fn apply(&self, cpu: &mut Cpu) {
+        cpu.set_bus_in_bit(1, 0, self.credit);
+        cpu.set_bus_in_bit(1, 2, self.start);
+        cpu.set_bus_in_bit(1, 4, self.fire);
+        cpu.set_bus_in_bit(1, 5, self.left);
+        cpu.set_bus_in_bit(1, 6, self.right);
+    }
+}
+
+/// Longest time a random input is held, in frames
+const MAX_HOLD: u32 = 16;
+
+/// Seeded input noise, random inputs held for a random number of frames like a (bad) human player.
+/// The sequence only depends on the seed, so it is reproducible on every machine.
+#[derive(Clone, Debug)]
+pub struct Noise {
+    /// Random number generator state
+    state: u64,
+    /// Current input
+    input: Input,
+    /// Frames left to hold the current input
+    hold: u32,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        Noise {
+            state: seed,
+            input: Input::default(),
+            hold: 0,
+        }
+    }
+
+    /// Next random number
+    pub fn next_u64(&mut self) -> u64 {
+        splitmix64(&mut self.state)
+    }
+
+    /// Player input (move and fire) for the next frame
+    pub fn input(&mut self) -> Input {
+        if self.hold == 0 {
+            let r = self.next_u64();
+            self.input = Input {
+                fire: r & 1 != 0,
+                left: r & 6 == 2,
+                right: r & 6 == 4,
+                ..Input::default()
+            };
+            self.hold = 1 + (r >> 32) as u32 % MAX_HOLD;
+        }
+        self.hold -= 1;
+        self.input
+    }
+}
+
+/// Result of one simulated game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outcome {
+    /// Seed of the run
+    pub seed: u64,
+    /// Frames played, from the start of the game until game over (or the frame limit)
+    pub frames: u32,
+    /// Player 1 score
+    pub score: u32,
+    /// The game ended before the frame limit
+    pub game_over: bool,
+}
+
+/// Statistics over a batch of outcomes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub runs: usize,
+    pub min_frames: u32,
+    pub median_frames: u32,
+    pub max_frames: u32,
+    pub mean_frames: f64,
+    pub mean_score: f64,
+}
+
+/// Run one frame: half a frame of cycles, RST 1 (mid screen), the other half and RST 2 (end of screen)
+pub fn run_frame(cpu: &mut Cpu) {
+    for i in [1, 2] {
+        let mut cycles = 0;
+        while cycles < FREQ / FPS / 2 {
+            cycles += cpu.step();
+        }
+        cpu.interrupt(i);
+    }
+}
+
+/// Player 1 score, decoded from BCD
+pub fn score(cpu: &Cpu) -> u32 {
+    player_score(cpu, 1)
+}
+
+/// Score of player 1 or 2, decoded from BCD
+pub fn player_score(cpu: &Cpu, player: u8) -> u32 {
+    let (name, default) = if player == 2 {
+        ("P2Scor", 0x20FC)
+    } else {
+        ("P1Scor", 0x20F8)
+    };
+    let addr = ram_map::find(name).map_or(default, |r| r.addr);
+    let bcd = |b: u8| (b >> 4) as u32 * 10 + (b & 0xF) as u32;
+    bcd(cpu.memory()[addr + 1]) * 100 + bcd(cpu.memory()[addr])
+}
+
+/// Player whose turn it is, 1 or 2
+pub fn current_player(cpu: &Cpu) -> u8 {
+    let addr = ram_map::find("playerDataMSB").map_or(0x2067, |r| r.addr);
+    if cpu.memory()[addr] == 0x22 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Number of credits, decoded from BCD
+pub fn credits(cpu: &Cpu) -> u32 {
+    let addr = ram_map::find("numCoins").map_or(0x20EB, |r| r.addr);
+    let bcd = cpu.memory()[addr];
+    (bcd >> 4) as u32 * 10 + (bcd & 0xF) as u32
+}
+
+/// Game in play (as opposed to attract mode)
+pub fn in_game(cpu: &Cpu) -> bool {
+    let addr = ram_map::find("gameMode").map_or(0x20EF, |r| r.addr);
+    cpu.memory()[addr] != 0
+}
+
+/// Simulate one game from power on with a policy deciding the input each frame from the machine state, the frame number and input noise.
+/// RAM is initialized randomly from the seed, which also seeds the noise.
+pub fn simulate<P>(rom: &[u8], seed: u64, max_frames: u32, policy: &P) -> Outcome
+where
+    P: Fn(&Cpu, u32, &mut Noise) -> Input,
+{
+    let mut cpu = Cpu::new(rom.to_vec());
+    cpu.set_ram_init(RamInit::Random(seed));
+    cpu.power_cycle();
+    let mut noise = Noise::new(seed);
+    let mut started = None;
+
+    for frame in 0..max_frames {
+        policy(&cpu, frame, &mut noise).apply(&mut cpu);
+        run_frame(&mut cpu);
+        match (started, in_game(&cpu)) {
+            (None, true) => started = Some(frame),
+            (Some(start), false) => {
+                return Outcome {
+                    seed,
+                    frames: frame - start,
+                    score: score(&cpu),
+                    game_over: true,
+                }
+            }
+            _ => {}
+        }
+    }
+    Outcome {
+        seed,
+        frames: max_frames - started.unwrap_or(max_frames),
+        score: score(&cpu),
+        game_over: false,
+    }
+}
+
+/// Simulate one game per seed, spread over all available cores. Outcomes are in the order of the seeds.
+pub fn run<P>(rom: &[u8], seeds: &[u64], max_frames: u32, policy: &P) -> Vec<Outcome>
+where
+    P: Fn(&Cpu, u32, &mut Noise) -> Input + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = seeds.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = seeds
+            .chunks(chunk)
+            .map(|seeds| {
+                scope.spawn(move || {
+                    seeds
+                        .iter()
+                        .map(|seed| simulate(rom, *seed, max_frames, policy))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("Simulation panicked"))
+            .collect()
+    })
+}
+
+/// Summarize the outcomes of a batch
+pub fn summarize(outcomes: &[Outcome]) -> Option<Summary> {
+    let mut frames: Vec<u32> = outcomes.iter().map(|o| o.frames).collect();
+    frames.sort_unstable();
+    let runs = outcomes.len();
+    Some(Summary {
+        runs,
+        min_frames: *frames.first()?,
+        median_frames: frames[runs / 2],
+        max_frames: *frames.last()?,
+        mean_frames: frames.iter().map(|f| *f as f64).sum::<f64>() / runs as f64,
+        mean_score: outcomes.iter().map(|o| o.score as f64).sum::<f64>() / runs as f64,
+    })
+}
+
+/// Input inserting a coin and pressing start every other second, for starting a game from attract mode
+pub fn start_input(frame: u32) -> Input {
+    // Hold each button for a few frames, the game polls them in its main loop
+    Input {
+        credit: frame % 120 < 10,
+        start: (60..70).contains(&(frame % 120)),
+        ..Input::default()
+    }
+}
+
+/// A simple bot: inserts a coin and starts a game in attract mode, then plays with the input noise
+pub fn random_policy(cpu: &Cpu, frame: u32, noise: &mut Noise) -> Input {
+    if !in_game(cpu) {
+        return start_input(frame);
+    }
+    noise.input()
+}
@@ -0,0 +1,131 @@
+use super::*;
+
+/// Waits for fire to start a game scoring 42, then for left to end it
+fn setup() -> Vec<u8> {
+    vec![
+        0xAF, // XRA A
+        0x32, 0xEF, 0x20, // STA 20EFh (gameMode)
+        0xDB, 0x01, // IN 1
+        0xE6, 0x10, // ANI 10h (fire)
+        0xCA, 0x04, 0x00, // JZ 0004h
+        0x3E, 0x01, // MVI A,1
+        0x32, 0xEF, 0x20, // STA 20EFh
+        0x21, 0x42, 0x00, // LXI H,0042h
+        0x22, 0xF8, 0x20, // SHLD 20F8h (P1Scor)
+        0xDB, 0x01, // IN 1
+        0xE6, 0x20, // ANI 20h (left)
+        0xCA, 0x16, 0x00, // JZ 0016h
+        0xAF, // XRA A
+        0x32, 0xEF, 0x20, // STA 20EFh
+        0x76, // HLT
+    ]
+}
+
+fn policy(_: &Cpu, frame: u32, _: &mut Noise) -> Input {
+    Input {
+        fire: frame >= 2,
+        left: frame >= 10,
+        ..Input::default()
+    }
+}
+
+#[test]
+fn simulate_game_over() {
+    assert_eq!(
+        simulate(&setup(), 1, 100, &policy),
+        Outcome {
+            seed: 1,
+            frames: 8,
+            score: 42,
+            game_over: true,
+        }
+    );
+}
+
+#[test]
+fn simulate_frame_limit() {
+    let outcome = simulate(&setup(), 1, 5, &policy);
+    assert_eq!(outcome.frames, 3);
+    assert!(!outcome.game_over);
+}
+
+#[test]
+fn run_in_order() {
+    let seeds: Vec<u64> = (0..10).collect();
+    let outcomes = run(&setup(), &seeds, 100, &policy);
+    assert_eq!(outcomes.len(), 10);
+    for (seed, outcome) in seeds.iter().zip(&outcomes) {
+        assert_eq!(outcome.seed, *seed);
+        assert_eq!(outcome.frames, 8);
+    }
+}
+
+#[test]
+fn run_random_policy_deterministic() {
+    let seeds = [7, 7, 8];
+    let policy = |cpu: &Cpu, frame, noise: &mut Noise| {
+        let mut input = random_policy(cpu, frame, noise);
+        input.fire = frame >= 2; // Start the game
+        input
+    };
+    let outcomes = run(&setup(), &seeds, 300, &policy);
+    assert_eq!(outcomes[0], outcomes[1]);
+    assert!(outcomes.iter().all(|o| o.game_over));
+}
+
+#[test]
+fn summary() {
+    let outcome = |frames, score| Outcome {
+        seed: 0,
+        frames,
+        score,
+        game_over: true,
+    };
+    let summary = summarize(&[outcome(30, 10), outcome(10, 20), outcome(20, 60)]).unwrap();
+    assert_eq!(summary.runs, 3);
+    assert_eq!(summary.min_frames, 10);
+    assert_eq!(summary.median_frames, 20);
+    assert_eq!(summary.max_frames, 30);
+    assert_eq!(summary.mean_frames, 20.0);
+    assert_eq!(summary.mean_score, 30.0);
+    assert_eq!(summarize(&[]), None);
+}
+
+#[test]
+fn score_bcd() {
+    let mut program = vec![0; 0x2100];
+    program[0x20F8] = 0x50;
+    program[0x20F9] = 0x12;
+    assert_eq!(score(&Cpu::new(program)), 1250);
+}
+
+#[test]
+fn noise_holds_input() {
+    let mut noise = Noise::new(42);
+    let inputs: Vec<Input> = (0..1000).map(|_| noise.input()).collect();
+    let changes = inputs.windows(2).filter(|w| w[0] != w[1]).count();
+    assert!(changes > 1000 / MAX_HOLD as usize / 2);
+    assert!(changes < 1000 / 2);
+    assert!(inputs
+        .iter()
+        .all(|i| !(i.credit || i.start || i.left && i.right)));
+}
+
+#[test]
+fn noise_reproducible() {
+    let sequence = |seed| {
+        let mut noise = Noise::new(seed);
+        (0..100).map(|_| noise.input()).collect::<Vec<_>>()
+    };
+    assert_eq!(sequence(7), sequence(7));
+    assert_ne!(sequence(7), sequence(8));
+    // The same on every machine
+    assert_eq!(Noise::new(0).next_u64(), 0xE220A8397B1DCDAF);
+}
+
+#[test]
+fn credits_from_bcd() {
+    let mut cpu = Cpu::new(vec![]);
+    cpu.poke(0x20EB, 0x12);
+    assert_eq!(credits(&cpu), 12);
+}
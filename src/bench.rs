@@ -0,0 +1,251 @@
+//! Core benchmark: runs a rom headless for a fixed emulated time and measures the speed of the emulation and of the
+//! conversion of the framebuffer to pixels, giving a standard number to report with performance issues. Free running
+//! (`--free-run`) measures the cycles a rom leaves spare per frame.
+
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    convert::Conversion,
+    cpu::Cpu,
+    frame::Frame,
+    rewind::{self, History},
+    FPS, FREQ,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Emulated seconds run by `--bench-core`
+pub const SECONDS: u64 = 60;
+
+/// Measurements of a run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bench {
+    pub frames: u64,
+    pub cycles: u64,
+    pub instructions: u64,
+    /// Real time spent emulating
+    pub emulation: Duration,
+    /// Real time spent converting the framebuffer, once per frame
+    pub rendering: Duration,
+    /// Bytes of the rewind history after the first state, a compressed state per frame
+    pub rewind_bytes: u64,
+    /// Real time spent saving and compressing the states
+    pub rewinding: Duration,
+}
+
+/// Run a rom from power on without input for a number of emulated seconds
+pub fn run(rom: &[u8], seconds: u64) -> Bench {
+    let mut cpu = Cpu::new(rom.to_vec());
+    let mut bench = Bench::default();
+    let mut history = History::new(usize::MAX);
+    for _ in 0..seconds * FPS as u64 {
+        let start = Instant::now();
+        for i in [1, 2] {
+            let mut cycles = 0;
+            while cycles < FREQ / FPS / 2 {
+                cycles += cpu.step();
+            }
+            bench.cycles += cycles as u64;
+            cpu.interrupt(i);
+        }
+        let emulated = Instant::now();
+        std::hint::black_box(Frame::render(&cpu));
+        bench.rendering += emulated.elapsed();
+        bench.emulation += emulated - start;
+        let rendered = Instant::now();
+        let bytes = history.bytes();
+        history.push(cpu.save_state());
+        bench.rewinding += rendered.elapsed();
+        if bench.frames > 0 {
+            bench.rewind_bytes += (history.bytes() - bytes) as u64;
+        }
+        bench.frames += 1;
+    }
+    bench.instructions = cpu.instructions();
+    bench
+}
+
+/// Cycles left spare per frame while free running: cycles spent halted (HLT) waiting for the next interrupt. A program
+/// busy waiting for the interrupts leaves none.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpareCycles {
+    pub frames: u64,
+    pub cycles: u64,
+    pub spare: u64,
+    /// Spare cycles of the busiest frame
+    pub least: Option<u32>,
+}
+
+impl SpareCycles {
+    /// Account a frame of `cycles` with `spare` of them spent halted
+    pub fn record(&mut self, spare: u32, cycles: u32) {
+        self.frames += 1;
+        self.cycles += cycles as u64;
+        self.spare += spare as u64;
+        self.least = Some(self.least.map_or(spare, |least| least.min(spare)));
+    }
+
+    /// Summary of a free run that took `real` time
+    pub fn report(&self, real: Duration) -> String {
+        let mut s = String::new();
+        let speed = rate(self.frames, real)
+            .map_or("-".to_string(), |fps| format!("{:.1}", fps / FPS as f64));
+        let _ = writeln!(
+            s,
+            "Free run: {} frames in {:.3} s, {speed}x real time",
+            self.frames,
+            real.as_secs_f64()
+        );
+        let _ = writeln!(
+            s,
+            "Spare cycles per frame: least {}, mean {} ({:.1}% of the cycles)",
+            self.least.unwrap_or_default(),
+            self.spare / self.frames.max(1),
+            self.spare as f64 * 100.0 / self.cycles.max(1) as f64
+        );
+        s
+    }
+}
+
+/// Per second of real time, none when too fast to measure
+fn rate(count: u64, time: Duration) -> Option<f64> {
+    (!time.is_zero()).then(|| count as f64 / time.as_secs_f64())
+}
+
+impl Bench {
+    /// Effective clock of the emulation in MHz (the board runs at 1.9968 MHz)
+    pub fn mhz(&self) -> Option<f64> {
+        rate(self.cycles, self.emulation).map(|hz| hz / 1_000_000.0)
+    }
+
+    pub fn instructions_per_second(&self) -> Option<f64> {
+        rate(self.instructions, self.emulation)
+    }
+
+    /// Framebuffer conversions per second
+    pub fn renders_per_second(&self) -> Option<f64> {
+        rate(self.frames, self.rendering)
+    }
+
+    /// Bytes of rewind history per second of play
+    pub fn rewind_rate(&self) -> Option<f64> {
+        let frames = self.frames.checked_sub(1).filter(|f| *f > 0)?;
+        Some(self.rewind_bytes as f64 / frames as f64 * FPS as f64)
+    }
+
+    /// Summary to paste into an issue
+    pub fn report(&self) -> String {
+        let number = |rate: Option<f64>| rate.map_or("-".to_string(), |r| format!("{r:.0}"));
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "Emulated {:.1} s: {} frames, {} cycles, {} instructions",
+            self.frames as f64 / FPS as f64,
+            self.frames,
+            self.cycles,
+            self.instructions
+        );
+        let _ = writeln!(
+            s,
+            "Emulation: {:.3} s, {} MHz ({}x real time), {} instructions/s",
+            self.emulation.as_secs_f64(),
+            self.mhz()
+                .map_or("-".to_string(), |mhz| format!("{mhz:.2}")),
+            self.mhz().map_or("-".to_string(), |mhz| format!(
+                "{:.0}",
+                mhz * 1e6 / FREQ as f64
+            )),
+            number(self.instructions_per_second())
+        );
+        let _ = writeln!(
+            s,
+            "Rendering: {:.3} s, {} frames/s",
+            self.rendering.as_secs_f64(),
+            number(self.renders_per_second())
+        );
+        if let Some(rate) = self.rewind_rate() {
+            let _ = writeln!(
+                s,
+                "Rewind: {:.3} s, {:.0} KB/s of history at {FPS} states/s, {:.1} minutes in {} MB",
+                self.rewinding.as_secs_f64(),
+                rate / 1000.0,
+                rewind::BUDGET as f64 / rate.max(1.0) / 60.0,
+                rewind::BUDGET >> 20
+            );
+        }
+        let _ = writeln!(
+            s,
+            "Build: {} {} {}",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        s
+    }
+}
+
+/// Time spent by each display conversion strategy, measured in the window by `--bench-conversion`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conversions {
+    /// Strategy, frames converted and the time spent converting and uploading them
+    pub times: Vec<(Conversion, u64, Duration)>,
+}
+
+impl Conversions {
+    /// Strategy to measure next: each in turn for `frames` frames, None when all are done
+    pub fn next(&self, frames: u64) -> Option<Conversion> {
+        Conversion::ALL.into_iter().find(|conversion| {
+            self.times
+                .iter()
+                .find(|(c, _, _)| c == conversion)
+                .is_none_or(|(_, n, _)| *n < frames)
+        })
+    }
+
+    pub fn record(&mut self, conversion: Conversion, time: Duration) {
+        match self.times.iter_mut().find(|(c, _, _)| *c == conversion) {
+            Some((_, frames, total)) => {
+                *frames += 1;
+                *total += time;
+            }
+            None => self.times.push((conversion, 1, time)),
+        }
+    }
+
+    /// Fastest strategy measured
+    pub fn fastest(&self) -> Option<Conversion> {
+        self.times
+            .iter()
+            .min_by_key(|(_, frames, time)| *time / (*frames).max(1) as u32)
+            .map(|(c, _, _)| *c)
+    }
+
+    /// Frames per second and time per frame of each strategy, and the fastest
+    pub fn report(&self) -> String {
+        let mut s = String::new();
+        for (conversion, frames, time) in &self.times {
+            let _ = writeln!(
+                s,
+                "Conversion {:<6} {:>6} frames/s, {:.3} ms/frame ({frames} frames)",
+                conversion.name(),
+                rate(*frames, *time).map_or("-".to_string(), |r| format!("{r:.0}")),
+                time.as_secs_f64() * 1000.0 / (*frames).max(1) as f64
+            );
+        }
+        if let Some(fastest) = self.fastest() {
+            let _ = writeln!(s, "Fastest: --conversion {}", fastest.name());
+        }
+        let _ = writeln!(
+            s,
+            "Build: {} {} {}",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        s
+    }
+}
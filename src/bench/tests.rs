@@ -0,0 +1,66 @@
+use super::*;
+
+#[test]
+fn counts_a_second() {
+    // Endless loop: JMP 0
+    let bench = run(&[0xC3, 0x00, 0x00], 1);
+    assert_eq!(bench.frames, FPS as u64);
+    assert!(bench.cycles >= FREQ as u64 && bench.cycles < FREQ as u64 + 100);
+    // JMP takes 10 cycles
+    assert_eq!(bench.instructions, bench.cycles / 10);
+}
+
+#[test]
+fn report() {
+    let bench = Bench {
+        frames: 120,
+        cycles: 4_000_000,
+        instructions: 1_000_000,
+        emulation: Duration::from_millis(500),
+        rendering: Duration::from_millis(100),
+        rewind_bytes: 119 * 2000,
+        rewinding: Duration::from_millis(10),
+    };
+    assert_eq!(bench.mhz(), Some(8.0));
+    assert_eq!(bench.renders_per_second(), Some(1200.0));
+    let report = bench.report();
+    assert!(report.contains("Emulated 2.0 s: 120 frames"));
+    assert!(report.contains("8.00 MHz (4x real time), 2000000 instructions/s"));
+    assert!(report
+        .contains("Rewind: 0.010 s, 120 KB/s of history at 60 states/s, 4.7 minutes in 32 MB"));
+    assert_eq!(Bench::default().mhz(), None);
+    assert_eq!(Bench::default().rewind_rate(), None);
+}
+
+#[test]
+fn spare_cycles() {
+    let mut spare = SpareCycles::default();
+    spare.record(10_000, 33_280);
+    spare.record(6_640, 33_280);
+    assert_eq!((spare.frames, spare.least), (2, Some(6_640)));
+    let report = spare.report(Duration::from_millis(10));
+    assert!(report.contains("Free run: 2 frames in 0.010 s, 3.3x real time"));
+    assert!(report.contains("least 6640, mean 8320 (25.0% of the cycles)"));
+}
+
+#[test]
+fn conversions() {
+    let mut conversions = Conversions::default();
+    assert_eq!(conversions.fastest(), None);
+    for (conversion, time) in [
+        (Conversion::Points, Duration::from_millis(4)),
+        (Conversion::Buffer, Duration::from_millis(1)),
+        (Conversion::Lut, Duration::from_micros(500)),
+    ] {
+        for _ in 0..2 {
+            assert_eq!(conversions.next(2), Some(conversion));
+            conversions.record(conversion, time);
+        }
+    }
+    assert_eq!(conversions.next(2), None);
+    assert_eq!(conversions.fastest(), Some(Conversion::Lut));
+    let report = conversions.report();
+    assert!(report.contains("Conversion points    250 frames/s, 4.000 ms/frame (2 frames)"));
+    assert!(report.contains("Conversion lut      2000 frames/s, 0.500 ms/frame (2 frames)"));
+    assert!(report.contains("Fastest: --conversion lut"));
+}
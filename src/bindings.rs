@@ -0,0 +1,216 @@
+//! Emulator commands and the keys and buttons running them. One binding table serves the keyboard, the game
+//! controllers and the command palette. Independent of SDL: keys and buttons are given by their SDL names, so the
+//! table is easy to test and could be read from a file.
+
+use crate::menu::Entry;
+
+#[cfg(test)]
+mod tests;
+
+/// A command of the emulator, as opposed to the game inputs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Pause,
+    /// Advance one frame while paused
+    StepFrame,
+    MemoryEditor,
+    FrameBlending,
+    Reset,
+    PowerCycle,
+    CycleBar,
+    Screenshot,
+    /// Print the text on screen to the terminal
+    PrintText,
+    CompareStates,
+    Slower,
+    Faster,
+    /// Store the machine state in slot 0 (A) or 1 (B)
+    StoreState(usize),
+    PortPanel,
+    FreePlay,
+    SwapControllers,
+    /// Toggle DIP switch `dip<n>`
+    ToggleDip(u8),
+    MuteSound(usize),
+    SoloSound(usize),
+    /// Go back a second, with `--rewind`
+    Rewind,
+    /// Open the command palette
+    Palette,
+    Quit,
+}
+
+impl Action {
+    /// Name in capitals, as listed in the command palette
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Pause => "PAUSE",
+            Action::StepFrame => "STEP FRAME",
+            Action::MemoryEditor => "MEMORY EDITOR",
+            Action::FrameBlending => "FRAME BLENDING",
+            Action::Reset => "RESET",
+            Action::PowerCycle => "POWER CYCLE",
+            Action::CycleBar => "CYCLE BAR",
+            Action::Screenshot => "SCREENSHOT",
+            Action::PrintText => "PRINT SCREEN TEXT",
+            Action::CompareStates => "COMPARE STATES",
+            Action::Slower => "SLOWER",
+            Action::Faster => "FASTER",
+            Action::StoreState(0) => "STORE STATE A",
+            Action::StoreState(_) => "STORE STATE B",
+            Action::PortPanel => "PORT PANEL",
+            Action::FreePlay => "FREE PLAY",
+            Action::SwapControllers => "SWAP CONTROLLERS",
+            Action::ToggleDip(3) => "DIP 3",
+            Action::ToggleDip(4) => "DIP 4",
+            Action::ToggleDip(5) => "DIP 5",
+            Action::ToggleDip(6) => "DIP 6",
+            Action::ToggleDip(_) => "DIP 7",
+            Action::MuteSound(_) => "MUTE SOUND",
+            Action::SoloSound(_) => "SOLO SOUND",
+            Action::Rewind => "REWIND",
+            Action::Palette => "COMMAND PALETTE",
+            Action::Quit => "QUIT",
+        }
+    }
+
+    /// Whether it is listed in the command palette. The sound channels are too many to list, and the palette is open
+    /// already.
+    fn listed(self) -> bool {
+        !matches!(
+            self,
+            Action::MuteSound(_) | Action::SoloSound(_) | Action::Palette
+        )
+    }
+}
+
+/// Modifier held with a key
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Modifier {
+    None,
+    Ctrl,
+    Shift,
+}
+
+/// What runs an action
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// A key by SDL key name, e.g. `F1`, `Space` or `Keypad 0`
+    Key(&'static str, Modifier),
+    /// A game controller button by SDL button name, e.g. `guide`
+    Button(&'static str),
+}
+
+impl Trigger {
+    /// The key as shown in the command palette, e.g. `CTRL-F` or `SHIFT-KP0`
+    pub fn label(self) -> String {
+        let (name, modifier) = match self {
+            Trigger::Key(name, modifier) => (name, modifier),
+            Trigger::Button(name) => (name, Modifier::None),
+        };
+        let name = match name {
+            "Escape" => "ESC".to_string(),
+            _ => name.replace("Keypad ", "KP").to_uppercase(),
+        };
+        match modifier {
+            Modifier::None => name,
+            Modifier::Ctrl => format!("CTRL-{name}"),
+            Modifier::Shift => format!("SHIFT-{name}"),
+        }
+    }
+
+    /// A character key without modifier, typed into the memory editor while it is shown
+    pub fn typing(self) -> bool {
+        matches!(self, Trigger::Key(name, Modifier::None) if name.chars().count() == 1)
+    }
+}
+
+/// Keypad keys of the sound channels
+const KEYPAD: [&str; 10] = [
+    "Keypad 0", "Keypad 1", "Keypad 2", "Keypad 3", "Keypad 4", "Keypad 5", "Keypad 6", "Keypad 7",
+    "Keypad 8", "Keypad 9",
+];
+
+/// Digit keys of the DIP switches
+const DIPS: [(&str, u8); 5] = [("3", 3), ("4", 4), ("5", 5), ("6", 6), ("7", 7)];
+
+/// Triggers and their actions, looked up in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings(pub Vec<(Trigger, Action)>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Action::*;
+        use Modifier::{Ctrl, Shift};
+        let key = |name| Trigger::Key(name, Modifier::None);
+        let mut bindings = vec![
+            (key("Space"), Pause),
+            (key("Pause"), Pause),
+            (Trigger::Button("guide"), Pause),
+            (key("Tab"), StepFrame),
+            (key("F1"), MemoryEditor),
+            (key("F2"), FrameBlending),
+            (key("F3"), Reset),
+            (key("F4"), PowerCycle),
+            (key("F5"), CycleBar),
+            (key("F6"), Screenshot),
+            (key("F7"), PrintText),
+            (key("F8"), CompareStates),
+            (key("F9"), Slower),
+            (key("F10"), Faster),
+            (key("F11"), StoreState(0)),
+            (key("F12"), StoreState(1)),
+            (key("P"), PortPanel),
+            (Trigger::Key("F", Ctrl), FreePlay),
+            (Trigger::Key("X", Ctrl), SwapControllers),
+        ];
+        bindings.extend(DIPS.map(|(k, n)| (Trigger::Key(k, Ctrl), ToggleDip(n))));
+        for (channel, k) in KEYPAD.into_iter().enumerate() {
+            bindings.push((key(k), MuteSound(channel)));
+            bindings.push((Trigger::Key(k, Shift), SoloSound(channel)));
+        }
+        bindings.push((key("Backspace"), Rewind));
+        bindings.push((key("`"), Palette));
+        bindings.push((key("Escape"), Quit));
+        Bindings(bindings)
+    }
+}
+
+impl Bindings {
+    /// The binding of a key. A binding with the modifier held is preferred, else the key without modifier is taken,
+    /// so e.g. `Shift+F1` still opens the memory editor.
+    pub fn key(&self, name: &str, modifier: Modifier) -> Option<(Trigger, Action)> {
+        let find = |modifier| {
+            self.0
+                .iter()
+                .find(|(t, _)| matches!(t, Trigger::Key(n, m) if *n == name && *m == modifier))
+                .copied()
+        };
+        find(modifier).or_else(|| find(Modifier::None))
+    }
+
+    pub fn button(&self, name: &str) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(t, _)| matches!(t, Trigger::Button(n) if *n == name))
+            .map(|(_, action)| *action)
+    }
+
+    /// Commands for the command palette, in the order of the table, with the first key of each
+    pub fn commands(&self) -> Vec<Entry<Action>> {
+        let mut commands: Vec<Entry<Action>> = vec![];
+        for (trigger, action) in &self.0 {
+            if action.listed()
+                && matches!(trigger, Trigger::Key(..))
+                && !commands.iter().any(|e| e.command == *action)
+            {
+                commands.push(Entry {
+                    name: action.name(),
+                    keys: trigger.label(),
+                    command: *action,
+                });
+            }
+        }
+        commands
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn keys_and_modifiers() {
+    let bindings = Bindings::default();
+    let action = |name, modifier| bindings.key(name, modifier).map(|(_, a)| a);
+    assert_eq!(action("F1", Modifier::None), Some(Action::MemoryEditor));
+    // Unbound modifiers fall back to the plain key
+    assert_eq!(action("F1", Modifier::Shift), Some(Action::MemoryEditor));
+    assert_eq!(action("F", Modifier::Ctrl), Some(Action::FreePlay));
+    assert_eq!(action("F", Modifier::None), None);
+    assert_eq!(action("5", Modifier::Ctrl), Some(Action::ToggleDip(5)));
+    assert_eq!(action("5", Modifier::None), None);
+    assert_eq!(
+        action("Keypad 3", Modifier::None),
+        Some(Action::MuteSound(3))
+    );
+    assert_eq!(
+        action("Keypad 3", Modifier::Shift),
+        Some(Action::SoloSound(3))
+    );
+    assert_eq!(action("Left Ctrl", Modifier::None), None);
+    assert_eq!(bindings.button("guide"), Some(Action::Pause));
+    assert_eq!(bindings.button("start"), None);
+}
+
+#[test]
+fn labels() {
+    assert_eq!(Trigger::Key("Space", Modifier::None).label(), "SPACE");
+    assert_eq!(Trigger::Key("X", Modifier::Ctrl).label(), "CTRL-X");
+    assert_eq!(
+        Trigger::Key("Keypad 0", Modifier::Shift).label(),
+        "SHIFT-KP0"
+    );
+    assert_eq!(Trigger::Key("Escape", Modifier::None).label(), "ESC");
+    assert!(Trigger::Key("P", Modifier::None).typing());
+    assert!(!Trigger::Key("X", Modifier::Ctrl).typing());
+    assert!(!Trigger::Key("F1", Modifier::None).typing());
+}
+
+#[test]
+fn palette_commands() {
+    let commands = Bindings::default().commands();
+    let names: Vec<&str> = commands.iter().map(|e| e.name).collect();
+    assert_eq!(names.len(), 24);
+    assert_eq!(names[..3], ["PAUSE", "STEP FRAME", "MEMORY EDITOR"]);
+    assert_eq!(names[names.len() - 2..], ["REWIND", "QUIT"]);
+    assert_eq!(commands[names.len() - 2].keys, "BACKSPACE");
+    // The first key of an action is listed, once
+    assert_eq!(commands[0].keys, "SPACE");
+    assert_eq!(names.iter().filter(|n| **n == "PAUSE").count(), 1);
+    assert!(!names.contains(&"COMMAND PALETTE"));
+    assert!(!names.contains(&"MUTE SOUND"));
+}
@@ -0,0 +1,116 @@
+//! Cheats and patches found next to the rom: `<name>.cht` and `<name>.ips` for `<name>.rom` are offered on screen when
+//! they appear or change, and applied when accepted. A cheat file has a line `<address> <value> [description]` per
+//! cheat, with numbers in decimal or `0x` hex and `#` starting a comment. The values are written to RAM every frame,
+//! like the cheats of MAME (e.g. `0x21FF 3 Infinite lives`).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{cpu::Cpu, ips, utils::parse_number, RAM};
+
+#[cfg(test)]
+mod tests;
+
+/// A value held in RAM
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cheat {
+    pub addr: usize,
+    pub value: u8,
+    pub description: String,
+}
+
+/// Parse a cheat file
+pub fn parse(text: &str) -> Result<Vec<Cheat>, String> {
+    let mut cheats = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = || format!("Line {}: invalid cheat `{line}`", i + 1);
+        let mut words = line.splitn(3, char::is_whitespace);
+        let addr = words
+            .next()
+            .and_then(parse_number)
+            .filter(|a| RAM.contains(a))
+            .ok_or_else(error)?;
+        let value = words.next().and_then(parse_number).ok_or_else(error)?;
+        cheats.push(Cheat {
+            addr,
+            value: u8::try_from(value).map_err(|_| error())?,
+            description: words.next().unwrap_or_default().trim().to_string(),
+        });
+    }
+    Ok(cheats)
+}
+
+/// Hold the values of the cheats
+pub fn apply(cheats: &[Cheat], cpu: &mut Cpu) {
+    for cheat in cheats {
+        cpu.poke(cheat.addr, cheat.value);
+    }
+}
+
+/// Cheats or a patch waiting to be accepted
+#[derive(Clone, Debug, PartialEq)]
+pub enum Offer {
+    Cheats(Vec<Cheat>),
+    /// IPS patch of the rom
+    Patch(Vec<u8>),
+}
+
+impl Offer {
+    /// Question shown on screen, in the characters of the font
+    pub fn message(&self) -> &'static str {
+        match self {
+            Offer::Cheats(_) => "APPLY CHEATS  Y OR N",
+            Offer::Patch(_) => "APPLY PATCH  Y OR N",
+        }
+    }
+}
+
+/// Watches the cheat and patch files next to a rom
+#[derive(Debug)]
+pub struct Companions {
+    /// Cheat and patch file, with the time they were modified when last seen
+    files: [(PathBuf, Option<SystemTime>); 2],
+}
+
+impl Companions {
+    pub fn new(rom: &Path) -> Self {
+        Companions {
+            files: [
+                (rom.with_extension("cht"), None),
+                (rom.with_extension("ips"), None),
+            ],
+        }
+    }
+
+    /// Offers for the files that appeared or changed since the last check, or errors for files that could not be read
+    pub fn check(&mut self) -> Vec<Result<Offer, String>> {
+        let mut offers = vec![];
+        for (i, (path, seen)) in self.files.iter_mut().enumerate() {
+            let Ok(modified) = fs::metadata(&*path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if *seen == Some(modified) {
+                continue;
+            }
+            *seen = Some(modified);
+            let offer = fs::read(&*path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| {
+                    if i == 0 {
+                        parse(&String::from_utf8_lossy(&data)).map(Offer::Cheats)
+                    } else {
+                        ips::apply(&mut vec![], &data).map(|_| Offer::Patch(data))
+                    }
+                });
+            offers.push(offer.map_err(|e| format!("{}: {e}", path.display())));
+        }
+        offers
+    }
+}
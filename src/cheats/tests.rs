@@ -0,0 +1,57 @@
+use super::*;
+
+#[test]
+fn parse_cheats() {
+    let cheats = parse("# lives\n0x21FF 3 Infinite lives\n\n8704 0x10\n").unwrap();
+    assert_eq!(
+        cheats,
+        [
+            Cheat {
+                addr: 0x21FF,
+                value: 3,
+                description: "Infinite lives".to_string()
+            },
+            Cheat {
+                addr: 0x2200,
+                value: 0x10,
+                description: String::new()
+            }
+        ]
+    );
+    assert_eq!(
+        parse("0x1000 3").unwrap_err(),
+        "Line 1: invalid cheat `0x1000 3`"
+    );
+    assert!(parse("0x2000 256").is_err());
+    assert!(parse("0x2000").is_err());
+}
+
+#[test]
+fn apply_cheats() {
+    let mut cpu = Cpu::new(vec![]);
+    apply(&parse("0x21FF 3").unwrap(), &mut cpu);
+    assert_eq!(cpu.memory()[0x21FF], 3);
+}
+
+#[test]
+fn offers_new_and_changed_files() {
+    let dir = std::env::temp_dir().join(format!("inv8080rs-cheats-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut companions = Companions::new(&dir.join("invaders.rom"));
+    assert!(companions.check().is_empty());
+    fs::write(dir.join("invaders.cht"), "0x21FF 3").unwrap();
+    fs::write(dir.join("invaders.ips"), ips::create(&[0], &[1])).unwrap();
+    let offers = companions.check();
+    assert!(matches!(
+        offers[..],
+        [Ok(Offer::Cheats(_)), Ok(Offer::Patch(_))]
+    ));
+    assert!(companions.check().is_empty());
+    fs::write(dir.join("invaders.ips"), "not a patch").unwrap();
+    // Modification times may be coarse
+    companions.files[1].1 = None;
+    assert!(companions.check()[0]
+        .as_ref()
+        .is_err_and(|e| e.ends_with("Not an IPS patch")));
+    fs::remove_dir_all(&dir).unwrap();
+}
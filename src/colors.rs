@@ -0,0 +1,71 @@
+//! Color RAM of the later Midway/Taito color boards ("Space Invaders CV", Part II): a byte of attribute memory colors
+//! each byte of the framebuffer, 8 pixels wide, over a block of 8 lines. The program writes it through a window of the
+//! address space outside the emulated memory, mirrored like the framebuffer in ram.
+
+use std::ops::RangeInclusive;
+
+use crate::palette::Color;
+
+#[cfg(test)]
+mod tests;
+
+/// Attribute memory giving the color of each byte of the framebuffer
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRam {
+    /// Addresses the program writes colors to, the offset into the window mirrors the offset into ram
+    pub window: RangeInclusive<usize>,
+    /// Address lines decoded by the color RAM, the rest are mirrors
+    pub mask: usize,
+    contents: Vec<u8>,
+}
+
+impl ColorRam {
+    /// Cleared color RAM over the window
+    pub fn new(window: RangeInclusive<usize>, mask: usize) -> Self {
+        ColorRam {
+            window,
+            mask,
+            contents: vec![0; mask + 1],
+        }
+    }
+
+    /// Read through the window
+    pub fn read(&self, addr: usize) -> u8 {
+        self.contents[(addr - self.window.start()) & self.mask]
+    }
+
+    /// Write through the window, only the 3 color bits are kept
+    pub fn write(&mut self, addr: usize, data: u8) {
+        self.contents[(addr - self.window.start()) & self.mask] = data & 0x07;
+    }
+
+    /// Color of the framebuffer byte at an offset into ram
+    pub fn color(&self, offset: usize) -> u8 {
+        self.contents[offset & self.mask]
+    }
+
+    /// All colors, for savestates
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// Restore colors as returned by `contents`
+    pub fn restore(&mut self, contents: &[u8]) -> Result<(), String> {
+        if contents.len() != self.contents.len() {
+            return Err("Color RAM does not match".to_string());
+        }
+        self.contents.copy_from_slice(contents);
+        Ok(())
+    }
+
+    /// Clear all colors, as at power on
+    pub fn clear(&mut self) {
+        self.contents.fill(0);
+    }
+}
+
+/// Opaque color of a 3-bit color, wired red, blue, green from the low bit. Black leaves the pixels dark.
+pub fn rgb(color: u8) -> Color {
+    let on = |bit: u8| if color & 1 << bit != 0 { 0xff } else { 0 };
+    Color::rgb(on(0), on(2), on(1))
+}
@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn mirrored_through_the_window() {
+    let mut colors = ColorRam::new(0xC000..=0xDFFF, 0x1F1F);
+    assert_eq!(colors.contents().len(), 0x1F20);
+    colors.write(0xC425, 0xFA);
+    assert_eq!(colors.read(0xC425), 2);
+    // Only 32 bytes of every 256 are decoded
+    assert_eq!(colors.read(0xC4E5), 2);
+    assert_eq!(colors.color(0x425), 2);
+    assert_eq!(colors.color(0x505), 0);
+    colors.clear();
+    assert_eq!(colors.color(0x425), 0);
+}
+
+#[test]
+fn restore() {
+    let mut colors = ColorRam::new(0xC000..=0xC00F, 0x0F);
+    assert!(colors.restore(&[1; 15]).is_err());
+    colors.restore(&[3; 16]).unwrap();
+    assert_eq!(colors.color(0x2F), 3);
+}
+
+#[test]
+fn three_bit_colors() {
+    assert_eq!(rgb(0), Color::rgb(0, 0, 0));
+    assert_eq!(rgb(1), Color::rgb(0xff, 0, 0));
+    assert_eq!(rgb(2), Color::rgb(0, 0, 0xff));
+    assert_eq!(rgb(4), Color::rgb(0, 0xff, 0));
+    assert_eq!(rgb(7), Color::rgb(0xff, 0xff, 0xff));
+}
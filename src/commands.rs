@@ -0,0 +1,75 @@
+//! Requests from other threads (network, scripting, debugger) to change the machine: memory pokes, input changes and
+//! savestate loads. They are queued and only applied between frames, so the machine is never changed in the middle of
+//! an instruction or a frame.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::{cpu::Cpu, ports::InputBit};
+
+#[cfg(test)]
+mod tests;
+
+/// Change requested
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Write a byte of memory
+    Poke { addr: usize, data: u8 },
+    /// Activate or deactivate an input
+    Input {
+        input: &'static InputBit,
+        active: bool,
+    },
+    /// Load a savestate made by `Cpu::save_state`
+    LoadState(Vec<u8>),
+}
+
+impl Command {
+    /// Apply to the machine
+    pub fn apply(self, cpu: &mut Cpu) -> Result<(), String> {
+        match self {
+            Command::Poke { addr, data } => {
+                if addr >= cpu.memory().len() {
+                    return Err(format!("Poke outside memory: {addr:04X}"));
+                }
+                cpu.poke(addr, data);
+            }
+            Command::Input { input, active } => input.set(cpu, active),
+            Command::LoadState(state) => cpu.load_state(&state)?,
+        }
+        Ok(())
+    }
+}
+
+/// Commands waiting for the next frame boundary
+#[derive(Debug)]
+pub struct CommandQueue {
+    sender: Sender<Command>,
+    receiver: Receiver<Command>,
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        CommandQueue { sender, receiver }
+    }
+}
+
+impl CommandQueue {
+    /// Sending end for another thread, commands are queued in the order sent
+    pub fn sender(&self) -> Sender<Command> {
+        self.sender.clone()
+    }
+
+    /// Take the commands queued so far
+    pub fn take(&self) -> Vec<Command> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Apply the commands queued so far, in order. Returns the errors of the commands that failed.
+    pub fn apply(&self, cpu: &mut Cpu) -> Vec<String> {
+        self.take()
+            .into_iter()
+            .filter_map(|command| command.apply(cpu).err())
+            .collect()
+    }
+}
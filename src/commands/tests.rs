@@ -0,0 +1,70 @@
+use std::thread;
+
+use super::*;
+use crate::ports::INVADERS;
+
+#[test]
+fn applied_in_order() {
+    let queue = CommandQueue::default();
+    let mut cpu = Cpu::new(vec![]);
+    let state = cpu.save_state();
+    let sender = queue.sender();
+    thread::spawn(move || {
+        sender
+            .send(Command::Poke {
+                addr: 0x2000,
+                data: 1,
+            })
+            .unwrap();
+        sender.send(Command::LoadState(state)).unwrap();
+        sender
+            .send(Command::Poke {
+                addr: 0x2001,
+                data: 2,
+            })
+            .unwrap();
+        let input = INVADERS.find("p1-fire").unwrap();
+        sender
+            .send(Command::Input {
+                input,
+                active: true,
+            })
+            .unwrap();
+    })
+    .join()
+    .unwrap();
+    // Nothing changes before the queue is applied
+    assert_eq!(cpu.memory()[0x2000], 0);
+    assert_eq!(queue.apply(&mut cpu), Vec::<String>::new());
+    assert_eq!(cpu.memory()[0x2000..0x2002], [0, 2]);
+    assert!(INVADERS.find("p1-fire").unwrap().active(&cpu));
+    assert!(queue.take().is_empty());
+}
+
+#[test]
+fn errors() {
+    let queue = CommandQueue::default();
+    let mut cpu = Cpu::new(vec![]);
+    let sender = queue.sender();
+    sender.send(Command::LoadState(vec![1, 2, 3])).unwrap();
+    sender
+        .send(Command::Poke {
+            addr: 0x10000,
+            data: 0,
+        })
+        .unwrap();
+    sender
+        .send(Command::Poke {
+            addr: 0x2000,
+            data: 7,
+        })
+        .unwrap();
+    assert_eq!(
+        queue.apply(&mut cpu),
+        vec![
+            "Not a savestate".to_string(),
+            "Poke outside memory: 10000".to_string()
+        ]
+    );
+    assert_eq!(cpu.memory()[0x2000], 7);
+}
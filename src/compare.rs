@@ -0,0 +1,75 @@
+//! Quick compare of two machine states (A/B slots): changed registers and RAM, annotated from the RAM map
+
+use std::fmt::Write;
+
+use crate::{cpu::Cpu, ram_map, FRAMEBUFFER, RAM};
+
+#[cfg(test)]
+mod tests;
+
+/// Registers and flags compared, by name as in `Cpu::register`
+pub const REGISTERS: [&str; 14] = [
+    "A", "B", "C", "D", "E", "H", "L", "SP", "PC", "S", "Z", "AC", "P", "CY",
+];
+
+/// Old name of `Slot`
+#[deprecated(since = "0.3.0", note = "renamed to Slot")]
+pub type State = Slot;
+
+/// Registers and RAM of the machine at some point
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+    /// Values of `REGISTERS`
+    pub registers: [u16; REGISTERS.len()],
+    /// Content of RAM, framebuffer included
+    pub ram: Vec<u8>,
+}
+
+impl Slot {
+    pub fn capture(cpu: &Cpu) -> Self {
+        Slot {
+            registers: REGISTERS.map(|r| cpu.register(r).unwrap_or_default()),
+            ram: cpu.memory()[RAM].to_vec(),
+        }
+    }
+}
+
+/// Register value in hex, as wide as the register
+fn hex(name: &str, value: u16) -> String {
+    match name {
+        "SP" | "PC" => format!("{value:04X}"),
+        "S" | "Z" | "AC" | "P" | "CY" => format!("{value}"),
+        _ => format!("{value:02X}"),
+    }
+}
+
+/// Changes from state `a` to `b`: registers, work RAM addresses with their names in the RAM map and the number of changed
+/// framebuffer bytes
+pub fn diff(a: &Slot, b: &Slot) -> String {
+    let mut r = String::new();
+    for ((name, x), y) in REGISTERS.iter().zip(a.registers).zip(b.registers) {
+        if x != y {
+            let _ = writeln!(r, "{name:<4} {} -> {}", hex(name, x), hex(name, y));
+        }
+    }
+    let mut framebuffer = 0;
+    for (i, (x, y)) in a.ram.iter().zip(&b.ram).enumerate() {
+        let addr = RAM.start() + i;
+        if x == y {
+            continue;
+        }
+        if FRAMEBUFFER.contains(&addr) {
+            framebuffer += 1;
+        } else {
+            let name = ram_map::annotate(addr).unwrap_or_default();
+            let _ = writeln!(r, "{addr:04X} {x:02X} -> {y:02X} {name}");
+        }
+    }
+    if framebuffer > 0 {
+        let _ = writeln!(r, "Framebuffer: {framebuffer} bytes changed");
+    }
+    if r.is_empty() {
+        r.push_str("No changes\n");
+    }
+    r
+}
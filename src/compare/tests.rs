@@ -0,0 +1,31 @@
+use super::*;
+
+#[test]
+fn no_changes() {
+    let cpu = Cpu::new(vec![]);
+    let state = Slot::capture(&cpu);
+    assert_eq!(state.ram.len(), RAM.count());
+    assert_eq!(diff(&state, &state), "No changes\n");
+}
+
+#[test]
+fn registers_and_ram() {
+    // MVI A,50h; STA 20F8h; LXI SP,2400h; STA 2400h; STA 2401h
+    let program = vec![
+        0x3E, 0x50, 0x32, 0xF8, 0x20, 0x31, 0x00, 0x24, 0x32, 0x00, 0x24, 0x32, 0x01, 0x24,
+    ];
+    let mut cpu = Cpu::new(program);
+    let a = Slot::capture(&cpu);
+    for _ in 0..5 {
+        cpu.step();
+    }
+    let b = Slot::capture(&cpu);
+    assert_eq!(
+        diff(&a, &b),
+        "A    00 -> 50\n\
+         SP   0000 -> 2400\n\
+         PC   0000 -> 000E\n\
+         20F8 00 -> 50 P1Scor\n\
+         Framebuffer: 2 bytes changed\n"
+    );
+}
@@ -0,0 +1,114 @@
+//! Strategies for converting a frame to the pixels of the game texture. Which is fastest depends on the renderer and
+//! platform, so it is chosen at runtime (`--conversion`) and `--bench-conversion` measures all of them in the window.
+//! Drawing points goes through the renderer and lives with the emulator, the others fill an ARGB8888 buffer uploaded
+//! to a streaming texture and are independent of SDL.
+
+use crate::{colors, frame::Frame, palette::Color, DISPLAY_WIDTH};
+
+#[cfg(test)]
+mod tests;
+
+/// Way of converting a frame
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Conversion {
+    /// Draw each lit pixel as a point on a target texture
+    #[default]
+    Points,
+    /// Compute each pixel into a buffer, uploaded to a streaming texture
+    Buffer,
+    /// Look up the pixels of each row strip in a table of the colors by intensity, uploaded like the buffer
+    Lut,
+}
+
+impl Conversion {
+    pub const ALL: [Conversion; 3] = [Conversion::Points, Conversion::Buffer, Conversion::Lut];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Conversion::ALL.into_iter().find(|c| c.name() == s)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Conversion::Points => "points",
+            Conversion::Buffer => "buffer",
+            Conversion::Lut => "lut",
+        }
+    }
+}
+
+/// ARGB8888 of a lit pixel: the color with the intensity as alpha
+fn lit(color: Color, intensity: u8) -> u32 {
+    color.with_alpha(intensity).argb()
+}
+
+/// Fill `pixels` (one ARGB8888 value a pixel, row by row) pixel by pixel. Lit pixels get the foreground color, or
+/// the color RAM color where `color_ram` gives one, with the intensity as alpha. Unlit pixels get the background.
+pub fn buffer(
+    frame: &Frame,
+    pixels: &mut [u32],
+    foreground: Color,
+    background: Color,
+    color_ram: impl Fn(u32, u32) -> Option<u8>,
+) {
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (i as u32 % DISPLAY_WIDTH, i as u32 / DISPLAY_WIDTH);
+        let intensity = frame.get(x, y);
+        *pixel = if intensity == 0 {
+            background.argb()
+        } else {
+            lit(color_ram(x, y).map_or(foreground, colors::rgb), intensity)
+        };
+    }
+}
+
+/// Pixels of every intensity, for the foreground and each color of the color RAM
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    /// By intensity: the foreground, then color RAM colors 0-7
+    tables: Vec<[u32; 256]>,
+}
+
+impl Lut {
+    pub fn new(foreground: Color, background: Color) -> Self {
+        let table = |color| {
+            let mut table = [background.argb(); 256];
+            for (intensity, pixel) in table.iter_mut().enumerate().skip(1) {
+                *pixel = lit(color, intensity as u8);
+            }
+            table
+        };
+        let mut tables = vec![table(foreground)];
+        tables.extend((0..8).map(|c| table(colors::rgb(c))));
+        Lut { tables }
+    }
+
+    /// Fill `pixels` like `buffer`. Without color RAM each row is a strip looked up in one table. With it the table
+    /// is picked per pixel.
+    pub fn convert(
+        &self,
+        frame: &Frame,
+        pixels: &mut [u32],
+        color_ram: Option<&dyn Fn(u32, u32) -> Option<u8>>,
+    ) {
+        let width = DISPLAY_WIDTH as usize;
+        let rows = frame.pixels().chunks(width).zip(pixels.chunks_mut(width));
+        match color_ram {
+            None => {
+                let table = &self.tables[0];
+                for (strip, row) in rows {
+                    for (pixel, intensity) in row.iter_mut().zip(strip) {
+                        *pixel = table[*intensity as usize];
+                    }
+                }
+            }
+            Some(color_ram) => {
+                for (y, (strip, row)) in rows.enumerate() {
+                    for (x, (pixel, intensity)) in row.iter_mut().zip(strip).enumerate() {
+                        let table = color_ram(x as u32, y as u32).map_or(0, |c| c as usize + 1);
+                        *pixel = self.tables[table][*intensity as usize];
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+use crate::DISPLAY_HEIGHT;
+
+const FOREGROUND: Color = Color::from_argb(0xFF11_2233);
+const BACKGROUND: Color = Color::from_argb(0x0000_0000);
+
+fn frame() -> Frame {
+    let mut frame = Frame::default();
+    frame.set(0, 0, 0xFF);
+    frame.set(5, 1, 0x80);
+    frame.set(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1, 0x01);
+    frame
+}
+
+fn pixels() -> Vec<u32> {
+    vec![0xDEAD_BEEF; (DISPLAY_WIDTH * DISPLAY_HEIGHT) as usize]
+}
+
+#[test]
+fn names() {
+    for conversion in Conversion::ALL {
+        assert_eq!(Conversion::parse(conversion.name()), Some(conversion));
+    }
+    assert_eq!(Conversion::parse("texture"), None);
+}
+
+#[test]
+fn buffer_pixels() {
+    let mut pixels = pixels();
+    buffer(&frame(), &mut pixels, FOREGROUND, BACKGROUND, |_, _| None);
+    assert_eq!(pixels[0], 0xFF11_2233);
+    assert_eq!(pixels[1], BACKGROUND.argb());
+    assert_eq!(pixels[DISPLAY_WIDTH as usize + 5], 0x8011_2233);
+    assert_eq!(pixels[pixels.len() - 1], 0x0111_2233);
+}
+
+#[test]
+fn strategies_agree() {
+    let frame = frame();
+    let lut = Lut::new(FOREGROUND, BACKGROUND);
+    let (mut a, mut b) = (pixels(), pixels());
+    buffer(&frame, &mut a, FOREGROUND, BACKGROUND, |_, _| None);
+    lut.convert(&frame, &mut b, None);
+    assert_eq!(a, b);
+
+    // Color RAM: red on the left half, none on the right
+    let color_ram = |x: u32, _| (x < DISPLAY_WIDTH / 2).then_some(1);
+    buffer(&frame, &mut a, FOREGROUND, BACKGROUND, color_ram);
+    lut.convert(&frame, &mut b, Some(&color_ram));
+    assert_eq!(a, b);
+    assert_eq!(a[0], 0xFFFF_0000);
+    assert_eq!(a[a.len() - 1], 0x0111_2233);
+}
@@ -0,0 +1,168 @@
+//! Corpus of framebuffer hashes of the attract mode, recorded with a known good build and replayed to detect behavioral drift
+
+use std::fmt;
+
+use crate::{
+    batch::run_frame,
+    cpu::Cpu,
+    hash::{crc32, framebuffer_hash},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Default corpus file name, looked up in the asset directories
+pub const CORPUS: &str = "attract.corpus";
+
+/// Framebuffer hashes at regular frames from power on, without input
+#[derive(Clone, Debug, PartialEq)]
+pub struct Corpus {
+    /// CRC-32 of the rom the corpus was recorded with
+    pub rom_crc: u32,
+    /// Frame numbers (counting from 1) and the CRC-32 of the framebuffer after them
+    pub hashes: Vec<(u64, u32)>,
+}
+
+/// First frame where the replay differs from the corpus
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub frame: u64,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Frame {}: framebuffer hash {:08x}, expected {:08x}",
+            self.frame, self.actual, self.expected
+        )
+    }
+}
+
+/// Run the rom from power on for a number of frames, calling back with the framebuffer hash every `interval` frames
+fn run(rom: &[u8], frames: u64, interval: u64, mut hash: impl FnMut(u64, u32) -> bool) {
+    let mut cpu = Cpu::new(rom.to_vec());
+    for frame in 1..=frames {
+        run_frame(&mut cpu);
+        if frame % interval == 0 && !hash(frame, framebuffer_hash(&cpu)) {
+            return;
+        }
+    }
+}
+
+impl Corpus {
+    /// Record the hashes of a number of frames, every `interval` frames
+    pub fn record(rom: &[u8], frames: u64, interval: u64) -> Self {
+        let mut hashes = vec![];
+        run(rom, frames, interval.max(1), |frame, hash| {
+            hashes.push((frame, hash));
+            true
+        });
+        Corpus {
+            rom_crc: crc32(rom),
+            hashes,
+        }
+    }
+
+    /// Replay the rom and compare the hashes, returns the first mismatch
+    pub fn replay(&self, rom: &[u8]) -> Option<Mismatch> {
+        let (last, _) = self.hashes.last()?;
+        let mut expected = self.hashes.iter().peekable();
+        let mut mismatch = None;
+        run(rom, *last, 1, |frame, actual| {
+            match expected.next_if(|(f, _)| *f == frame) {
+                Some((_, hash)) if *hash != actual => {
+                    mismatch = Some(Mismatch {
+                        frame,
+                        expected: *hash,
+                        actual,
+                    })
+                }
+                _ => {}
+            }
+            mismatch.is_none()
+        });
+        mismatch
+    }
+
+    /// Parse a corpus file: a `rom <crc>` line followed by `<frame> <hash>` lines, `#` starts a comment
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rom_crc = None;
+        let mut hashes: Vec<(u64, u32)> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = || format!("Line {}: invalid corpus entry `{line}`", i + 1);
+            let hex = |s: &str| u32::from_str_radix(s, 16).map_err(|_| error());
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["rom", crc] if rom_crc.is_none() => rom_crc = Some(hex(crc)?),
+                [frame, hash] if rom_crc.is_some() => {
+                    let frame: u64 = frame.parse().map_err(|_| error())?;
+                    if hashes.last().is_some_and(|(f, _)| *f >= frame) || frame == 0 {
+                        return Err(format!("Line {}: frames must be increasing", i + 1));
+                    }
+                    hashes.push((frame, hex(hash)?));
+                }
+                _ => return Err(error()),
+            }
+        }
+        Ok(Corpus {
+            rom_crc: rom_crc.ok_or("Missing rom line in corpus")?,
+            hashes,
+        })
+    }
+}
+
+/// Frame by frame comparison of a running machine against a corpus, to watch where it diverges
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    corpus: Corpus,
+    /// Index of the next hash to compare
+    next: usize,
+}
+
+impl Comparison {
+    pub fn new(corpus: Corpus) -> Self {
+        Comparison { corpus, next: 0 }
+    }
+
+    /// Compare after a frame (counting from 1 at power on), returns the mismatch if the hash of the frame differs
+    pub fn check(&mut self, frame: u64, cpu: &Cpu) -> Option<Mismatch> {
+        let (expected_frame, expected) = *self.corpus.hashes.get(self.next)?;
+        if frame < expected_frame {
+            return None;
+        }
+        self.next += 1;
+        let actual = framebuffer_hash(cpu);
+        (frame != expected_frame || actual != expected).then_some(Mismatch {
+            frame,
+            expected,
+            actual,
+        })
+    }
+
+    /// Hashes compared so far
+    pub fn compared(&self) -> usize {
+        self.next
+    }
+
+    /// All hashes compared
+    pub fn finished(&self) -> bool {
+        self.next == self.corpus.hashes.len()
+    }
+}
+
+impl fmt::Display for Corpus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Framebuffer hashes of the attract mode from power on")?;
+        writeln!(f, "rom {:08x}", self.rom_crc)?;
+        for (frame, hash) in &self.hashes {
+            writeln!(f, "{frame} {hash:08x}")?;
+        }
+        Ok(())
+    }
+}
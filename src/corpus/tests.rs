@@ -0,0 +1,124 @@
+use super::*;
+use crate::assets::Locator;
+
+/// Increment the first byte of the framebuffer once per interrupt
+fn program() -> Vec<u8> {
+    let mut program = vec![
+        0x31, 0x00, 0x24, // LXI SP,2400h
+        0xFB, // EI
+        0xC3, 0x04, 0x00, // JMP 0004h
+    ];
+    program.resize(0x08, 0);
+    program.extend([
+        0x21, 0x00, 0x24, // RST 1: LXI H,2400h
+        0x34, // INR M
+        0xFB, // EI
+        0xC9, // RET
+    ]);
+    program.resize(0x10, 0);
+    program.extend([0xFB, 0xC9]); // RST 2: EI, RET
+    program
+}
+
+#[test]
+fn record_and_replay() {
+    let corpus = Corpus::record(&program(), 10, 3);
+    assert_eq!(corpus.rom_crc, crc32(&program()));
+    assert_eq!(
+        corpus.hashes.iter().map(|(f, _)| *f).collect::<Vec<_>>(),
+        [3, 6, 9]
+    );
+    assert_ne!(corpus.hashes[0].1, corpus.hashes[1].1);
+    assert_eq!(corpus.replay(&program()), None);
+}
+
+#[test]
+fn replay_drift() {
+    let mut corpus = Corpus::record(&program(), 10, 3);
+    let actual = corpus.hashes[1].1;
+    corpus.hashes[1].1 ^= 1;
+    assert_eq!(
+        corpus.replay(&program()),
+        Some(Mismatch {
+            frame: 6,
+            expected: actual ^ 1,
+            actual,
+        })
+    );
+    // Another program draws something else
+    let mut other = program();
+    other[0x0C] = 0x35; // DCR M
+    assert_eq!(corpus.replay(&other).map(|m| m.frame), Some(3));
+}
+
+#[test]
+fn text_round_trip() {
+    let corpus = Corpus::record(&program(), 6, 2);
+    let text = corpus.to_string();
+    assert!(text.contains(&format!("rom {:08x}\n", corpus.rom_crc)));
+    assert_eq!(Corpus::parse(&text), Ok(corpus));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Corpus::parse("# nothing"),
+        Err("Missing rom line in corpus".into())
+    );
+    assert_eq!(
+        Corpus::parse("60 0000abcd"),
+        Err("Line 1: invalid corpus entry `60 0000abcd`".into())
+    );
+    assert_eq!(
+        Corpus::parse("rom 12345678\n60 xyz"),
+        Err("Line 2: invalid corpus entry `60 xyz`".into())
+    );
+    assert_eq!(
+        Corpus::parse("rom 12345678\n60 0\n60 0"),
+        Err("Line 3: frames must be increasing".into())
+    );
+    assert_eq!(
+        Corpus::parse("rom 1 # comment\n\n2 ff"),
+        Ok(Corpus {
+            rom_crc: 1,
+            hashes: vec![(2, 0xff)]
+        })
+    );
+}
+
+/// Replay the recorded corpus of the game, if both the rom and the corpus are available
+#[test]
+fn replay_game_corpus() {
+    let locator = Locator::default();
+    let (Some(rom), Some(corpus)) = (locator.find("invaders.rom"), locator.find(CORPUS)) else {
+        return;
+    };
+    let rom = std::fs::read(rom).unwrap();
+    let corpus = Corpus::parse(&std::fs::read_to_string(corpus).unwrap()).unwrap();
+    if corpus.rom_crc == crc32(&rom) {
+        assert_eq!(corpus.replay(&rom), None);
+    }
+}
+
+#[test]
+fn compare_frame_by_frame() {
+    let mut corpus = Corpus::record(&program(), 10, 3);
+    corpus.hashes[1].1 ^= 1;
+    let mut comparison = Comparison::new(corpus.clone());
+    let mut cpu = Cpu::new(program());
+    let mut mismatches = vec![];
+    for frame in 1..=10 {
+        run_frame(&mut cpu);
+        mismatches.extend(comparison.check(frame, &cpu));
+    }
+    assert_eq!(
+        mismatches,
+        [Mismatch {
+            frame: 6,
+            expected: corpus.hashes[1].1,
+            actual: corpus.hashes[1].1 ^ 1
+        }]
+    );
+    assert!(comparison.finished());
+    assert_eq!(comparison.compared(), 3);
+}
@@ -1,7 +1,19 @@
 //! CPU module
 
+use std::collections::VecDeque;
+
 use crate::{
-    utils::*, DISPLAY_HEIGHT, FRAMEBUFFER, MEMORY, MEMORY_SIZE, NPORTS, NREGS, RAM, ROM, STACK,
+    banks::Banks,
+    colors::ColorRam,
+    ports::{Direction, PortMonitor, INVADERS},
+    profile::{Profile, EXECUTED, READ},
+    ram_map::{Region, RAM_MAP},
+    serial::Serial,
+    stack::{StackPolicy, StackUsage, StackWarning},
+    timer::Timer,
+    utils::*,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, FRAMEBUFFER, MEMORY, MEMORY_SIZE, NPORTS, NREGS, RAM, ROM,
+    STACK,
 };
 use Condition::*;
 use Flag::*;
@@ -171,6 +183,177 @@ enum Instruction {
     Err(Data),
 }
 
+impl Instruction {
+    /// Assembler mnemonic including operands
+    fn mnemonic(&self) -> String {
+        match *self {
+            MoveRegister(r1, r2) => format!("MOV {r1:?}, {r2:?}"),
+            MoveFromMemory(r) => format!("MOV {r:?}, M"),
+            MoveToMemory(r) => format!("MOV M, {r:?}"),
+            MoveImmediate(r, data) => format!("MVI {r:?}, {data:02X}H"),
+            MoveToMemoryImmediate(data) => format!("MVI M, {data:02X}H"),
+            LoadRegisterPairImmediate(rp, data) => format!("LXI {}, {data:04X}H", rp.name()),
+            LoadAccumulatorDirect(addr) => format!("LDA {addr:04X}H"),
+            StoreAccumulatorDirect(addr) => format!("STA {addr:04X}H"),
+            LoadHLDirect(addr) => format!("LHLD {addr:04X}H"),
+            StoreHLDirect(addr) => format!("SHLD {addr:04X}H"),
+            LoadAccumulatorIndirect(rp) => format!("LDAX {}", rp.name()),
+            StoreAccumulatorIndirect(rp) => format!("STAX {}", rp.name()),
+            ExchangeHLWithDE => "XCHG".into(),
+            AddRegister(r) => format!("ADD {r:?}"),
+            AddMemory => "ADD M".into(),
+            AddImmediate(data) => format!("ADI {data:02X}H"),
+            AddRegisterWithCarry(r) => format!("ADC {r:?}"),
+            AddMemoryWithCarry => "ADC M".into(),
+            AddImmediateWithCarry(data) => format!("ACI {data:02X}H"),
+            SubtractRegister(r) => format!("SUB {r:?}"),
+            SubtractMemory => "SUB M".into(),
+            SubtractImmediate(data) => format!("SUI {data:02X}H"),
+            SubtractRegisterWithBorrow(r) => format!("SBB {r:?}"),
+            SubtractMemoryWithBorrow => "SBB M".into(),
+            SubtractImmediateWithBorrow(data) => format!("SBI {data:02X}H"),
+            IncrementRegister(r) => format!("INR {r:?}"),
+            IncrementMemory => "INR M".into(),
+            DecrementRegister(r) => format!("DCR {r:?}"),
+            DecrementMemory => "DCR M".into(),
+            IncrementRegisterPair(rp) => format!("INX {}", rp.name()),
+            DecrementRegisterPair(rp) => format!("DCX {}", rp.name()),
+            AddRegisterPairToHL(rp) => format!("DAD {}", rp.name()),
+            DecimalAdjustAccumulator => "DAA".into(),
+            AndRegister(r) => format!("ANA {r:?}"),
+            AndMemory => "ANA M".into(),
+            AndImmediate(data) => format!("ANI {data:02X}H"),
+            XorRegister(r) => format!("XRA {r:?}"),
+            XorMemory => "XRA M".into(),
+            XorImmediate(data) => format!("XRI {data:02X}H"),
+            OrRegister(r) => format!("ORA {r:?}"),
+            OrMemory => "ORA M".into(),
+            OrImmediate(data) => format!("ORI {data:02X}H"),
+            CompareRegister(r) => format!("CMP {r:?}"),
+            CompareMemory => "CMP M".into(),
+            CompareImmediate(data) => format!("CPI {data:02X}H"),
+            RotateLeft => "RLC".into(),
+            RotateRight => "RRC".into(),
+            RotateLeftThroughCarry => "RAL".into(),
+            RotateRightThroughCarry => "RAR".into(),
+            ComplementAccumulator => "CMA".into(),
+            ComplementCarry => "CMC".into(),
+            SetCarry => "STC".into(),
+            Jump(addr) => format!("JMP {addr:04X}H"),
+            ConditionalJump(c, addr) => format!("J{} {addr:04X}H", c.code()),
+            Call(addr) => format!("CALL {addr:04X}H"),
+            ConditionalCall(c, addr) => format!("C{} {addr:04X}H", c.code()),
+            Return => "RET".into(),
+            ConditionalReturn(c) => format!("R{}", c.code()),
+            Restart(n) => format!("RST {n}"),
+            JumpHLIndirect => "PCHL".into(),
+            Push(rp) => format!("PUSH {}", rp.name()),
+            PushProcessorStatusWord => "PUSH PSW".into(),
+            Pop(rp) => format!("POP {}", rp.name()),
+            PopProcessorStatusWord => "POP PSW".into(),
+            ExchangeSPWithHL => "XTHL".into(),
+            MoveHLToSP => "SPHL".into(),
+            Input(data) => format!("IN {data:02X}H"),
+            Output(data) => format!("OUT {data:02X}H"),
+            EnableInterrupts => "EI".into(),
+            DisableInterrupts => "DI".into(),
+            Halt => "HLT".into(),
+            NoOperation => "NOP".into(),
+            Err(data) => format!("??? {data:02X}H"),
+        }
+    }
+
+    /// Short description of what the instruction does
+    fn description(&self) -> &'static str {
+        match self {
+            MoveRegister(_, _) => "Move register",
+            MoveFromMemory(_) => "Move from memory",
+            MoveToMemory(_) => "Move to memory",
+            MoveImmediate(_, _) => "Move to register immediate",
+            MoveToMemoryImmediate(_) => "Move to memory immediate",
+            LoadRegisterPairImmediate(_, _) => "Load register pair immediate",
+            LoadAccumulatorDirect(_) => "Load accumulator direct",
+            StoreAccumulatorDirect(_) => "Store accumulator direct",
+            LoadHLDirect(_) => "Load H and L direct",
+            StoreHLDirect(_) => "Store H and L direct",
+            LoadAccumulatorIndirect(_) => "Load accumulator indirect",
+            StoreAccumulatorIndirect(_) => "Store accumulator indirect",
+            ExchangeHLWithDE => "Exchange H and L with D and E",
+            AddRegister(_) => "Add register",
+            AddMemory => "Add memory",
+            AddImmediate(_) => "Add immediate",
+            AddRegisterWithCarry(_) => "Add register with carry",
+            AddMemoryWithCarry => "Add memory with carry",
+            AddImmediateWithCarry(_) => "Add immediate with carry",
+            SubtractRegister(_) => "Subtract register",
+            SubtractMemory => "Subtract memory",
+            SubtractImmediate(_) => "Subtract immediate",
+            SubtractRegisterWithBorrow(_) => "Subtract register with borrow",
+            SubtractMemoryWithBorrow => "Subtract memory with borrow",
+            SubtractImmediateWithBorrow(_) => "Subtract immediate with borrow",
+            IncrementRegister(_) => "Increment register",
+            IncrementMemory => "Increment memory",
+            DecrementRegister(_) => "Decrement register",
+            DecrementMemory => "Decrement memory",
+            IncrementRegisterPair(_) => "Increment register pair",
+            DecrementRegisterPair(_) => "Decrement register pair",
+            AddRegisterPairToHL(_) => "Add register pair to HL",
+            DecimalAdjustAccumulator => "Decimal adjust accumulator",
+            AndRegister(_) => "AND register",
+            AndMemory => "AND memory",
+            AndImmediate(_) => "AND immediate",
+            XorRegister(_) => "Exclusive OR register",
+            XorMemory => "Exclusive OR memory",
+            XorImmediate(_) => "Exclusive OR immediate",
+            OrRegister(_) => "OR register",
+            OrMemory => "OR memory",
+            OrImmediate(_) => "OR immediate",
+            CompareRegister(_) => "Compare register",
+            CompareMemory => "Compare memory",
+            CompareImmediate(_) => "Compare immediate",
+            RotateLeft => "Rotate left",
+            RotateRight => "Rotate right",
+            RotateLeftThroughCarry => "Rotate left through carry",
+            RotateRightThroughCarry => "Rotate right through carry",
+            ComplementAccumulator => "Complement accumulator",
+            ComplementCarry => "Complement carry",
+            SetCarry => "Set carry",
+            Jump(_) => "Jump to address",
+            ConditionalJump(_, _) => "Conditional jump",
+            Call(_) => "Call",
+            ConditionalCall(_, _) => "Conditional call",
+            Return => "Return",
+            ConditionalReturn(_) => "Conditional return",
+            Restart(_) => "Restart",
+            JumpHLIndirect => "Jump H and L indirect, move H and L to PC",
+            Push(_) => "Push",
+            PushProcessorStatusWord => "Push processor status word",
+            Pop(_) => "Pop",
+            PopProcessorStatusWord => "Pop processor status word",
+            ExchangeSPWithHL => "Exchange stack top with H and L",
+            MoveHLToSP => "Move HL to SP",
+            Input(_) => "Input",
+            Output(_) => "Output",
+            EnableInterrupts => "Enable interrupts",
+            DisableInterrupts => "Disable interrupts",
+            Halt => "Halt",
+            NoOperation => "No operation",
+            Err(_) => "Error in decoding opcode",
+        }
+    }
+
+    /// How the instruction passes control on
+    fn flow(&self) -> Flow {
+        match *self {
+            Jump(addr) => Flow::Jump(addr),
+            ConditionalJump(_, addr) | Call(addr) | ConditionalCall(_, addr) => Flow::Branch(addr),
+            Restart(n) => Flow::Branch(n as Address * 8),
+            Return | JumpHLIndirect | Halt | Err(_) => Flow::Stop,
+            _ => Flow::Next,
+        }
+    }
+}
+
 /// Register pairs
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum RegisterPair {
@@ -206,6 +389,34 @@ enum Condition {
     Minus = 0b111,
 }
 
+impl RegisterPair {
+    /// Name as used in assembler (B, D, H or SP)
+    fn name(&self) -> &'static str {
+        match self {
+            BC => "B",
+            DE => "D",
+            HL => "H",
+            SP => "SP",
+        }
+    }
+}
+
+impl Condition {
+    /// Condition code as used in assembler mnemonics
+    fn code(&self) -> &'static str {
+        match self {
+            NotZero => "NZ",
+            Zero => "Z",
+            NoCarry => "NC",
+            Carry => "C",
+            ParityOdd => "PO",
+            ParityEven => "PE",
+            Plus => "P",
+            Minus => "M",
+        }
+    }
+}
+
 /// Flags
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum Flag {
@@ -216,6 +427,77 @@ enum Flag {
     AC = 4,
 }
 
+/// How an instruction passes control on, for following the code of a program
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Flow {
+    /// To the next instruction
+    Next,
+    /// To the address only
+    Jump(usize),
+    /// To the address and (on return or when the condition fails) the next instruction
+    Branch(usize),
+    /// Somewhere unknown (return, PCHL) or nowhere (HLT, unimplemented)
+    Stop,
+}
+
+/// A disassembled instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disassembly {
+    /// Address of the instruction
+    pub addr: usize,
+    /// Bytes of the instruction (opcode and immediate data)
+    pub bytes: Vec<u8>,
+    /// Assembler mnemonic including operands
+    pub mnemonic: String,
+    /// Short description of what the instruction does
+    pub description: &'static str,
+    /// Where control goes after the instruction
+    pub flow: Flow,
+}
+
+/// Initial content of RAM at power on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RamInit {
+    /// All zeros
+    Zeros,
+    /// All ones (0xFF)
+    Ones,
+    /// Alternating 0xAA and 0x55
+    Alternating,
+    /// Pseudo-random bytes from a seed (deterministic)
+    Random(u64),
+}
+
+/// Maximum depth of the reconstructed call stack, older frames are dropped
+const CALL_STACK_DEPTH: usize = 32;
+/// Stack warnings kept until taken
+pub const STACK_WARNINGS: usize = 16;
+/// Instructions kept in the history, when recorded
+pub const HISTORY: usize = 256;
+
+/// A frame of the reconstructed call stack
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StackFrame {
+    /// Address called (subroutine or restart vector)
+    pub target: usize,
+    /// Address that will be returned to
+    pub return_address: usize,
+    /// Stack pointer where the return address is stored
+    pub sp: usize,
+    /// Frame was pushed by an interrupt
+    pub interrupt: bool,
+}
+
+/// Start of a savestate, identifying its format
+pub const STATE_MAGIC: &[u8] = b"INV8080S";
+/// Size of a savestate: magic, memory, PC, registers, SP, output ports, shift register, shift offset and interrupt state
+const STATE_SIZE: usize = STATE_MAGIC.len() + MEMORY_SIZE + 2 + NREGS + 2 + NPORTS + 2 + 1 + 1;
+
+/// Names of registers, register pairs and flags accepted by `Cpu::register`
+pub const REGISTERS: [&str; 17] = [
+    "A", "B", "C", "D", "E", "H", "L", "BC", "DE", "HL", "SP", "PC", "S", "Z", "AC", "P", "CY",
+];
+
 /// The CPU-model including memory etc.
 pub struct Cpu {
     /// ROM/RAM all writable for now
@@ -236,42 +518,272 @@ pub struct Cpu {
     offset: Data,
     /// CPU interruptable
     interruptable: bool,
+    /// Call stack reconstructed from calls, restarts and returns
+    call_stack: Vec<StackFrame>,
     /// Display should be updated (this is set to true on memory writes to the framebuffer region of memory, then emulator clears it after drawing is finished)
     /// Probably next to useless optimization for a game where everything is moving on the screen :)
     display_update: bool,
+    /// Initial content of RAM at power on
+    ram_init: RamInit,
+    /// CPU halted (HLT), waiting for an interrupt
+    halted: bool,
+    /// Instructions executed since the machine was created
+    instructions: u64,
+    /// Rom bytes executed and read as data, when profiling
+    profile: Option<Profile>,
+    /// IN and OUT activity, when monitoring the ports
+    port_monitor: Option<PortMonitor>,
+    /// Stack usage, when analyzing it
+    stack_usage: Option<StackUsage>,
+    /// Banked memory, on boards with bank switching
+    banks: Option<Banks>,
+    /// Address of the instruction executing
+    instruction_pc: Address,
+    /// What to do when the stack leaves its region
+    stack_policy: StackPolicy,
+    /// Stack warnings not taken yet, at most `STACK_WARNINGS`
+    stack_warnings: Vec<StackWarning>,
+    /// Color RAM, on color boards
+    colors: Option<ColorRam>,
+    /// Addresses of the last `HISTORY` instructions executed, when recorded
+    history: Option<VecDeque<Address>>,
+    /// Timer peripheral, for homebrew programs
+    timer: Option<Timer>,
+    /// Serial console, for homebrew programs
+    serial: Option<Serial>,
+    /// Variables of the game that pushes are warned about overwriting
+    ram_map: &'static [Region],
 }
 
 impl Cpu {
+    /// Machine with the program loaded from address 0, bytes beyond the memory are left out (see
+    /// `Machine::check_rom`)
     pub fn new(program: Vec<u8>) -> Self {
         let mut memory: [u8; MEMORY_SIZE] = [0; MEMORY_SIZE];
-        memory[..program.len()].copy_from_slice(&program);
+        let len = program.len().min(MEMORY_SIZE);
+        memory[..len].copy_from_slice(&program[..len]);
 
         Cpu {
             memory,
             pc: 0,
             registers: [0; NREGS],
             sp: 0,
-            bus_in: [0b0000_1110, 0b0000_1000, 0, 0, 0, 0, 0, 0],
+            bus_in: INVADERS.defaults(),
             bus_out: [0; NPORTS],
             shift: 0,
             offset: 0,
             interruptable: false,
             display_update: true,
+            call_stack: Vec::new(),
+            ram_init: RamInit::Zeros,
+            halted: false,
+            instructions: 0,
+            profile: None,
+            port_monitor: None,
+            stack_usage: None,
+            banks: None,
+            instruction_pc: 0,
+            stack_policy: StackPolicy::default(),
+            stack_warnings: vec![],
+            colors: None,
+            history: None,
+            timer: None,
+            serial: None,
+            ram_map: &RAM_MAP,
         }
     }
 
     /// Fetch, decode and execute one instruction
     pub fn step(&mut self) -> u32 {
+        if self.halted {
+            if let Some(timer) = &mut self.timer {
+                timer.run(4);
+            }
+            return 4;
+        }
+        let pc = self.pc;
+        self.instruction_pc = pc;
         let instr = self.fetch_and_decode();
-        self.execute(instr)
+        self.instructions += 1;
+        if self.profile.is_some() {
+            self.profile_instruction(pc, &instr);
+        }
+        if let Some(history) = &mut self.history {
+            if history.len() == HISTORY {
+                history.pop_front();
+            }
+            history.push_back(pc);
+        }
+        let cycles = self.execute(instr);
+        if let Some(timer) = &mut self.timer {
+            timer.run(cycles);
+        }
+        if let Some(mut usage) = self.stack_usage.take() {
+            usage.update(self, pc);
+            self.stack_usage = Some(usage);
+        }
+        cycles
+    }
+
+    /// What to do when the stack leaves its region, warn by default
+    pub fn set_stack_policy(&mut self, policy: StackPolicy) {
+        self.stack_policy = policy;
+    }
+
+    /// Stack warnings since last taken. Only the first `STACK_WARNINGS` are kept when not taken.
+    pub fn take_stack_warnings(&mut self) -> Vec<StackWarning> {
+        std::mem::take(&mut self.stack_warnings)
+    }
+
+    fn stack_warning(&mut self, warning: StackWarning) {
+        match self.stack_policy {
+            StackPolicy::Panic => panic!("{warning}"),
+            StackPolicy::Warn if self.stack_warnings.len() < STACK_WARNINGS => {
+                self.stack_warnings.push(warning)
+            }
+            StackPolicy::Warn => {}
+        }
+    }
+
+    /// Start analyzing the stack usage
+    pub fn enable_stack_usage(&mut self) {
+        self.stack_usage.get_or_insert_with(StackUsage::default);
+    }
+
+    /// Stack usage so far, if analyzed
+    pub fn stack_usage(&self) -> Option<&StackUsage> {
+        self.stack_usage.as_ref()
+    }
+
+    /// Start recording the addresses of the last `HISTORY` instructions executed
+    pub fn enable_history(&mut self) {
+        self.history
+            .get_or_insert_with(|| VecDeque::with_capacity(HISTORY));
+    }
+
+    /// Addresses of the last instructions executed, oldest first, if recorded
+    pub fn history(&self) -> Option<&VecDeque<Address>> {
+        self.history.as_ref()
+    }
+
+    /// Start profiling which rom bytes are executed and which are read as data
+    pub fn enable_profile(&mut self) {
+        self.profile.get_or_insert_with(Profile::default);
+    }
+
+    /// Profile of the rom so far, if enabled
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Start monitoring the IN and OUT activity of the ports
+    pub fn enable_port_monitor(&mut self) {
+        self.port_monitor.get_or_insert_with(PortMonitor::default);
+    }
+
+    /// Port activity so far, if monitored
+    pub fn port_monitor(&self) -> Option<&PortMonitor> {
+        self.port_monitor.as_ref()
+    }
+
+    /// Port activity so far, if monitored, e.g. for starting a new second
+    pub fn port_monitor_mut(&mut self) -> Option<&mut PortMonitor> {
+        self.port_monitor.as_mut()
     }
 
-    /// Return true if pixel at logical display coordinate (x, y) is on.
-    pub fn display(&self, x: u32, y: u32) -> bool {
-        let framebuffer = &self.memory[0x2400..0x4000];
-        let byte =
-            framebuffer[(x * DISPLAY_HEIGHT / 8 + (DISPLAY_HEIGHT / 8 - y / 8) - 1) as usize];
-        get_bit(byte, 7 - (y % 8) as u8)
+    /// Mark the instruction executed and the memory it is about to read as data
+    fn profile_instruction(&mut self, pc: Address, instr: &Instruction) {
+        let read = match *instr {
+            MoveFromMemory(_)
+            | AddMemory
+            | AddMemoryWithCarry
+            | SubtractMemory
+            | SubtractMemoryWithBorrow
+            | AndMemory
+            | XorMemory
+            | OrMemory
+            | CompareMemory
+            | IncrementMemory
+            | DecrementMemory => Some((self.get_register_pair(HL) as Address, 1)),
+            LoadAccumulatorIndirect(rp) => Some((self.get_register_pair(rp) as Address, 1)),
+            LoadAccumulatorDirect(addr) => Some((addr, 1)),
+            LoadHLDirect(addr) => Some((addr, 2)),
+            _ => None,
+        };
+        if let Some(profile) = &mut self.profile {
+            profile.mark(pc, EXECUTED);
+            if let Some((addr, len)) = read {
+                for a in addr..addr + len {
+                    profile.mark(a, READ);
+                }
+            }
+        }
+    }
+
+    /// Instructions executed since the machine was created, including restarts by interrupts
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Step over: like step, but a call (or restart) is run until it returns.
+    /// Returns the number of cycles taken or None if `limit` cycles were reached first.
+    pub fn step_over(&mut self, limit: u32) -> Option<u32> {
+        let depth = self.call_stack.len();
+        let mut cycles = self.step();
+        while self.call_stack.len() > depth {
+            if cycles >= limit {
+                return None;
+            }
+            cycles += self.step();
+        }
+        Some(cycles)
+    }
+
+    /// Step out: run until the current subroutine returns.
+    /// Returns the number of cycles taken or None if `limit` cycles were reached first.
+    pub fn step_out(&mut self, limit: u32) -> Option<u32> {
+        let depth = self.call_stack.len();
+        self.run_while(limit, |cpu| cpu.call_stack.len() >= depth && depth > 0)
+    }
+
+    /// Run until program counter reaches `addr`.
+    /// Returns the number of cycles taken or None if `limit` cycles were reached first.
+    pub fn run_to(&mut self, addr: usize, limit: u32) -> Option<u32> {
+        self.run_while(limit, |cpu| cpu.get_pc() != addr)
+    }
+
+    /// Step while the condition holds, at most `limit` cycles
+    fn run_while(&mut self, limit: u32, condition: impl Fn(&Cpu) -> bool) -> Option<u32> {
+        let mut cycles = 0;
+        while condition(self) {
+            if cycles >= limit {
+                return None;
+            }
+            cycles += self.step();
+        }
+        Some(cycles)
+    }
+
+    /// Pixel at logical display coordinate (x, y) with (0, 0) at the top left of the (rotated) display, None outside the display.
+    /// Video memory has 32 bytes per column starting from the left, each column goes from the bottom up with bit 0 lowest in each byte.
+    pub fn display(&self, x: u32, y: u32) -> Option<bool> {
+        if x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT {
+            return None;
+        }
+        let from_bottom = DISPLAY_HEIGHT - 1 - y;
+        let offset = x * DISPLAY_HEIGHT / 8 + from_bottom / 8;
+        let byte = self.memory[*FRAMEBUFFER.start() + offset as usize];
+        Some(get_bit(byte, (from_bottom % 8) as u8))
+    }
+
+    /// Color at a display pixel as set in the color RAM, on color boards
+    pub fn color(&self, x: u32, y: u32) -> Option<u8> {
+        let colors = self.colors.as_ref()?;
+        if x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT {
+            return None;
+        }
+        let offset = x * DISPLAY_HEIGHT / 8 + (DISPLAY_HEIGHT - 1 - y) / 8;
+        Some(colors.color(*FRAMEBUFFER.start() - *RAM.start() + offset as usize))
     }
 
     /// Get display update
@@ -285,10 +797,16 @@ impl Cpu {
     }
 
     /// Get CPU input bus (read external input)
-    fn get_bus_in(&self, port: usize) -> u8 {
+    pub(crate) fn get_bus_in(&self, port: usize) -> u8 {
         if port == 3 {
             return ((self.shift << self.offset) >> 8) as u8;
         }
+        if let Some(timer) = self.timer.as_ref().filter(|t| t.port == port) {
+            return timer.read();
+        }
+        if let Some(serial) = self.serial.as_ref().filter(|s| s.status == port) {
+            return serial.status();
+        }
         self.bus_in[port]
     }
 
@@ -315,6 +833,16 @@ impl Cpu {
             self.shift = ((data as Data16) << 8) | (self.shift >> 8);
         }
 
+        if let Some(banks) = self.banks.as_mut().filter(|b| b.port == port) {
+            banks.select(&mut self.memory, data as usize);
+        }
+        if let Some(timer) = self.timer.as_mut().filter(|t| t.port == port) {
+            timer.write(data);
+        }
+        if let Some(serial) = self.serial.as_mut().filter(|s| s.data == port) {
+            serial.write(data);
+        }
+
         self.bus_out[port] = data
     }
 
@@ -700,13 +1228,11 @@ impl Cpu {
                 7
             }
             Call(addr) => {
-                self.push(self.get_pc());
-                self.set_pc(addr);
+                self.call(addr);
                 17
             }
             Return => {
-                let addr = self.pop();
-                self.set_pc(addr);
+                self.ret();
                 10
             }
             LoadAccumulatorIndirect(rp) => {
@@ -785,8 +1311,7 @@ impl Cpu {
             }
             ConditionalCall(c, addr) => {
                 if self.is_condition(c) {
-                    self.push(self.get_pc());
-                    self.set_pc(addr);
+                    self.call(addr);
                     17
                 } else {
                     11
@@ -794,8 +1319,7 @@ impl Cpu {
             }
             ConditionalReturn(c) => {
                 if self.is_condition(c) {
-                    let addr = self.pop();
-                    self.set_pc(addr);
+                    self.ret();
                     11
                 } else {
                     5
@@ -810,23 +1334,16 @@ impl Cpu {
                 5
             }
             CompareImmediate(data) => {
-                let before = self.get_register(A);
-                let (after, carry) = before.overflowing_sub(data);
-                self.set_flags_for_arithmetic(before, after, carry);
+                self.subtract_with_borrow(data, false);
                 7
             }
             CompareRegister(r) => {
-                let before = self.get_register(A);
-                let data = self.get_register(r);
-                let (after, carry) = before.overflowing_sub(data);
-                self.set_flags_for_arithmetic(before, after, carry);
+                self.subtract_with_borrow(self.get_register(r), false);
                 4
             }
             CompareMemory => {
-                let before = self.get_register(A);
                 let data = self.get_memory(self.get_register_pair(HL) as Address);
-                let (after, carry) = before.overflowing_sub(data);
-                self.set_flags_for_arithmetic(before, after, carry);
+                self.subtract_with_borrow(data, false);
                 7
             }
             Push(rp) => {
@@ -879,11 +1396,20 @@ impl Cpu {
             }
             Output(port) => {
                 self.set_bus_out(port as usize, self.get_register(A));
+                if let Some(monitor) = &mut self.port_monitor {
+                    monitor.access(Direction::Out, port as usize, self.registers[A as usize]);
+                }
                 10
             }
             Input(port) => {
-                let bus = self.get_bus_in(port as usize);
+                let bus = match self.serial.as_mut().filter(|s| s.data == port as usize) {
+                    Some(serial) => serial.read(),
+                    None => self.get_bus_in(port as usize),
+                };
                 self.set_register(A, bus);
+                if let Some(monitor) = &mut self.port_monitor {
+                    monitor.access(Direction::In, port as usize, bus);
+                }
                 10
             }
             MoveFromMemory(r) => {
@@ -969,34 +1495,52 @@ impl Cpu {
                 4
             }
             AddRegisterWithCarry(r) => {
-                self.add(self.get_register(r) + if self.get_flag(CY) { 1 } else { 0 });
+                self.add_with_carry(self.get_register(r), self.get_flag(CY));
                 4
             }
+            AddMemoryWithCarry => {
+                let data = self.get_memory(self.get_register_pair(HL) as Address);
+                self.add_with_carry(data, self.get_flag(CY));
+                7
+            }
+            AddImmediateWithCarry(data) => {
+                self.add_with_carry(data, self.get_flag(CY));
+                7
+            }
             AddMemory => {
                 self.add(self.get_memory(self.get_register_pair(HL) as Address));
                 7
             }
             SubtractRegister(r) => {
-                let before = self.get_register(A);
-                let data = self.get_register(r);
-                let (after, carry) = before.overflowing_sub(data);
-                self.set_register(A, after);
-                self.set_flags_for_arithmetic(before, self.get_register(A), carry);
+                let result = self.subtract_with_borrow(self.get_register(r), false);
+                self.set_register(A, result);
                 4
             }
+            SubtractMemory => {
+                let data = self.get_memory(self.get_register_pair(HL) as Address);
+                let result = self.subtract_with_borrow(data, false);
+                self.set_register(A, result);
+                7
+            }
             SubtractImmediate(data) => {
-                let before = self.get_register(A);
-                let (after, carry) = before.overflowing_sub(data);
-                self.set_register(A, after);
-                self.set_flags_for_arithmetic(before, self.get_register(A), carry);
+                let result = self.subtract_with_borrow(data, false);
+                self.set_register(A, result);
+                7
+            }
+            SubtractRegisterWithBorrow(r) => {
+                let result = self.subtract_with_borrow(self.get_register(r), self.get_flag(CY));
+                self.set_register(A, result);
+                4
+            }
+            SubtractMemoryWithBorrow => {
+                let data = self.get_memory(self.get_register_pair(HL) as Address);
+                let result = self.subtract_with_borrow(data, self.get_flag(CY));
+                self.set_register(A, result);
                 7
             }
             SubtractImmediateWithBorrow(data) => {
-                let before = self.get_register(A);
-                let (after, carry) =
-                    before.overflowing_sub(data + if self.get_flag(CY) { 1 } else { 0 });
-                self.set_register(A, after);
-                self.set_flags_for_arithmetic(before, self.get_register(A), carry);
+                let result = self.subtract_with_borrow(data, self.get_flag(CY));
+                self.set_register(A, result);
                 7
             }
             LoadAccumulatorDirect(addr) => {
@@ -1034,10 +1578,13 @@ impl Cpu {
                 4
             }
             Restart(data) => {
-                self.push(self.get_pc());
-                self.set_pc((8 * data as i32) as Address);
+                self.call((8 * data as i32) as Address);
                 11
             }
+            Halt => {
+                self.halted = true;
+                7
+            }
             SetCarry => {
                 self.set_flag(CY, true);
                 4
@@ -1054,30 +1601,356 @@ impl Cpu {
             }
             DecimalAdjustAccumulator => {
                 let acc = self.get_register(A);
-                let mut new_acc = acc;
+                let carry = self.get_flag(CY) || acc > 0x99;
+                let mut correction = 0;
 
                 if acc & 0xF > 9 || self.get_flag(AC) {
-                    new_acc = new_acc.wrapping_add(0x6);
-                    self.set_flag(AC, true);
+                    correction |= 0x06;
                 }
-
-                if acc > 0x99 || self.get_flag(CY) {
-                    new_acc = new_acc.wrapping_add(0x60);
-                    self.set_flag(CY, true);
+                if carry {
+                    correction |= 0x60;
                 }
 
-                self.set_register(A, new_acc);
+                // Adding the correction sets AC, Z, S and P. CY is set by the upper correction and otherwise kept.
+                self.add(correction);
+                self.set_flag(CY, carry);
                 4
             }
-            _ => panic!("Unimplemented {:04X?} now at {:04X?}", instr, self.pc),
+            _ => panic!(
+                "Unimplemented {:04X?} now at {:04X?}, call stack:\n{}",
+                instr,
+                self.pc,
+                self.format_call_stack()
+            ),
         }
     }
 
+    /// Reconstructed call stack, innermost call last. This is a heuristic as the program may manipulate the stack directly.
+    pub fn call_stack(&self) -> &[StackFrame] {
+        &self.call_stack
+    }
+
+    /// Cpu is running an interrupt handler (according to the call stack)
+    pub fn in_interrupt(&self) -> bool {
+        self.call_stack.iter().any(|frame| frame.interrupt)
+    }
+
+    /// Format the call stack for crash dumps
+    pub fn format_call_stack(&self) -> String {
+        let mut out = String::new();
+        for frame in self.call_stack.iter().rev() {
+            out += &format!(
+                "  {:04X} (return to {:04X}, SP {:04X})\n",
+                frame.target, frame.return_address, frame.sp
+            );
+        }
+        out
+    }
+
+    /// Format registers, call stack and the next instructions for a debugger
+    pub fn format_state(&mut self, instructions: usize) -> String {
+        let mut out = format!("{}\n", self.format_registers());
+        if !self.call_stack.is_empty() {
+            out += "Call stack:\n";
+            out += &self.format_call_stack();
+        }
+        let mut addr = self.pc;
+        for _ in 0..instructions {
+            if !MEMORY.contains(&addr) {
+                break;
+            }
+            let instr = self.disassemble(addr);
+            let marker = if addr == self.pc { '>' } else { ' ' };
+            out += &format!(
+                "{marker} {:04X}  {:<14} {}\n",
+                addr, instr.mnemonic, instr.description
+            );
+            addr += instr.bytes.len();
+        }
+        out
+    }
+
+    /// Disassemble the instruction at any address, operands past the end of memory wrap around to the start
+    pub fn disassemble(&self, addr: usize) -> Disassembly {
+        let read = |i: usize| self.memory[(addr + i) % MEMORY_SIZE];
+        // Decoded by a scratch machine holding only the bytes of the instruction, this one is left as it is
+        let mut scratch = Cpu::new((0..3).map(read).collect());
+        let instr = scratch.fetch_and_decode();
+        let bytes = (0..scratch.pc).map(read).collect();
+
+        Disassembly {
+            addr,
+            bytes,
+            mnemonic: instr.mnemonic(),
+            description: instr.description(),
+            flow: instr.flow(),
+        }
+    }
+
+    /// Program counter, address of the next instruction
+    pub fn program_counter(&self) -> usize {
+        self.get_pc()
+    }
+
+    /// Read-only view of the whole memory
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Write memory anywhere, ROM included, e.g. from an editor
+    pub fn poke(&mut self, addr: usize, data: u8) {
+        self.memory[addr] = data;
+        if FRAMEBUFFER.contains(&addr) {
+            self.display_update = true;
+        }
+    }
+
+    /// Jump to address, e.g. the entry point of a program not starting at 0
+    pub fn jump(&mut self, addr: usize) {
+        self.set_pc(addr);
+    }
+
+    /// Format registers and flags for dumps
+    pub fn format_registers(&self) -> String {
+        let flags = [(S, "S"), (Z, "Z"), (AC, "AC"), (P, "P"), (CY, "CY")]
+            .iter()
+            .map(|(f, name)| format!("{}={}", name, self.get_flag(*f) as u8))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "A={:02X} B={:02X} C={:02X} D={:02X} E={:02X} H={:02X} L={:02X} SP={:04X} PC={:04X} {}",
+            self.get_register(A),
+            self.get_register(B),
+            self.get_register(C),
+            self.get_register(D),
+            self.get_register(E),
+            self.get_register(H),
+            self.get_register(L),
+            self.get_sp(),
+            self.get_pc(),
+            flags
+        )
+    }
+
+    /// Value of a register, register pair or flag by name as in `format_registers` (e.g. A, HL, SP, CY)
+    pub fn register(&self, name: &str) -> Option<u16> {
+        let value = match name {
+            "A" => self.get_register(A) as u16,
+            "B" => self.get_register(B) as u16,
+            "C" => self.get_register(C) as u16,
+            "D" => self.get_register(D) as u16,
+            "E" => self.get_register(E) as u16,
+            "H" => self.get_register(H) as u16,
+            "L" => self.get_register(L) as u16,
+            "BC" => self.get_register_pair(BC),
+            "DE" => self.get_register_pair(DE),
+            "HL" => self.get_register_pair(HL),
+            "SP" => self.get_sp() as u16,
+            "PC" => self.get_pc() as u16,
+            "S" => self.get_flag(S) as u16,
+            "Z" => self.get_flag(Z) as u16,
+            "AC" => self.get_flag(AC) as u16,
+            "P" => self.get_flag(P) as u16,
+            "CY" => self.get_flag(CY) as u16,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Replace the program in ROM, the rest of the machine state is kept. Bytes beyond the memory are left out.
+    pub fn load_rom(&mut self, program: &[u8]) {
+        self.memory[ROM].fill(0);
+        let len = program.len().min(MEMORY_SIZE);
+        self.memory[..len].copy_from_slice(&program[..len]);
+    }
+
+    /// CPU is halted, waiting for an interrupt
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Soft reset (RESET line): program counter to 0 and interrupts disabled, memory and registers are kept
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.interruptable = false;
+        self.halted = false;
+        self.call_stack.clear();
+    }
+
+    /// Set initial content of RAM and fill RAM accordingly
+    pub fn set_ram_init(&mut self, ram_init: RamInit) {
+        self.ram_init = ram_init;
+        self.init_ram();
+    }
+
+    /// Fill RAM according to the RAM init pattern
+    fn init_ram(&mut self) {
+        let ram = &mut self.memory[RAM];
+        match self.ram_init {
+            RamInit::Zeros => ram.fill(0),
+            RamInit::Ones => ram.fill(0xFF),
+            RamInit::Alternating => {
+                for (i, b) in ram.iter_mut().enumerate() {
+                    *b = if i % 2 == 0 { 0xAA } else { 0x55 };
+                }
+            }
+            RamInit::Random(seed) => {
+                let mut state = seed;
+                for b in ram.iter_mut() {
+                    *b = splitmix64(&mut state) as u8;
+                }
+            }
+        }
+    }
+
+    /// Power cycle: like reset, but RAM (according to the RAM init pattern), registers and I/O state are cleared as well
+    pub fn power_cycle(&mut self) {
+        self.reset();
+        self.init_ram();
+        self.registers = [0; NREGS];
+        self.sp = 0;
+        self.bus_out = [0; NPORTS];
+        self.shift = 0;
+        self.offset = 0;
+        if let Some(banks) = self.banks.as_mut() {
+            banks.select(&mut self.memory, 0);
+        }
+        if let Some(colors) = self.colors.as_mut() {
+            colors.clear();
+        }
+        if let Some(timer) = self.timer.as_mut() {
+            timer.clear();
+        }
+        if let Some(serial) = self.serial.as_mut() {
+            serial.clear();
+        }
+        self.display_update = true;
+    }
+
+    /// Switch banks of memory over a window, filled from data laid out bank after bank
+    pub fn set_banks(&mut self, mut banks: Banks, data: &[u8]) {
+        banks.load(&mut self.memory, data);
+        self.banks = Some(banks);
+    }
+
+    /// Banked memory, if any
+    pub fn banks(&self) -> Option<&Banks> {
+        self.banks.as_ref()
+    }
+
+    /// Add color RAM over a window of the address space
+    pub fn set_colors(&mut self, colors: ColorRam) {
+        self.colors = Some(colors);
+        self.display_update = true;
+    }
+
+    /// Color RAM, if any
+    pub fn colors(&self) -> Option<&ColorRam> {
+        self.colors.as_ref()
+    }
+
+    /// Add a timer on a port
+    pub fn set_timer(&mut self, timer: Timer) {
+        self.timer = Some(timer);
+    }
+
+    /// Timer, if any
+    pub fn timer(&self) -> Option<&Timer> {
+        self.timer.as_ref()
+    }
+
+    /// Variables of the game that pushes are warned about overwriting, the ones of Space Invaders unless set
+    pub fn set_ram_map(&mut self, ram_map: &'static [Region]) {
+        self.ram_map = ram_map;
+    }
+
+    /// Add a serial console on a data and a status port
+    pub fn set_serial(&mut self, serial: Serial) {
+        self.serial = Some(serial);
+    }
+
+    /// Serial console, if any. The bytes in flight are not part of savestates, they belong to the terminal.
+    pub fn serial_mut(&mut self) -> Option<&mut Serial> {
+        self.serial.as_mut()
+    }
+
+    /// Savestate of the machine: memory, registers, output ports, shift register, interrupt state, the banks, the color RAM
+    /// and the timer if any.
+    /// The input bus is left out, inputs belong to the cabinet.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = STATE_MAGIC.to_vec();
+        state.extend_from_slice(&self.memory);
+        state.extend_from_slice(&(self.pc as u16).to_le_bytes());
+        state.extend_from_slice(&self.registers);
+        state.extend_from_slice(&(self.sp as u16).to_le_bytes());
+        state.extend_from_slice(&self.bus_out);
+        state.extend_from_slice(&self.shift.to_le_bytes());
+        state.push(self.offset);
+        state.push(self.interruptable as u8 | (self.halted as u8) << 1);
+        if let Some(banks) = &self.banks {
+            state.push(banks.selected() as u8);
+            state.extend_from_slice(&banks.contents(&self.memory));
+        }
+        if let Some(colors) = &self.colors {
+            state.extend_from_slice(colors.contents());
+        }
+        if let Some(timer) = &self.timer {
+            state.extend_from_slice(&timer.contents());
+        }
+        state
+    }
+
+    /// Load a savestate made by `save_state`. The call stack starts over.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        let banked = self.banks.as_ref().map_or(0, |b| 1 + b.count() * b.size());
+        let colored = self.colors.as_ref().map_or(0, |c| c.contents().len());
+        let timed = self.timer.as_ref().map_or(0, |t| t.contents().len());
+        if state.len() != STATE_SIZE + banked + colored + timed || !state.starts_with(STATE_MAGIC) {
+            return Result::Err("Not a savestate".to_string());
+        }
+        if let Some(banks) = &mut self.banks {
+            let (selected, contents) = (state[STATE_SIZE], &state[STATE_SIZE + 1..]);
+            banks.restore(&mut self.memory, &contents[..banked - 1], selected as usize)?;
+        }
+        if let Some(colors) = &mut self.colors {
+            colors.restore(&state[STATE_SIZE + banked..STATE_SIZE + banked + colored])?;
+        }
+        if let Some(timer) = &mut self.timer {
+            timer.restore(&state[STATE_SIZE + banked + colored..])?;
+        }
+        let (memory, rest) = state[STATE_MAGIC.len()..].split_at(MEMORY_SIZE);
+        let word = |i: usize| u16::from_le_bytes([rest[i], rest[i + 1]]);
+        self.memory.copy_from_slice(memory);
+        self.pc = word(0) as Address;
+        self.registers.copy_from_slice(&rest[2..2 + NREGS]);
+        let rest = &rest[2 + NREGS..];
+        self.sp = u16::from_le_bytes([rest[0], rest[1]]) as Address;
+        self.bus_out.copy_from_slice(&rest[2..2 + NPORTS]);
+        let rest = &rest[2 + NPORTS..];
+        self.shift = u16::from_le_bytes([rest[0], rest[1]]);
+        self.offset = rest[2];
+        self.interruptable = rest[3] & 1 != 0;
+        self.halted = rest[3] & 2 != 0;
+        self.call_stack.clear();
+        self.display_update = true;
+        Ok(())
+    }
+
     /// Interrupt
     pub fn interrupt(&mut self, data: Data) -> u32 {
         if self.interruptable {
             self.interruptable = false; // TODO Should this be done?
-            self.execute(Restart(data))
+            self.halted = false;
+            self.instructions += 1;
+            self.instruction_pc = self.pc;
+            let cycles = self.execute(Restart(data));
+            if let Some(frame) = self.call_stack.last_mut() {
+                frame.interrupt = true;
+            }
+            if let Some(timer) = &mut self.timer {
+                timer.run(cycles);
+            }
+            cycles
         } else {
             0
         }
@@ -1110,18 +1983,21 @@ impl Cpu {
         self.sp
     }
 
-    /// Set stack pointer
+    /// Set stack pointer, warning when it leaves the stack (not again while it stays outside)
     fn set_sp(&mut self, sp: usize) {
-        debug_assert!(
-            STACK.contains(&sp),
-            "Stack pointer {:04X} outside STACK memory!",
-            sp
-        );
+        let was_outside = self.sp != 0 && !STACK.contains(&self.sp);
+        if !STACK.contains(&sp) && (!was_outside || self.stack_policy == StackPolicy::Panic) {
+            let pc = self.instruction_pc;
+            self.stack_warning(StackWarning::Outside { pc, sp });
+        }
         self.sp = sp;
     }
 
     /// Get memory
     fn get_memory(&self, addr: Address) -> Data {
+        if let Some(colors) = self.colors.as_ref().filter(|c| c.window.contains(&addr)) {
+            return colors.read(addr);
+        }
         debug_assert!(
             MEMORY.contains(&addr),
             "Reading outside memory at {:02X}",
@@ -1132,6 +2008,11 @@ impl Cpu {
 
     /// Set memory
     fn set_memory(&mut self, addr: Address, data: Data) {
+        if let Some(colors) = self.colors.as_mut().filter(|c| c.window.contains(&addr)) {
+            colors.write(addr, data);
+            self.display_update = true;
+            return;
+        }
         debug_assert!(RAM.contains(&addr), "Writing outside ram at {:02X}", addr);
         self.memory[addr] = data;
 
@@ -1201,17 +2082,43 @@ impl Cpu {
         );
     }
 
+    /// Set the zero, sign and parity flags from a result
+    fn set_flags_zsp(&mut self, result: Data) {
+        self.set_flag(Z, result == 0);
+        self.set_flag(S, result & 0x80 == 0x80);
+        self.set_flag(P, result.count_ones() % 2 == 0);
+    }
+
     /// Add and set flags
     fn add(&mut self, addend: Data) {
+        self.add_with_carry(addend, false);
+    }
+
+    /// Add with carry in and set flags. Computed in 16 bits so that e.g. 0xFF plus carry wraps to 0 with carry out.
+    fn add_with_carry(&mut self, addend: Data, carry: bool) {
         let acc = self.get_register(A);
+        let sum = acc as u16 + addend as u16 + carry as u16;
+        let result = sum as Data;
 
-        self.set_flag(AC, (acc & 0xF) + (addend & 0xF) > 0xF);
-        let (result, carry) = acc.overflowing_add(addend);
+        self.set_flag(AC, (acc & 0xF) + (addend & 0xF) + carry as u8 > 0xF);
+        self.set_flag(CY, sum > 0xFF);
+        self.set_flags_zsp(result);
         self.set_register(A, result);
-        self.set_flag(CY, carry);
-        self.set_flag(Z, result == 0);
-        self.set_flag(S, result & 0x80 == 0x80);
-        self.set_flag(P, result.count_ones() % 2 == 0);
+    }
+
+    /// Subtract with borrow in from the accumulator and set flags, returning the result without storing it.
+    /// The 8080 adds the two's complement, so AC is the carry out of bit 3 of A + !data + !borrow, while CY is the borrow.
+    fn subtract_with_borrow(&mut self, subtrahend: Data, borrow: bool) -> Data {
+        let acc = self.get_register(A);
+        let result = acc.wrapping_sub(subtrahend).wrapping_sub(borrow as Data);
+
+        self.set_flag(
+            AC,
+            (acc & 0xF) + (!subtrahend & 0xF) + (!borrow) as u8 > 0xF,
+        );
+        self.set_flag(CY, (acc as u16) < subtrahend as u16 + borrow as u16);
+        self.set_flags_zsp(result);
+        result
     }
 
     /// Set register pair
@@ -1253,6 +2160,29 @@ impl Cpu {
         }
     }
 
+    /// Call subroutine and track it in the call stack
+    fn call(&mut self, addr: Address) {
+        self.push(self.get_pc());
+        if self.call_stack.len() == CALL_STACK_DEPTH {
+            self.call_stack.remove(0);
+        }
+        self.call_stack.push(StackFrame {
+            target: addr,
+            return_address: self.get_pc(),
+            sp: self.get_sp(),
+            interrupt: false,
+        });
+        self.set_pc(addr);
+    }
+
+    /// Return from subroutine, dropping any call stack frames that are no longer on the stack
+    fn ret(&mut self) {
+        let addr = self.pop();
+        let sp = self.get_sp();
+        self.call_stack.retain(|frame| frame.sp >= sp);
+        self.set_pc(addr);
+    }
+
     /// Push
     fn push(&mut self, data: Address) {
         self.push_data(((data & 0xFF00) >> 8) as Data);
@@ -1261,7 +2191,16 @@ impl Cpu {
 
     fn push_data(&mut self, data: Data) {
         self.set_sp(self.get_sp() - 1);
-        self.set_memory(self.get_sp(), data);
+        let addr = self.get_sp();
+        let variable = self
+            .ram_map
+            .iter()
+            .any(|r| (r.addr..r.addr + r.len).contains(&addr));
+        if !STACK.contains(&addr) && variable {
+            let pc = self.instruction_pc;
+            self.stack_warning(StackWarning::Clobber { pc, addr });
+        }
+        self.set_memory(addr, data);
     }
 
     /// Pop
@@ -1287,3 +2226,49 @@ impl Cpu {
         self.get_memory(self.get_sp())
     }
 }
+
+/// White-box access for tests in downstream crates, with the `test-api` feature. Interrupts (`interrupt`) and the input
+/// bus (`set_bus_in`, `set_bus_in_bit`) are always available. Nothing here is checked, so the machine can be put in states
+/// a program could never reach.
+#[cfg(feature = "test-api")]
+impl Cpu {
+    /// Registers B, C, D, E, H, L, F (flags) and A
+    pub fn registers(&self) -> [u8; NREGS] {
+        self.registers
+    }
+
+    /// Force registers B, C, D, E, H, L, F (flags) and A
+    pub fn force_registers(&mut self, registers: [u8; NREGS]) {
+        self.registers = registers;
+    }
+
+    /// Force the flags register (S Z 0 AC 0 P 1 CY from bit 7 down)
+    pub fn force_flags(&mut self, flags: u8) {
+        self.set_flags(flags);
+    }
+
+    /// Force the stack pointer, also outside the stack
+    pub fn force_sp(&mut self, sp: usize) {
+        self.sp = sp;
+    }
+
+    /// Force interrupts enabled (EI) or disabled (DI)
+    pub fn force_interruptable(&mut self, interruptable: bool) {
+        self.interruptable = interruptable;
+    }
+
+    /// Force memory, also in ROM
+    pub fn force_memory(&mut self, addr: usize, data: u8) {
+        self.memory[addr] = data;
+    }
+
+    /// Input port as read by IN (port 3 is the shift register)
+    pub fn bus_in(&self, port: usize) -> u8 {
+        self.get_bus_in(port)
+    }
+
+    /// Force an output port, without the side effects of OUT on the shift register
+    pub fn force_bus_out(&mut self, port: usize, data: u8) {
+        self.bus_out[port] = data;
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{RAM, STACK};
+use crate::{
+    serial::{RX_READY, TX_READY},
+    stack::{StackPolicy, StackWarning},
+    RAM, STACK,
+};
 
 use super::*;
 
@@ -43,10 +47,41 @@ fn get_and_set_sp() {
     assert_eq!(*STACK.start(), cpu.get_sp());
 }
 
+#[test]
+fn set_sp_outside_warns() {
+    // LXI SP,2300h; PUSH B; PUSH B; LXI SP,2400h
+    let mut cpu = Cpu::new(vec![0x31, 0x00, 0x23, 0xC5, 0xC5, 0x31, 0x00, 0x24]);
+    for _ in 0..4 {
+        cpu.step();
+    }
+    assert_eq!(
+        cpu.take_stack_warnings(),
+        [
+            StackWarning::Outside { pc: 0, sp: 0x2300 },
+            // Once while outside, the second push writes no variables
+            StackWarning::Clobber {
+                pc: 3,
+                addr: 0x22FF
+            },
+        ]
+    );
+    assert_eq!(cpu.get_sp(), 0x2400);
+    assert!(cpu.take_stack_warnings().is_empty());
+    assert_eq!(
+        StackWarning::Clobber {
+            pc: 3,
+            addr: 0x22FF
+        }
+        .to_string(),
+        "PC 0003: push overwrote 22FF p2ShipsRem"
+    );
+}
+
 #[test]
 #[should_panic]
 fn set_sp_panic_start() {
     let mut cpu = setup();
+    cpu.set_stack_policy(StackPolicy::Panic);
     cpu.set_sp(*STACK.start() - 1);
 }
 
@@ -54,6 +89,7 @@ fn set_sp_panic_start() {
 #[should_panic]
 fn set_sp_panic_end() {
     let mut cpu = setup();
+    cpu.set_stack_policy(StackPolicy::Panic);
     cpu.set_sp(*STACK.end() + 1);
 }
 
@@ -303,6 +339,305 @@ fn ret() {
     assert_eq!(*STACK.start() + 2, cpu.get_sp());
 }
 
+#[test]
+fn call_stack() {
+    let mut cpu = setup();
+    cpu.set_sp(*STACK.end());
+    cpu.set_pc(0x0100);
+    cpu.execute(Call(0x0200));
+    cpu.execute(Restart(1));
+    cpu.execute(Call(0x0300));
+    assert_eq!(
+        cpu.call_stack(),
+        [
+            StackFrame {
+                target: 0x0200,
+                return_address: 0x0100,
+                sp: *STACK.end() - 2,
+                interrupt: false
+            },
+            StackFrame {
+                target: 0x0008,
+                return_address: 0x0200,
+                sp: *STACK.end() - 4,
+                interrupt: false
+            },
+            StackFrame {
+                target: 0x0300,
+                return_address: 0x0008,
+                sp: *STACK.end() - 6,
+                interrupt: false
+            }
+        ]
+    );
+    assert_eq!(
+        cpu.format_call_stack(),
+        "  0300 (return to 0008, SP 23FA)\n  0008 (return to 0200, SP 23FC)\n  0200 (return to 0100, SP 23FE)\n"
+    );
+
+    cpu.execute(Return);
+    assert_eq!(cpu.call_stack().len(), 2);
+
+    // Program discards a return address from the stack and returns to the outer caller
+    cpu.execute(Pop(BC));
+    cpu.execute(Return);
+    assert_eq!(cpu.call_stack(), []);
+    assert_eq!(cpu.get_pc(), 0x0100);
+}
+
+/// Program with a subroutine that loops three times before returning
+fn setup_subroutine() -> Cpu {
+    let mut cpu = Cpu::new(vec![
+        0x31, 0x00, 0x24, // 0000 LXI SP, 2400
+        0xCD, 0x0A, 0x00, // 0003 CALL 000A
+        0x00, // 0006 NOP
+        0xC3, 0x06, 0x00, // 0007 JMP 0006
+        0x06, 0x03, // 000A MVI B, 3
+        0x05, // 000C DCR B
+        0xC2, 0x0C, 0x00, // 000D JNZ 000C
+        0xC9, // 0010 RET
+    ]);
+    cpu.step();
+    cpu
+}
+
+#[test]
+fn step_over() {
+    let mut cpu = setup_subroutine();
+    assert_eq!(cpu.step_over(1000), Some(17 + 7 + 3 * (5 + 10) + 10));
+    assert_eq!(cpu.get_pc(), 0x0006);
+    assert_eq!(cpu.get_register(B), 0);
+    assert_eq!(cpu.step_over(1000), Some(4));
+    assert_eq!(cpu.get_pc(), 0x0007);
+
+    let mut cpu = setup_subroutine();
+    assert_eq!(cpu.step_over(20), None);
+}
+
+#[test]
+fn step_out() {
+    let mut cpu = setup_subroutine();
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.get_pc(), 0x000C);
+    assert_eq!(cpu.step_out(1000), Some(3 * (5 + 10) + 10));
+    assert_eq!(cpu.get_pc(), 0x0006);
+
+    // Not in a subroutine, nothing to do
+    assert_eq!(cpu.step_out(1000), Some(0));
+}
+
+#[test]
+fn reset() {
+    let mut cpu = setup_subroutine();
+    cpu.execute(EnableInterrupts);
+    cpu.step();
+    cpu.step();
+    cpu.reset();
+    assert_eq!(cpu.get_pc(), 0);
+    assert!(!cpu.interruptable);
+    assert_eq!(cpu.call_stack(), []);
+    assert_eq!(cpu.get_register(B), 3);
+    assert_eq!(cpu.get_sp(), 0x23FE);
+    assert_eq!(cpu.get_memory(0x23FE), 0x06);
+}
+
+#[test]
+fn power_cycle() {
+    let mut cpu = setup_subroutine();
+    cpu.step();
+    cpu.step();
+    cpu.set_register(A, 0xFF);
+    cpu.execute(Output(3));
+    assert_eq!(cpu.get_bus_out(3), 0xFF);
+    cpu.set_display_update(false);
+    cpu.power_cycle();
+    assert_eq!(cpu.get_pc(), 0);
+    assert_eq!(cpu.registers, [0; NREGS]);
+    assert_eq!(cpu.get_sp(), 0);
+    assert!(cpu.memory[RAM].iter().all(|b| *b == 0));
+    assert_eq!(cpu.get_memory(0), 0x31);
+    assert_eq!(cpu.get_bus_out(3), 0);
+    assert!(cpu.get_display_update());
+}
+
+#[test]
+fn ram_init() {
+    let mut cpu = setup();
+    cpu.set_ram_init(RamInit::Ones);
+    assert!(cpu.memory[RAM].iter().all(|b| *b == 0xFF));
+    assert_eq!(cpu.get_memory(*ROM.end()), 0);
+
+    cpu.set_ram_init(RamInit::Alternating);
+    assert_eq!(cpu.get_memory(*RAM.start()), 0xAA);
+    assert_eq!(cpu.get_memory(*RAM.start() + 1), 0x55);
+    assert_eq!(cpu.get_memory(*RAM.end()), 0x55);
+
+    cpu.set_ram_init(RamInit::Random(1));
+    let random = cpu.memory;
+    assert!(random[RAM].iter().any(|b| *b != random[*RAM.start()]));
+    cpu.set_memory(*RAM.start(), !random[*RAM.start()]);
+    cpu.power_cycle();
+    assert_eq!(cpu.memory, random);
+
+    cpu.set_ram_init(RamInit::Random(2));
+    assert_ne!(cpu.memory, random);
+
+    cpu.set_ram_init(RamInit::Zeros);
+    assert!(cpu.memory[RAM].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn load_rom() {
+    let mut cpu = Cpu::new(vec![1, 2, 3]);
+    cpu.set_memory(*RAM.start(), 4);
+    cpu.load_rom(&[5, 6]);
+    assert_eq!(cpu.memory[0..3], [5, 6, 0]);
+    assert_eq!(cpu.get_memory(*RAM.start()), 4);
+    // Bytes beyond the memory are left out
+    cpu.load_rom(&[7; MEMORY_SIZE + 1]);
+    assert_eq!(cpu.memory[MEMORY_SIZE - 1], 7);
+}
+
+#[test]
+fn format_registers() {
+    let mut cpu = setup_subroutine();
+    cpu.jump(0x000A);
+    cpu.step();
+    cpu.set_flag(Z, true);
+    cpu.set_flag(CY, true);
+    assert_eq!(
+        cpu.format_registers(),
+        "A=00 B=03 C=00 D=00 E=00 H=00 L=00 SP=2400 PC=000C S=0 Z=1 AC=0 P=0 CY=1"
+    );
+    assert_eq!(cpu.memory()[0x000A], 0x06);
+}
+
+#[test]
+fn register() {
+    let mut cpu = setup_subroutine();
+    cpu.jump(0x000A);
+    cpu.step();
+    cpu.set_register_pair(HL, 0x1234);
+    cpu.set_flag(CY, true);
+    assert_eq!(cpu.register("B"), Some(0x03));
+    assert_eq!(cpu.register("BC"), Some(0x0300));
+    assert_eq!(cpu.register("HL"), Some(0x1234));
+    assert_eq!(cpu.register("L"), Some(0x34));
+    assert_eq!(cpu.register("SP"), Some(0x2400));
+    assert_eq!(cpu.register("PC"), Some(0x000C));
+    assert_eq!(cpu.register("CY"), Some(1));
+    assert_eq!(cpu.register("Z"), Some(0));
+    assert_eq!(cpu.register("X"), None);
+}
+
+#[test]
+fn halt() {
+    let mut cpu = Cpu::new(vec![0xFB, 0x76, 0x00]); // EI, HLT, NOP
+    cpu.set_sp(*STACK.end());
+    cpu.step();
+    assert_eq!(cpu.step(), 7);
+    assert!(cpu.is_halted());
+    assert_eq!(cpu.step(), 4);
+    assert_eq!(cpu.get_pc(), 0x0002);
+    cpu.interrupt(1);
+    assert!(!cpu.is_halted());
+    assert_eq!(cpu.get_pc(), 0x0008);
+    assert_eq!(cpu.peek(), 0x0002);
+}
+
+#[test]
+fn count_instructions() {
+    let mut cpu = Cpu::new(vec![0xFB, 0x76, 0x00]); // EI, HLT, NOP
+    cpu.set_sp(*STACK.end());
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.instructions(), 2);
+    cpu.step(); // Halted
+    assert_eq!(cpu.instructions(), 2);
+    cpu.interrupt(1);
+    assert_eq!(cpu.instructions(), 3);
+    cpu.power_cycle();
+    assert_eq!(cpu.instructions(), 3);
+}
+
+#[test]
+fn disassemble() {
+    let cpu = setup_subroutine();
+    assert_eq!(
+        cpu.disassemble(0x0003),
+        Disassembly {
+            addr: 0x0003,
+            bytes: vec![0xCD, 0x0A, 0x00],
+            mnemonic: "CALL 000AH".into(),
+            description: "Call",
+            flow: Flow::Branch(0x000A),
+        }
+    );
+    assert_eq!(cpu.disassemble(0x0000).mnemonic, "LXI SP, 2400H");
+    assert_eq!(cpu.disassemble(0x000A).mnemonic, "MVI B, 03H");
+    assert_eq!(cpu.disassemble(0x000C).mnemonic, "DCR B");
+    assert_eq!(cpu.disassemble(0x000D).mnemonic, "JNZ 000CH");
+    assert_eq!(cpu.disassemble(0x000D).description, "Conditional jump");
+    assert_eq!(cpu.disassemble(0x0010).mnemonic, "RET");
+    assert_eq!(cpu.disassemble(0x0010).flow, Flow::Stop);
+    assert_eq!(cpu.disassemble(0x000C).flow, Flow::Next);
+    assert_eq!(cpu.program_counter(), 0x0003);
+}
+
+#[test]
+fn disassemble_anywhere() {
+    // JMP 1234H with the operand past the end of ROM
+    let mut rom = vec![0; 0x2000];
+    rom[0x1FFF] = 0xC3;
+    let mut cpu = Cpu::new(rom);
+    cpu.poke(0x2000, 0x34);
+    cpu.poke(0x2001, 0x12);
+    let at_end = cpu.disassemble(0x1FFF);
+    assert_eq!(at_end.mnemonic, "JMP 1234H");
+    assert_eq!(at_end.bytes, [0xC3, 0x34, 0x12]);
+    // MVI A,42H in RAM
+    cpu.poke(0x2100, 0x3E);
+    cpu.poke(0x2101, 0x42);
+    assert_eq!(cpu.disassemble(0x2100).mnemonic, "MVI A, 42H");
+    // Operands wrap around at the end of memory
+    cpu.poke(MEMORY_SIZE - 1, 0x3E);
+    assert_eq!(cpu.disassemble(MEMORY_SIZE - 1).bytes, [0x3E, 0x00]);
+    assert_eq!(cpu.program_counter(), 0);
+}
+
+#[test]
+fn mnemonics() {
+    assert_eq!(MoveRegister(A, B).mnemonic(), "MOV A, B");
+    assert_eq!(Push(BC).mnemonic(), "PUSH B");
+    assert_eq!(ConditionalReturn(ParityEven).mnemonic(), "RPE");
+    assert_eq!(ConditionalCall(Minus, 0x1234).mnemonic(), "CM 1234H");
+    assert_eq!(Restart(7).mnemonic(), "RST 7");
+    assert_eq!(Output(3).mnemonic(), "OUT 03H");
+    assert_eq!(Err(0xCB).mnemonic(), "??? CBH");
+}
+
+#[test]
+fn in_interrupt() {
+    let mut cpu = setup_subroutine();
+    cpu.execute(EnableInterrupts);
+    assert!(!cpu.in_interrupt());
+    cpu.interrupt(2);
+    assert!(cpu.in_interrupt());
+    assert_eq!(cpu.get_pc(), 0x0010);
+    cpu.step(); // RET
+    assert!(!cpu.in_interrupt());
+    assert_eq!(cpu.get_pc(), 0x0003);
+}
+
+#[test]
+fn run_to() {
+    let mut cpu = setup_subroutine();
+    assert_eq!(cpu.run_to(0x0010, 1000), Some(17 + 7 + 3 * (5 + 10)));
+    assert_eq!(cpu.get_pc(), 0x0010);
+    assert_eq!(cpu.run_to(0x1000, 1000), None);
+}
+
 #[test]
 fn load_accumulator_indirect() {
     let mut cpu = setup();
@@ -840,7 +1175,154 @@ fn add_register() {
 
 #[test]
 fn add_register_with_carry() {
-    //panic!("Implement the test!");
+    let mut cpu = setup();
+    cpu.set_register(A, 0x0F);
+    cpu.set_register(B, 0x00);
+    cpu.set_flag(CY, true);
+    assert_eq!(4, cpu.execute(AddRegisterWithCarry(B)));
+    assert_eq!(0x10, cpu.get_register(A));
+    assert!(cpu.get_flag(AC));
+    assert!(!cpu.get_flag(CY));
+    assert_eq!(4, cpu.execute(AddRegisterWithCarry(B)));
+    assert_eq!(0x10, cpu.get_register(A));
+    assert!(!cpu.get_flag(AC));
+}
+
+#[test]
+fn add_with_carry_overflow() {
+    // 0xFF + carry and A + 0xFF + carry must wrap, not overflow
+    let mut cpu = setup();
+    cpu.set_register(A, 0xFF);
+    cpu.set_register(C, 0x00);
+    cpu.set_flag(CY, true);
+    assert_eq!(4, cpu.execute(AddRegisterWithCarry(C)));
+    assert_eq!(0x00, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+    assert!(cpu.get_flag(AC));
+    assert!(cpu.get_flag(Z));
+
+    cpu.set_register(A, 0x01);
+    cpu.set_flag(CY, true);
+    assert_eq!(7, cpu.execute(AddImmediateWithCarry(0xFF)));
+    assert_eq!(0x01, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+    assert!(!cpu.get_flag(Z));
+
+    cpu.set_register(A, 0x80);
+    cpu.set_register_pair(HL, *RAM.start() as u16);
+    cpu.set_memory(*RAM.start(), 0x7F);
+    cpu.set_flag(CY, true);
+    assert_eq!(7, cpu.execute(AddMemoryWithCarry));
+    assert_eq!(0x00, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+}
+
+#[test]
+fn subtract_with_borrow_overflow() {
+    // Subtracting 0xFF with borrow must wrap, not overflow
+    let mut cpu = setup();
+    cpu.set_register(A, 0x00);
+    cpu.set_register(D, 0xFF);
+    cpu.set_flag(CY, true);
+    assert_eq!(4, cpu.execute(SubtractRegisterWithBorrow(D)));
+    assert_eq!(0x00, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+    assert!(cpu.get_flag(Z));
+
+    cpu.set_register(A, 0xFF);
+    cpu.set_flag(CY, true);
+    assert_eq!(7, cpu.execute(SubtractImmediateWithBorrow(0xFF)));
+    assert_eq!(0xFF, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+
+    cpu.set_register(A, 0x10);
+    cpu.set_register_pair(HL, *RAM.start() as u16);
+    cpu.set_memory(*RAM.start(), 0x01);
+    cpu.set_flag(CY, false);
+    assert_eq!(7, cpu.execute(SubtractMemoryWithBorrow));
+    assert_eq!(0x0F, cpu.get_register(A));
+    assert!(!cpu.get_flag(CY));
+    assert!(!cpu.get_flag(AC));
+}
+
+/// Subtraction vector as (A, data, borrow) before and (A, CY, AC, Z, S, P) after
+type SubtractVector = (u8, u8, bool, u8, bool, bool, bool, bool, bool);
+
+#[rustfmt::skip]
+const SUBTRACT_VECTORS: [SubtractVector; 14] = [
+    (0x00, 0x01, false, 0xFF, true, false, false, true, true),
+    (0x10, 0x01, false, 0x0F, false, false, false, false, true),
+    (0x10, 0x10, false, 0x00, false, true, true, false, true),
+    (0x05, 0x03, false, 0x02, false, true, false, false, false),
+    (0x03, 0x05, false, 0xFE, true, false, false, true, false),
+    (0x80, 0x01, false, 0x7F, false, false, false, false, false),
+    (0x7F, 0xFF, false, 0x80, true, true, false, true, false),
+    (0xFF, 0xFF, true, 0xFF, true, false, false, true, true),
+    (0x00, 0x00, true, 0xFF, true, false, false, true, true),
+    (0x12, 0x02, true, 0x0F, false, false, false, false, true),
+    (0x20, 0x0F, false, 0x11, false, false, false, false, true),
+    (0x0F, 0x0F, false, 0x00, false, true, true, false, true),
+    (0x44, 0x44, false, 0x00, false, true, true, false, true),
+    (0xFE, 0x7F, true, 0x7E, false, false, false, false, true),
+];
+
+/// Assert the flags after a subtraction vector
+fn assert_subtract_flags(cpu: &Cpu, instr: Instruction, vector: SubtractVector) {
+    let (a, data, borrow, _, cy, ac, z, s, p) = vector;
+    let name = format!("{instr:?} {a:02X} - {data:02X} - {borrow}");
+    assert_eq!(cy, cpu.get_flag(CY), "CY after {name}");
+    assert_eq!(ac, cpu.get_flag(AC), "AC after {name}");
+    assert_eq!(z, cpu.get_flag(Z), "Z after {name}");
+    assert_eq!(s, cpu.get_flag(S), "S after {name}");
+    assert_eq!(p, cpu.get_flag(P), "P after {name}");
+}
+
+#[test]
+fn subtract_vectors() {
+    for vector in SUBTRACT_VECTORS {
+        let (a, data, borrow, result, ..) = vector;
+        let mut instructions = vec![
+            SubtractImmediateWithBorrow(data),
+            SubtractRegisterWithBorrow(B),
+            SubtractMemoryWithBorrow,
+        ];
+        if !borrow {
+            instructions.extend([SubtractImmediate(data), SubtractRegister(B), SubtractMemory]);
+        }
+        for instr in instructions {
+            let mut cpu = setup();
+            cpu.set_register(A, a);
+            cpu.set_register(B, data);
+            cpu.set_register_pair(HL, *RAM.start() as u16);
+            cpu.set_memory(*RAM.start(), data);
+            cpu.set_flag(CY, borrow);
+            cpu.execute(instr);
+            assert_eq!(
+                result,
+                cpu.get_register(A),
+                "{instr:?} {a:02X} - {data:02X}"
+            );
+            assert_subtract_flags(&cpu, instr, vector);
+        }
+    }
+}
+
+#[test]
+fn compare_vectors() {
+    for vector in SUBTRACT_VECTORS.into_iter().filter(|v| !v.2) {
+        let (a, data, ..) = vector;
+        for instr in [CompareImmediate(data), CompareRegister(C), CompareMemory] {
+            let mut cpu = setup();
+            cpu.set_register(A, a);
+            cpu.set_register(C, data);
+            cpu.set_register_pair(HL, *RAM.start() as u16);
+            cpu.set_memory(*RAM.start(), data);
+            cpu.set_flag(CY, true); // Compare ignores the borrow
+            cpu.execute(instr);
+            assert_eq!(a, cpu.get_register(A), "{instr:?} must not change A");
+            assert_subtract_flags(&cpu, instr, vector);
+        }
+    }
 }
 
 #[test]
@@ -1056,6 +1538,65 @@ fn complement_accumulator() {
     assert_eq!(0b0101_0101, cpu.get_register(A));
 }
 
+/// DAA vector as (A, AC, CY) before and (A, AC, CY, Z, S, P) after
+type DaaVector = (u8, bool, bool, u8, bool, bool, bool, bool, bool);
+
+#[rustfmt::skip]
+const DAA_VECTORS: [DaaVector; 18] = [
+    (0x00, false, false, 0x00, false, false, true, false, true),
+    (0x09, false, false, 0x09, false, false, false, false, true),
+    (0x0A, false, false, 0x10, true, false, false, false, false),
+    (0x0F, false, false, 0x15, true, false, false, false, false),
+    (0x10, true, false, 0x16, false, false, false, false, false),
+    (0x15, false, false, 0x15, false, false, false, false, false),
+    (0x19, true, false, 0x1F, false, false, false, false, false),
+    (0x42, false, true, 0xA2, false, true, false, true, false),
+    (0x99, false, false, 0x99, false, false, false, true, true),
+    (0x9A, false, false, 0x00, true, true, true, false, true),
+    (0xA0, false, false, 0x00, false, true, true, false, true),
+    (0xFA, false, false, 0x60, true, true, false, false, true),
+    (0x9A, true, true, 0x00, true, true, true, false, true),
+    (0x66, true, true, 0xCC, false, true, false, true, true),
+    (0x00, true, true, 0x66, false, true, false, false, true),
+    (0x3C, false, false, 0x42, true, false, false, false, true),
+    (0x7D, false, false, 0x83, true, false, false, true, false),
+    (0xFF, true, true, 0x65, true, true, false, false, true),
+];
+
+#[test]
+fn decimal_adjust_accumulator() {
+    for (a, ac, cy, result, ac_after, cy_after, z, s, p) in DAA_VECTORS {
+        let mut cpu = setup();
+        cpu.set_register(A, a);
+        cpu.set_flag(AC, ac);
+        cpu.set_flag(CY, cy);
+        assert_eq!(4, cpu.execute(DecimalAdjustAccumulator));
+        let vector = format!("DAA {a:02X} AC={ac} CY={cy}");
+        assert_eq!(result, cpu.get_register(A), "{vector}");
+        assert_eq!(ac_after, cpu.get_flag(AC), "AC after {vector}");
+        assert_eq!(cy_after, cpu.get_flag(CY), "CY after {vector}");
+        assert_eq!(z, cpu.get_flag(Z), "Z after {vector}");
+        assert_eq!(s, cpu.get_flag(S), "S after {vector}");
+        assert_eq!(p, cpu.get_flag(P), "P after {vector}");
+    }
+}
+
+#[test]
+fn decimal_addition() {
+    // BCD scores: 19 + 28 = 47 and 75 + 25 = 100 (carry)
+    let mut cpu = setup();
+    cpu.set_register(A, 0x19);
+    cpu.add(0x28);
+    cpu.execute(DecimalAdjustAccumulator);
+    assert_eq!(0x47, cpu.get_register(A));
+    assert!(!cpu.get_flag(CY));
+    cpu.set_register(A, 0x75);
+    cpu.add(0x25);
+    cpu.execute(DecimalAdjustAccumulator);
+    assert_eq!(0x00, cpu.get_register(A));
+    assert!(cpu.get_flag(CY));
+}
+
 #[test]
 fn add() {
     let mut cpu = setup();
@@ -1075,3 +1616,250 @@ fn add() {
     assert!(cpu.get_flag(CY));
     assert_eq!(0, cpu.get_register(A));
 }
+
+/// Framebuffer address and bit of display pixel (x, y), straight from the documented layout
+fn pixel_address(x: u32, y: u32) -> (usize, u8) {
+    let from_bottom = 255 - y;
+    (
+        0x2400 + (x * 32 + from_bottom / 8) as usize,
+        (from_bottom % 8) as u8,
+    )
+}
+
+#[test]
+fn display_pixels() {
+    let mut cpu = setup();
+    for x in 0..DISPLAY_WIDTH {
+        for y in 0..DISPLAY_HEIGHT {
+            let (addr, bit) = pixel_address(x, y);
+            cpu.set_memory(addr, 1 << bit);
+            assert_eq!(cpu.display(x, y), Some(true), "({x}, {y})");
+            for (nx, ny) in [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ] {
+                assert_ne!(
+                    cpu.display(nx, ny),
+                    Some(true),
+                    "({nx}, {ny}) next to ({x}, {y})"
+                );
+            }
+            cpu.set_memory(addr, 0);
+        }
+    }
+}
+
+#[test]
+fn display_bytes() {
+    // Every bit of video memory is exactly one pixel
+    let mut cpu = setup();
+    let mut seen = vec![false; (DISPLAY_WIDTH * DISPLAY_HEIGHT) as usize];
+    for addr in 0x2400..0x4000 {
+        for bit in 0..8 {
+            cpu.set_memory(addr, 1 << bit);
+            let offset = (addr - 0x2400) as u32;
+            let (x, y) = (offset / 32, 255 - ((offset % 32) * 8 + bit as u32));
+            assert_eq!(pixel_address(x, y), (addr, bit));
+            assert_eq!(cpu.display(x, y), Some(true));
+            seen[(y * DISPLAY_WIDTH + x) as usize] = true;
+            cpu.set_memory(addr, 0);
+        }
+    }
+    assert!(seen.iter().all(|s| *s));
+}
+
+#[test]
+fn display_corners_and_bounds() {
+    let mut cpu = setup();
+    cpu.set_memory(0x2400, 0x01); // Bottom left
+    cpu.set_memory(0x3FFF, 0x80); // Top right
+    assert_eq!(cpu.display(0, 255), Some(true));
+    assert_eq!(cpu.display(223, 0), Some(true));
+    assert_eq!(cpu.display(0, 0), Some(false));
+    assert_eq!(cpu.display(223, 255), Some(false));
+    assert_eq!(cpu.display(224, 0), None);
+    assert_eq!(cpu.display(0, 256), None);
+    assert_eq!(cpu.display(u32::MAX, u32::MAX), None);
+}
+
+#[cfg(feature = "test-api")]
+#[test]
+fn test_api() {
+    let mut cpu = Cpu::new(vec![0xCA, 0x10, 0x00]); // JZ 0010h
+    cpu.force_registers([1, 2, 3, 4, 5, 6, 0, 8]);
+    cpu.force_flags(0b0100_0010); // Z
+    assert_eq!(cpu.registers(), [1, 2, 3, 4, 5, 6, 0b0100_0010, 8]);
+    cpu.step();
+    assert_eq!(cpu.get_pc(), 0x0010);
+
+    cpu.force_sp(0x2400);
+    assert_eq!(cpu.interrupt(1), 0);
+    cpu.force_interruptable(true);
+    assert_eq!(cpu.interrupt(1), 11);
+    assert_eq!((cpu.get_pc(), cpu.get_sp()), (0x0008, 0x23FE));
+
+    cpu.force_memory(0x0000, 0xFF);
+    assert_eq!(cpu.memory()[0], 0xFF);
+    cpu.set_bus_in(1, 0x55);
+    assert_eq!(cpu.bus_in(1), 0x55);
+    cpu.force_bus_out(3, 0x02);
+    assert_eq!(cpu.get_bus_out(3), 0x02);
+}
+
+#[test]
+fn profile() {
+    // LXI H,000Bh; MOV A,M; LHLD 000Ch; JMP 0007h; data
+    let mut cpu = Cpu::new(vec![
+        0x21, 0x0B, 0x00, 0x7E, 0x2A, 0x0C, 0x00, 0xC3, 0x07, 0x00, 0x00, 0x01, 0x02, 0x03,
+    ]);
+    cpu.step();
+    assert_eq!(cpu.profile(), None);
+    cpu.enable_profile();
+    for _ in 0..3 {
+        cpu.step();
+    }
+    let profile = cpu.profile().unwrap();
+    assert!(!profile.executed(0x00));
+    assert!(profile.executed(0x03) && profile.executed(0x04) && profile.executed(0x07));
+    assert!(!profile.executed(0x05));
+    assert!(profile.data(0x0B) && profile.data(0x0C) && profile.data(0x0D));
+    assert!(!profile.data(0x0A));
+}
+
+#[test]
+fn format_state() {
+    let mut cpu = setup_subroutine();
+    cpu.step();
+    cpu.step();
+    let state = cpu.format_state(2);
+    assert!(state.starts_with("A=00 B=03 "));
+    assert!(state.contains("Call stack:\n  000A (return to 0006, SP 23FE)\n"));
+    assert!(state.ends_with(
+        "> 000C  DCR B          Decrement register\n  000D  JNZ 000CH      Conditional jump\n"
+    ));
+    assert_eq!(cpu.get_pc(), 0x000C);
+}
+
+#[test]
+fn save_and_load_state() {
+    let mut cpu = setup_subroutine();
+    cpu.step();
+    cpu.step();
+    cpu.set_bus_out(3, 0x12);
+    let state = cpu.save_state();
+    cpu.step();
+    cpu.poke(0x2100, 0x42);
+    let mut other = Cpu::new(vec![]);
+    assert_eq!(
+        other.load_state(&state[1..]),
+        Result::Err("Not a savestate".to_string())
+    );
+    other.load_state(&state).unwrap();
+    assert_eq!(other.get_pc(), 0x000C);
+    assert_eq!(other.save_state(), state);
+    assert_eq!(other.memory()[0x2100], 0);
+    assert_eq!(other.get_bus_out(3), 0x12);
+    assert!(other.call_stack().is_empty());
+}
+
+#[test]
+fn bank_switching() {
+    let mut cpu = Cpu::new(vec![]);
+    cpu.set_banks(
+        Banks::new(0x1000..=0x1FFF, 7, 2),
+        &[[1; 0x1000], [2; 0x1000]].concat(),
+    );
+    assert_eq!(cpu.memory()[0x1000], 1);
+    cpu.set_bus_out(7, 1);
+    assert_eq!(
+        (cpu.memory()[0x1FFF], cpu.banks().unwrap().selected()),
+        (2, 1)
+    );
+    let state = cpu.save_state();
+    cpu.set_bus_out(7, 0);
+    assert_eq!(cpu.memory()[0x1000], 1);
+    cpu.load_state(&state).unwrap();
+    assert_eq!(cpu.memory()[0x1000], 2);
+    assert_eq!(cpu.banks().unwrap().contents(cpu.memory())[0], 1);
+    assert!(Cpu::new(vec![]).load_state(&state).is_err());
+}
+
+#[test]
+fn color_ram() {
+    // MVI A,5; STA C400; MVI A,0; LDA C400
+    let mut cpu = Cpu::new(vec![0x3E, 5, 0x32, 0x00, 0xC4, 0x3E, 0, 0x3A, 0x00, 0xC4]);
+    assert_eq!(cpu.color(0, 255), None);
+    cpu.set_colors(ColorRam::new(0xC000..=0xDFFF, 0x1F1F));
+    for _ in 0..4 {
+        cpu.step();
+    }
+    assert_eq!(cpu.get_register(A), 5);
+    // One color for 8x8 pixels
+    assert_eq!(cpu.color(0, 255), Some(5));
+    assert_eq!(cpu.color(7, 248), Some(5));
+    assert_eq!((cpu.color(8, 255), cpu.color(0, 247)), (Some(0), Some(0)));
+    let state = cpu.save_state();
+    cpu.power_cycle();
+    assert_eq!(cpu.color(0, 255), Some(0));
+    cpu.load_state(&state).unwrap();
+    assert_eq!(cpu.color(0, 255), Some(5));
+    assert!(Cpu::new(vec![]).load_state(&state).is_err());
+}
+
+#[test]
+fn serial() {
+    // IN 01; MOV B,A; IN 00; OUT 00; IN 01
+    let mut cpu = Cpu::new(vec![0xDB, 1, 0x47, 0xDB, 0, 0xD3, 0, 0xDB, 1]);
+    cpu.set_serial(Serial::new(0, 1));
+    cpu.serial_mut().unwrap().receive(b"A");
+    for _ in 0..5 {
+        cpu.step();
+    }
+    assert_eq!(cpu.get_register(B), TX_READY | RX_READY);
+    assert_eq!(cpu.get_register(A), TX_READY);
+    assert_eq!(cpu.serial_mut().unwrap().take_output(), b"A");
+}
+
+#[test]
+fn history() {
+    // NOP, then JMP 0001
+    let mut cpu = Cpu::new(vec![0x00, 0xC3, 0x01, 0x00]);
+    cpu.step();
+    assert_eq!(cpu.history(), None);
+    cpu.enable_history();
+    for _ in 0..HISTORY + 10 {
+        cpu.step();
+    }
+    let history = cpu.history().unwrap();
+    assert_eq!(history.len(), HISTORY);
+    assert!(history.iter().all(|pc| *pc == 1));
+    cpu.power_cycle();
+    cpu.step();
+    assert_eq!(cpu.history().unwrap().back(), Some(&0));
+}
+
+#[test]
+fn timer() {
+    // OUT 07 (A=0 latches); IN 07; MOV B,A; MVI A,1; OUT 07; IN 07
+    let mut cpu = Cpu::new(vec![0xD3, 7, 0xDB, 7, 0x47, 0x3E, 1, 0xD3, 7, 0xDB, 7]);
+    let mut timer = Timer::new(7, 1, 2);
+    timer.run(2 * 0x1FF);
+    cpu.set_timer(timer);
+    cpu.set_bus_in(7, 0xFF);
+    for _ in 0..6 {
+        cpu.step();
+    }
+    // Latched by the first OUT, as of its start
+    assert_eq!(cpu.get_register(B), 0xFF);
+    assert_eq!(cpu.get_register(A), 0x01);
+    let count = cpu.timer().unwrap().count();
+    assert_eq!(count, 0x1FF + (10 + 10 + 5 + 7 + 10 + 10) / 2);
+    let state = cpu.save_state();
+    cpu.power_cycle();
+    assert_eq!(cpu.timer().unwrap().count(), 0);
+    cpu.load_state(&state).unwrap();
+    assert_eq!(cpu.timer().unwrap().count(), count);
+    assert!(Cpu::new(vec![]).load_state(&state).is_err());
+}
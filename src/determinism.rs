@@ -0,0 +1,93 @@
+//! End-to-end determinism check: the same seeded game replayed twice and the machine state compared after every frame.
+//! The second replay restores its own savestate every frame, like run-ahead does, and may run on another thread at the
+//! same time as the first. Anything making the emulation depend on more than the rom and the inputs shows up as a
+//! mismatch.
+
+use std::{fmt, thread};
+
+use crate::{
+    batch::{random_policy, run_frame, Noise},
+    compare::{self, Slot},
+    cpu::{Cpu, RamInit},
+    hash::state_hash,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Seed of the replay: random RAM at power on and the input noise of the bot playing
+pub const SEED: u64 = 8080;
+
+/// First frame where the replays differ
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Frame number, counting from 1
+    pub frame: u64,
+    /// Hashes of the machine state after the frame in the first and the second replay
+    pub hashes: (u32, u32),
+    /// Changes from the first replay to the second, as by `compare::diff`
+    pub diff: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Frame {}: state hash {:08x}, then {:08x}",
+            self.frame, self.hashes.0, self.hashes.1
+        )?;
+        write!(f, "{}", self.diff)
+    }
+}
+
+/// Replay the seeded game for a number of frames, restoring a savestate after every frame if `restore`
+fn replay(rom: &[u8], frames: u64, restore: bool, mut frame_done: impl FnMut(&Cpu)) -> Cpu {
+    let mut cpu = Cpu::new(rom.to_vec());
+    cpu.set_ram_init(RamInit::Random(SEED));
+    cpu.power_cycle();
+    let mut noise = Noise::new(SEED);
+    for frame in 0..frames {
+        random_policy(&cpu, frame as u32, &mut noise).apply(&mut cpu);
+        run_frame(&mut cpu);
+        if restore {
+            let state = cpu.save_state();
+            cpu.load_state(&state)
+                .expect("Could not restore own savestate");
+        }
+        frame_done(&cpu);
+    }
+    cpu
+}
+
+/// Hash of the machine state after each frame of the replay
+pub fn hashes(rom: &[u8], frames: u64, restore: bool) -> Vec<u32> {
+    let mut hashes = vec![];
+    replay(rom, frames, restore, |cpu| hashes.push(state_hash(cpu)));
+    hashes
+}
+
+/// Index of the first hash that differs
+fn first_difference(a: &[u32], b: &[u32]) -> Option<usize> {
+    a.iter().zip(b).position(|(a, b)| a != b)
+}
+
+/// Replay twice, on two threads at the same time if `threads`, returns the first mismatch
+pub fn verify(rom: &[u8], frames: u64, threads: bool) -> Option<Mismatch> {
+    let (first, second) = if threads {
+        thread::scope(|scope| {
+            let second = scope.spawn(|| hashes(rom, frames, true));
+            let first = hashes(rom, frames, false);
+            (first, second.join().expect("Replay panicked"))
+        })
+    } else {
+        (hashes(rom, frames, false), hashes(rom, frames, true))
+    };
+    let i = first_difference(&first, &second)?;
+    let frame = i as u64 + 1;
+    let state = |restore| Slot::capture(&replay(rom, frame, restore, |_| {}));
+    Some(Mismatch {
+        frame,
+        hashes: (first[i], second[i]),
+        diff: compare::diff(&state(false), &state(true)),
+    })
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+/// Increment the first byte of the framebuffer forever
+const PROGRAM: [u8; 10] = [
+    0x31, 0x00, 0x24, // LXI SP,2400h
+    0x21, 0x00, 0x24, // LXI H,2400h
+    0x34, // INR M
+    0xC3, 0x06, 0x00, // JMP 0006h
+];
+
+#[test]
+fn hash_every_frame() {
+    let hashes = hashes(&PROGRAM, 5, false);
+    assert_eq!(hashes.len(), 5);
+    assert_ne!(hashes[0], hashes[1]);
+    assert_eq!(hashes, super::hashes(&PROGRAM, 5, true));
+}
+
+#[test]
+fn replays_match() {
+    assert_eq!(verify(&PROGRAM, 10, false), None);
+    assert_eq!(verify(&PROGRAM, 10, true), None);
+}
+
+#[test]
+fn first_mismatch() {
+    assert_eq!(first_difference(&[1, 2, 3], &[1, 2, 3]), None);
+    assert_eq!(first_difference(&[1, 2, 3], &[1, 5, 6]), Some(1));
+}
@@ -0,0 +1,161 @@
+//! Troubleshooting of rom files: identification of the chips of the game by CRC and a report of likely problems
+
+use std::fmt::Write;
+
+use crate::{
+    hash::{crc32, Algorithm},
+    soak::{self, Violation},
+    FPS, MEMORY_SIZE,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Size of each rom chip
+pub const CHIP_SIZE: usize = 0x800;
+/// Frames run from power on to see if the game gets going
+pub const EARLY_FRAMES: u64 = 3 * FPS as u64;
+
+/// A rom chip of the game, as in the MAME `invaders` set
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Chip {
+    /// File name
+    pub name: &'static str,
+    /// Address of the chip
+    pub addr: usize,
+    /// CRC-32 of the content
+    pub crc: u32,
+}
+
+/// The chips in address order
+pub const CHIPS: [Chip; 4] = [
+    Chip {
+        name: "invaders.h",
+        addr: 0x0000,
+        crc: 0x734f5ad8,
+    },
+    Chip {
+        name: "invaders.g",
+        addr: 0x0800,
+        crc: 0x6bfaca4a,
+    },
+    Chip {
+        name: "invaders.f",
+        addr: 0x1000,
+        crc: 0x0ccead96,
+    },
+    Chip {
+        name: "invaders.e",
+        addr: 0x1800,
+        crc: 0x14e538b0,
+    },
+];
+
+/// The rom has exactly the chips (e.g. `CHIPS`), each in place
+pub fn identify(rom: &[u8], chips: &[Chip]) -> bool {
+    rom.len() == chips.len() * CHIP_SIZE
+        && chips
+            .iter()
+            .all(|c| crc32(&rom[c.addr..c.addr + CHIP_SIZE]) == c.crc)
+}
+
+/// First invariant broken (program counter outside ROM, bad stack pointer or unimplemented instruction) running the rom
+/// without input for a number of frames, with the frame it happened in
+pub fn misbehaves(rom: &[u8], frames: u64) -> Option<(u64, Violation)> {
+    if rom.len() > MEMORY_SIZE {
+        return None;
+    }
+    let mut first = None;
+    soak::run(rom, frames, u32::MAX, |frame, violation, _| {
+        first.get_or_insert((frame, violation.clone()));
+    });
+    first
+}
+
+/// Troubleshooting report for a rom that should consist of the chips (e.g. `CHIPS`): size, CRCs of the chips, suspected
+/// problems and common fixes
+pub fn report(rom: &[u8], chips: &[Chip], misbehavior: Option<&(u64, Violation)>) -> String {
+    let mut r = String::new();
+    let expected = chips.len() * CHIP_SIZE;
+    let _ = writeln!(r, "Rom size: {} bytes (expected {expected})", rom.len());
+    for algorithm in Algorithm::ALL {
+        let _ = writeln!(r, "Rom {}: {}", algorithm.name(), algorithm.hex(rom));
+    }
+
+    // Bytes in front of the chips, e.g. a header, shift every chip
+    let skip = rom.len() % CHIP_SIZE;
+    if skip > 0 {
+        let _ = writeln!(
+            r,
+            "Size is not a multiple of {CHIP_SIZE}, checking chips after the first {skip} bytes"
+        );
+    }
+    let crcs: Vec<u32> = rom[skip..].chunks(CHIP_SIZE).map(crc32).collect();
+    let chunks: Vec<Option<&Chip>> = crcs
+        .iter()
+        .map(|crc| chips.iter().find(|c| c.crc == *crc))
+        .collect();
+    for (i, (crc, found)) in crcs.iter().zip(&chunks).enumerate() {
+        let offset = skip + i * CHIP_SIZE;
+        let status = match (*found, chips.get(i)) {
+            (Some(c), Some(e)) if c == e => format!("{} ok", c.name),
+            (Some(c), Some(e)) => format!("{}, expected {} here", c.name, e.name),
+            (Some(c), None) => format!("{}, extra copy", c.name),
+            (None, Some(e)) => format!("unknown, expected {}", e.name),
+            (None, None) => "unknown, extra data".to_string(),
+        };
+        let end = offset + CHIP_SIZE - 1;
+        let _ = writeln!(r, "  {offset:04X}-{end:04X} CRC {crc:08x}: {status}");
+    }
+    if let Some((frame, violation)) = misbehavior {
+        let _ = writeln!(r, "Frame {frame} after power on: {violation}");
+    }
+
+    let mut fixes: Vec<String> = vec![];
+    let matched = chunks.iter().flatten().count();
+    let missing: Vec<&str> = chips
+        .iter()
+        .filter(|c| !chunks.contains(&Some(*c)))
+        .map(|c| c.name)
+        .collect();
+    if skip > 0 && matched > 0 {
+        fixes.push(format!(
+            "The file starts with {skip} extra bytes (a header?), remove them"
+        ));
+    }
+    if matched > 0 && !missing.is_empty() {
+        fixes.push(format!(
+            "Chips missing: {}. Concatenate all chips into one file",
+            missing.join(", ")
+        ));
+    }
+    let in_place = chunks
+        .iter()
+        .enumerate()
+        .all(|(i, found)| found.is_none_or(|c| Some(c) == chips.get(i)));
+    if !in_place {
+        fixes.push("Chips are in the wrong order".into());
+    }
+    if matched > 0 && (!missing.is_empty() || !in_place || skip > 0) {
+        fixes.push(format!(
+            "Create the rom with the chips in address order, e.g. `cat {} > invaders.rom`",
+            chips.iter().map(|c| c.name).collect::<Vec<_>>().join(" ")
+        ));
+    }
+    if matched == 0 {
+        fixes.push("No chip of the game was recognized".into());
+        fixes
+            .push("Use the Midway Space Invaders set (MAME `invaders`), clones may not run".into());
+        fixes.push("Check that the files are not zipped or corrupt".into());
+    }
+    if fixes.is_empty() && misbehavior.is_some() {
+        fixes.push("The chips are fine, please report the problem".into());
+    }
+    if !fixes.is_empty() {
+        let _ = writeln!(r, "Suggestions:");
+        for fix in fixes {
+            let _ = writeln!(r, "  * {fix}");
+        }
+    }
+    r
+}
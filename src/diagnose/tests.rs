@@ -0,0 +1,95 @@
+use super::*;
+
+/// Made-up chips, each filled with its own byte
+fn setup() -> (Vec<u8>, Vec<Chip>) {
+    let names = ["a.h", "a.g", "a.f", "a.e"];
+    let data: Vec<Vec<u8>> = (1..=4).map(|b| vec![b; CHIP_SIZE]).collect();
+    let chips = names
+        .iter()
+        .zip(&data)
+        .enumerate()
+        .map(|(i, (name, d))| Chip {
+            name,
+            addr: i * CHIP_SIZE,
+            crc: crc32(d),
+        })
+        .collect();
+    (data.concat(), chips)
+}
+
+#[test]
+fn identify_rom() {
+    let (rom, chips) = setup();
+    assert!(identify(&rom, &chips));
+    assert!(!identify(&rom[..3 * CHIP_SIZE], &chips));
+    let mut bad = rom.clone();
+    bad[0x1234] ^= 1;
+    assert!(!identify(&bad, &chips));
+    assert!(!identify(&rom, &CHIPS));
+}
+
+#[test]
+fn report_good_rom() {
+    let (rom, chips) = setup();
+    let report = report(&rom, &chips, None);
+    assert!(report.starts_with("Rom size: 8192 bytes (expected 8192)\n"));
+    assert!(report.contains("  0800-0FFF CRC "));
+    assert_eq!(report.matches(" ok\n").count(), 4);
+    assert!(!report.contains("Suggestions"));
+}
+
+#[test]
+fn report_wrong_order() {
+    let (rom, chips) = setup();
+    let swapped = [
+        &rom[CHIP_SIZE..2 * CHIP_SIZE],
+        &rom[..CHIP_SIZE],
+        &rom[2 * CHIP_SIZE..],
+    ]
+    .concat();
+    let report = report(&swapped, &chips, None);
+    assert!(report.contains(": a.g, expected a.h here\n"));
+    assert!(report.contains(": a.h, expected a.g here\n"));
+    assert!(report.contains("* Chips are in the wrong order\n"));
+    assert!(report.contains("`cat a.h a.g a.f a.e > invaders.rom`"));
+}
+
+#[test]
+fn report_split_and_header() {
+    let (rom, chips) = setup();
+    let report = super::report(&rom[..CHIP_SIZE], &chips, None);
+    assert!(report.contains("* Chips missing: a.g, a.f, a.e."));
+
+    let headed = [&[0; 16], &rom[..]].concat();
+    let report = super::report(&headed, &chips, None);
+    assert!(report.contains("checking chips after the first 16 bytes"));
+    assert!(report.contains("  0010-080F CRC "));
+    assert!(report.contains("* The file starts with 16 extra bytes"));
+    assert!(!report.contains("wrong order"));
+}
+
+#[test]
+fn report_unknown_and_misbehaving() {
+    let (rom, chips) = setup();
+    let report = super::report(&[0xFF; 100], &chips, None);
+    assert!(report.contains("* No chip of the game was recognized\n"));
+
+    let violation = (12, Violation::ProgramCounter(0x4000));
+    let report = super::report(&rom, &chips, Some(&violation));
+    assert!(report.contains("Frame 12 after power on: Program counter 4000 outside ROM\n"));
+    assert!(report.contains("* The chips are fine"));
+}
+
+#[test]
+fn misbehaving_program() {
+    // LXI SP,3000h
+    let program = vec![0x31, 0x00, 0x30, 0xC3, 0x03, 0x00];
+    assert_eq!(
+        misbehaves(&program, 2),
+        Some((0, Violation::StackPointer(0x3000)))
+    );
+    // LXI SP,2400h and loop forever
+    let program = vec![0x31, 0x00, 0x24, 0xC3, 0x03, 0x00];
+    assert_eq!(misbehaves(&program, 2), None);
+    assert_eq!(misbehaves(&vec![0; MEMORY_SIZE + 1], 2), None);
+}
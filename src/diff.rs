@@ -0,0 +1,87 @@
+//! Visual diff of two frames (screenshots or framebuffer dumps), for debugging rendering or CPU regressions
+
+use crate::{
+    cpu::Cpu,
+    frame::Frame,
+    pbm,
+    png::{self, Image},
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, FRAMEBUFFER, MEMORY_SIZE,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Size of the framebuffer as shown on the display
+const FRAMEBUFFER_SIZE: usize = (DISPLAY_WIDTH * DISPLAY_HEIGHT / 8) as usize;
+
+/// Color of pixels lit in both frames
+pub const SAME: [u8; 4] = [0x80, 0x80, 0x80, 0xFF];
+/// Color of pixels lit only in the expected frame
+pub const MISSING: [u8; 4] = [0xFF, 0x00, 0x00, 0xFF];
+/// Color of pixels lit only in the actual frame
+pub const EXTRA: [u8; 4] = [0x00, 0xFF, 0x00, 0xFF];
+/// Color of pixels lit in neither frame
+pub const BACKGROUND: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+
+/// Load a frame from a screenshot (PNG of the display size or plain PBM), a memory dump (as saved by soak tests) or a
+/// bare framebuffer
+pub fn load(data: &[u8]) -> Result<Frame, String> {
+    if data.starts_with(b"P1") {
+        return pbm::parse_pbm(&String::from_utf8_lossy(data));
+    }
+    if data.starts_with(b"\x89PNG") {
+        let image = png::decode(data)?;
+        if (image.width, image.height) != (DISPLAY_WIDTH, DISPLAY_HEIGHT) {
+            return Err(format!(
+                "Image is {}x{}, expected {DISPLAY_WIDTH}x{DISPLAY_HEIGHT}",
+                image.width, image.height
+            ));
+        }
+        let mut frame = Frame::default();
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..DISPLAY_WIDTH {
+                // Any opaque color counts, screenshots are colored by the overlay
+                let [r, g, b, a] = image.get(x, y);
+                if a >= 0x80 && r.max(g).max(b) >= 0x80 {
+                    frame.set(x, y, 0xFF);
+                }
+            }
+        }
+        return Ok(frame);
+    }
+    let framebuffer = match data.len() {
+        MEMORY_SIZE => &data[FRAMEBUFFER],
+        FRAMEBUFFER_SIZE => data,
+        len => {
+            return Err(format!(
+                "Not a PNG, memory dump ({MEMORY_SIZE} bytes) or framebuffer ({FRAMEBUFFER_SIZE} bytes), but {len} bytes"
+            ))
+        }
+    };
+    let mut memory = vec![0; MEMORY_SIZE];
+    memory[*FRAMEBUFFER.start()..*FRAMEBUFFER.start() + framebuffer.len()]
+        .copy_from_slice(framebuffer);
+    Ok(Frame::render(&Cpu::new(memory)))
+}
+
+/// Diff image of two frames: pixels missing from the actual frame in red, extra pixels in green and pixels lit in both in
+/// gray. Returns the image and the number of pixels that differ.
+pub fn diff(expected: &Frame, actual: &Frame) -> (Image, usize) {
+    let mut image = Image::new(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    let mut differing = 0;
+    for y in 0..DISPLAY_HEIGHT {
+        for x in 0..DISPLAY_WIDTH {
+            let color = match (expected.get(x, y) > 0, actual.get(x, y) > 0) {
+                (true, true) => SAME,
+                (true, false) => MISSING,
+                (false, true) => EXTRA,
+                (false, false) => BACKGROUND,
+            };
+            if color == MISSING || color == EXTRA {
+                differing += 1;
+            }
+            image.set(x, y, color);
+        }
+    }
+    (image, differing)
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::palette::Color;
+
+#[test]
+fn load_dumps() {
+    let mut memory = vec![0; MEMORY_SIZE];
+    memory[0x2400] = 0x01; // Bottom left
+    let frame = load(&memory).unwrap();
+    assert_eq!(frame.get(0, 255), 0xFF);
+    assert_eq!(frame.get(0, 254), 0);
+
+    let framebuffer = &memory[0x2400..0x4000];
+    assert_eq!(load(framebuffer), Ok(frame));
+    assert!(load(&[0; 100]).unwrap_err().contains("but 100 bytes"));
+}
+
+#[test]
+fn load_screenshot() {
+    let mut frame = Frame::default();
+    frame.set(10, 20, 0xFF);
+    frame.set(11, 40, 0xFF);
+    // Red and green overlays still count as lit
+    let colors = |_, y| Color::from_argb(if y < 30 { 0xFFFF0000 } else { 0xFF00FF00 });
+    let png = png::encode(&frame.to_image(colors));
+    assert_eq!(load(&png), Ok(frame));
+
+    let small = png::encode(&Image::new(8, 8));
+    assert_eq!(load(&small), Err("Image is 8x8, expected 224x256".into()));
+}
+
+#[test]
+fn diff_frames() {
+    let mut expected = Frame::default();
+    let mut actual = Frame::default();
+    expected.set(1, 1, 0xFF);
+    actual.set(1, 1, 0x80);
+    expected.set(2, 2, 0xFF);
+    actual.set(3, 3, 0xFF);
+
+    let (image, differing) = diff(&expected, &actual);
+    assert_eq!(differing, 2);
+    assert_eq!(image.get(1, 1), SAME);
+    assert_eq!(image.get(2, 2), MISSING);
+    assert_eq!(image.get(3, 3), EXTRA);
+    assert_eq!(image.get(0, 0), BACKGROUND);
+
+    assert_eq!(diff(&expected, &expected).1, 0);
+}
+
+#[test]
+fn load_pbm() {
+    let mut frame = Frame::default();
+    frame.set(3, 7, 0xFF);
+    assert_eq!(load(pbm::pbm(&frame).as_bytes()), Ok(frame));
+}
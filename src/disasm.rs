@@ -0,0 +1,187 @@
+//! Disassembly of a rom into a labeled, commented listing. Code is told from data by following jumps and calls from the
+//! entry points and from every address executed when running the rom, while bytes only ever read as data are left alone.
+
+use std::{
+    fmt::Write,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    cpu::{Cpu, Flow},
+    hash::crc32,
+    profile::Profile,
+    ram_map,
+    soak::check,
+    sprites::SPRITES,
+    FPS, FREQ, RAM,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Reset and the interrupt vectors used by the board
+pub const ENTRY_POINTS: [(usize, &str); 3] = [
+    (0x0000, "Reset"),
+    (0x0008, "Interrupt RST 1, middle of the screen"),
+    (0x0010, "Interrupt RST 2, end of the screen"),
+];
+
+/// Data bytes per DB line
+const DATA_PER_LINE: usize = 8;
+
+/// Profile of the rom running from power on without input for a number of frames. The run ends early if the program
+/// counter or stack pointer go astray.
+pub fn trace(rom: &[u8], frames: u64) -> Profile {
+    let mut cpu = Cpu::new(rom.to_vec());
+    cpu.enable_profile();
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        for _ in 0..frames {
+            for i in [1, 2] {
+                let mut cycles = 0;
+                while cycles < FREQ / FPS / 2 {
+                    cycles += cpu.step();
+                    if check(&cpu).is_some() {
+                        return;
+                    }
+                }
+                cpu.interrupt(i);
+            }
+        }
+    }));
+    cpu.profile().cloned().unwrap_or_default()
+}
+
+/// Instruction starts in the rom, found by following the flow of control from the entry points and the addresses
+/// executed in the profile. Bytes covered by an instruction are never the start of another one, and bytes only read as
+/// data are never code.
+pub fn find_code(rom: &[u8], profile: &Profile) -> Vec<bool> {
+    let cpu = Cpu::new(rom.to_vec());
+    let mut code = vec![false; rom.len()];
+    let mut covered = vec![false; rom.len()];
+    // Entry points first, from reset
+    let mut todo: Vec<usize> = (0..rom.len())
+        .rev()
+        .filter(|a| profile.executed(*a))
+        .collect();
+    todo.extend(ENTRY_POINTS.iter().rev().map(|(addr, _)| *addr));
+    while let Some(mut addr) = todo.pop() {
+        while addr < rom.len() && !covered[addr] {
+            let instr = cpu.disassemble(addr);
+            let end = addr + instr.bytes.len();
+            if end > rom.len() || (addr..end).any(|a| covered[a] || profile.data(a)) {
+                break;
+            }
+            code[addr] = true;
+            covered[addr..end].fill(true);
+            match instr.flow {
+                Flow::Next => addr = end,
+                Flow::Jump(target) => {
+                    todo.push(target);
+                    break;
+                }
+                Flow::Branch(target) => {
+                    todo.push(target);
+                    addr = end;
+                }
+                Flow::Stop => break,
+            }
+        }
+    }
+    code
+}
+
+/// Address operand of an instruction as written in the mnemonic (e.g. `JMP 18D4H`)
+fn operand(mnemonic: &str) -> Option<usize> {
+    let last = mnemonic.rsplit([' ', ',']).next()?;
+    let hex = last.strip_suffix('H').filter(|h| h.len() == 4)?;
+    usize::from_str_radix(hex, 16).ok()
+}
+
+/// Labeled listing of the rom: code as instructions with descriptions and the names of the RAM addresses used, data as
+/// DB lines. The profile is from `trace` or a play session, possibly empty.
+pub fn listing(rom: &[u8], profile: &Profile) -> String {
+    let code = find_code(rom, profile);
+    let cpu = Cpu::new(rom.to_vec());
+    let label = |addr: usize| format!("L{addr:04X}");
+
+    // Targets of jumps and calls get labels
+    let mut labeled = vec![false; rom.len()];
+    for (addr, _) in ENTRY_POINTS {
+        if let Some(l) = labeled.get_mut(addr) {
+            *l = true;
+        }
+    }
+    for addr in (0..rom.len()).filter(|a| code[*a]) {
+        if let Flow::Jump(target) | Flow::Branch(target) = cpu.disassemble(addr).flow {
+            if let Some(l) = labeled.get_mut(target) {
+                *l = true;
+            }
+        }
+    }
+
+    let mut r = String::new();
+    let _ = writeln!(
+        r,
+        "; Disassembly of a {} byte rom, CRC-32 {:08x}",
+        rom.len(),
+        crc32(rom)
+    );
+    let instructions = code.iter().filter(|c| **c).count();
+    let executed = (0..rom.len()).filter(|a| profile.executed(*a)).count();
+    let data = (0..rom.len()).filter(|a| profile.data(*a)).count();
+    let _ = writeln!(
+        r,
+        "; {instructions} instructions found following the code from the entry points and {executed} executed addresses"
+    );
+    let _ = writeln!(r, "; {data} bytes read as data and never executed");
+    let _ = writeln!(r, "\n        ORG 0000H");
+
+    let mut addr = 0;
+    while addr < rom.len() {
+        if let Some((_, name)) = ENTRY_POINTS.iter().find(|(a, _)| *a == addr) {
+            let _ = writeln!(r, "\n; {name}");
+        }
+        if let Some(sprite) = SPRITES.iter().find(|s| s.addr == addr) {
+            let _ = writeln!(r, "\n; Sprite {}", sprite.name);
+        }
+        let name = if labeled[addr] {
+            format!("{}:", label(addr))
+        } else {
+            String::new()
+        };
+        if code[addr] {
+            let instr = cpu.disassemble(addr);
+            let mut mnemonic = instr.mnemonic.clone();
+            let mut comment = instr.description.to_string();
+            if let Some(target) = operand(&mnemonic) {
+                if labeled.get(target).is_some_and(|l| *l) {
+                    mnemonic = mnemonic.replace(&format!("{target:04X}H"), &label(target));
+                } else if RAM.contains(&target) {
+                    if let Some(ram) = ram_map::annotate(target) {
+                        comment = format!("{comment} ({ram})");
+                    }
+                }
+            }
+            let _ = writeln!(r, "{name:<8}{mnemonic:<24}; {addr:04X} {comment}");
+            addr += instr.bytes.len();
+        } else {
+            // Data up to the next code, label, sprite or entry point
+            let mut end = addr + 1;
+            while end < rom.len()
+                && end - addr < DATA_PER_LINE
+                && !code[end]
+                && !labeled[end]
+                && !SPRITES.iter().any(|s| s.addr == end)
+                && !ENTRY_POINTS.iter().any(|(a, _)| *a == end)
+            {
+                end += 1;
+            }
+            let bytes: Vec<String> = rom[addr..end].iter().map(|b| format!("{b:02X}H")).collect();
+            let db = format!("DB {}", bytes.join(","));
+            let _ = writeln!(r, "{name:<8}{db:<24}; {addr:04X}");
+            addr = end;
+        }
+    }
+    let _ = writeln!(r, "\n        END");
+    r
+}
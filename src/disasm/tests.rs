@@ -0,0 +1,65 @@
+use super::*;
+use crate::profile::{EXECUTED, READ};
+
+/// Jump over a data byte to a loop copying data to the player 1 score, data after the loop
+fn program() -> Vec<u8> {
+    vec![
+        0xC3, 0x04, 0x00, // JMP 0004h
+        0xAA, // Data
+        0x3A, 0x0E, 0x00, // LDA 000Eh
+        0x32, 0xF8, 0x20, // STA 20F8h
+        0xC2, 0x04, 0x00, // JNZ 0004h
+        0x76, // HLT
+        0x01, 0x02, // Data
+    ]
+}
+
+#[test]
+fn operands() {
+    assert_eq!(operand("JMP 18D4H"), Some(0x18D4));
+    assert_eq!(operand("LXI SP, 2400H"), Some(0x2400));
+    assert_eq!(operand("MVI B, 03H"), None);
+    assert_eq!(operand("RET"), None);
+}
+
+#[test]
+fn follow_code() {
+    let code = find_code(&program(), &Profile::default());
+    let starts: Vec<usize> = (0..code.len()).filter(|a| code[*a]).collect();
+    assert_eq!(starts, [0x00, 0x04, 0x07, 0x0A, 0x0D]);
+
+    // An executed address makes the data byte code
+    let mut profile = Profile::default();
+    profile.mark(0x03, EXECUTED);
+    assert!(find_code(&program(), &profile)[0x03]);
+
+    // Bytes only read as data are never code
+    let mut profile = Profile::default();
+    profile.mark(0x0D, READ);
+    assert!(!find_code(&program(), &profile)[0x0D]);
+}
+
+#[test]
+fn trace_program() {
+    let profile = trace(&program(), 1);
+    assert!([0x00, 0x04, 0x07, 0x0A]
+        .iter()
+        .all(|a| profile.executed(*a)));
+    // The loop never ends, zero is not set
+    assert!(!profile.executed(0x03) && !profile.executed(0x0D));
+    assert!(profile.data(0x0E) && !profile.data(0x0F));
+}
+
+#[test]
+fn labeled_listing() {
+    let listing = listing(&program(), &trace(&program(), 1));
+    assert!(listing.contains("; Disassembly of a 16 byte rom"));
+    assert!(listing.contains("and 4 executed addresses\n; 1 bytes read as data"));
+    assert!(listing.contains("; Reset\nL0000:  JMP L0004               ; 0000 Jump to address\n"));
+    assert!(listing.contains("        DB AAH                  ; 0003\n"));
+    assert!(listing
+        .contains("        STA 20F8H               ; 0007 Store accumulator direct (P1Scor)\n"));
+    assert!(listing.contains("        JNZ L0004"));
+    assert!(listing.contains("        DB 01H,02H              ; 000E\n"));
+    assert!(listing.ends_with("END\n"));
+}
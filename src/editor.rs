@@ -0,0 +1,134 @@
+//! Hex editor for the memory of the running machine, with undo and redo. ROM is protected from edits by default.
+
+use crate::{cpu::Cpu, ram_map, MEMORY, RAM, ROM};
+
+#[cfg(test)]
+mod tests;
+
+/// Rows of bytes shown
+pub const ROWS: usize = 16;
+/// Bytes per row
+pub const PER_ROW: usize = 8;
+
+/// A byte changed by the editor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub addr: usize,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// Editor state: cursor, the high nibble typed so far and the edit history
+#[derive(Debug)]
+pub struct Editor {
+    /// Address of the byte being edited
+    pub cursor: usize,
+    /// First address shown
+    top: usize,
+    /// High nibble typed, waiting for the low one
+    nibble: Option<u8>,
+    /// Edits of ROM are refused
+    pub protect_rom: bool,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Editor {
+            cursor: *RAM.start(),
+            top: *RAM.start(),
+            nibble: None,
+            protect_rom: true,
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+}
+
+impl Editor {
+    /// Move the cursor to an address (clamped to memory), scrolling it into view
+    pub fn goto(&mut self, addr: usize) {
+        self.cursor = addr.min(*MEMORY.end());
+        self.nibble = None;
+        if self.cursor < self.top {
+            self.top = self.cursor - self.cursor % PER_ROW;
+        } else if self.cursor >= self.top + ROWS * PER_ROW {
+            self.top = self.cursor - self.cursor % PER_ROW - (ROWS - 1) * PER_ROW;
+        }
+    }
+
+    /// Move the cursor by a number of bytes, e.g. `PER_ROW` for a row down
+    pub fn move_by(&mut self, delta: isize) {
+        self.goto(self.cursor.saturating_add_signed(delta));
+    }
+
+    /// Type a hex digit (0-15). The second digit writes the byte and moves to the next one.
+    pub fn type_digit(&mut self, cpu: &mut Cpu, digit: u8) -> Result<(), String> {
+        if self.protect_rom && ROM.contains(&self.cursor) {
+            return Err("ROM PROTECTED".into());
+        }
+        match self.nibble.take() {
+            None => self.nibble = Some(digit & 0xF),
+            Some(high) => {
+                let edit = Edit {
+                    addr: self.cursor,
+                    old: cpu.memory()[self.cursor],
+                    new: high << 4 | digit & 0xF,
+                };
+                cpu.poke(edit.addr, edit.new);
+                self.undo.push(edit);
+                self.redo.clear();
+                self.move_by(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Undo the last edit, returns it if there was one
+    pub fn undo(&mut self, cpu: &mut Cpu) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        cpu.poke(edit.addr, edit.old);
+        self.redo.push(edit);
+        self.goto(edit.addr);
+        Some(edit)
+    }
+
+    /// Redo the last undone edit, returns it if there was one
+    pub fn redo(&mut self, cpu: &mut Cpu) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        cpu.poke(edit.addr, edit.new);
+        self.undo.push(edit);
+        self.goto(edit.addr);
+        Some(edit)
+    }
+
+    /// Text lines to show, in the characters of the font: the cursor address with its name in the RAM map, then rows
+    /// of bytes with `>` in front of the cursor and `-` for the low nibble not typed yet
+    pub fn lines(&self, cpu: &Cpu) -> Vec<String> {
+        let name = ram_map::annotate(self.cursor).unwrap_or_default();
+        let lock = if self.protect_rom && ROM.contains(&self.cursor) {
+            " ROM"
+        } else {
+            ""
+        };
+        let header = format!("{:04X}{lock} {}", self.cursor, name.to_uppercase());
+        let mut lines = vec![header.trim_end().to_string()];
+        for row in 0..ROWS {
+            let start = self.top + row * PER_ROW;
+            if start > *MEMORY.end() {
+                break;
+            }
+            let mut line = format!("{start:04X}");
+            for addr in start..(start + PER_ROW).min(MEMORY.end() + 1) {
+                line.push(if addr == self.cursor { '>' } else { ' ' });
+                match self.nibble.filter(|_| addr == self.cursor) {
+                    Some(high) => line += &format!("{high:X}-"),
+                    None => line += &format!("{:02X}", cpu.memory()[addr]),
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+
+#[test]
+fn navigate() {
+    let mut editor = Editor::default();
+    assert_eq!(editor.cursor, 0x2000);
+    editor.move_by(-1);
+    assert_eq!((editor.cursor, editor.top), (0x1FFF, 0x1FF8));
+    editor.move_by((ROWS * PER_ROW) as isize);
+    assert_eq!((editor.cursor, editor.top), (0x207F, 0x2000));
+    editor.goto(0);
+    editor.move_by(-1);
+    assert_eq!((editor.cursor, editor.top), (0, 0));
+    editor.goto(0xFFFF);
+    assert_eq!(editor.cursor, *MEMORY.end());
+}
+
+#[test]
+fn edit_undo_redo() {
+    let mut cpu = Cpu::new(vec![]);
+    let mut editor = Editor::default();
+    editor.goto(0x20F8);
+    editor.type_digit(&mut cpu, 0x1).unwrap();
+    assert_eq!(cpu.memory()[0x20F8], 0);
+    editor.type_digit(&mut cpu, 0x2).unwrap();
+    editor.type_digit(&mut cpu, 0x3).unwrap();
+    editor.type_digit(&mut cpu, 0x4).unwrap();
+    assert_eq!(&cpu.memory()[0x20F8..0x20FA], [0x12, 0x34]);
+    assert_eq!(editor.cursor, 0x20FA);
+
+    assert_eq!(
+        editor.undo(&mut cpu),
+        Some(Edit {
+            addr: 0x20F9,
+            old: 0,
+            new: 0x34
+        })
+    );
+    assert_eq!(cpu.memory()[0x20F9], 0);
+    assert_eq!(editor.cursor, 0x20F9);
+    assert!(editor.redo(&mut cpu).is_some());
+    assert_eq!(cpu.memory()[0x20F9], 0x34);
+    assert!(editor.redo(&mut cpu).is_none());
+
+    // A new edit forgets what was undone
+    editor.undo(&mut cpu);
+    editor.type_digit(&mut cpu, 0xA).unwrap();
+    editor.type_digit(&mut cpu, 0xB).unwrap();
+    assert!(editor.redo(&mut cpu).is_none());
+    assert_eq!(cpu.memory()[0x20F9], 0xAB);
+}
+
+#[test]
+fn protect_rom() {
+    let mut cpu = Cpu::new(vec![0x00; 0x10]);
+    let mut editor = Editor::default();
+    editor.goto(0x0008);
+    assert_eq!(
+        editor.type_digit(&mut cpu, 0xC),
+        Err("ROM PROTECTED".into())
+    );
+    editor.protect_rom = false;
+    editor.type_digit(&mut cpu, 0xC).unwrap();
+    editor.type_digit(&mut cpu, 0x9).unwrap();
+    assert_eq!(cpu.memory()[0x0008], 0xC9);
+}
+
+#[test]
+fn show_lines() {
+    let mut cpu = Cpu::new(vec![]);
+    let mut editor = Editor::default();
+    editor.goto(0x20F9);
+    editor.type_digit(&mut cpu, 0x5).unwrap();
+    let lines = editor.lines(&cpu);
+    assert_eq!(lines.len(), ROWS + 1);
+    assert_eq!(lines[0], "20F9 P1SCOR+1");
+    assert_eq!(lines[ROWS - 1], "20F0 00 00 00 00 00 00 00 00");
+    assert_eq!(lines[ROWS], "20F8 00>5- 00 00 00 00 00 00");
+    assert!(lines.iter().all(|l| l.len() <= 28));
+
+    editor.goto(0x0000);
+    assert_eq!(editor.lines(&cpu)[0], "0000 ROM");
+}
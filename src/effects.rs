@@ -0,0 +1,79 @@
+//! Controller effects of game events: rumble and LED colors of the game controllers, for cabinet builders. The events
+//! are the sounds of the board starting, as the game has no other way of telling (`ufo` when the UFO appears, `die`
+//! when the player dies, `xp` for an extra life on Space Invaders). An effects file has a line per effect,
+//! `<sound> rumble <strength> <ms>` with strength 0-65535 or `<sound> led <color>`, and comment lines starting with `#`
+//! (e.g. `ufo led red`). The LED keeps its color until the next LED effect.
+
+use crate::{palette::Color, utils::parse_number};
+
+#[cfg(test)]
+mod tests;
+
+/// What a controller does
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Rumble { strength: u16, ms: u32 },
+    Led(Color),
+}
+
+/// Effects of the sounds of a board
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Effects {
+    effects: Vec<(String, Effect)>,
+}
+
+impl Effects {
+    /// Parse an effects file for a board with the given sounds
+    pub fn parse(text: &str, sounds: &[&str]) -> Result<Self, String> {
+        let mut effects = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("Line {}: invalid effect `{line}`", i + 1);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let effect = match words[1..] {
+                ["rumble", strength, ms] => Effect::Rumble {
+                    strength: parse_number(strength)
+                        .and_then(|s| u16::try_from(s).ok())
+                        .ok_or_else(error)?,
+                    ms: parse_number(ms)
+                        .and_then(|ms| u32::try_from(ms).ok())
+                        .ok_or_else(error)?,
+                },
+                ["led", color] => {
+                    Effect::Led(Color::parse(color).map_err(|e| format!("Line {}: {e}", i + 1))?)
+                }
+                _ => return Err(error()),
+            };
+            if !sounds.contains(&words[0]) {
+                return Err(format!(
+                    "Line {}: no sound {}, the board has {}",
+                    i + 1,
+                    words[0],
+                    sounds.join(", ")
+                ));
+            }
+            effects.push((words[0].to_string(), effect));
+        }
+        Ok(Effects { effects })
+    }
+
+    /// Add an effect of a sound
+    pub fn push(&mut self, sound: &str, effect: Effect) {
+        self.effects.push((sound.to_string(), effect));
+    }
+
+    /// Effects of a sound starting, in the order given
+    pub fn of<'a>(&'a self, sound: &'a str) -> impl Iterator<Item = Effect> + 'a {
+        self.effects
+            .iter()
+            .filter(move |(name, _)| name == sound)
+            .map(|(_, effect)| *effect)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
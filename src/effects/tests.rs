@@ -0,0 +1,54 @@
+use super::*;
+
+const SOUNDS: [&str; 3] = ["ufo", "die", "xp"];
+
+#[test]
+fn parse_effects() {
+    let text = "# cabinet\nufo led red\ndie rumble 40000 500\n\ndie led #000080\nxp led green\n";
+    let effects = Effects::parse(text, &SOUNDS).unwrap();
+    assert_eq!(
+        effects.of("die").collect::<Vec<_>>(),
+        [
+            Effect::Rumble {
+                strength: 40000,
+                ms: 500
+            },
+            Effect::Led(Color::rgb(0, 0, 0x80))
+        ]
+    );
+    assert_eq!(
+        effects.of("ufo").collect::<Vec<_>>(),
+        [Effect::Led(Color::rgb(0xff, 0, 0))]
+    );
+    assert_eq!(effects.of("shot").count(), 0);
+    assert!(Effects::parse("", &SOUNDS).unwrap().is_empty());
+}
+
+#[test]
+fn invalid_effects() {
+    assert_eq!(
+        Effects::parse("die rumble 70000 500", &SOUNDS).unwrap_err(),
+        "Line 1: invalid effect `die rumble 70000 500`"
+    );
+    assert!(Effects::parse("die rumble 100", &SOUNDS).is_err());
+    assert!(Effects::parse("die", &SOUNDS).is_err());
+    assert!(Effects::parse("die flash", &SOUNDS).is_err());
+    assert!(Effects::parse("\ndie led mauve", &SOUNDS)
+        .unwrap_err()
+        .starts_with("Line 2: Invalid color mauve"));
+    assert_eq!(
+        Effects::parse("shot led red", &SOUNDS).unwrap_err(),
+        "Line 1: no sound shot, the board has ufo, die, xp"
+    );
+}
+
+#[test]
+fn pushed_effects() {
+    let mut effects = Effects::default();
+    let rumble = Effect::Rumble {
+        strength: 100,
+        ms: 10,
+    };
+    effects.push("die", rumble);
+    assert_eq!(effects.of("die").collect::<Vec<_>>(), [rumble]);
+}
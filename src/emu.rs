@@ -1,21 +1,84 @@
 //! Emulator implementation using SDL3 for I/O
 
 use std::{
-    thread::sleep,
-    time::{Duration, Instant},
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::mpsc::{Receiver, Sender},
+    time::{Duration, Instant, SystemTime},
 };
 
 use sdl3::{
     audio::{AudioSpec, AudioSpecWAV, AudioStreamOwner},
-    event::Event,
-    keyboard::{Keycode, Scancode},
+    event::{Event, WindowEvent},
+    gamepad::{Button, Gamepad},
+    iostream::IOStream,
+    keyboard::{Keycode, Mod, Scancode},
     pixels::{Color, PixelFormat},
     rect::{Point, Rect},
     render::{self, BlendMode, ScaleMode},
-    sys::pixels::{SDL_PixelFormat, SDL_PIXELFORMAT_ARGB8888},
+    surface::Surface,
+    sys::{
+        joystick::SDL_JoystickID,
+        pixels::{SDL_PixelFormat, SDL_PIXELFORMAT_ARGB8888, SDL_PIXELFORMAT_RGBA32},
+        render::{SDL_SetRenderVSync, SDL_LOGICAL_PRESENTATION_LETTERBOX},
+        timer::SDL_GetTicksNS,
+    },
+    AudioSubsystem, GamepadSubsystem,
 };
 
-use crate::{cpu::Cpu, utils::get_bit, DISPLAY_HEIGHT, DISPLAY_WIDTH, FPS, FREQ};
+use crate::{
+    assets::Locator,
+    avsync::AvSync,
+    batch::{self, random_policy, Noise},
+    bench::{Conversions, SpareCycles},
+    bindings::{Action, Bindings, Modifier},
+    cheats::{self, Cheat, Companions, Offer},
+    colors,
+    commands::{Command, CommandQueue},
+    compare::{self, Slot},
+    convert::{self, Conversion, Lut},
+    corpus::{Comparison, Corpus},
+    cpu::Cpu,
+    editor::{Editor, PER_ROW, ROWS},
+    effects::{Effect, Effects},
+    expr::{Trigger, Watch},
+    frame::{Blend, Frame, Glow},
+    freeplay::FreePlay,
+    fullscreen,
+    histogram::FramePacing,
+    hotplug::{self, Controllers, Devices, PLAYERS},
+    inset::{self, Corner, Feed, Image},
+    ips,
+    latency::{FlashTest, Pipeline, Sensor},
+    machine::{Layer, Machine, Overlay},
+    masking::{self, Diagnosis, Masking},
+    menu::Menu,
+    palette::{self, Palette},
+    pbm, png,
+    ports::INVADERS,
+    present::{Pacing, Present, VsyncCheck},
+    profile::Profile,
+    ram_map,
+    remote::Remote,
+    rewind::History,
+    runahead::RunAhead,
+    scaling::{self, Scaling},
+    serial::Console,
+    shutdown,
+    stack::StackWarning,
+    stats::Stats,
+    text::{self, Announcer},
+    timing::{self, Clock, ClockMeter, Delivery, InterruptTiming, Pacer, RealClock},
+    tournament::{Event as TournamentEvent, Tournament},
+    user::User,
+    utils::get_bit,
+    view::{Publisher, Reader},
+    watchdog::Watchdog,
+    wav::{self, Recorder},
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, FPS, FREQ, NPORTS,
+};
 
 #[cfg(test)]
 mod tests;
@@ -25,14 +88,208 @@ mod tests;
 pub struct Options {
     /// Scale of the display
     pub scale: u32,
-    /// Foreground color
-    pub color: u32,
-    /// Background color
-    pub background: u32,
-    /// Color of top overlay
-    pub top: u32,
-    /// Color of bottom overlay
-    pub bottom: u32,
+    /// Foreground, background and overlay colors
+    pub palette: Palette,
+    /// Cabinet artwork drawn around the playfield
+    pub bezel: Option<Bezel>,
+    /// Index of the display to open the window on (primary display if none)
+    pub display: Option<usize>,
+    /// Start in fullscreen
+    pub fullscreen: bool,
+    /// Make the fullscreen exclusive, in a display mode refreshing at the frame rate (or a multiple of it)
+    pub exclusive: bool,
+    /// Way of presenting frames, for a display or any display
+    pub present: Vec<(Option<usize>, Present)>,
+    /// Blending of consecutive frames to reduce flicker (toggle with F2)
+    pub blend: Blend,
+    /// Pixel glow
+    pub glow: Option<Glow>,
+    /// Rom file to watch, the rom is reloaded and the machine power cycled whenever it changes
+    pub watch_rom: Option<String>,
+    /// Rom file run, cheats (`.cht`) and patches (`.ips`) next to it are offered when they appear or change
+    pub rom: Option<PathBuf>,
+    /// Teaching mode, run this many instructions per second and explain each of them
+    pub teach: Option<u32>,
+    /// Show a bar with the cycles spent in interrupt handlers vs main loop and the effective clock (toggle with F5)
+    pub cycle_bar: bool,
+    /// Percent of the cycles during active display stolen by the video circuitry (0 is off)
+    pub cycle_steal: u32,
+    /// Emulation speed in percent of normal (change with F9 and F10), the game sounds are ducked when not 100
+    pub speed: u32,
+    /// Run as fast as possible, with the interrupts still every frame of cycles, and report the spare cycles per frame
+    pub free_run: bool,
+    /// Measure the input latency: flash the playfield and time the responses, and time key presses to the display
+    pub latency_test: bool,
+    /// Watch the frame loop from another thread, dump the state and reset when it hangs
+    pub watchdog: bool,
+    /// Keep the state of every frame, to go back with the rewind key
+    pub rewind: bool,
+    /// Tell when no interrupt is delivered for this many frames, with where the CPU is spinning
+    pub masked_frames: Option<u32>,
+    /// Conversion of frames to the pixels of the game texture
+    pub conversion: Conversion,
+    /// Scaling of the game picture to the window
+    pub scaling: Scaling,
+    /// Benchmark the conversions in the window, each for 10 seconds, then quit
+    pub bench_conversion: bool,
+    /// Called with new text appearing on screen (scores, credits, messages), e.g. for text to speech
+    pub announce: Option<fn(&str)>,
+    /// Rumble game controllers when the player dies, as (strength, duration in ms)
+    pub rumble: Option<(u16, u32)>,
+    /// Rumble and LED colors of game controllers when sounds start, for events like the UFO appearing
+    pub effects: Effects,
+    /// Directory with ambient cabinet sounds (hum.wav, coin.wav, click.wav, startup.wav), mixed in at low volume
+    pub ambience: Option<String>,
+    /// Record the game sounds to this WAV file
+    pub record_audio: Option<String>,
+    /// Write the session summary printed on quit to this JSON file
+    pub stats_json: Option<String>,
+    /// Print histograms of the frame times and sleep errors on quit
+    pub pacing_histogram: bool,
+    /// Draw the histograms of the frame times and sleep errors to this PNG file on quit
+    pub pacing_png: Option<String>,
+    /// Log when the interrupts were delivered vs their scan lines to this CSV file
+    pub interrupt_log: Option<String>,
+    /// Print the stack usage (lowest stack pointer, overflows and deepest call chain) on quit
+    pub stack_report: bool,
+    /// Profile which rom bytes are executed and read as data, added to this file on quit (for `disasm --profile`)
+    pub profile: Option<String>,
+    /// Do not play sound on the audio device
+    pub mute: bool,
+    /// What to do while the window is in the background
+    pub focus_loss: FocusLoss,
+    /// Correct the frame time for drift against the audio clock
+    pub av_sync: bool,
+    /// Present a frame run ahead with the inputs predicted, rolling back when they change
+    pub run_ahead: bool,
+    /// Expressions printed whenever their value changes, checked every frame
+    pub watches: Vec<Watch>,
+    /// Insert the coins needed when start is pressed without credits
+    pub free_play: bool,
+    /// Tournament of one-credit games between registered players
+    pub tournament: Option<Tournament>,
+    /// Board emulated: ports, interrupts, sounds and overlays
+    pub machine: Machine,
+    /// User whose key bindings are used and whose leaderboard gets the best score of the session, saved on quit
+    pub user: Option<User>,
+    /// Receive input from a remote control panel on this UDP address, e.g. `0.0.0.0:9000`
+    pub remote: Option<String>,
+    /// Token that remote input messages must carry
+    pub remote_token: Option<String>,
+    /// Player 1's keys and controller also control player 2 in player 2's turn
+    pub mirror_p2: bool,
+    /// Save a screenshot when any of these first becomes true, checked every frame
+    pub screenshot_triggers: Vec<Trigger>,
+    /// Image format of screenshots
+    pub screenshot_format: ScreenshotFormat,
+    /// Picture-in-picture image (BMP) shown until the frontend sends another, e.g. a logo
+    pub inset: Option<String>,
+    /// Corner of the picture-in-picture
+    pub inset_corner: Corner,
+    /// Pause and open the debugger whenever any of these becomes true, checked every frame
+    pub break_triggers: Vec<Trigger>,
+    /// Compare the framebuffer with the hashes of a corpus every frame, pausing where it first diverges
+    pub reference: Option<Corpus>,
+    /// Let a bot play with input noise from this seed, reproducible on every machine
+    pub bot: Option<u64>,
+    /// Where to look for the sounds
+    pub assets: Locator,
+}
+
+impl Default for Options {
+    /// Space Invaders in a window at 3 times the size, with the colors of the cabinet
+    fn default() -> Self {
+        Options {
+            scale: 3,
+            palette: Palette::default(),
+            bezel: None,
+            display: None,
+            fullscreen: false,
+            exclusive: false,
+            present: vec![],
+            blend: Blend::Off,
+            glow: None,
+            watch_rom: None,
+            rom: None,
+            teach: None,
+            cycle_bar: false,
+            cycle_steal: 0,
+            speed: 100,
+            free_run: false,
+            latency_test: false,
+            watchdog: false,
+            rewind: false,
+            masked_frames: Some(masking::FRAMES),
+            conversion: Conversion::default(),
+            scaling: Scaling::default(),
+            bench_conversion: false,
+            announce: None,
+            rumble: None,
+            effects: Effects::default(),
+            ambience: None,
+            record_audio: None,
+            stats_json: None,
+            pacing_histogram: false,
+            pacing_png: None,
+            interrupt_log: None,
+            stack_report: false,
+            profile: None,
+            mute: false,
+            focus_loss: FocusLoss::Run,
+            watches: vec![],
+            screenshot_triggers: vec![],
+            screenshot_format: ScreenshotFormat::Png,
+            inset: None,
+            inset_corner: Corner::default(),
+            break_triggers: vec![],
+            mirror_p2: false,
+            machine: Machine::invaders(),
+            free_play: false,
+            tournament: None,
+            av_sync: false,
+            run_ahead: false,
+            user: None,
+            remote: None,
+            remote_token: None,
+            reference: None,
+            bot: None,
+            assets: Locator::default(),
+        }
+    }
+}
+
+/// What the emulator does while the window is in the background
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FocusLoss {
+    /// Keep running as usual
+    Run,
+    /// Pause until the window is focused again
+    Pause,
+    /// Mute and run `BACKGROUND_FPS` frames per second, to save battery
+    Throttle,
+}
+
+/// Image format of screenshots
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScreenshotFormat {
+    /// The displayed frame in the colors of the overlay, on a transparent background
+    Png,
+    /// The framebuffer in memory as a plain PBM, 1 bit per pixel
+    Pbm,
+    /// The framebuffer in memory as XBM
+    Xbm,
+}
+
+/// Cabinet bezel artwork, a much simplified take on MAME artwork files
+#[derive(Debug)]
+pub struct Bezel {
+    /// Path to the artwork image (BMP)
+    pub path: String,
+    /// Opacity of the artwork, 0 (invisible) to 255 (opaque)
+    pub opacity: u8,
+    /// Placement of the playfield within the artwork as (x, y, width, height) in artwork pixels, a width or height of 0
+    /// taken as 1
+    pub playfield: (i32, i32, u32, u32),
 }
 
 type SoundState<'a> = (
@@ -40,11 +297,56 @@ type SoundState<'a> = (
     u8,
     &'a str,
     Option<AudioStreamOwner>,
-    Option<AudioSpecWAV>,
+    Option<Vec<u8>>,
     bool,
 );
+
+/// Ambient sounds that may be provided in the ambience directory
+const AMBIENT_SOUNDS: [&str; 4] = ["hum", "coin", "click", "startup"];
+/// Gain of ambient sounds relative to the game sounds
+const AMBIENT_GAIN: f32 = 0.25;
+/// How long on-screen messages are shown, in frames
+const OSD_FRAMES: u32 = 5 * FPS;
+/// Frames converted by each strategy in the benchmark of the display conversions
+const CONVERSION_BENCH_FRAMES: u64 = 10 * FPS as u64;
+/// Top of the memory editor on screen, below the messages
+const EDITOR_TOP: u32 = 32;
+/// Keys typing the hex digits 0-F in the memory editor
+const HEX_KEYS: [Keycode; 16] = [
+    Keycode::_0,
+    Keycode::_1,
+    Keycode::_2,
+    Keycode::_3,
+    Keycode::_4,
+    Keycode::_5,
+    Keycode::_6,
+    Keycode::_7,
+    Keycode::_8,
+    Keycode::_9,
+    Keycode::A,
+    Keycode::B,
+    Keycode::C,
+    Keycode::D,
+    Keycode::E,
+    Keycode::F,
+];
+/// Gain of the game sounds when not running at normal speed
+const DUCK_GAIN: f32 = 0.3;
+/// Frames per second while throttled in the background
+const BACKGROUND_FPS: u32 = 10;
+/// Format of the game sounds, like the samples
+const AUDIO_SPEC: AudioSpec = AudioSpec {
+    channels: Some(1),
+    freq: Some(11025),
+    format: Some(sdl3::audio::AudioFormat::U8),
+};
+
+/// Old name of `Frontend`
+#[deprecated(since = "0.3.0", note = "renamed to Frontend, use inv8080rs::Frontend")]
+pub type Emu<'a> = Frontend<'a>;
+
 /// The state of the emulator
-pub struct Emu<'a> {
+pub struct Frontend<'a> {
     /// CPU-model
     cpu: Cpu,
     /// Options
@@ -55,91 +357,529 @@ pub struct Emu<'a> {
     freq: u32,
     /// Emulator should quit
     quit: bool,
+    /// Emulation is paused (toggle with Space or Pause)
+    paused: bool,
+    /// Paused because the window lost focus, continued when it is focused again
+    paused_in_background: bool,
+    /// Muted and throttled because the window lost focus
+    throttled: bool,
+    /// Run a single frame while paused (Tab)
+    advance: bool,
+    /// Operator mode, entered by holding the service key at boot, allows changing the settings of the machine
+    operator: bool,
     /// SDL Canvas<Window>
     canvas: render::Canvas<sdl3::video::Window>,
     /// SDL Event Pump
     event_pump: sdl3::EventPump,
     /// Sound channels
-    sounds: [SoundState<'a>; 10],
+    sounds: Vec<SoundState<'a>>,
+    /// Bezel artwork and its size in window pixels
+    bezel: Option<(Surface<'static>, u32, u32)>,
+    /// Where the playfield is drawn in the window
+    playfield: Rect,
+    /// Previous (unblended) frame
+    previous: Frame,
+    /// Modification time of the watched rom file
+    watched: Option<SystemTime>,
+    /// Cheat and patch files next to the rom
+    companions: Option<Companions>,
+    /// Cheats and patches waiting to be accepted, the first is asked about on screen
+    offers: Vec<Offer>,
+    /// Cheats accepted, held every frame
+    cheats: Vec<Cheat>,
+    /// Comparison against the reference corpus, until it diverges or ends
+    comparison: Option<Comparison>,
+    /// Teaching mode instruction credit (instructions per second accumulated each frame)
+    teach_credit: u32,
+    /// Teaching mode cycles run since last interrupt and next interrupt
+    teach_cycles: (u32, u8),
+    /// Cycles spent in interrupt handlers and in the main loop during the last frame
+    frame_cycles: (u32, u32),
+    /// Cycles run past the end of the last frame, taken from this one
+    overshoot: u32,
+    /// Output ports after the last real frame, the sounds play from these and not from a frame run ahead
+    outputs: [u8; NPORTS],
+    /// Time read and slept by the frame loop
+    time: Box<dyn Clock>,
+    /// Deadlines of the frames
+    pacer: Pacer,
+    /// Spare cycles per frame, when free running
+    spare_cycles: Option<SpareCycles>,
+    /// Flashes timed to responses and key presses timed to presented frames, in the latency test
+    flash_test: Option<FlashTest>,
+    pipeline: Option<Pipeline>,
+    /// Sensor responses to the flashes
+    sensor: Sensor,
+    /// Time spent by each conversion, when benchmarking them
+    conversions: Option<Conversions>,
+    /// Watching for a hung frame loop
+    watchdog: Option<Watchdog>,
+    /// States of the frames played, when rewinding is on
+    rewind: Option<History>,
+    /// Bytes for the serial console, on machines with one
+    console: Option<Console>,
+    /// Counting the frames the interrupts stay disabled
+    masking: Option<Masking>,
+    /// Histograms of the frame times and sleep errors
+    frame_pacing: Option<FramePacing>,
+    /// Views of the machine for readers on other threads
+    views: Publisher,
+    /// Stack warnings printed, each is printed once
+    stack_warned: HashSet<StackWarning>,
+    /// How frames are paced, by sleeping or by the display
+    pacing: Pacing,
+    /// Vsync really waits for the display
+    vsync_check: VsyncCheck,
+    /// Effective clock measurement
+    clock: ClockMeter,
+    /// Interrupt deliveries of the last frame and their latencies
+    interrupt_timing: InterruptTiming,
+    /// Interrupt deliveries are logged to this CSV file
+    interrupt_log: Option<BufWriter<File>>,
+    /// Remote input backend
+    remote: Option<Remote>,
+    /// Free play input filter, when enabled
+    free_play: Option<FreePlay>,
+    /// Run-ahead, when enabled
+    run_ahead: Option<RunAhead>,
+    /// Changes requested by other threads, applied between frames
+    commands: CommandQueue,
+    /// Picture-in-picture images sent by the frontend
+    inset_feed: Feed,
+    /// Session statistics, summarized on quit
+    stats: Stats,
+    /// On-screen text tracking for announcements
+    announcer: Announcer,
+    /// SDL audio subsystem, to reopen the sounds on another device
+    audio: AudioSubsystem,
+    /// Connected playback devices
+    audio_devices: Devices,
+    /// The default playback device may have changed, the sounds are moved to it
+    reopen_audio: bool,
+    /// SDL gamepad subsystem
+    gamepad: GamepadSubsystem,
+    /// Connected game controllers
+    gamepads: Vec<Gamepad>,
+    /// Game controllers assigned to players
+    controllers: Controllers,
+    /// Ambient cabinet sounds, separate from the sounds of the game
+    ambience: Vec<(&'static str, AudioStreamOwner, AudioSpecWAV)>,
+    /// Recording of the game sounds
+    recorder: Option<(Recorder, wav::Writer)>,
+    /// Drift measurement and the silent stream measured on
+    av_sync: Option<(AvSync, AudioStreamOwner)>,
+    /// Muted sound channels
+    muted: Vec<bool>,
+    /// Sound channel playing solo
+    solo: Option<usize>,
+    /// Input noise of the bot
+    noise: Option<Noise>,
+    /// Keys held down
+    keys: Keys,
+    /// Sound samples being loaded in the background, and the number of sounds received and missing so far
+    loading: Option<(Receiver<wav::Loaded>, usize, usize)>,
+    /// On-screen messages and the number of frames left to show them
+    osd: Vec<(String, u32)>,
+    /// Quick compare slots A and B
+    slots: [Option<Slot>; 2],
+    /// Memory editor, keeping the cursor and history while hidden
+    editor: Editor,
+    /// Memory editor shown over the playfield (toggle with F1)
+    editing: bool,
+    /// Command palette, shown over the top of the playfield when open
+    menu: Menu<Action>,
+    /// Keys and buttons of the emulator commands
+    bindings: Bindings,
+    menu_open: bool,
+    /// Port activity panel shown at the bottom of the playfield (toggle with P)
+    port_panel: bool,
 }
 
 const PIXEL_FORMAT: SDL_PixelFormat = SDL_PIXELFORMAT_ARGB8888;
 
-impl Emu<'_> {
+impl Frontend<'_> {
+    /// Emulator running a rom given in memory, with banks following the rom. With the assets given in memory too
+    /// (`Locator::memory`) nothing is read from the file system. Fails on an empty rom or one too large for the
+    /// machine.
+    pub fn from_rom_bytes(rom: &[u8], options: Options) -> Result<Self, String> {
+        options.machine.check_rom(rom)?;
+        let cpu = options.machine.load(rom);
+        Ok(Frontend::new(cpu, options))
+    }
+
+    /// Number of displays connected, for checking `Options.display` before starting
+    pub fn display_count() -> Result<usize, String> {
+        let sdl = sdl3::init().map_err(|e| format!("Could not initialize SDL: {e}"))?;
+        let video = sdl
+            .video()
+            .map_err(|e| format!("Could not initialize video: {e}"))?;
+        let displays = video
+            .displays()
+            .map_err(|e| format!("Could not get displays: {e}"))?;
+        Ok(displays.len())
+    }
+
     pub fn new(cpu: Cpu, options: Options) -> Self {
+        let mut keys = Keys {
+            mirror: options.mirror_p2,
+            ..Default::default()
+        };
+        if let Some(user) = &options.user {
+            let mut bindings = vec![];
+            for (input, key) in &user.profile.keys {
+                match (options.machine.board.find(input), Scancode::from_name(key)) {
+                    (Some(input), Some(scancode)) => bindings.push((scancode, input.name)),
+                    _ => eprintln!("Could not bind key {key} to input {input}"),
+                }
+            }
+            keys.bind(&bindings);
+        }
         let sdl = sdl3::init().expect("Could not initialize SDL");
         let video = sdl.video().expect("Could not initialize video");
-        let mut canvas = video
-            .window(
-                "Intel 8080 Space Invaders Emulator",
-                DISPLAY_WIDTH * options.scale,
-                DISPLAY_HEIGHT * options.scale,
-            )
-            .position_centered()
-            .build()
-            .expect("Could not initialize window")
-            .into_canvas();
+
+        // With a bezel the window is sized after the artwork, scaled so the playfield gets the requested scale
+        let mut playfield = Rect::new(
+            0,
+            0,
+            DISPLAY_WIDTH * options.scale,
+            DISPLAY_HEIGHT * options.scale,
+        );
+        let mut osd = vec![];
+        let bezel = options.bezel.as_ref().and_then(|bezel| {
+            let artwork = options.assets.read_path(&bezel.path).and_then(|bmp| {
+                let mut stream = IOStream::from_bytes(&bmp).map_err(|e| e.to_string())?;
+                Surface::load_bmp_rw(&mut stream).map_err(|e| e.to_string())
+            });
+            let mut artwork = match artwork {
+                Ok(artwork) => artwork,
+                Err(e) => {
+                    eprintln!("Could not load bezel {}: {e}", bezel.path);
+                    osd.push(("BEZEL MISSING".to_string(), OSD_FRAMES));
+                    return None;
+                }
+            };
+            artwork.set_alpha_mod(bezel.opacity);
+            let (x, y, w, h) = bezel.playfield;
+            let (w, h) = (w.max(1), h.max(1));
+            let sx = playfield.width() as f32 / w as f32;
+            let sy = playfield.height() as f32 / h as f32;
+            playfield.set_x((x as f32 * sx) as i32);
+            playfield.set_y((y as f32 * sy) as i32);
+            let (aw, ah) = artwork.size();
+            Some((artwork, (aw as f32 * sx) as u32, (ah as f32 * sy) as u32))
+        });
+        let (width, height) = match &bezel {
+            Some((_, w, h)) => (*w, *h),
+            None => (playfield.width(), playfield.height()),
+        };
+
+        let displays = video.displays().expect("Could not get displays");
+        let display = match options.display.map(|i| (i, displays.get(i))) {
+            Some((_, Some(display))) => *display,
+            chosen => {
+                if let Some((i, _)) = chosen {
+                    eprintln!("No display {i}, using the primary display");
+                }
+                video
+                    .get_primary_display()
+                    .expect("Could not get primary display")
+            }
+        };
+        let bounds = display.get_bounds().expect("Could not get display bounds");
+        // Size the window in screen coordinates according to the content scale of the display (DPI)
+        let content_scale = display.get_content_scale().unwrap_or(1.0);
+        let window_width = (width as f32 * content_scale) as u32;
+        let window_height = (height as f32 * content_scale) as u32;
+
+        let mut window = video.window(
+            "Intel 8080 Space Invaders Emulator",
+            window_width,
+            window_height,
+        );
+        window.high_pixel_density().position(
+            bounds.x() + (bounds.width() as i32 - window_width as i32) / 2,
+            bounds.y() + (bounds.height() as i32 - window_height as i32) / 2,
+        );
+        if options.fullscreen {
+            window.fullscreen();
+        }
+
+        let mut window = window.build().expect("Could not initialize window");
+        let desktop = display.get_mode().ok();
+        let exclusive = options.exclusive.then(|| {
+            let modes = display.get_fullscreen_modes().unwrap_or_default();
+            let desktop = desktop.map_or((0, 0), |m| (m.w, m.h));
+            let sizes: Vec<_> = modes.iter().map(|m| (m.w, m.h, m.refresh_rate)).collect();
+            let Some((i, _)) = fullscreen::pick(&sizes, desktop, FPS) else {
+                println!("No display mode refreshing at {FPS} Hz, using the desktop fullscreen");
+                return None;
+            };
+            let mode = modes[i];
+            if let Err(e) = window.set_display_mode(Some(mode)) {
+                eprintln!("Could not set display mode: {e}");
+                return None;
+            }
+            println!(
+                "Display mode {}x{} at {} Hz",
+                mode.w, mode.h, mode.refresh_rate
+            );
+            Some(mode.refresh_rate)
+        });
+        let mut canvas = window.into_canvas();
+
+        // Frames are paced by the display when it refreshes at a multiple of the frame rate
+        let displays = video.displays().unwrap_or_default();
+        let index = options
+            .display
+            .unwrap_or_else(|| displays.iter().position(|d| *d == display).unwrap_or(0));
+        let refresh = exclusive.flatten().or(desktop.map(|m| m.refresh_rate));
+        let mut pacing = if options.free_run {
+            Pacing::Sleep
+        } else {
+            Present::for_display(&options.present, index).pacing(refresh.unwrap_or_default(), FPS)
+        };
+        let refresh = refresh.unwrap_or_default();
+        let vsync = match pacing {
+            Pacing::Sleep => None,
+            Pacing::Vsync(repeats) => Some((
+                repeats as i32,
+                format!("Each frame shown for {repeats} refreshes at {refresh} Hz"),
+            )),
+            Pacing::Vrr => Some((
+                0,
+                "Presenting without vsync for variable refresh rate".into(),
+            )),
+        };
+        // Not wrapped by the sdl3 crate, the renderer lives as long as the canvas
+        if let Some((vsync, message)) = vsync {
+            if unsafe { SDL_SetRenderVSync(canvas.raw(), vsync) } {
+                println!("{message}");
+            } else {
+                eprintln!("Could not set vsync, pacing by sleeping");
+                pacing = Pacing::Sleep;
+            }
+        }
+        // Render in logical pixels regardless of window size and pixel density
+        canvas
+            .set_logical_size(width, height, SDL_LOGICAL_PRESENTATION_LETTERBOX)
+            .expect("Could not set logical size");
 
         // Support alpha blending
         canvas.set_blend_mode(BlendMode::Blend);
         let audio = sdl.audio().expect("Could not initialize audio");
 
-        let mut sounds: [SoundState; 10] = [
-            (3, 0, "ufo", None, None, false),  // Ufo movement
-            (3, 1, "shot", None, None, false), // Player shoots
-            (3, 2, "die", None, None, false),  // Player dies
-            (3, 3, "hit", None, None, false),  // Invader hit
-            (3, 4, "xp", None, None, false),   // Extended play?
-            // (3, 5, "amp"),  // Amp enable, turn on/off all sounds?
-            (5, 0, "fleet1", None, None, false),  // Fleet 1
-            (5, 1, "fleet2", None, None, false),  // Fleet 2
-            (5, 2, "fleet1", None, None, false),  // Fleet 3
-            (5, 3, "fleet2", None, None, false),  // Fleet 4
-            (5, 4, "ufo_hit", None, None, false), // Fleet 4
-        ];
-
-        let audio_spec = AudioSpec {
-            channels: Some(1),
-            freq: Some(11025),
-            format: Some(sdl3::audio::AudioFormat::U8),
-        };
+        let mut sounds: Vec<SoundState> = options
+            .machine
+            .sounds
+            .iter()
+            .map(|s| (s.port as u8, s.bit, s.sample, None, None, false))
+            .collect();
 
         let audio_device = audio
-            .open_playback_device(&audio_spec)
+            .open_playback_device(&AUDIO_SPEC)
             .expect("Could not open audio device");
+        let audio_devices = audio.audio_playback_device_ids().unwrap_or_default();
+        let audio_devices = Devices::new(audio_devices.iter().map(|d| d.id().0).collect());
 
-        for (_, _, w, queue, wav, _) in &mut sounds {
-            *wav = Some(
-                AudioSpecWAV::load_wav(format!("assets/{}.wav", w)).expect("Could not load wav"),
-            );
+        // Samples are loaded in the background, a channel stays silent until (or if not) its sample is loaded
+        let loading = wav::load_in_background(
+            options.assets.clone(),
+            sounds
+                .iter()
+                .map(|(_, _, name, _, _, _)| format!("{name}.wav"))
+                .collect(),
+        );
+        for (_, _, _, queue, _, _) in &mut sounds {
             let aso = audio_device
                 .clone()
-                .open_device_stream(Some(&audio_spec))
+                .open_device_stream(Some(&AUDIO_SPEC))
                 .unwrap();
             *queue = Some(aso);
         }
 
+        // Ambient sounds are optional, each in its own format, and played on separate streams. Not even loaded muted.
+        let mut ambience = vec![];
+        if let Some(dir) = options.ambience.as_ref().filter(|_| !options.mute) {
+            for name in AMBIENT_SOUNDS {
+                let Ok(data) = options.assets.read_path(&format!("{dir}/{name}.wav")) else {
+                    continue;
+                };
+                let Ok(wav) =
+                    IOStream::from_bytes(&data).and_then(|mut s| AudioSpecWAV::load_wav_rw(&mut s))
+                else {
+                    continue;
+                };
+                let spec = AudioSpec {
+                    channels: Some(wav.channels.into()),
+                    freq: Some(wav.freq),
+                    format: Some(wav.format),
+                };
+                let stream = audio_device
+                    .clone()
+                    .open_device_stream(Some(&spec))
+                    .expect("Could not open ambient audio stream");
+                stream
+                    .set_gain(AMBIENT_GAIN)
+                    .expect("Could not set ambient gain");
+                ambience.push((name, stream, wav));
+            }
+        }
+
+        let spare_cycles = options.free_run.then(SpareCycles::default);
+        let flash_test = options.latency_test.then(FlashTest::default);
+        let pipeline = options.latency_test.then(Pipeline::default);
+        let conversions = options.bench_conversion.then(Conversions::default);
+        let watchdog = options.watchdog.then(Watchdog::start);
+        let rewind = options.rewind.then(History::default);
+        let masking = options.masked_frames.map(Masking::new);
+        let frame_pacing =
+            (options.pacing_histogram || options.pacing_png.is_some()).then(FramePacing::default);
+        let console = options.machine.serial.is_some().then(Console::stdin);
+        let fps = FPS;
+        let av_sync = (options.av_sync && !options.mute && !options.free_run).then(|| {
+            let stream = audio_device
+                .clone()
+                .open_device_stream(Some(&AUDIO_SPEC))
+                .expect("Could not open sync audio stream");
+            (AvSync::new(11025, fps), stream)
+        });
+
+        let gamepad = sdl.gamepad().expect("Could not initialize gamepads");
+        let preferred = options.user.as_ref().map(|u| u.profile.controllers.clone());
+        let controllers = Controllers::new(preferred.unwrap_or_default());
+        let recorder = options.record_audio.as_ref().and_then(|path| {
+            wav::Writer::create(path, 11025)
+                .map(|writer| (Recorder::new(11025, fps), writer))
+                .map_err(|e| eprintln!("Could not record {path}: {e}"))
+                .ok()
+        });
+        let noise = options.bot.map(Noise::new);
+        let interrupt_log = options.interrupt_log.as_ref().and_then(|path| {
+            let mut log = File::create(path)
+                .map(BufWriter::new)
+                .map_err(|e| eprintln!("Could not create {path}: {e}"))
+                .ok()?;
+            writeln!(log, "{}", Delivery::CSV_HEADER).ok()?;
+            Some(log)
+        });
+        let remote = options.remote.as_ref().and_then(|addr| {
+            let token = options.remote_token.as_deref().unwrap_or_default();
+            Remote::bind(addr, token).map_err(|e| eprintln!("{e}")).ok()
+        });
+        let free_play = options.free_play.then(FreePlay::default);
+        let companions = options.rom.as_deref().map(Companions::new);
+        let comparison = options.reference.clone().map(Comparison::new);
+        // Frames run ahead would not line up with the reference
+        let run_ahead = (options.run_ahead && options.reference.is_none()).then(RunAhead::default);
+        let muted = vec![false; sounds.len()];
         let event_pump = sdl.event_pump().expect("Could not initialize event pump");
-        Emu {
+        let inset_feed = Feed::default();
+        if let Some(path) = &options.inset {
+            match load_inset(&options.assets, path) {
+                Ok(image) => inset_feed
+                    .sender()
+                    .send(Some(image))
+                    .expect("Inset feed closed"),
+                Err(e) => eprintln!("Could not load inset {path}: {e}"),
+            }
+        }
+        let bindings = Bindings::default();
+        Frontend {
             cpu,
             options,
-            fps: FPS,
+            fps,
             freq: FREQ,
             quit: false,
+            paused: false,
+            paused_in_background: false,
+            throttled: false,
+            advance: false,
+            operator: false,
             canvas,
             event_pump,
             sounds,
+            bezel,
+            playfield,
+            previous: Frame::default(),
+            watched: None,
+            companions,
+            offers: vec![],
+            cheats: vec![],
+            comparison,
+            teach_credit: 0,
+            teach_cycles: (0, 1),
+            frame_cycles: (0, 0),
+            overshoot: 0,
+            outputs: [0; NPORTS],
+            time: Box::new(RealClock),
+            pacer: Pacer::new(Instant::now()),
+            spare_cycles,
+            flash_test,
+            pipeline,
+            sensor: Sensor::default(),
+            conversions,
+            watchdog,
+            rewind,
+            masking,
+            frame_pacing,
+            views: Publisher::default(),
+            console,
+            stack_warned: HashSet::new(),
+            pacing,
+            vsync_check: VsyncCheck::new(Instant::now()),
+            clock: ClockMeter::new(Instant::now()),
+            interrupt_timing: InterruptTiming::default(),
+            interrupt_log,
+            remote,
+            free_play,
+            run_ahead,
+            commands: CommandQueue::default(),
+            inset_feed,
+            stats: Stats::default(),
+            announcer: Announcer::default(),
+            audio,
+            audio_devices,
+            reopen_audio: false,
+            gamepad,
+            gamepads: vec![],
+            controllers,
+            ambience,
+            recorder,
+            av_sync,
+            muted,
+            solo: None,
+            noise,
+            keys,
+            loading: Some((loading, 0, 0)),
+            osd,
+            slots: [None, None],
+            editor: Editor::default(),
+            editing: false,
+            menu: Menu::new(bindings.commands()),
+            bindings,
+            menu_open: false,
+            port_panel: false,
         }
     }
 
+    /// Pace the frames and measure the time by another clock, e.g. a `VirtualClock` to run faster than real time
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        let now = clock.now();
+        self.pacer = Pacer::new(now);
+        self.vsync_check = VsyncCheck::new(now);
+        self.clock = ClockMeter::new(now);
+        self.time = clock;
+    }
+
     pub fn run(&mut self) {
         let pixel_format =
             PixelFormat::try_from(PIXEL_FORMAT).expect("Could not convert pixel format enum");
 
-        let background_color = Color::from_u32(&pixel_format, self.options.background);
-        let foreground_color = Color::from_u32(&pixel_format, self.options.color);
-        let top_color = Color::from_u32(&pixel_format, self.options.top);
-        let bottom_color = Color::from_u32(&pixel_format, self.options.bottom);
+        let palette = self.options.palette;
+        let background_color = sdl_color(palette.background);
+        let foreground_color = sdl_color(palette.foreground);
+        let top_color = sdl_color(palette.top);
+        let bottom_color = sdl_color(palette.bottom);
 
         // Create an overlay grid for pixelation effect as a texture
         let texture_creator = self.canvas.texture_creator();
@@ -190,16 +930,17 @@ impl Emu<'_> {
         overlay_texture.set_blend_mode(BlendMode::Mul);
         overlay_texture.set_scale_mode(ScaleMode::Nearest);
 
+        let overlays = &self.options.machine.overlays;
         self.canvas
             .with_texture_canvas(&mut overlay_texture, |c| {
-                c.set_draw_color(top_color);
-                c.fill_rect(Rect::new(0, 32, DISPLAY_WIDTH, 32))
-                    .expect("Could not fill top rect");
-                c.set_draw_color(bottom_color);
-                c.fill_rect(Rect::new(0, 184, DISPLAY_WIDTH, 56))
-                    .expect("Could not fill bottom rect");
-                c.fill_rect(Rect::new(16, 240, 120, 15))
-                    .expect("Could not fill remaining ship area");
+                for overlay in overlays {
+                    c.set_draw_color(match overlay.layer {
+                        Layer::Top => top_color,
+                        Layer::Bottom => bottom_color,
+                    });
+                    c.fill_rect(Rect::from(overlay.rect))
+                        .expect("Could not fill overlay rect");
+                }
             })
             .expect("Could not draw overlay");
 
@@ -207,142 +948,1910 @@ impl Emu<'_> {
             .create_texture_target(pixel_format, DISPLAY_WIDTH, DISPLAY_HEIGHT)
             .expect("Could not create game texture");
         game_texture.set_blend_mode(BlendMode::Blend);
-        game_texture.set_scale_mode(ScaleMode::Nearest);
+        game_texture.set_scale_mode(scale_mode(self.options.scaling));
+
+        let mut inset_texture: Option<render::Texture> = None;
+        // Game picture scaled by the whole factor of sharp scaling, recreated when the factor changes
+        let mut sharp_texture: Option<(u32, render::Texture)> = None;
+
+        // Game texture of the conversions filling a buffer
+        let mut stream_texture = texture_creator
+            .create_texture_streaming(pixel_format, DISPLAY_WIDTH, DISPLAY_HEIGHT)
+            .expect("Could not create game texture");
+        stream_texture.set_blend_mode(BlendMode::Blend);
+        stream_texture.set_scale_mode(scale_mode(self.options.scaling));
+        let mut pixels = vec![0; (DISPLAY_WIDTH * DISPLAY_HEIGHT) as usize];
+        let lut = Lut::new(palette.foreground, palette.background);
+
+        let bezel_texture = self.bezel.as_ref().map(|(artwork, _, _)| {
+            let mut t = texture_creator
+                .create_texture_from_surface(artwork)
+                .expect("Could not create bezel texture");
+            t.set_blend_mode(BlendMode::Blend);
+            t
+        });
 
         println!("{:?}", self.canvas.renderer_name);
 
-        let cycles_per_frame = self.freq / self.fps;
+        let mut frame: u32 = 0;
+        // Iterations of the loop, which go on while paused unlike the frames
+        let mut ticks: u32 = 0;
+        let started = self.time.now();
+        self.play_ambient("startup");
+        if let Some(tournament) = &self.options.tournament {
+            println!("Tournament: {}", tournament.prompt());
+            self.osd.push((tournament.prompt(), 2 * OSD_FRAMES));
+        }
+        if self.options.speed != 100 {
+            self.set_speed(self.options.speed);
+        }
 
         while !self.quit {
-            let t = Instant::now();
+            if shutdown::requested() {
+                println!("Shutting down");
+                self.quit = true;
+            }
+            if self.watchdog.as_ref().is_some_and(Watchdog::take_reset) {
+                self.cpu.reset();
+                self.osd.push(("WATCHDOG RESET".to_string(), OSD_FRAMES));
+            }
+            // Paused, unless advancing a single frame
+            let running = !self.paused || std::mem::take(&mut self.advance);
+
+            // Like the service switch of a cabinet, the service key held at boot enters operator mode
+            if frame < self.fps
+                && !self.operator
+                && self
+                    .event_pump
+                    .keyboard_state()
+                    .is_scancode_pressed(SERVICE_KEY)
+            {
+                self.operator = true;
+                println!("Operator mode");
+                self.osd.push(("OPERATOR MODE".to_string(), OSD_FRAMES));
+            }
+
+            // Port activity is counted per second
+            if running && frame.is_multiple_of(self.fps) {
+                if let Some(monitor) = self.cpu.port_monitor_mut() {
+                    monitor.second();
+                }
+            }
+
+            // Check watched rom and announce text changes twice a second, paused too
+            if ticks.is_multiple_of(self.fps / 2) {
+                self.reload_watched_rom();
+                self.check_companions();
+                if let Some(announce) = self.options.announce {
+                    for line in self.announcer.update(text::decode(self.cpu.memory())) {
+                        announce(&line);
+                    }
+                }
+            }
+            if running {
+                frame = frame.wrapping_add(1);
+            }
+            ticks = ticks.wrapping_add(1);
+
+            self.receive_sounds();
+            self.apply_commands();
 
             // Handle input/controls
             self.handle_input();
+            if std::mem::take(&mut self.reopen_audio) {
+                self.move_audio();
+            }
+            if let Some(noise) = self.noise.as_mut().filter(|_| running) {
+                random_policy(&self.cpu, frame, noise).apply(&mut self.cpu);
+            }
 
             // Run correct number of cycles, generate interrupts etc
-            self.run_cpu(cycles_per_frame);
+            if running {
+                if let Some(free_play) = &mut self.free_play {
+                    free_play.apply(&mut self.cpu);
+                }
+                if let Some(tournament) = &mut self.options.tournament {
+                    tournament.apply(&mut self.cpu);
+                }
+                let cycles_per_frame = timing::effective_cycles(
+                    timing::frame_cycles(self.freq, self.fps, frame as u64),
+                    self.options.cycle_steal,
+                );
+                let mut timing = (self.overshoot, self.frame_cycles);
+                let real = self
+                    .run_ahead
+                    .as_mut()
+                    .is_none_or(|r| r.resume(&mut self.cpu, &mut timing));
+                (self.overshoot, self.frame_cycles) = timing;
+                // Cheats after a rollback, so their pokes aren't taken as changes from outside
+                if real {
+                    cheats::apply(&self.cheats, &mut self.cpu);
+                    self.run_frame(cycles_per_frame);
+                }
+                self.outputs = std::array::from_fn(|port| self.cpu.get_bus_out(port));
+                // The frame presented is run ahead, with the inputs of this frame
+                if let Some(mut run_ahead) = self.run_ahead.take() {
+                    run_ahead.speculate(&self.cpu, (self.overshoot, self.frame_cycles));
+                    cheats::apply(&self.cheats, &mut self.cpu);
+                    self.run_frame(cycles_per_frame);
+                    run_ahead.speculated(&self.cpu);
+                    self.run_ahead = Some(run_ahead);
+                }
+                let (interrupt, main) = self.frame_cycles;
+                self.clock.update(interrupt + main, self.time.now());
+                self.log_interrupts(frame);
+                self.stats.frame(&self.cpu);
+                self.follow_tournament();
+                self.check_breaks(frame);
+                self.report_stack_warnings();
+                self.report_masking();
+                self.compare_reference(frame);
+                self.views.publish(&self.cpu, self.stats.frames);
+                if let Some(history) = &mut self.rewind {
+                    history.push(self.cpu.save_state());
+                }
+            }
+
+            // Keep the cabinet humming, unless throttled in the background
+            let hum = self.ambience.iter().find(|(n, _, _)| *n == "hum");
+            if let Some((_, stream, wav)) = hum.filter(|_| !self.throttled) {
+                if stream.queued_bytes().unwrap_or(0) < wav.buffer().len() as i32 {
+                    stream
+                        .put_data(wav.buffer())
+                        .expect("Could not queue audio");
+                    stream.resume().expect("Could not resume audio");
+                }
+            }
+
+            for watch in self.options.watches.iter_mut().filter(|_| running) {
+                let Some((old, new)) = watch.check(&self.cpu, frame) else {
+                    continue;
+                };
+                let name = match watch.expr.address().and_then(ram_map::annotate) {
+                    Some(name) => format!(" ({name})"),
+                    None => String::new(),
+                };
+                match old {
+                    Some(old) => {
+                        println!(
+                            "Frame {frame}: {}{name} = 0x{new:X} (was 0x{old:X})",
+                            watch.expr
+                        )
+                    }
+                    None => println!("Frame {frame}: {}{name} = 0x{new:X}", watch.expr),
+                }
+            }
 
             // Handle sound
-            for (port, bit, _, queue, wav, playing) in &mut self.sounds {
-                if get_bit(self.cpu.get_bus_out((*port).into()), *bit) {
+            for (channel, (port, bit, name, queue, wav, playing)) in
+                self.sounds.iter_mut().enumerate()
+            {
+                if get_bit(self.outputs[*port as usize], *bit) {
                     if !(*playing) {
                         *playing = true;
                         let q = queue.as_ref().expect("No audio queue for sound");
-                        let w = wav.as_ref().expect("No audio content for sound");
-                        q.put_data(w.buffer()).expect("Could not queue audio");
-                        q.resume().expect("Could not resume audio");
+                        let w: &[u8] = wav.as_deref().unwrap_or_default();
+                        let audible = self.solo.map_or(!self.muted[channel], |s| s == channel);
+                        if audible && !self.options.mute && !self.throttled {
+                            // Off normal speed only the latest sound plays, so nothing lags behind the game
+                            if self.options.speed != 100 {
+                                q.clear().expect("Could not clear audio");
+                            }
+                            q.put_data(w).expect("Could not queue audio");
+                            q.resume().expect("Could not resume audio");
+                        }
+                        if let (true, Some((recorder, _))) = (audible, &mut self.recorder) {
+                            recorder.play(w);
+                        }
+
+                        let rumble = self.options.rumble.filter(|_| *name == "die");
+                        let rumble = rumble.map(|(strength, ms)| Effect::Rumble { strength, ms });
+                        for effect in self.options.effects.of(name).chain(rumble) {
+                            for gamepad in &mut self.gamepads {
+                                // Not all controllers support rumble or have a LED
+                                let _ = match effect {
+                                    Effect::Rumble { strength, ms } => {
+                                        gamepad.set_rumble(strength, strength, ms)
+                                    }
+                                    Effect::Led(c) => gamepad.set_led(c.r, c.g, c.b),
+                                };
+                            }
+                        }
                     }
                 } else if *playing {
                     *playing = false;
                 }
             }
 
-            // Handle display
+            if let Some((recorder, writer)) = &mut self.recorder {
+                recorder.next_frame();
+                if let Err(e) = writer.write(&recorder.take()) {
+                    eprintln!("Stopped recording audio: {e}");
+                    self.recorder = None;
+                }
+            }
+
+            // Feed the silent stream and measure how far the video is from the audio
+            if let Some((sync, stream)) = &mut self.av_sync {
+                if running && self.options.speed == 100 && !self.throttled {
+                    let silence = vec![0x80; sync.frame_samples()];
+                    stream.put_data(&silence).expect("Could not queue audio");
+                    stream.resume().expect("Could not resume audio");
+                    sync.update(stream.queued_bytes().unwrap_or(0) as u32);
+                } else {
+                    stream.clear().expect("Could not clear audio");
+                    sync.restart();
+                }
+            }
+
+            // Handle display, redrawn every frame while there is a message on screen
+            if self.loading.is_some() || !self.osd.is_empty() {
+                self.osd.retain_mut(|(_, frames)| {
+                    *frames -= 1;
+                    *frames > 0
+                });
+                self.cpu.set_display_update(true);
+            }
+            // A new picture-in-picture image is uploaded, the texture kept while the size stays
+            if let Some(image) = self.inset_feed.latest() {
+                inset_texture = image.map(|image| {
+                    let mut texture = inset_texture
+                        .take()
+                        .filter(|t| (t.width(), t.height()) == (image.width, image.height))
+                        .unwrap_or_else(|| {
+                            let format = PixelFormat::try_from(SDL_PIXELFORMAT_RGBA32)
+                                .expect("Could not convert pixel format enum");
+                            let mut t = texture_creator
+                                .create_texture_streaming(format, image.width, image.height)
+                                .expect("Could not create inset texture");
+                            t.set_blend_mode(BlendMode::Blend);
+                            t
+                        });
+                    texture
+                        .update(None, &image.pixels, image.width as usize * 4)
+                        .expect("Could not update inset texture");
+                    texture
+                });
+                self.cpu.set_display_update(true);
+            }
+
+            // The latency test flashes the playfield, and times the sensor responses
+            let flash = self.flash_test.as_mut().is_some_and(|t| t.frame());
+            for at in self.sensor.take() {
+                self.respond_to_flash(at, "Sensor");
+            }
+
+            // Paced by the display, which waits only when presenting
+            let vsync = self.paced_by_display();
+            if self.editing
+                || self.conversions.is_some()
+                || self.menu_open
+                || self.port_panel
+                || !self.offers.is_empty()
+                || self.flash_test.is_some()
+                || vsync
+            {
+                self.cpu.set_display_update(true);
+            }
             if self.cpu.get_display_update() {
-                self.canvas
-                    .with_texture_canvas(&mut game_texture, |c| {
-                        c.set_draw_color(background_color);
-                        c.clear();
+                let current = Frame::render(&self.cpu);
+                let mut frame = current.blend(&self.previous, self.options.blend);
+                if let Some(glow) = self.options.glow {
+                    frame = frame.glow(glow);
+                }
+                if flash {
+                    for y in 0..DISPLAY_HEIGHT {
+                        for x in 0..DISPLAY_WIDTH {
+                            frame.set(x, y, 0xFF);
+                        }
+                    }
+                }
+                self.previous = current;
+                let progress = self.loading.as_ref().map(|(_, received, _)| {
+                    format!("LOADING SOUNDS {received} OF {}", self.sounds.len())
+                });
+                let clock = self.clock.hz().filter(|_| self.options.cycle_bar);
+                let clock = clock.map(|hz| format!("CLOCK {hz} HZ"));
+                let latencies = if self.options.cycle_bar {
+                    self.interrupt_timing.lines()
+                } else {
+                    vec![]
+                };
+                let offer = self.offers.first().map(|o| o.message().to_string());
+                let messages = progress
+                    .iter()
+                    .chain(&offer)
+                    .chain(self.osd.iter().map(|(m, _)| m))
+                    .chain(&clock)
+                    .chain(&latencies);
+                for (row, message) in messages.enumerate() {
+                    text::draw(&mut frame, self.cpu.memory(), 0, row as u32 * 8, message);
+                }
+                if let Some(monitor) = self.cpu.port_monitor().filter(|_| self.port_panel) {
+                    let lines = monitor.lines(&self.options.machine.board);
+                    let top = DISPLAY_HEIGHT.saturating_sub(lines.len() as u32 * 8);
+                    for (row, line) in lines.iter().enumerate() {
+                        text::draw(&mut frame, self.cpu.memory(), 0, top + row as u32 * 8, line);
+                    }
+                }
+                if self.editing {
+                    for (row, line) in self.editor.lines(&self.cpu).iter().enumerate() {
+                        text::draw(
+                            &mut frame,
+                            self.cpu.memory(),
+                            0,
+                            EDITOR_TOP + row as u32 * 8,
+                            line,
+                        );
+                    }
+                }
+                if self.menu_open {
+                    for (row, line) in self.menu.lines().iter().enumerate() {
+                        text::draw(&mut frame, self.cpu.memory(), 0, row as u32 * 8, line);
+                    }
+                }
 
-                        for (color, range) in [(foreground_color, 0..DISPLAY_HEIGHT)] {
-                            c.set_draw_color(color);
-                            for y in range {
+                // The strategy in use, or each in turn when benchmarking them
+                let conversion = match &self.conversions {
+                    Some(conversions) => match conversions.next(CONVERSION_BENCH_FRAMES) {
+                        Some(conversion) => conversion,
+                        None => {
+                            print!("{}", conversions.report());
+                            self.quit = true;
+                            self.options.conversion
+                        }
+                    },
+                    None => self.options.conversion,
+                };
+                let converting = Instant::now();
+                let cpu = &self.cpu;
+                let color_ram = |x, y| cpu.color(x, y);
+                match conversion {
+                    Conversion::Points => self
+                        .canvas
+                        .with_texture_canvas(&mut game_texture, |c| {
+                            c.set_draw_color(background_color);
+                            c.clear();
+
+                            let mut color = foreground_color;
+                            for y in 0..DISPLAY_HEIGHT {
                                 for x in 0..DISPLAY_WIDTH {
-                                    if self.cpu.display(x, y) {
+                                    let intensity = frame.get(x, y);
+                                    if intensity > 0 {
+                                        // Color RAM, when the board has it, replaces the foreground color
+                                        let next = match cpu.color(x, y).map(colors::rgb) {
+                                            Some(ram) => sdl_color(ram.with_alpha(intensity)),
+                                            None => Color {
+                                                a: intensity,
+                                                ..foreground_color
+                                            },
+                                        };
+                                        if color != next {
+                                            color = next;
+                                            c.set_draw_color(color);
+                                        }
                                         c.draw_point(Point::new(x as i32, y as i32))
                                             .expect("Could not draw pixel on display");
                                     }
                                 }
                             }
-                        }
-                    })
-                    .expect("Could not render game frame");
+                        })
+                        .expect("Could not render game frame"),
+                    Conversion::Buffer => convert::buffer(
+                        &frame,
+                        &mut pixels,
+                        palette.foreground,
+                        palette.background,
+                        color_ram,
+                    ),
+                    Conversion::Lut => {
+                        let color_ram: Option<&dyn Fn(u32, u32) -> Option<u8>> =
+                            cpu.colors().is_some().then_some(&color_ram);
+                        lut.convert(&frame, &mut pixels, color_ram)
+                    }
+                }
+                if conversion != Conversion::Points {
+                    stream_texture
+                        .with_lock(None, |bytes, pitch| {
+                            let rows = pixels.chunks(DISPLAY_WIDTH as usize);
+                            for (row, line) in rows.zip(bytes.chunks_mut(pitch)) {
+                                for (pixel, out) in row.iter().zip(line.chunks_exact_mut(4)) {
+                                    out.copy_from_slice(&pixel.to_ne_bytes());
+                                }
+                            }
+                        })
+                        .expect("Could not update game texture");
+                }
+                if let Some(conversions) = &mut self.conversions {
+                    conversions.record(conversion, converting.elapsed());
+                }
+                let mut game_texture = match conversion {
+                    Conversion::Points => &mut game_texture,
+                    Conversion::Buffer | Conversion::Lut => &mut stream_texture,
+                };
+                if self.options.scaling == Scaling::Sharp {
+                    // Size of the playfield on the output, the logical size is letterboxed into it
+                    let (width, height) = self.canvas.output_size().unwrap_or_default();
+                    let (logical_width, logical_height, _) = self.canvas.logical_size();
+                    let zoom = (width as f32 / logical_width.max(1) as f32)
+                        .min(height as f32 / logical_height.max(1) as f32);
+                    let output = (
+                        (self.playfield.width() as f32 * zoom) as u32,
+                        (self.playfield.height() as f32 * zoom) as u32,
+                    );
+                    let factor = scaling::prescale(output, (DISPLAY_WIDTH, DISPLAY_HEIGHT));
+                    if sharp_texture.as_ref().map(|(f, _)| *f) != Some(factor) {
+                        let mut texture = texture_creator
+                            .create_texture_target(
+                                pixel_format,
+                                DISPLAY_WIDTH * factor,
+                                DISPLAY_HEIGHT * factor,
+                            )
+                            .expect("Could not create sharp scaling texture");
+                        texture.set_blend_mode(BlendMode::Blend);
+                        texture.set_scale_mode(ScaleMode::Linear);
+                        sharp_texture = Some((factor, texture));
+                    }
+                    let (_, texture) = sharp_texture.as_mut().expect("Sharp scaling texture");
+                    // Copied as is, alpha included, to be blended once onto the canvas
+                    game_texture.set_blend_mode(BlendMode::None);
+                    self.canvas
+                        .with_texture_canvas(texture, |c| {
+                            c.copy(&*game_texture, None, None)
+                                .expect("Could not scale game texture");
+                        })
+                        .expect("Could not draw on sharp scaling texture");
+                    game_texture.set_blend_mode(BlendMode::Blend);
+                    game_texture = texture;
+                }
+
+                // Bezel artwork goes behind the playfield
+                if let Some(bezel_texture) = &bezel_texture {
+                    self.canvas.set_draw_color(Color::BLACK);
+                    self.canvas.clear();
+                    self.canvas
+                        .copy(bezel_texture, None, None)
+                        .expect("Could not copy bezel texture to canvas");
+                }
 
                 self.canvas
-                    .copy(&game_texture, None, None)
+                    .copy(&*game_texture, None, self.playfield)
                     .expect("Could not copy game texture to canvas");
                 // Copy grid texture on top to give a slight pixelated look
                 self.canvas
-                    .copy(&grid_texture, None, None)
+                    .copy(&grid_texture, None, self.playfield)
                     .expect("Could not copy grid texture to canvas");
-                // Copy overlay texture at last
-                self.canvas
-                    .copy(&overlay_texture, None, None)
-                    .expect("Could not copy overlay texture to canvas");
+                // Copy overlay texture at last, color boards need none
+                if self.cpu.colors().is_none() {
+                    self.canvas
+                        .copy(&overlay_texture, None, self.playfield)
+                        .expect("Could not copy overlay texture to canvas");
+                }
+
+                if self.options.cycle_bar {
+                    self.draw_cycle_bar();
+                    self.draw_interrupt_timeline();
+                }
+
+                if let Some(texture) = &inset_texture {
+                    let (width, height, _) = self.canvas.logical_size();
+                    let size = (texture.width(), texture.height());
+                    let (x, y, w, h) =
+                        inset::place(self.options.inset_corner, size, (width, height));
+                    self.canvas
+                        .copy(texture, None, Rect::new(x, y, w, h))
+                        .expect("Could not copy inset texture to canvas");
+                }
 
                 self.canvas.present();
+                let presented = self.time.now();
+                if let Some(pipeline) = &mut self.pipeline {
+                    pipeline.presented(presented);
+                }
+                if let Some(flash_test) = &mut self.flash_test {
+                    flash_test.presented(presented);
+                }
 
                 self.cpu.set_display_update(false); // Cpu will set this to true whenever something changes on screen
             }
 
-            self.sleep_before_next_frame(t);
+            for i in 0..self.options.screenshot_triggers.len() {
+                let trigger = &mut self.options.screenshot_triggers[i];
+                if running && trigger.check(&self.cpu, frame) {
+                    println!("Frame {frame}: {}", trigger.condition);
+                    self.save_screenshot(&format!("frame{frame}"));
+                }
+            }
+
+            self.sleep_until_next_frame();
+            if let Some(pacing) = &mut self.frame_pacing {
+                pacing.frame(self.time.now());
+            }
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.frame(&mut self.cpu);
+            }
+            self.update_serial();
+        }
+
+        // Silence the sounds still queued while saving
+        for (_, _, _, queue, _, _) in &self.sounds {
+            if let Some(q) = queue {
+                q.clear().expect("Could not clear audio");
+            }
+        }
+        for (_, stream, _) in &self.ambience {
+            stream.clear().expect("Could not clear audio");
+        }
+        if let Some((_, stream)) = &self.av_sync {
+            stream.clear().expect("Could not clear audio");
+        }
+        if let Some(log) = &mut self.interrupt_log {
+            if let Err(e) = log.flush() {
+                eprintln!("Could not log interrupts: {e}");
+            }
+        }
+
+        if let (Some(path), Some((recorder, mut writer))) =
+            (&self.options.record_audio, self.recorder.take())
+        {
+            match writer
+                .write(&recorder.samples())
+                .and_then(|_| writer.finish())
+            {
+                Ok(_) => println!("Saved {path}"),
+                Err(e) => eprintln!("Could not save {path}: {e}"),
+            }
+        }
+
+        if let (Some(path), Some(profile)) = (&self.options.profile, self.cpu.profile()) {
+            let mut profile = profile.clone();
+            if let Ok(earlier) = fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|d| Profile::from_bytes(&d))
+            {
+                profile.merge(&earlier);
+            }
+            match shutdown::write(path, profile.to_bytes()) {
+                Ok(_) => println!("Saved {path}"),
+                Err(e) => eprintln!("Could not save {path}: {e}"),
+            }
+        }
+
+        if let Some(usage) = self.cpu.stack_usage() {
+            print!("{}", usage.report());
+        }
+
+        if let Some(remote) = &self.remote {
+            print!("{}", remote.report());
+        }
+
+        if let Some((sync, _)) = &self.av_sync {
+            print!("{}", sync.report());
+        }
+
+        if let Some(run_ahead) = &self.run_ahead {
+            print!("{}", run_ahead.report());
+        }
+
+        if let Some(spare) = &self.spare_cycles {
+            print!("{}", spare.report(self.time.now() - started));
+        }
+
+        if let Some(tournament) = &self.options.tournament {
+            print!("{}", tournament.report());
+        }
+
+        if let Some(flash_test) = &self.flash_test {
+            print!("{}", flash_test.report());
+        }
+        if let Some(pipeline) = &self.pipeline {
+            print!("{}", pipeline.report());
+        }
+
+        if let Some(user) = &mut self.options.user {
+            user.profile.controllers = self.controllers.preferred();
+            let place = Some(self.stats.best_score)
+                .filter(|score| *score > 0)
+                .and_then(|score| user.profile.add_score(score));
+            print!("{}", user.profile.leaderboard(&user.name, place));
+            match user.save() {
+                Ok(_) => println!("Saved {}", user.path.display()),
+                Err(e) => eprintln!("{e}"),
+            }
+        }
+
+        if let Some(pacing) = &self.frame_pacing {
+            if self.options.pacing_histogram {
+                print!("{}", pacing.report());
+            }
+            if let Some(path) = &self.options.pacing_png {
+                match shutdown::write(path, png::encode(&pacing.to_image())) {
+                    Ok(_) => println!("Saved {path}"),
+                    Err(e) => eprintln!("Could not save {path}: {e}"),
+                }
+            }
+        }
+
+        let play_time = self.time.now() - started;
+        print!("{}", self.stats.summary(play_time));
+        if let Some(path) = &self.options.stats_json {
+            match shutdown::write(path, self.stats.to_json(play_time)) {
+                Ok(_) => println!("Saved {path}"),
+                Err(e) => eprintln!("Could not save {path}: {e}"),
+            }
+        }
+        shutdown::done();
+    }
+
+    /// Take the sound samples loaded so far, with a warning on screen if any are missing
+    fn receive_sounds(&mut self) {
+        let Some((receiver, received, missing)) = &mut self.loading else {
+            return;
+        };
+        for (i, samples) in receiver.try_iter() {
+            *received += 1;
+            let synth = self.options.machine.sounds.get(i).and_then(|s| s.synth);
+            match (samples, synth) {
+                (Ok(samples), _) => self.sounds[i].4 = Some(samples),
+                (Err(e), Some(warble)) => {
+                    println!("Could not load sound, synthesizing it: {e}");
+                    self.sounds[i].4 = Some(warble.samples(11025));
+                }
+                (Err(e), None) => {
+                    eprintln!("Could not load sound: {e}");
+                    *missing += 1;
+                }
+            }
+        }
+        let (received, missing, total) = (*received, *missing, self.sounds.len());
+        if received < total {
+            return;
+        }
+        self.loading = None;
+        match missing {
+            0 => {}
+            n if n == total => self.osd.push(("SOUNDS MISSING".to_string(), OSD_FRAMES)),
+            n => self.osd.push((format!("{n} SOUNDS MISSING"), OSD_FRAMES)),
+        }
+    }
+
+    /// Reload the watched rom and power cycle if the file has been modified
+    fn reload_watched_rom(&mut self) {
+        let Some(path) = &self.options.watch_rom else {
+            return;
+        };
+        let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) else {
+            return;
+        };
+        if self.watched.is_some_and(|watched| watched != modified) {
+            // An empty file may still be being written by the assembler, it is reloaded when written
+            let read = fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|program| {
+                    self.options.machine.check_rom(&program)?;
+                    Ok(program)
+                });
+            match read {
+                Ok(program) => {
+                    println!("Reloading {path}");
+                    self.cpu.load_rom(&program);
+                    self.cpu.power_cycle();
+                    self.previous = Frame::default();
+                }
+                Err(e) => eprintln!("Could not reload {path}: {e}"),
+            }
+        }
+        self.watched = Some(modified);
+    }
+
+    /// Offer cheats and patches next to the rom that appeared or changed
+    fn check_companions(&mut self) {
+        let Some(companions) = &mut self.companions else {
+            return;
+        };
+        for offer in companions.check() {
+            match offer {
+                Ok(offer) => {
+                    println!("{}", offer.message());
+                    self.offers.push(offer);
+                }
+                Err(e) => eprintln!("{e}"),
+            }
+        }
+    }
+
+    /// Apply or dismiss the cheats or patch asked about
+    fn answer_offer(&mut self, accept: bool) {
+        let offer = self.offers.remove(0);
+        if !accept {
+            return;
+        }
+        match offer {
+            Offer::Cheats(cheats) => {
+                for cheat in &cheats {
+                    println!(
+                        "Cheat {:04X}={:02X} {}",
+                        cheat.addr, cheat.value, cheat.description
+                    );
+                }
+                self.cheats = cheats;
+                self.osd.push(("CHEATS ON".to_string(), OSD_FRAMES));
+            }
+            Offer::Patch(patch) => {
+                let rom = self.options.machine.rom.clone();
+                let mut program = self.cpu.memory()[rom.clone()].to_vec();
+                match ips::apply(&mut program, &patch) {
+                    Ok(()) if program.len() <= rom.end() + 1 - rom.start() => {
+                        self.cpu.load_rom(&program);
+                        self.cpu.power_cycle();
+                        self.previous = Frame::default();
+                        println!("Patch applied");
+                        self.osd.push(("PATCH APPLIED".to_string(), OSD_FRAMES));
+                    }
+                    Ok(()) => eprintln!("Could not apply patch: it writes outside the rom"),
+                    Err(e) => eprintln!("Could not apply patch: {e}"),
+                }
+            }
+        }
+    }
+
+    /// Draw a bar at the bottom of the playfield showing the share of cycles spent in interrupt handlers (red) and in the main loop (green)
+    fn draw_cycle_bar(&mut self) {
+        let (interrupt, main) = self.frame_cycles;
+        let total = (interrupt + main).max(1);
+        let height = 2 * self.options.scale;
+        let y = self.playfield.bottom() - height as i32;
+        let width = self.playfield.width() * interrupt / total;
+
+        self.canvas
+            .set_draw_color(Color::RGBA(0xff, 0x00, 0x00, 0xc0));
+        self.canvas
+            .fill_rect(Rect::new(self.playfield.x(), y, width, height))
+            .expect("Could not draw cycle bar");
+        self.canvas
+            .set_draw_color(Color::RGBA(0x00, 0xff, 0x00, 0xc0));
+        self.canvas
+            .fill_rect(Rect::new(
+                self.playfield.x() + width as i32,
+                y,
+                self.playfield.width() - width,
+                height,
+            ))
+            .expect("Could not draw cycle bar");
+    }
+
+    /// Draw ticks above the cycle bar where the interrupts should have come within the frame (white) and where they came
+    /// (yellow, red if lost with interrupts disabled)
+    fn draw_interrupt_timeline(&mut self) {
+        let (interrupt, main) = self.frame_cycles;
+        let total = (interrupt + main).max(1) as u64;
+        let height = 2 * self.options.scale;
+        let y = self.playfield.bottom() - 2 * height as i32;
+        let x = |cycle: u32| {
+            let offset = (self.playfield.width() as u64 * cycle as u64 / total) as i32;
+            self.playfield.x() + offset.min(self.playfield.width() as i32 - 1)
+        };
+        let mut ticks = vec![];
+        for d in &self.interrupt_timing.frame {
+            ticks.push((x(d.ideal), Color::RGBA(0xff, 0xff, 0xff, 0xc0)));
+            let color = if d.delivered {
+                Color::RGBA(0xff, 0xff, 0x00, 0xc0)
+            } else {
+                Color::RGBA(0xff, 0x00, 0x00, 0xc0)
+            };
+            ticks.push((x(d.cycle), color));
+        }
+        for (x, color) in ticks {
+            self.canvas.set_draw_color(color);
+            self.canvas
+                .fill_rect(Rect::new(x, y, self.options.scale, height))
+                .expect("Could not draw interrupt timeline");
+        }
+    }
+
+    /// Foreground color at (x, y) with the overlay applied, or from the color RAM on color boards
+    fn foreground_color(&self, x: u32, y: u32) -> palette::Color {
+        if let Some(ram) = self.cpu.color(x, y).map(colors::rgb) {
+            return ram;
+        }
+        let inside = |(bx, by, w, h): (i32, i32, u32, u32)| {
+            (bx as u32..bx as u32 + w).contains(&x) && (by as u32..by as u32 + h).contains(&y)
+        };
+        let overlay = match self
+            .options
+            .machine
+            .overlays
+            .iter()
+            .find(|o| inside(o.rect))
+        {
+            Some(Overlay {
+                layer: Layer::Top, ..
+            }) => self.options.palette.top,
+            Some(Overlay {
+                layer: Layer::Bottom,
+                ..
+            }) => self.options.palette.bottom,
+            None => return self.options.palette.foreground,
+        };
+        // Multiply blend, like the overlay texture
+        self.options.palette.foreground.multiply(overlay)
+    }
+
+    /// Move the sounds to the default playback device after a device came or went, e.g. headphones plugged in.
+    /// Sounds playing continue where they were, the hum keeps going and A/V sync starts measuring over.
+    fn move_audio(&mut self) {
+        let device = match self.audio.open_playback_device(&AUDIO_SPEC) {
+            Ok(device) => device,
+            Err(e) => return eprintln!("Could not reopen audio: {e}"),
+        };
+        let reopen = |old: &AudioStreamOwner, spec: &AudioSpec, samples: &[u8], gain: f32| {
+            let stream = device.clone().open_device_stream(Some(spec)).ok()?;
+            let rest = hotplug::unplayed(samples, old.queued_bytes().unwrap_or(0));
+            stream.set_gain(gain).ok()?;
+            if !rest.is_empty() {
+                stream.put_data(rest).ok()?;
+                stream.resume().ok()?;
+            }
+            Some(stream)
+        };
+        let gain = if self.options.speed == 100 {
+            1.0
+        } else {
+            DUCK_GAIN
+        };
+        for (_, _, _, queue, wav, _) in &mut self.sounds {
+            let samples = wav.as_deref().unwrap_or_default();
+            if let Some(stream) = queue
+                .as_ref()
+                .and_then(|q| reopen(q, &AUDIO_SPEC, samples, gain))
+            {
+                *queue = Some(stream);
+            }
+        }
+        for (_, stream, wav) in &mut self.ambience {
+            let spec = AudioSpec {
+                channels: Some(wav.channels.into()),
+                freq: Some(wav.freq),
+                format: Some(wav.format),
+            };
+            if let Some(new) = reopen(stream, &spec, wav.buffer(), AMBIENT_GAIN) {
+                *stream = new;
+            }
+        }
+        if let Some((sync, stream)) = &mut self.av_sync {
+            if let Some(new) = reopen(stream, &AUDIO_SPEC, &[], 1.0) {
+                *stream = new;
+                sync.restart();
+            }
+        }
+        println!(
+            "Sound moved to the default device, {} connected",
+            self.audio_devices.count()
+        );
+        self.osd
+            .push(("AUDIO DEVICE CHANGED".to_string(), OSD_FRAMES));
+    }
+
+    /// Play an ambient sound, if provided
+    fn play_ambient(&self, name: &str) {
+        if let Some((_, stream, wav)) = self.ambience.iter().find(|(n, _, _)| *n == name) {
+            stream.clear().expect("Could not clear audio");
+            stream
+                .put_data(wav.buffer())
+                .expect("Could not queue audio");
+            stream.resume().expect("Could not resume audio");
         }
     }
 
-    fn sleep_before_next_frame(&mut self, instant_at_start_of_frame: Instant) {
-        let sleep_duration = (1_000_000_000_i64 / self.fps as i64)
-            - instant_at_start_of_frame.elapsed().as_nanos() as i64;
+    /// Save the current frame as PNG with transparent background, as screenshot-<name>.png
+    fn save_screenshot(&self, name: &str) {
+        let (extension, data) = match self.options.screenshot_format {
+            ScreenshotFormat::Png => {
+                let image = self.previous.to_image(|x, y| self.foreground_color(x, y));
+                ("png", png::encode(&image))
+            }
+            ScreenshotFormat::Pbm => ("pbm", pbm::pbm(&Frame::render(&self.cpu)).into_bytes()),
+            ScreenshotFormat::Xbm => {
+                let xbm = pbm::xbm(&Frame::render(&self.cpu), "screenshot");
+                ("xbm", xbm.into_bytes())
+            }
+        };
+        let path = format!("screenshot-{name}.{extension}");
+        match fs::write(&path, data) {
+            Ok(_) => println!("Saved {path}"),
+            Err(e) => eprintln!("Could not save {path}: {e}"),
+        }
+    }
 
-        if sleep_duration >= 0 {
-            sleep(Duration::new(0, sleep_duration as u32));
+    fn sleep_until_next_frame(&mut self) {
+        if self.options.free_run {
+            return;
+        }
+        if self.paced_by_display() {
+            let now = self.time.now();
+            // Presenting the frame waited for the display, unless vsync is ignored
+            let frame = timing::frame_duration(self.fps, 100);
+            if self.vsync_check.presented(now, frame) {
+                eprintln!("Vsync does not wait for the display, pacing by sleeping");
+                self.pacing = Pacing::Sleep;
+                self.set_vsync(0);
+            }
+            return;
+        }
+        let correction = self
+            .av_sync
+            .as_ref()
+            .map_or(0, |(sync, _)| sync.correction());
+        let deadline = if self.throttled {
+            self.pacer.wait(self.time.as_ref(), BACKGROUND_FPS, 100, 0)
+        } else {
+            self.pacer
+                .wait(self.time.as_ref(), self.fps, self.options.speed, correction)
+        };
+        if let (Some(pacing), Some(deadline)) = (&mut self.frame_pacing, deadline) {
+            pacing.slept(deadline, self.time.now());
+        }
+    }
+
+    /// Frames are paced by presenting with vsync: at normal speed and not throttled
+    fn paced_by_display(&self) -> bool {
+        matches!(self.pacing, Pacing::Vsync(_)) && !self.throttled && self.options.speed == 100
+    }
+
+    /// Wait for this many refreshes when presenting, 0 is off
+    fn set_vsync(&mut self, refreshes: u32) {
+        // Not wrapped by the sdl3 crate, the renderer lives as long as the canvas
+        if !unsafe { SDL_SetRenderVSync(self.canvas.raw(), refreshes as i32) } {
+            eprintln!("Could not set vsync");
+        }
+    }
+
+    /// Change the emulation speed. Off normal speed the game sounds are ducked, since they are retriggered faster or
+    /// slower than they play and would otherwise pile up or drift out of sync with the game.
+    fn set_speed(&mut self, speed: u32) {
+        self.options.speed = speed;
+        let gain = if speed == 100 { 1.0 } else { DUCK_GAIN };
+        for (_, _, _, queue, _, _) in &self.sounds {
+            if let Some(q) = queue {
+                q.set_gain(gain).expect("Could not set gain");
+            }
+        }
+        // Off normal speed the frames are paced by sleeping, presenting must not wait for the display
+        if let Pacing::Vsync(repeats) = self.pacing {
+            self.set_vsync(if speed == 100 { repeats } else { 0 });
+        }
+        println!("Speed {speed}%");
+        self.osd.push((format!("SPEED {speed}"), OSD_FRAMES));
+    }
+
+    /// Run a frame, explaining each instruction in teaching mode. Compared against a reference the frames are run like
+    /// when it was recorded.
+    fn run_frame(&mut self, cycles_per_frame: u32) {
+        if self.comparison.is_some() {
+            return batch::run_frame(&mut self.cpu);
+        }
+        match self.options.teach {
+            Some(ips) => self.run_cpu_teaching(cycles_per_frame, ips),
+            None => self.run_cpu(cycles_per_frame),
         }
     }
 
     fn run_cpu(&mut self, cycles_per_frame: u32) {
-        for i in [1, 2] {
-            let mut cycles: u32 = 0;
+        self.frame_cycles = (0, 0);
+        self.interrupt_timing.new_frame();
+        let mut elapsed = self.overshoot;
+        let mut spare = 0;
+        // Each interrupt when the beam reaches its line, then the rest of the frame (the vertical blank)
+        let interrupts = self
+            .options
+            .machine
+            .interrupts
+            .iter()
+            .map(|&(i, line)| (timing::line_cycle(line, cycles_per_frame), Some(i)));
+        let sections: Vec<_> = interrupts.chain([(cycles_per_frame, None)]).collect();
+        for (end, interrupt) in sections {
+            while elapsed < end {
+                let in_interrupt = self.cpu.in_interrupt();
+                let halted = self.cpu.is_halted();
+                let c = self.cpu.step();
+                if halted {
+                    spare += c;
+                }
+                if in_interrupt {
+                    self.frame_cycles.0 += c;
+                } else {
+                    self.frame_cycles.1 += c;
+                }
+                elapsed += c;
+            }
+            let Some(i) = interrupt else {
+                continue;
+            };
+            let delivered = self.cpu.interrupt(i) > 0;
+            if let Some(masking) = &mut self.masking {
+                masking.interrupt(delivered, self.cpu.program_counter());
+            }
+            self.interrupt_timing.record(Delivery {
+                rst: i,
+                ideal: end,
+                cycle: elapsed,
+                delivered,
+            });
+        }
+        self.overshoot = elapsed - cycles_per_frame;
+        if let Some(spare_cycles) = &mut self.spare_cycles {
+            spare_cycles.record(spare, cycles_per_frame);
+        }
+    }
+
+    /// Tell when the interrupts have stayed disabled for long, and when they come back
+    fn report_masking(&mut self) {
+        if let Some(diagnosis) = self.masking.as_mut().and_then(Masking::frame) {
+            eprintln!("{diagnosis}");
+            if let Diagnosis::Masked { .. } = diagnosis {
+                self.osd
+                    .push(("INTERRUPTS DISABLED".to_string(), 2 * OSD_FRAMES));
+            }
+        }
+    }
+
+    /// Print the stack warnings of the frame not printed before
+    fn report_stack_warnings(&mut self) {
+        for warning in self.cpu.take_stack_warnings() {
+            if self.stack_warned.insert(warning) {
+                eprintln!("Stack warning: {warning}");
+            }
+        }
+    }
 
-            while cycles < cycles_per_frame / 2 {
-                cycles += self.cpu.step();
+    /// Tell the players how the tournament goes: whose turn it is, the result of each game (saved to the leaderboard
+    /// of the player) and the winner
+    fn follow_tournament(&mut self) {
+        let Some(tournament) = &mut self.options.tournament else {
+            return;
+        };
+        match tournament.update(&self.cpu) {
+            Some(TournamentEvent::Started(player)) => {
+                println!("Tournament: {} playing", tournament.players[player].name)
+            }
+            Some(TournamentEvent::Finished {
+                player,
+                score,
+                place,
+            }) => {
+                let user = &tournament.players[player];
+                println!("Tournament: {} scored {score}", user.name);
+                print!("{}", user.profile.leaderboard(&user.name, place));
+                if let Err(e) = user.save() {
+                    eprintln!("{e}");
+                }
+                let message = format!("{} SCORED {score}", tournament.display_name(player));
+                self.osd.push((message, OSD_FRAMES));
+                if tournament.over() {
+                    print!("{}", tournament.report());
+                    let (winner, total, _) = tournament.ranking()[0];
+                    let message = format!("WINNER {} {total}", tournament.display_name(winner));
+                    self.osd.push((message, 2 * OSD_FRAMES));
+                } else {
+                    self.osd.push((tournament.prompt(), 2 * OSD_FRAMES));
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// When an event happened on the clock of the frame loop, from its SDL timestamp in nanoseconds
+    fn event_time(&self, timestamp: u64) -> Instant {
+        let now = self.time.now();
+        let ago = Duration::from_nanos(unsafe { SDL_GetTicksNS() }.saturating_sub(timestamp));
+        now.checked_sub(ago).unwrap_or(now)
+    }
+
+    /// Time a response to the flash of the latency test
+    fn respond_to_flash(&mut self, at: Instant, by: &str) {
+        let Some(flash_test) = &mut self.flash_test else {
+            return;
+        };
+        match flash_test.respond(at) {
+            Some(latency) => {
+                let ms = latency.as_millis();
+                println!("{by} response: {ms} ms");
+                self.osd.push((format!("LATENCY {ms} MS"), OSD_FRAMES));
+            }
+            None => println!("{by} response without a flash"),
+        }
+    }
+
+    /// Sending end of the latency test sensor, for a thread reading e.g. a photodiode over a serial port, which sends
+    /// the time it saw the flash
+    pub fn latency_sensor(&self) -> Sender<Instant> {
+        self.sensor.sender()
+    }
+
+    /// Sending end of the command queue, for changing the machine from another thread while running
+    pub fn commands(&self) -> Sender<Command> {
+        self.commands.sender()
+    }
+
+    /// Sending end of the picture-in-picture, for compositing an external image (webcam, logo) into a corner of the
+    /// output from another thread. The latest image sent is shown from the next frame, `None` removes it.
+    pub fn inset(&self) -> Sender<Option<Image>> {
+        self.inset_feed.sender()
+    }
+
+    /// Reader of a view of the machine published at the end of every frame, for UI, HTTP or scripting threads. Reading
+    /// never waits for the emulation, nor the emulation for the reader.
+    pub fn view(&mut self) -> Reader {
+        self.views.reader()
+    }
+
+    /// Sending end of the serial console on machines with one, for feeding the program bytes from another thread along
+    /// with the terminal
+    pub fn serial_input(&self) -> Option<Sender<Vec<u8>>> {
+        self.console.as_ref().map(Console::sender)
+    }
+
+    /// Pass the bytes typed since the last frame to the serial console, and print the bytes the program sent
+    fn update_serial(&mut self) {
+        let (Some(console), Some(serial)) = (&self.console, self.cpu.serial_mut()) else {
+            return;
+        };
+        serial.receive(&console.take());
+        let output = serial.take_output();
+        if !output.is_empty() {
+            let mut stdout = io::stdout();
+            if let Err(e) = stdout.write_all(&output).and_then(|_| stdout.flush()) {
+                eprintln!("Could not print serial output: {e}");
+            }
+        }
+    }
+
+    /// Apply the commands queued since the last frame. Inputs are held like keys until changed by another command.
+    fn apply_commands(&mut self) {
+        for command in self.commands.take() {
+            let result = match command {
+                Command::Input { input, active } => {
+                    self.keys.set_remote(input.name, active);
+                    Ok(())
+                }
+                command => command.apply(&mut self.cpu),
+            };
+            if let Err(e) = result {
+                eprintln!("Command failed: {e}");
+            }
+        }
+    }
+
+    /// Go back a second in the rewind history
+    fn rewind(&mut self) {
+        let Some(history) = &mut self.rewind else {
+            self.osd.push(("REWIND IS OFF".to_string(), OSD_FRAMES));
+            return;
+        };
+        let state = (0..self.fps).map_while(|_| history.pop()).last();
+        match state {
+            Some(state) => {
+                self.cpu
+                    .load_state(&state)
+                    .expect("Savestate of the same machine");
+                self.previous = Frame::default();
+                self.osd.push(("REWIND".to_string(), OSD_FRAMES));
+            }
+            None => self.osd.push(("NOTHING TO REWIND".to_string(), OSD_FRAMES)),
+        }
+    }
+
+    /// Pause in the frame where a break condition becomes true, print the machine state and open the memory editor at the
+    /// address in the condition
+    fn check_breaks(&mut self, frame: u32) {
+        let mut hit = None;
+        for trigger in &mut self.options.break_triggers {
+            // Check all, so each knows whether its condition held in this frame
+            if trigger.rising(&self.cpu, frame) && hit.is_none() {
+                hit = Some((
+                    trigger.condition.to_string(),
+                    trigger.condition.left.address(),
+                ));
+            }
+        }
+        let Some((condition, addr)) = hit else {
+            return;
+        };
+        println!("Frame {frame}: break on {condition}");
+        print!("{}", self.cpu.format_state(8));
+        self.paused = true;
+        if let Some(addr) = addr {
+            self.editor.goto(addr);
+            self.editing = true;
+        }
+        self.osd.push(("BREAK".to_string(), OSD_FRAMES));
+    }
+
+    /// Compare the frame with the reference, pausing at the first frame that diverges
+    fn compare_reference(&mut self, frame: u32) {
+        let Some(comparison) = &mut self.comparison else {
+            return;
+        };
+        if let Some(mismatch) = comparison.check(frame as u64, &self.cpu) {
+            println!("{mismatch}");
+            print!("{}", self.cpu.format_state(8));
+            self.paused = true;
+            self.osd
+                .push((format!("DIVERGED AT FRAME {frame}"), 4 * OSD_FRAMES));
+            self.comparison = None;
+        } else if comparison.finished() {
+            println!(
+                "Frame {frame}: all {} hashes match the reference",
+                comparison.compared()
+            );
+            self.osd.push(("REFERENCE MATCHED".to_string(), OSD_FRAMES));
+            self.comparison = None;
+        }
+    }
+
+    /// Write the interrupt deliveries of the frame to the log, if any
+    fn log_interrupts(&mut self, frame: u32) {
+        let Some(log) = &mut self.interrupt_log else {
+            return;
+        };
+        let lines: String = self
+            .interrupt_timing
+            .frame
+            .iter()
+            .map(|d| d.csv(frame as u64) + "\n")
+            .collect();
+        if let Err(e) = log.write_all(lines.as_bytes()) {
+            eprintln!("Could not log interrupts: {e}");
+            self.interrupt_log = None;
+        }
+    }
+
+    /// Run the cpu slowly, explaining each instruction. Interrupts are generated after the same number of cycles as in normal speed.
+    fn run_cpu_teaching(&mut self, cycles_per_frame: u32, instructions_per_second: u32) {
+        self.teach_credit += instructions_per_second;
+        while self.teach_credit >= self.fps {
+            self.teach_credit -= self.fps;
+
+            let instr = self.cpu.disassemble(self.cpu.program_counter());
+            let before = self.cpu.format_registers();
+            let cycles = self.cpu.step();
+            let after = self.cpu.format_registers();
+            let changed: Vec<&str> = before
+                .split(' ')
+                .zip(after.split(' '))
+                .filter(|(b, a)| b != a && !a.starts_with("PC="))
+                .map(|(_, a)| a)
+                .collect();
+            println!(
+                "{:04X}  {:<14} {:<42} {}",
+                instr.addr,
+                instr.mnemonic,
+                instr.description,
+                changed.join(" ")
+            );
+
+            let (run, next) = &mut self.teach_cycles;
+            *run += cycles;
+            if *run >= cycles_per_frame / 2 {
+                *run -= cycles_per_frame / 2;
+                if self.cpu.interrupt(*next) > 0 {
+                    println!("----  Interrupt RST {next}");
+                }
+                *next = if *next == 1 { 2 } else { 1 };
             }
-            self.cpu.interrupt(i);
         }
     }
 
     fn handle_input(&mut self) {
-        for event in self.event_pump.poll_iter() {
+        let events: Vec<Event> = self.event_pump.poll_iter().collect();
+        for event in events {
+            if let Some(action) = self.action(&event) {
+                self.run_action(action);
+                continue;
+            }
             match event {
-                // Quit
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape | Keycode::Grave),
+                    ..
+                } if self.menu_open => self.menu_open = false,
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if self.menu_open => self.menu_key(keycode),
+                Event::Quit { .. } => self.quit = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    timestamp,
+                    ..
+                } if self.flash_test.is_some() => {
+                    self.respond_to_flash(self.event_time(timestamp), "Key")
+                }
+                Event::AudioDeviceAdded {
+                    which,
+                    iscapture: false,
                     ..
-                } => self.quit = true,
+                } => self.reopen_audio |= self.audio_devices.added(which),
+                Event::AudioDeviceRemoved {
+                    which,
+                    iscapture: false,
+                    ..
+                } => self.reopen_audio |= self.audio_devices.removed(which),
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match self.gamepad.open(SDL_JoystickID(which)) {
+                        Ok(gamepad) => {
+                            let before = self.controllers.players();
+                            let guid = self.gamepad.guid_for_id(SDL_JoystickID(which));
+                            let name = gamepad.name().unwrap_or_default();
+                            self.controllers.connect(which, &guid.string(), &name);
+                            self.gamepads.push(gamepad);
+                            self.reassign_controllers(before);
+                        }
+                        Err(e) => eprintln!("Could not open game controller: {e}"),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    let before = self.controllers.players();
+                    self.gamepads
+                        .retain(|g| g.id().is_ok_and(|id| id != SDL_JoystickID(which)));
+                    self.controllers.disconnect(which);
+                    self.keys.release_controller(which);
+                    self.reassign_controllers(before);
+                }
+                Event::ControllerButtonDown { which, button, .. } => {
+                    if let Some(name) = self.controller_input(which, button) {
+                        if self.keys.press_button(which, name) {
+                            self.play_ambient(if name == "credit" { "coin" } else { "click" });
+                        }
+                    }
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    if let Some(name) = self.controller_input(which, button) {
+                        self.keys.release_button(which, name);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } if self.editing => self.edit(keycode, keymod),
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::Y | Keycode::N)),
+                    ..
+                } if !self.offers.is_empty() => self.answer_offer(keycode == Keycode::Y),
                 Event::KeyDown {
                     scancode: Some(scancode),
+                    which,
+                    timestamp,
                     ..
                 } => {
-                    if let Some((port, bit)) = Self::keymap(scancode) {
-                        self.cpu.set_bus_in_bit(port, bit, true);
+                    let input = self.keys.press_on(which, scancode);
+                    if input.is_some() {
+                        let (event, polled) = (self.event_time(timestamp), self.time.now());
+                        if let Some(pipeline) = &mut self.pipeline {
+                            pipeline.press(event, polled);
+                        }
+                    }
+                    match input {
+                        Some("credit") => self.play_ambient("coin"),
+                        Some("p1-start" | "p2-start" | "p1-fire" | "p2-fire") => {
+                            self.play_ambient("click")
+                        }
+                        _ => {}
                     }
                 }
                 Event::KeyUp {
                     scancode: Some(scancode),
+                    which,
+                    ..
+                } => self.keys.release_on(which, scancode),
+                // Key releases are not seen without focus, so nothing is held after getting it back
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => {
+                    self.keys.clear();
+                    self.lose_focus();
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
                     ..
                 } => {
-                    if let Some((port, bit)) = Self::keymap(scancode) {
-                        self.cpu.set_bus_in_bit(port, bit, false);
+                    if std::mem::take(&mut self.paused_in_background) {
+                        self.paused = false;
                     }
+                    self.throttled = false;
                 }
                 _ => {}
             }
         }
+        if let Some(remote) = &mut self.remote {
+            for (name, active) in remote.poll() {
+                self.keys.set_remote(name, active);
+            }
+        }
+        self.keys.apply(&mut self.cpu);
     }
 
-    /// Match MAME controls somewhat
-    fn keymap(scancode: Scancode) -> Option<(usize, u8)> {
-        match scancode {
-            Scancode::T => Some((2, 2)),     // Tilt
-            Scancode::_5 => Some((1, 0)),    // Add Credit
-            Scancode::_1 => Some((1, 2)),    // P1 Start
-            Scancode::_2 => Some((1, 1)),    // P2 Start
-            Scancode::LCtrl => Some((1, 4)), // P1 Fire
-            Scancode::Left => Some((1, 5)),  // P1 Left
-            Scancode::Right => Some((1, 6)), // P1 Right
-            Scancode::A => Some((2, 4)),     // P2 Fire
-            Scancode::D => Some((2, 5)),     // P2 Left
-            Scancode::G => Some((2, 6)),     // P2 Right
+    /// The action bound to a key or controller button, if any. While the command palette is open it takes all keys,
+    /// and the character keys go to the memory editor while it is shown.
+    fn action(&self, event: &Event) -> Option<Action> {
+        match *event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } if !self.menu_open => {
+                let modifier = if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                    Modifier::Ctrl
+                } else if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                    Modifier::Shift
+                } else {
+                    Modifier::None
+                };
+                let (trigger, action) = self.bindings.key(&keycode.name(), modifier)?;
+                (!(self.editing && trigger.typing())).then_some(action)
+            }
+            Event::ControllerButtonDown { button, .. } => self.bindings.button(&button.string()),
             _ => None,
         }
     }
+
+    /// Run an emulator command, from its key or button or the command palette
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Pause => {
+                self.paused = !self.paused;
+                if self.paused {
+                    println!("Paused");
+                    print!("{}", self.cpu.format_state(8));
+                    self.osd.push(("PAUSED".to_string(), OSD_FRAMES));
+                }
+            }
+            Action::StepFrame => self.advance |= self.paused,
+            Action::MemoryEditor => {
+                self.editing = !self.editing;
+                self.cpu.set_display_update(true);
+            }
+            Action::FrameBlending => self.options.blend = self.options.blend.next(),
+            Action::Reset => self.cpu.reset(),
+            Action::PowerCycle => {
+                self.cpu.power_cycle();
+                self.previous = Frame::default();
+            }
+            Action::CycleBar => self.options.cycle_bar = !self.options.cycle_bar,
+            Action::Screenshot => {
+                let secs = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                self.save_screenshot(&secs.to_string());
+            }
+            Action::PrintText => {
+                for line in text::decode(self.cpu.memory()) {
+                    println!("{line}");
+                }
+            }
+            Action::CompareStates => match &self.slots {
+                [Some(a), Some(b)] => print!("State A -> B:\n{}", compare::diff(a, b)),
+                _ => println!("Store states A (F11) and B (F12) first"),
+            },
+            Action::Slower | Action::Faster => {
+                let speed = timing::next_speed(self.options.speed, action == Action::Faster);
+                self.set_speed(speed);
+            }
+            Action::StoreState(slot) => {
+                let name = if slot == 0 { 'A' } else { 'B' };
+                self.slots[slot] = Some(Slot::capture(&self.cpu));
+                println!("State {name} stored");
+                self.osd.push((format!("STATE {name} STORED"), OSD_FRAMES));
+            }
+            Action::PortPanel => {
+                self.port_panel = !self.port_panel;
+                self.cpu.enable_port_monitor();
+                self.cpu.set_display_update(true);
+            }
+            Action::FreePlay => self.toggle_free_play(),
+            Action::SwapControllers => {
+                let before = self.controllers.players();
+                self.controllers.swap();
+                self.reassign_controllers(before);
+            }
+            Action::ToggleDip(n) => self.toggle_dip(n),
+            Action::MuteSound(channel) if channel < self.sounds.len() => {
+                self.muted[channel] = !self.muted[channel];
+                let state = if self.muted[channel] {
+                    "muted"
+                } else {
+                    "unmuted"
+                };
+                println!("Sound {channel} ({}) {state}", self.sounds[channel].2);
+            }
+            Action::SoloSound(channel) if channel < self.sounds.len() => {
+                self.solo = if self.solo == Some(channel) {
+                    None
+                } else {
+                    Some(channel)
+                };
+                match self.solo {
+                    Some(_) => println!("Sound {channel} ({}) solo", self.sounds[channel].2),
+                    None => println!("Sound solo off"),
+                }
+            }
+            Action::MuteSound(_) | Action::SoloSound(_) => {}
+            Action::Palette => self.menu_open = true,
+            Action::Rewind => self.rewind(),
+            Action::Quit => self.quit = true,
+        }
+    }
+
+    /// Command palette keys: letters, digits and space search, arrows select and enter runs the selected command
+    fn menu_key(&mut self, keycode: Keycode) {
+        match keycode {
+            Keycode::Return | Keycode::KpEnter => {
+                self.menu_open = false;
+                if let Some(action) = self.menu.choose() {
+                    self.run_action(action);
+                }
+            }
+            Keycode::Backspace => self.menu.backspace(),
+            Keycode::Up => self.menu.move_by(-1),
+            Keycode::Down => self.menu.move_by(1),
+            Keycode::Space => self.menu.type_char(' '),
+            _ => {
+                if let [c] = keycode.name().as_bytes() {
+                    if c.is_ascii_alphanumeric() {
+                        self.menu.type_char(*c as char);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The window went to the background: pause, or silence the sounds and throttle, as chosen with `--focus-loss`
+    fn lose_focus(&mut self) {
+        match self.options.focus_loss {
+            FocusLoss::Run => {}
+            FocusLoss::Pause => {
+                self.paused_in_background = !self.paused;
+                self.paused = true;
+            }
+            FocusLoss::Throttle => {
+                self.throttled = true;
+                let queues = self
+                    .sounds
+                    .iter()
+                    .filter_map(|(_, _, _, q, _, _)| q.as_ref());
+                let ambience = self.ambience.iter().map(|(_, stream, _)| stream);
+                for stream in queues.chain(ambience) {
+                    stream.clear().expect("Could not clear audio");
+                }
+            }
+        }
+    }
+
+    /// Toggle DIP switch `dip<n>`, only in operator mode. The new setting is kept in the user profile.
+    fn toggle_dip(&mut self, n: u8) {
+        if !self.operator {
+            self.osd
+                .push(("OPERATOR MODE REQUIRED".to_string(), OSD_FRAMES));
+            return;
+        }
+        let Some(dip) = self.options.machine.board.find(&format!("dip{n}")) else {
+            return;
+        };
+        let on = !dip.active(&self.cpu);
+        dip.set(&mut self.cpu, on);
+        let state = if on { "ON" } else { "OFF" };
+        println!("{} {state}: {}", dip.name, dip.description);
+        self.osd
+            .push((format!("{} {state}", dip.name.to_uppercase()), OSD_FRAMES));
+        if let Some(user) = &mut self.options.user {
+            user.profile.inputs.retain(|(name, _)| name != dip.name);
+            user.profile.inputs.push((dip.name.to_string(), on));
+        }
+    }
+
+    /// Turn free play on or off, only in operator mode
+    fn toggle_free_play(&mut self) {
+        if !self.operator {
+            self.osd
+                .push(("OPERATOR MODE REQUIRED".to_string(), OSD_FRAMES));
+            return;
+        }
+        self.free_play = match self.free_play {
+            Some(_) => None,
+            None => Some(FreePlay::default()),
+        };
+        let state = if self.free_play.is_some() {
+            "ON"
+        } else {
+            "OFF"
+        };
+        self.osd.push((format!("FREE PLAY {state}"), OSD_FRAMES));
+    }
+
+    /// Input of a controller button. Controllers not assigned to a player control player 1.
+    fn controller_input(&self, which: u32, button: Button) -> Option<&'static str> {
+        let name = BUTTONMAP.iter().find(|(b, _)| *b == button)?.1;
+        let second = self.controllers.player(which) == Some(1);
+        Some(if second { player2(name) } else { name })
+    }
+
+    /// Tell the players whose controller changed since the assignment `before`. Buttons held on the controllers
+    /// changing players are released, they would be stuck on the inputs of the other player.
+    fn reassign_controllers(&mut self, before: [Option<u32>; PLAYERS]) {
+        let after = self.controllers.players();
+        for (player, name) in self.controllers.changes(before) {
+            let player = player + 1;
+            match name {
+                Some(name) => {
+                    println!("Player {player} controller: {name}");
+                    let message = format!("PLAYER {player} CONTROLLER ASSIGNED");
+                    self.osd.push((message, OSD_FRAMES));
+                }
+                None => {
+                    println!("Player {player} controller removed");
+                    let message = format!("PLAYER {player} CONTROLLER REMOVED");
+                    self.osd.push((message, OSD_FRAMES));
+                }
+            }
+        }
+        for (before, after) in before.into_iter().zip(after).filter(|(b, a)| b != a) {
+            for controller in [before, after].into_iter().flatten() {
+                self.keys.release_controller(controller);
+            }
+        }
+    }
+
+    /// Memory editor keys: arrows and page up/down move, hex digits edit, `Ctrl+Z`/`Ctrl+Y` undo/redo and `Ctrl+R`
+    /// toggles the protection of ROM
+    fn edit(&mut self, keycode: Keycode, keymod: Mod) {
+        let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        let (row, page) = (PER_ROW as isize, (ROWS * PER_ROW) as isize);
+        match keycode {
+            Keycode::Left => self.editor.move_by(-1),
+            Keycode::Right => self.editor.move_by(1),
+            Keycode::Up => self.editor.move_by(-row),
+            Keycode::Down => self.editor.move_by(row),
+            Keycode::PageUp => self.editor.move_by(-page),
+            Keycode::PageDown => self.editor.move_by(page),
+            Keycode::Z if ctrl => {
+                self.editor.undo(&mut self.cpu);
+            }
+            Keycode::Y if ctrl => {
+                self.editor.redo(&mut self.cpu);
+            }
+            Keycode::R if ctrl && !self.operator => self
+                .osd
+                .push(("OPERATOR MODE REQUIRED".to_string(), OSD_FRAMES)),
+            Keycode::R if ctrl => {
+                self.editor.protect_rom = !self.editor.protect_rom;
+                let state = if self.editor.protect_rom {
+                    "ROM PROTECTED"
+                } else {
+                    "ROM WRITABLE"
+                };
+                self.osd.push((state.to_string(), OSD_FRAMES));
+            }
+            _ => {
+                if let Some(digit) = HEX_KEYS.iter().position(|k| *k == keycode) {
+                    if let Err(e) = self.editor.type_digit(&mut self.cpu, digit as u8) {
+                        self.osd.push((e, OSD_FRAMES));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Match MAME controls somewhat, keys by input name
+const KEYMAP: [(Scancode, &str); 10] = [
+    (Scancode::T, "tilt"),
+    (Scancode::_5, "credit"),
+    (Scancode::_1, "p1-start"),
+    (Scancode::_2, "p2-start"),
+    (Scancode::LCtrl, "p1-fire"),
+    (Scancode::Left, "p1-left"),
+    (Scancode::Right, "p1-right"),
+    (Scancode::A, "p2-fire"),
+    (Scancode::D, "p2-left"),
+    (Scancode::G, "p2-right"),
+];
+
+/// Service key, held at boot for operator mode (the service coin key of MAME)
+const SERVICE_KEY: Scancode = Scancode::_9;
+
+/// Controller buttons by input name, for player 1
+const BUTTONMAP: [(Button, &str); 6] = [
+    (Button::Back, "credit"),
+    (Button::Start, "p1-start"),
+    (Button::South, "p1-fire"),
+    (Button::West, "p1-fire"),
+    (Button::DPadLeft, "p1-left"),
+    (Button::DPadRight, "p1-right"),
+];
+
+/// Player 1 inputs and the same inputs of player 2
+const PLAYER2: [(&str, &str); 4] = [
+    ("p1-start", "p2-start"),
+    ("p1-fire", "p2-fire"),
+    ("p1-left", "p2-left"),
+    ("p1-right", "p2-right"),
+];
+
+/// The player 2 input for a player 1 input, other inputs are shared
+fn player2(name: &'static str) -> &'static str {
+    PLAYER2
+        .iter()
+        .find(|(p1, _)| *p1 == name)
+        .map_or(name, |(_, p2)| p2)
+}
+
+/// SDL color of a palette color
+fn sdl_color(color: palette::Color) -> Color {
+    Color::RGBA(color.r, color.g, color.b, color.a)
+}
+
+/// Scale mode of the game textures, sharp scaling copies them by a whole factor first
+fn scale_mode(scaling: Scaling) -> ScaleMode {
+    match scaling {
+        Scaling::Nearest | Scaling::Sharp => ScaleMode::Nearest,
+        Scaling::Linear => ScaleMode::Linear,
+    }
+}
+
+/// Load a BMP for the picture-in-picture as RGBA
+fn load_inset(assets: &Locator, path: &str) -> Result<Image, String> {
+    let bmp = assets.read_path(path)?;
+    let mut stream = IOStream::from_bytes(&bmp).map_err(|e| e.to_string())?;
+    let surface = Surface::load_bmp_rw(&mut stream).map_err(|e| e.to_string())?;
+    let format = PixelFormat::try_from(SDL_PIXELFORMAT_RGBA32).map_err(|e| e.to_string())?;
+    let surface = surface.convert_format(format).map_err(|e| e.to_string())?;
+    let (width, height) = surface.size();
+    let pitch = surface.pitch() as usize;
+    let pixels = surface.with_lock(|data| {
+        data.chunks(pitch)
+            .take(height as usize)
+            .flat_map(|row| &row[..width as usize * 4])
+            .copied()
+            .collect()
+    });
+    Image::new(width, height, pixels)
+}
+
+/// Keys held down. The input bits are recomputed from the whole set, so overlapping presses and releases
+/// (or several keys bound to the same input) never leave an input stuck.
+#[derive(Debug)]
+struct Keys {
+    /// Keys by input name
+    keymap: Vec<(Scancode, &'static str)>,
+    pressed: HashSet<Scancode>,
+    /// Keys held on the second keyboard, where the player 1 keys control player 2
+    second: HashSet<Scancode>,
+    /// Keyboards in the order they were first used
+    keyboards: Vec<u32>,
+    /// Inputs held on controllers, by joystick id
+    buttons: HashSet<(u32, &'static str)>,
+    /// Inputs held on the remote control panel or by commands
+    remote: HashSet<&'static str>,
+    /// Player 1 inputs also control player 2 in player 2's turn
+    mirror: bool,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Keys {
+            keymap: KEYMAP.to_vec(),
+            pressed: HashSet::new(),
+            second: HashSet::new(),
+            keyboards: vec![],
+            buttons: HashSet::new(),
+            remote: HashSet::new(),
+            mirror: false,
+        }
+    }
+}
+
+impl Keys {
+    /// Bind keys to inputs, replacing the default keys of those inputs
+    fn bind(&mut self, bindings: &[(Scancode, &'static str)]) {
+        self.keymap
+            .retain(|(_, name)| !bindings.iter().any(|(_, n)| n == name));
+        self.keymap.extend_from_slice(bindings);
+    }
+
+    /// The keyboard is the second one used. SDL reports 0 for events it can't tell the keyboard of.
+    fn is_second(&mut self, keyboard: u32) -> bool {
+        if keyboard == 0 {
+            return false;
+        }
+        if !self.keyboards.contains(&keyboard) {
+            self.keyboards.push(keyboard);
+        }
+        self.keyboards.get(1) == Some(&keyboard)
+    }
+
+    /// Key pressed on a keyboard, returns the name of its input if it was not already held
+    fn press_on(&mut self, keyboard: u32, scancode: Scancode) -> Option<&'static str> {
+        if self.is_second(keyboard) {
+            let name = self.keymap.iter().find(|(s, _)| *s == scancode)?.1;
+            self.second.insert(scancode).then_some(player2(name))
+        } else {
+            self.press(scancode)
+        }
+    }
+
+    /// Key released on a keyboard
+    fn release_on(&mut self, keyboard: u32, scancode: Scancode) {
+        if self.is_second(keyboard) {
+            self.second.remove(&scancode);
+        } else {
+            self.release(scancode);
+        }
+    }
+
+    /// Controller button pressed, true if it was not already held
+    fn press_button(&mut self, controller: u32, name: &'static str) -> bool {
+        self.buttons.insert((controller, name))
+    }
+
+    /// Controller button released
+    fn release_button(&mut self, controller: u32, name: &'static str) {
+        self.buttons.remove(&(controller, name));
+    }
+
+    /// Input set by the remote control panel or a command
+    fn set_remote(&mut self, name: &'static str, active: bool) {
+        if active {
+            self.remote.insert(name);
+        } else {
+            self.remote.remove(name);
+        }
+    }
+
+    /// Release all buttons of a disconnected controller
+    fn release_controller(&mut self, controller: u32) {
+        self.buttons.retain(|(c, _)| *c != controller);
+    }
+
+    /// Key pressed, returns the name of its input if it was not already held
+    fn press(&mut self, scancode: Scancode) -> Option<&'static str> {
+        let name = self.keymap.iter().find(|(s, _)| *s == scancode)?.1;
+        self.pressed.insert(scancode).then_some(name)
+    }
+
+    /// Key released
+    fn release(&mut self, scancode: Scancode) {
+        self.pressed.remove(&scancode);
+    }
+
+    /// Release all keys and buttons
+    fn clear(&mut self) {
+        self.pressed.clear();
+        self.second.clear();
+        self.buttons.clear();
+    }
+
+    /// An input is active while any of its keys or buttons is held
+    fn active(&self, name: &str) -> bool {
+        self.keymap.iter().any(|(s, n)| {
+            (*n == name && self.pressed.contains(s))
+                || (player2(n) == name && self.second.contains(s))
+        }) || self.buttons.iter().any(|(_, n)| *n == name)
+            || self.remote.contains(name)
+    }
+
+    /// Set every mapped input from the keys held. When mirroring, player 1's inputs control player 2 in its turn.
+    fn apply(&self, cpu: &mut Cpu) {
+        let mirror = self.mirror && batch::current_player(cpu) == 2;
+        for (_, name) in self.keymap.iter().copied() {
+            if let Some(input) = INVADERS.find(name) {
+                let mirrored = mirror
+                    && PLAYER2
+                        .iter()
+                        .any(|(p1, p2)| *p2 == name && self.active(p1));
+                input.set(cpu, self.active(name) || mirrored);
+            }
+        }
+    }
 }
@@ -1 +1,137 @@
+use super::*;
 
+fn port(keys: &Keys, port: usize) -> u8 {
+    let mut cpu = Cpu::new(vec![]);
+    keys.apply(&mut cpu);
+    cpu.get_bus_in(port)
+}
+
+#[test]
+fn overlapping_keys() {
+    let mut keys = Keys::default();
+    assert_eq!(keys.press(Scancode::LCtrl), Some("p1-fire"));
+    assert_eq!(keys.press(Scancode::Left), Some("p1-left"));
+    assert_eq!(port(&keys, 1), 0b0011_1000);
+    keys.release(Scancode::LCtrl);
+    assert_eq!(keys.press(Scancode::Right), Some("p1-right"));
+    assert_eq!(port(&keys, 1), 0b0110_1000);
+    keys.release(Scancode::Left);
+    keys.release(Scancode::Right);
+    assert_eq!(port(&keys, 1), 0b0000_1000);
+}
+
+#[test]
+fn repeat_and_unmapped() {
+    let mut keys = Keys::default();
+    assert_eq!(keys.press(Scancode::_5), Some("credit"));
+    assert_eq!(keys.press(Scancode::_5), None);
+    assert_eq!(keys.press(Scancode::Q), None);
+    keys.release(Scancode::Q);
+    keys.release(Scancode::T);
+    assert_eq!(port(&keys, 1), 0b0000_1001);
+    assert_eq!(port(&keys, 2), 0);
+    keys.release(Scancode::_5);
+    assert_eq!(port(&keys, 1), 0b0000_1000);
+}
+
+#[test]
+fn release_without_press() {
+    let mut keys = Keys::default();
+    keys.release(Scancode::A);
+    keys.press(Scancode::A);
+    keys.press(Scancode::D);
+    keys.release(Scancode::A);
+    keys.release(Scancode::A);
+    assert_eq!(port(&keys, 2), 0b0010_0000);
+}
+
+#[test]
+fn clear_releases_everything() {
+    let mut keys = Keys::default();
+    for (scancode, _) in KEYMAP {
+        keys.press(scancode);
+    }
+    assert_eq!(port(&keys, 1), 0b0111_1111);
+    assert_eq!(port(&keys, 2), 0b0111_0100);
+    keys.clear();
+    assert_eq!(port(&keys, 1), 0b0000_1000);
+    assert_eq!(port(&keys, 2), 0);
+}
+
+#[test]
+fn stale_bits_are_cleared() {
+    let mut cpu = Cpu::new(vec![]);
+    cpu.set_bus_in_bit(1, 4, true);
+    Keys::default().apply(&mut cpu);
+    assert_eq!(cpu.get_bus_in(1), 0b0000_1000);
+}
+
+#[test]
+fn second_keyboard_controls_player2() {
+    let mut keys = Keys::default();
+    assert_eq!(keys.press_on(7, Scancode::LCtrl), Some("p1-fire"));
+    assert_eq!(keys.press_on(9, Scancode::LCtrl), Some("p2-fire"));
+    assert_eq!(keys.press_on(9, Scancode::_5), Some("credit"));
+    assert_eq!(port(&keys, 1), 0b0001_1001);
+    assert_eq!(port(&keys, 2) & 0b0001_0000, 0b0001_0000);
+    keys.release_on(9, Scancode::LCtrl);
+    assert_eq!(port(&keys, 2) & 0b0001_0000, 0);
+    // Unknown keyboards are player 1
+    assert_eq!(keys.press_on(0, Scancode::Left), Some("p1-left"));
+}
+
+#[test]
+fn controller_buttons() {
+    let mut keys = Keys::default();
+    assert!(keys.press_button(3, "p2-left"));
+    assert!(!keys.press_button(3, "p2-left"));
+    assert_eq!(port(&keys, 2) & 0b0010_0000, 0b0010_0000);
+    keys.release_controller(3);
+    assert_eq!(port(&keys, 2) & 0b0010_0000, 0);
+}
+
+#[test]
+fn mirror_in_player2_turn() {
+    let mut keys = Keys {
+        mirror: true,
+        ..Default::default()
+    };
+    keys.press(Scancode::Right);
+    let mut cpu = Cpu::new(vec![]);
+    keys.apply(&mut cpu);
+    assert_eq!(cpu.get_bus_in(2) & 0b0100_0000, 0);
+    cpu.poke(0x2067, 0x22);
+    keys.apply(&mut cpu);
+    assert_eq!(cpu.get_bus_in(2) & 0b0100_0000, 0b0100_0000);
+    assert_eq!(cpu.get_bus_in(1) & 0b0100_0000, 0b0100_0000);
+}
+
+#[test]
+fn remote_inputs() {
+    let mut keys = Keys::default();
+    keys.set_remote("p1-fire", true);
+    keys.press(Scancode::LCtrl);
+    keys.release(Scancode::LCtrl);
+    assert_eq!(port(&keys, 1), 0b0001_1000);
+    keys.set_remote("p1-fire", false);
+    assert_eq!(port(&keys, 1), 0b0000_1000);
+}
+
+#[test]
+fn bound_keys_replace_defaults() {
+    let mut keys = Keys::default();
+    keys.bind(&[(Scancode::Space, "p1-fire"), (Scancode::Up, "p1-fire")]);
+    assert_eq!(keys.press(Scancode::LCtrl), None);
+    assert_eq!(keys.press(Scancode::Up), Some("p1-fire"));
+    assert_eq!(port(&keys, 1), 0b0001_1000);
+    assert_eq!(keys.press(Scancode::Left), Some("p1-left"));
+}
+
+#[test]
+fn dip_keys_name_dip_switches() {
+    for (_, action) in Bindings::default().0 {
+        if let Action::ToggleDip(n) = action {
+            assert!(INVADERS.find(&format!("dip{n}")).is_some(), "dip{n}");
+        }
+    }
+}
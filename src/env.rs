@@ -0,0 +1,148 @@
+//! Reinforcement learning environment in the style of OpenAI Gym: reset, then step with an action and get an observation, a reward and whether the game is over
+
+use crate::{
+    batch::{in_game, run_frame, score, start_input, Input},
+    cpu::{Cpu, RamInit},
+    frame::Frame,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Downsampling of the display in observations
+pub const OBSERVATION_SCALE: u32 = 2;
+/// Width of observations
+pub const OBSERVATION_WIDTH: u32 = DISPLAY_WIDTH / OBSERVATION_SCALE;
+/// Height of observations
+pub const OBSERVATION_HEIGHT: u32 = DISPLAY_HEIGHT / OBSERVATION_SCALE;
+/// Frames to wait for a game to start after reset
+const START_FRAMES: u32 = 1200;
+
+/// Actions of the player
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Noop,
+    Fire,
+    Left,
+    Right,
+    LeftFire,
+    RightFire,
+}
+
+/// All actions, index them to map from a discrete action space
+pub const ACTIONS: [Action; 6] = [
+    Action::Noop,
+    Action::Fire,
+    Action::Left,
+    Action::Right,
+    Action::LeftFire,
+    Action::RightFire,
+];
+
+impl Action {
+    fn input(self) -> Input {
+        let (left, right, fire) = match self {
+            Action::Noop => (false, false, false),
+            Action::Fire => (false, false, true),
+            Action::Left => (true, false, false),
+            Action::Right => (false, true, false),
+            Action::LeftFire => (true, false, true),
+            Action::RightFire => (false, true, true),
+        };
+        Input {
+            left,
+            right,
+            fire,
+            ..Input::default()
+        }
+    }
+}
+
+/// Result of a step
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Downsampled display, OBSERVATION_WIDTH x OBSERVATION_HEIGHT intensities row by row
+    pub observation: Vec<u8>,
+    /// Points scored during the step
+    pub reward: u32,
+    /// The game is over
+    pub done: bool,
+}
+
+/// A single player game, stepped one frame at a time
+pub struct Env {
+    /// The rom
+    rom: Vec<u8>,
+    /// The machine
+    cpu: Cpu,
+    /// Score after the last step
+    score: u32,
+}
+
+impl Env {
+    pub fn new(rom: Vec<u8>) -> Self {
+        Env {
+            cpu: Cpu::new(rom.clone()),
+            rom,
+            score: 0,
+        }
+    }
+
+    /// Power on with RAM initialized from the seed, insert a coin and start a one player game. Returns the first observation.
+    pub fn reset(&mut self, seed: u64) -> Result<Vec<u8>, String> {
+        self.cpu = Cpu::new(self.rom.clone());
+        self.cpu.set_ram_init(RamInit::Random(seed));
+        self.cpu.power_cycle();
+        for frame in 0..START_FRAMES {
+            start_input(frame).apply(&mut self.cpu);
+            run_frame(&mut self.cpu);
+            if in_game(&self.cpu) {
+                Input::default().apply(&mut self.cpu);
+                self.score = score(&self.cpu);
+                return Ok(self.observation());
+            }
+        }
+        Err("The game did not start".to_string())
+    }
+
+    /// Run one frame with the action
+    pub fn step(&mut self, action: Action) -> Step {
+        action.input().apply(&mut self.cpu);
+        run_frame(&mut self.cpu);
+        let score = score(&self.cpu);
+        let reward = score.saturating_sub(self.score);
+        self.score = score;
+        Step {
+            observation: self.observation(),
+            reward,
+            done: !in_game(&self.cpu),
+        }
+    }
+
+    /// Downsampled display, each pixel the brightest of the pixels it covers
+    pub fn observation(&self) -> Vec<u8> {
+        let frame = Frame::render(&self.cpu);
+        let mut observation = Vec::with_capacity((OBSERVATION_WIDTH * OBSERVATION_HEIGHT) as usize);
+        for y in 0..OBSERVATION_HEIGHT {
+            for x in 0..OBSERVATION_WIDTH {
+                let pixel = (0..OBSERVATION_SCALE * OBSERVATION_SCALE)
+                    .map(|i| {
+                        frame.get(
+                            x * OBSERVATION_SCALE + i % OBSERVATION_SCALE,
+                            y * OBSERVATION_SCALE + i / OBSERVATION_SCALE,
+                        )
+                    })
+                    .max()
+                    .unwrap_or_default();
+                observation.push(pixel);
+            }
+        }
+        observation
+    }
+
+    /// The machine, e.g. for reading RAM
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+}
@@ -0,0 +1,82 @@
+use super::*;
+
+/// Starts a game on start, scores 10 on fire and ends the game on left
+fn setup() -> Vec<u8> {
+    let mut program = vec![
+        0xAF, // XRA A
+        0x32, 0xEF, 0x20, // STA 20EFh (gameMode)
+        0x32, 0xF8, 0x20, // STA 20F8h (P1Scor)
+        0x32, 0xF9, 0x20, // STA 20F9h
+        0xDB, 0x01, // IN 1
+        0xE6, 0x04, // ANI 04h (start)
+        0xCA, 0x0A, 0x00, // JZ 000Ah
+        0x3E, 0x01, // MVI A,1
+        0x32, 0xEF, 0x20, // STA 20EFh
+        0xDB, 0x01, // IN 1
+        0xE6, 0x20, // ANI 20h (left)
+        0xC2, 0x2C, 0x00, // JNZ 002Ch
+        0xDB, 0x01, // IN 1
+        0xE6, 0x10, // ANI 10h (fire)
+        0xCA, 0x16, 0x00, // JZ 0016h
+        0x3E, 0x10, // MVI A,10h
+        0x32, 0xF8, 0x20, // STA 20F8h
+        0xC3, 0x16, 0x00, // JMP 0016h
+        0xAF, // XRA A
+        0x32, 0xEF, 0x20, // STA 20EFh
+        0x76, // HLT
+    ];
+    program.resize(0x2000, 0);
+    program
+}
+
+#[test]
+fn reset_starts_game() {
+    let mut env = Env::new(setup());
+    let observation = env.reset(1).unwrap();
+    assert_eq!(
+        observation.len(),
+        (OBSERVATION_WIDTH * OBSERVATION_HEIGHT) as usize
+    );
+    assert!(in_game(env.cpu()));
+}
+
+#[test]
+fn reset_fails_without_game() {
+    let mut env = Env::new(vec![0xAF, 0x32, 0xEF, 0x20, 0x76]); // XRA A; STA 20EFh; HLT
+    assert!(env.reset(1).is_err());
+}
+
+#[test]
+fn step_rewards_and_ends() {
+    let mut env = Env::new(setup());
+    env.reset(1).unwrap();
+    let step = env.step(Action::Noop);
+    assert_eq!((step.reward, step.done), (0, false));
+    let step = env.step(Action::Fire);
+    assert_eq!((step.reward, step.done), (10, false));
+    let step = env.step(Action::Fire);
+    assert_eq!((step.reward, step.done), (0, false));
+    let step = env.step(Action::Left);
+    assert!(step.done);
+}
+
+#[test]
+fn observation_downsampled() {
+    let mut program = setup();
+    program.resize(0x2400 + 0x20, 0);
+    program[0x2400] = 0x01; // Bottom left pixel of the display
+    let env = Env::new(program);
+    let observation = env.observation();
+    assert_eq!(
+        observation[((OBSERVATION_HEIGHT - 1) * OBSERVATION_WIDTH) as usize],
+        0xFF
+    );
+    assert_eq!(observation.iter().filter(|p| **p > 0).count(), 1);
+}
+
+#[test]
+fn actions() {
+    assert_eq!(ACTIONS.len(), 6);
+    assert!(Action::LeftFire.input().left && Action::LeftFire.input().fire);
+    assert_eq!(Action::Noop.input(), Input::default());
+}
@@ -0,0 +1,212 @@
+//! Expressions over the machine state (registers, memory and frame count), for watches and triggers
+
+use std::fmt;
+
+use crate::{
+    cpu::{Cpu, REGISTERS},
+    ram_map,
+    utils::parse_number,
+    MEMORY_SIZE,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// An expression evaluating to a value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// Constant
+    Number(u32),
+    /// Number of frames since start, `frame`
+    Frame,
+    /// Register, register pair or flag, e.g. `A`, `HL` or `CY`
+    Register(String),
+    /// Byte in memory, `mem[addr]` or `[addr]`, where addr may be a name from the RAM map with an offset
+    Byte(usize),
+    /// Word (little endian) in memory, `word[addr]`
+    Word(usize),
+}
+
+impl Expr {
+    /// Parse an expression
+    pub fn parse(s: &str) -> Result<Expr, String> {
+        let s = s.trim();
+        // Address of `len` bytes, all of them in memory
+        let address = |inner: &str, len: usize| match parse_address(inner) {
+            Some(addr) if addr.checked_add(len).is_some_and(|end| end <= MEMORY_SIZE) => Ok(addr),
+            _ => Err(format!("Invalid address in {s}")),
+        };
+        if let Some(inner) = s
+            .strip_prefix("mem[")
+            .or_else(|| s.strip_prefix('['))
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            return Ok(Expr::Byte(address(inner, 1)?));
+        }
+        if let Some(inner) = s
+            .strip_prefix("word[")
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            return Ok(Expr::Word(address(inner, 2)?));
+        }
+        if let Some(n) = parse_number(s) {
+            return u32::try_from(n)
+                .map(Expr::Number)
+                .map_err(|_| format!("Number too large: {s}"));
+        }
+        if s == "frame" {
+            return Ok(Expr::Frame);
+        }
+        let name = s.to_uppercase();
+        if REGISTERS.contains(&name.as_str()) {
+            return Ok(Expr::Register(name));
+        }
+        Err(format!("Invalid expression: {s}"))
+    }
+
+    /// Memory address read by the expression
+    pub fn address(&self) -> Option<usize> {
+        match self {
+            Expr::Byte(addr) | Expr::Word(addr) => Some(*addr),
+            _ => None,
+        }
+    }
+
+    /// Evaluate the expression on the current machine state and frame
+    pub fn eval(&self, cpu: &Cpu, frame: u32) -> u32 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Frame => frame,
+            Expr::Register(name) => cpu.register(name).unwrap_or_default().into(),
+            Expr::Byte(addr) => cpu.memory()[*addr].into(),
+            Expr::Word(addr) => {
+                u16::from_le_bytes([cpu.memory()[*addr], cpu.memory()[addr + 1]]).into()
+            }
+        }
+    }
+}
+
+/// Parse an address, a number or a name from the RAM map, optionally with an offset (e.g. `P1Scor+1`)
+fn parse_address(s: &str) -> Option<usize> {
+    let (base, offset) = match s.split_once('+') {
+        Some((base, offset)) => (base.trim(), parse_number(offset.trim())?),
+        None => (s.trim(), 0),
+    };
+    let base = parse_number(base).or_else(|| ram_map::find(base).map(|r| r.addr))?;
+    base.checked_add(offset)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "0x{n:X}"),
+            Expr::Frame => write!(f, "frame"),
+            Expr::Register(name) => write!(f, "{name}"),
+            Expr::Byte(addr) => write!(f, "mem[0x{addr:04X}]"),
+            Expr::Word(addr) => write!(f, "word[0x{addr:04X}]"),
+        }
+    }
+}
+
+/// An expression that is checked for changes, e.g. once a frame
+#[derive(Debug)]
+pub struct Watch {
+    /// Watched expression
+    pub expr: Expr,
+    /// Value at the last check
+    value: Option<u32>,
+}
+
+impl Watch {
+    pub fn new(expr: Expr) -> Self {
+        Watch { expr, value: None }
+    }
+
+    /// Evaluate the expression, returning the previous value (if any) and the new value when it has changed
+    pub fn check(&mut self, cpu: &Cpu, frame: u32) -> Option<(Option<u32>, u32)> {
+        let value = self.expr.eval(cpu, frame);
+        if self.value == Some(value) {
+            return None;
+        }
+        Some((self.value.replace(value), value))
+    }
+}
+
+/// Comparison of two expressions, e.g. `mem[P1Scor+1] >= 0x15` or `frame == 3600`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub left: Expr,
+    /// Comparison operator, one of `==`, `!=`, `<=`, `>=`, `<` and `>`
+    pub op: &'static str,
+    pub right: Expr,
+}
+
+impl Condition {
+    /// Parse a condition
+    pub fn parse(s: &str) -> Result<Condition, String> {
+        for op in ["==", "!=", "<=", ">=", "<", ">"] {
+            if let Some((left, right)) = s.split_once(op) {
+                return Ok(Condition {
+                    left: Expr::parse(left)?,
+                    op,
+                    right: Expr::parse(right)?,
+                });
+            }
+        }
+        Err(format!("Invalid condition: {s}"))
+    }
+
+    /// Evaluate the condition on the current machine state and frame
+    pub fn eval(&self, cpu: &Cpu, frame: u32) -> bool {
+        let (l, r) = (self.left.eval(cpu, frame), self.right.eval(cpu, frame));
+        match self.op {
+            "==" => l == r,
+            "!=" => l != r,
+            "<=" => l <= r,
+            ">=" => l >= r,
+            "<" => l < r,
+            _ => l > r,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op, self.right)
+    }
+}
+
+/// A condition that fires when it becomes true, once (`check`) or every time (`rising`)
+#[derive(Debug)]
+pub struct Trigger {
+    pub condition: Condition,
+    /// Has fired
+    fired: bool,
+    /// Condition held at the last check
+    held: bool,
+}
+
+impl Trigger {
+    pub fn new(condition: Condition) -> Self {
+        Trigger {
+            condition,
+            fired: false,
+            held: false,
+        }
+    }
+
+    /// True whenever the condition holds after not holding at the last check (or holds at the first check)
+    pub fn rising(&mut self, cpu: &Cpu, frame: u32) -> bool {
+        let held = std::mem::replace(&mut self.held, self.condition.eval(cpu, frame));
+        self.held && !held
+    }
+
+    /// True the first time the condition holds, false before and after
+    pub fn check(&mut self, cpu: &Cpu, frame: u32) -> bool {
+        if self.fired || !self.condition.eval(cpu, frame) {
+            return false;
+        }
+        self.fired = true;
+        true
+    }
+}
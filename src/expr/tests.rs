@@ -0,0 +1,105 @@
+use super::*;
+
+fn setup() -> Cpu {
+    // MVI A,42h; LXI H,1234h; HLT
+    let mut program = vec![0x3E, 0x42, 0x21, 0x34, 0x12, 0x76];
+    program.resize(0x2000, 0);
+    let mut cpu = Cpu::new(program);
+    for _ in 0..3 {
+        cpu.step();
+    }
+    cpu
+}
+
+#[test]
+fn parse() {
+    assert_eq!(Expr::parse("0x15"), Ok(Expr::Number(0x15)));
+    assert_eq!(Expr::parse("21"), Ok(Expr::Number(21)));
+    assert_eq!(Expr::parse("a"), Ok(Expr::Register("A".into())));
+    assert_eq!(Expr::parse(" HL "), Ok(Expr::Register("HL".into())));
+    assert_eq!(Expr::parse("mem[0x20EF]"), Ok(Expr::Byte(0x20EF)));
+    assert_eq!(Expr::parse("[0x20EF]"), Ok(Expr::Byte(0x20EF)));
+    assert_eq!(Expr::parse("word[0x20F8]"), Ok(Expr::Word(0x20F8)));
+    assert_eq!(Expr::parse("mem[numAliens]"), Ok(Expr::Byte(0x2082)));
+    assert_eq!(Expr::parse("word[P1Scor]"), Ok(Expr::Word(0x20F8)));
+    assert_eq!(Expr::parse("mem[P1Scor+1]"), Ok(Expr::Byte(0x20F9)));
+    assert_eq!(Expr::parse("mem[0x2000 + 0x10]"), Ok(Expr::Byte(0x2010)));
+    assert_eq!(Expr::parse("frame"), Ok(Expr::Frame));
+    assert!(Expr::parse("mem[nothing]").is_err());
+    assert!(Expr::parse("mem[0x10000]").is_err());
+    assert_eq!(Expr::parse("mem[0x41FF]"), Ok(Expr::Byte(0x41FF)));
+    assert!(Expr::parse("word[0x41FF]").is_err());
+    assert_eq!(Expr::parse("word[0x41FE]"), Ok(Expr::Word(0x41FE)));
+    assert!(Expr::parse("0x100000000").is_err());
+    assert!(Expr::parse("XY").is_err());
+    assert!(Expr::parse("mem[0x20EF").is_err());
+}
+
+#[test]
+fn eval() {
+    let cpu = setup();
+    assert_eq!(Expr::parse("A").unwrap().eval(&cpu, 0), 0x42);
+    assert_eq!(Expr::parse("HL").unwrap().eval(&cpu, 0), 0x1234);
+    assert_eq!(Expr::parse("mem[1]").unwrap().eval(&cpu, 0), 0x42);
+    assert_eq!(Expr::parse("word[3]").unwrap().eval(&cpu, 0), 0x1234);
+    assert_eq!(Expr::parse("7").unwrap().eval(&cpu, 0), 7);
+    assert_eq!(Expr::parse("frame").unwrap().eval(&cpu, 3600), 3600);
+}
+
+#[test]
+fn display() {
+    for s in ["0x15", "frame", "HL", "mem[0x20EF]", "word[0x20F8]"] {
+        assert_eq!(Expr::parse(s).unwrap().to_string(), s);
+    }
+}
+
+#[test]
+fn watch() {
+    let mut cpu = Cpu::new(vec![0x3E, 0x42, 0x3E, 0x42, 0x3E, 0x17]); // MVI A,42h; MVI A,42h; MVI A,17h
+    let mut watch = Watch::new(Expr::parse("A").unwrap());
+    assert_eq!(watch.check(&cpu, 0), Some((None, 0)));
+    cpu.step();
+    assert_eq!(watch.check(&cpu, 0), Some((Some(0), 0x42)));
+    cpu.step();
+    assert_eq!(watch.check(&cpu, 0), None);
+    cpu.step();
+    assert_eq!(watch.check(&cpu, 0), Some((Some(0x42), 0x17)));
+}
+
+#[test]
+fn condition() {
+    let cpu = setup();
+    let eval = |s| Condition::parse(s).unwrap().eval(&cpu, 3600);
+    assert!(eval("A == 0x42"));
+    assert!(!eval("A != 0x42"));
+    assert!(eval("HL >= 0x1234"));
+    assert!(!eval("HL > 0x1234"));
+    assert!(eval("mem[1] < 0x43"));
+    assert!(eval("frame <= 3600"));
+    assert!(Condition::parse("A").is_err());
+    assert!(Condition::parse("A == X").is_err());
+    assert_eq!(
+        Condition::parse("mem[P1Scor+1]>=0x15").unwrap().to_string(),
+        "mem[0x20F9] >= 0x15"
+    );
+}
+
+#[test]
+fn trigger() {
+    let cpu = setup();
+    let mut trigger = Trigger::new(Condition::parse("frame >= 2").unwrap());
+    assert!(!trigger.check(&cpu, 1));
+    assert!(trigger.check(&cpu, 2));
+    assert!(!trigger.check(&cpu, 3));
+}
+
+#[test]
+fn rising_trigger() {
+    let cpu = setup();
+    let mut trigger = Trigger::new(Condition::parse("frame != 3").unwrap());
+    assert!(trigger.rising(&cpu, 1));
+    assert!(!trigger.rising(&cpu, 2));
+    assert!(!trigger.rising(&cpu, 3));
+    assert!(trigger.rising(&cpu, 4));
+    assert!(!trigger.rising(&cpu, 5));
+}
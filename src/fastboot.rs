@@ -0,0 +1,105 @@
+//! Fast boot: the machine state right after the power-on self-test of the rom, made once per rom and cached, so later
+//! launches start where the game takes interrupts in its main loop (attract mode for Space Invaders) instead of from
+//! power on.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cpu::{Cpu, RamInit},
+    hash,
+    machine::Machine,
+    shutdown, timing, FPS, FREQ,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Frames in a row taking both interrupts after which the self-test is taken as done
+pub const SETTLE_FRAMES: u32 = FPS;
+/// Longest boot, for roms that never settle
+pub const MAX_FRAMES: u32 = 10 * FPS;
+
+/// Where the fast boot came from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Boot {
+    /// Loaded from the cache
+    Cached(PathBuf),
+    /// Booted for this many frames and saved to the cache
+    Created(PathBuf, u32),
+}
+
+/// Cached state in a directory, named by the CRC-32 of the rom file (with any banks) and of the machine and RAM init
+/// it boots with
+pub fn path(dir: &Path, data: &[u8], machine: &Machine, ram_init: RamInit) -> PathBuf {
+    let setup = format!("{machine:?} {ram_init:?}");
+    dir.join("boot").join(format!(
+        "{:08x}-{:08x}.state",
+        hash::crc32(data),
+        hash::crc32(setup.as_bytes())
+    ))
+}
+
+/// The machine at power on
+fn power_on(machine: &Machine, data: &[u8], ram_init: RamInit) -> Cpu {
+    let mut cpu = machine.load(data);
+    cpu.set_ram_init(ram_init);
+    cpu
+}
+
+/// Run from power on until the self-test is done: all interrupts of a frame, at their scan lines, taken for
+/// `SETTLE_FRAMES` in a row, or `MAX_FRAMES`. Returns the frames run.
+pub fn boot(cpu: &mut Cpu, interrupts: &[(u8, u32)]) -> u32 {
+    let cycles_per_frame = FREQ / FPS;
+    let mut settled = 0;
+    let mut elapsed = 0;
+    for frame in 1..=MAX_FRAMES {
+        let mut taken = true;
+        for &(i, line) in interrupts {
+            while elapsed < timing::line_cycle(line, cycles_per_frame) {
+                elapsed += cpu.step();
+            }
+            taken &= cpu.interrupt(i) > 0;
+        }
+        while elapsed < cycles_per_frame {
+            elapsed += cpu.step();
+        }
+        elapsed -= cycles_per_frame;
+        settled = if taken { settled + 1 } else { 0 };
+        if settled == SETTLE_FRAMES {
+            return frame;
+        }
+    }
+    MAX_FRAMES
+}
+
+/// The machine loaded with the rom file and booted: from the cached state, or from power on, caching the state. A
+/// cached state that does not load (from another version) is made again. The machine is booted even when the state
+/// can't be cached, with the error.
+pub fn load_or_create(
+    machine: &Machine,
+    data: &[u8],
+    ram_init: RamInit,
+    dir: &Path,
+) -> (Cpu, Result<Boot, String>) {
+    let path = path(dir, data, machine, ram_init);
+    if let Ok(state) = fs::read(&path) {
+        let mut cpu = power_on(machine, data, ram_init);
+        if cpu.load_state(&state).is_ok() {
+            return (cpu, Ok(Boot::Cached(path)));
+        }
+    }
+    // Not the machine a failed load may have changed partly
+    let mut cpu = power_on(machine, data, ram_init);
+    let frames = boot(&mut cpu, &machine.interrupts);
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|e| format!("{}: {e}", path.display()))
+        .and_then(|_| {
+            shutdown::write(&path, cpu.save_state()).map_err(|e| format!("{}: {e}", path.display()))
+        });
+    (cpu, saved.map(|_| Boot::Created(path, frames)))
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+/// Sets the stack, enables interrupts and loops, with handlers enabling them again
+fn program() -> Vec<u8> {
+    let mut program = vec![0x31, 0x00, 0x24, 0xFB, 0xC3, 0x04, 0x00];
+    program.resize(0x08, 0);
+    program.extend([0xFB, 0xC9]);
+    program.resize(0x10, 0);
+    program.extend([0xFB, 0xC9]);
+    program
+}
+
+#[test]
+fn boot_until_settled() {
+    let mut cpu = Cpu::new(program());
+    assert_eq!(
+        boot(&mut cpu, &Machine::invaders().interrupts),
+        SETTLE_FRAMES
+    );
+}
+
+#[test]
+fn boot_gives_up() {
+    // Never enables interrupts
+    let mut cpu = Cpu::new(vec![0xC3, 0x00, 0x00]);
+    assert_eq!(boot(&mut cpu, &Machine::invaders().interrupts), MAX_FRAMES);
+}
+
+#[test]
+fn boot_with_the_machine_interrupts() {
+    // Only a handler for RST 3, an RST 1 halts with interrupts disabled
+    let mut program = vec![0x31, 0x00, 0x24, 0xFB, 0xC3, 0x04, 0x00, 0x00, 0x76];
+    program.resize(0x18, 0);
+    program.extend([0xFB, 0xC9]);
+    let mut cpu = Cpu::new(program.clone());
+    assert_eq!(boot(&mut cpu, &[(3, 100)]), SETTLE_FRAMES);
+    let mut cpu = Cpu::new(program);
+    assert_eq!(boot(&mut cpu, &[(1, 96), (3, 224)]), MAX_FRAMES);
+}
+
+#[test]
+fn cached_per_rom_and_machine() {
+    let dir = std::env::temp_dir().join(format!("inv8080rs-fastboot-{}", std::process::id()));
+    let program = program();
+    let machine = Machine::invaders();
+    let path = path(&dir, &program, &machine, RamInit::Zeros);
+    assert!(path.starts_with(dir.join("boot")));
+    assert_ne!(path, super::path(&dir, &program, &machine, RamInit::Ones));
+    let other = Machine {
+        interrupts: vec![(1, 100), (2, 224)],
+        ..machine.clone()
+    };
+    assert_ne!(path, super::path(&dir, &program, &other, RamInit::Zeros));
+
+    let (cpu, created) = load_or_create(&machine, &program, RamInit::Zeros, &dir);
+    assert_eq!(created, Ok(Boot::Created(path.clone(), SETTLE_FRAMES)));
+    let state = cpu.save_state();
+
+    let (cpu, cached) = load_or_create(&machine, &program, RamInit::Zeros, &dir);
+    assert_eq!(cached, Ok(Boot::Cached(path.clone())));
+    assert_eq!(cpu.save_state(), state);
+
+    // A state that does not load is made again, from power on
+    fs::write(&path, b"junk").unwrap();
+    let (cpu, again) = load_or_create(&machine, &program, RamInit::Zeros, &dir);
+    assert_eq!(again, Ok(Boot::Created(path, SETTLE_FRAMES)));
+    assert_eq!(cpu.save_state(), state);
+    fs::remove_dir_all(&dir).unwrap();
+}
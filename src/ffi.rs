@@ -0,0 +1,138 @@
+//! C API for embedding the machine in other frontends. The header `include/inv8080rs.h` is generated from the functions
+//! here with `cbindgen --config cbindgen.toml --output include/inv8080rs.h`.
+
+use std::{ptr, slice};
+
+use crate::{batch::run_frame, cpu::Cpu, machine::Machine, FRAMEBUFFER, NPORTS, ROM};
+
+#[cfg(test)]
+mod tests;
+
+/// Create a Space Invaders machine with a rom. Returns null if the rom is empty or too large.
+///
+/// # Safety
+/// `rom` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_create(rom: *const u8, len: usize) -> *mut Cpu {
+    if rom.is_null() {
+        return ptr::null_mut();
+    }
+    let data = slice::from_raw_parts(rom, len);
+    let machine = Machine::invaders();
+    if machine.check_rom(data).is_err() {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(machine.load(data)))
+}
+
+/// Destroy a machine created with `inv8080_create`
+///
+/// # Safety
+/// `cpu` must come from `inv8080_create` (or be null) and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_destroy(cpu: *mut Cpu) {
+    if !cpu.is_null() {
+        drop(Box::from_raw(cpu));
+    }
+}
+
+/// Replace the rom, the rest of the machine state is kept. Returns false if the rom is too large.
+///
+/// # Safety
+/// `cpu` must be a valid machine and `rom` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_load_rom(cpu: *mut Cpu, rom: *const u8, len: usize) -> bool {
+    if rom.is_null() || len > ROM.end() + 1 {
+        return false;
+    }
+    (*cpu).load_rom(slice::from_raw_parts(rom, len));
+    true
+}
+
+/// Reset (program counter to 0 and interrupts disabled, RAM is kept)
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_reset(cpu: *mut Cpu) {
+    (*cpu).reset();
+}
+
+/// Power cycle (like reset, but RAM, registers and I/O are cleared)
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_power_cycle(cpu: *mut Cpu) {
+    (*cpu).power_cycle();
+}
+
+/// Run one frame (1/60 s) including the two interrupts
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_run_frame(cpu: *mut Cpu) {
+    run_frame(&mut *cpu);
+}
+
+/// Pointer to the framebuffer, 7168 bytes of 32 bytes per column from the bottom-left of the display with the lowest bit of each byte at the bottom.
+/// Valid until the machine is destroyed.
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_framebuffer(cpu: *const Cpu) -> *const u8 {
+    (*cpu).memory()[*FRAMEBUFFER.start()..].as_ptr()
+}
+
+/// Save the machine state into `buf` if it has room for it. Returns the size of the state, so a call with a null
+/// `buf` (or too small `len`) tells how much room is needed.
+///
+/// # Safety
+/// `cpu` must be a valid machine and `buf` must be null or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_save_state(cpu: *const Cpu, buf: *mut u8, len: usize) -> usize {
+    let state = (*cpu).save_state();
+    if !buf.is_null() && len >= state.len() {
+        slice::from_raw_parts_mut(buf, state.len()).copy_from_slice(&state);
+    }
+    state.len()
+}
+
+/// Load a state saved with `inv8080_save_state`. Returns false, keeping the machine as it was, if it is not a state
+/// of this machine.
+///
+/// # Safety
+/// `cpu` must be a valid machine and `state` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_load_state(cpu: *mut Cpu, state: *const u8, len: usize) -> bool {
+    if state.is_null() {
+        return false;
+    }
+    (*cpu).load_state(slice::from_raw_parts(state, len)).is_ok()
+}
+
+/// Set the value of an input port (0-7), e.g. port 1 for coin, start and player 1 controls
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_set_input(cpu: *mut Cpu, port: usize, value: u8) {
+    if port < NPORTS {
+        (*cpu).set_bus_in(port, value);
+    }
+}
+
+/// Value of an output port (0-7), e.g. ports 3 and 5 for sounds
+///
+/// # Safety
+/// `cpu` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn inv8080_get_output(cpu: *const Cpu, port: usize) -> u8 {
+    if port < NPORTS {
+        (*cpu).get_bus_out(port)
+    } else {
+        0
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+#[test]
+fn create_and_destroy() {
+    let rom = [0x00; 16];
+    unsafe {
+        let cpu = inv8080_create(rom.as_ptr(), rom.len());
+        assert!(!cpu.is_null());
+        inv8080_destroy(cpu);
+        inv8080_destroy(ptr::null_mut());
+    }
+}
+
+#[test]
+fn create_rom_too_large() {
+    let rom = vec![0x00; 0x2001];
+    unsafe {
+        assert!(inv8080_create(rom.as_ptr(), rom.len()).is_null());
+        assert!(inv8080_create(ptr::null(), 0).is_null());
+    }
+}
+
+#[test]
+fn create_rom_empty() {
+    let rom = [0x00; 0];
+    unsafe {
+        assert!(inv8080_create(rom.as_ptr(), 0).is_null());
+    }
+}
+
+#[test]
+fn run_frame_and_framebuffer() {
+    // MVI A,FFh; STA 2400h; OUT 3; HLT
+    let rom = [0x3E, 0xFF, 0x32, 0x00, 0x24, 0xD3, 0x03, 0x76];
+    unsafe {
+        let cpu = inv8080_create(rom.as_ptr(), rom.len());
+        inv8080_run_frame(cpu);
+        let framebuffer = slice::from_raw_parts(inv8080_framebuffer(cpu), 0x1C00);
+        assert_eq!(framebuffer[0], 0xFF);
+        assert_eq!(framebuffer[1], 0x00);
+        assert_eq!(inv8080_get_output(cpu, 3), 0xFF);
+        assert_eq!(inv8080_get_output(cpu, 8), 0);
+
+        inv8080_power_cycle(cpu);
+        assert_eq!(*inv8080_framebuffer(cpu), 0x00);
+        inv8080_destroy(cpu);
+    }
+}
+
+#[test]
+fn input_and_rom() {
+    // IN 1; STA 2400h; HLT
+    let rom = [0xDB, 0x01, 0x32, 0x00, 0x24, 0x76];
+    unsafe {
+        let cpu = inv8080_create([0x76].as_ptr(), 1);
+        assert!(inv8080_load_rom(cpu, rom.as_ptr(), rom.len()));
+        inv8080_set_input(cpu, 1, 0x15);
+        inv8080_set_input(cpu, 8, 0xFF);
+        inv8080_reset(cpu);
+        inv8080_run_frame(cpu);
+        assert_eq!(*inv8080_framebuffer(cpu), 0x15);
+        inv8080_destroy(cpu);
+    }
+}
+
+#[test]
+fn save_and_load_state() {
+    // MVI A,FFh; STA 2400h; HLT
+    let rom = [0x3E, 0xFF, 0x32, 0x00, 0x24, 0x76];
+    unsafe {
+        let cpu = inv8080_create(rom.as_ptr(), rom.len());
+        inv8080_run_frame(cpu);
+        let len = inv8080_save_state(cpu, ptr::null_mut(), 0);
+        let mut state = vec![0; len];
+        assert_eq!(inv8080_save_state(cpu, state.as_mut_ptr(), 1), len);
+        assert!(state.iter().all(|b| *b == 0));
+        assert_eq!(inv8080_save_state(cpu, state.as_mut_ptr(), len), len);
+
+        inv8080_power_cycle(cpu);
+        assert_eq!(*inv8080_framebuffer(cpu), 0x00);
+        assert!(inv8080_load_state(cpu, state.as_ptr(), len));
+        assert_eq!(*inv8080_framebuffer(cpu), 0xFF);
+        assert!(!inv8080_load_state(cpu, state.as_ptr(), len - 1));
+        assert!(!inv8080_load_state(cpu, ptr::null(), 0));
+        assert_eq!(*inv8080_framebuffer(cpu), 0xFF);
+        inv8080_destroy(cpu);
+    }
+}
@@ -0,0 +1,165 @@
+//! Pure frame rendering, independent of SDL
+
+use crate::{cpu::Cpu, palette::Color, png::Image, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+#[cfg(test)]
+mod tests;
+
+/// How consecutive frames are blended to reduce flicker
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Blend {
+    /// No blending (original look)
+    Off,
+    /// Pixel is lit if it is lit in any of the two frames
+    Or,
+    /// Pixel intensity is the average of the two frames
+    Average,
+}
+
+impl Blend {
+    /// Next blend mode, used for toggling
+    pub fn next(self) -> Self {
+        match self {
+            Blend::Off => Blend::Or,
+            Blend::Or => Blend::Average,
+            Blend::Average => Blend::Off,
+        }
+    }
+}
+
+/// Glow where lit pixels bleed into their neighbors, approximating CRT bloom
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glow {
+    /// How far (in pixels) the glow reaches
+    pub radius: u32,
+    /// Intensity of the glow next to a fully lit pixel, 0 to 255
+    pub intensity: u8,
+}
+
+impl Glow {
+    /// Largest radius, a wider glow costs too much per frame and blurs the picture away
+    pub const MAX_RADIUS: u32 = 8;
+
+    /// Glow with the radius clamped to `MAX_RADIUS`
+    pub fn new(radius: u32, intensity: u8) -> Self {
+        Glow {
+            radius: radius.min(Self::MAX_RADIUS),
+            intensity,
+        }
+    }
+}
+
+/// A display frame in logical (rotated) coordinates with pixel intensities 0 (off) to 255 (on)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pixels: Vec<u8>,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame {
+            pixels: vec![0; (DISPLAY_WIDTH * DISPLAY_HEIGHT) as usize],
+        }
+    }
+}
+
+impl Frame {
+    /// Render the framebuffer of the cpu
+    pub fn render(cpu: &Cpu) -> Self {
+        let mut frame = Frame::default();
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..DISPLAY_WIDTH {
+                if cpu.display(x, y) == Some(true) {
+                    frame.set(x, y, 0xff);
+                }
+            }
+        }
+        frame
+    }
+
+    /// Get intensity of pixel at (x, y)
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * DISPLAY_WIDTH + x) as usize]
+    }
+
+    /// Intensities row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Set intensity of pixel at (x, y)
+    pub fn set(&mut self, x: u32, y: u32, intensity: u8) {
+        self.pixels[(y * DISPLAY_WIDTH + x) as usize] = intensity;
+    }
+
+    /// Blend with the previous frame
+    pub fn blend(&self, previous: &Frame, blend: Blend) -> Self {
+        let pixels = match blend {
+            Blend::Off => self.pixels.clone(),
+            Blend::Or => self
+                .pixels
+                .iter()
+                .zip(&previous.pixels)
+                .map(|(a, b)| *a.max(b))
+                .collect(),
+            Blend::Average => self
+                .pixels
+                .iter()
+                .zip(&previous.pixels)
+                .map(|(a, b)| ((*a as u16 + *b as u16) / 2) as u8)
+                .collect(),
+        };
+        Frame { pixels }
+    }
+
+    /// Apply glow, the glow fades linearly with the (chessboard) distance from a lit pixel
+    pub fn glow(&self, glow: Glow) -> Self {
+        let glow = Glow::new(glow.radius, glow.intensity);
+        let mut frame = self.clone();
+        let r = glow.radius as i32;
+
+        for y in 0..DISPLAY_HEIGHT as i32 {
+            for x in 0..DISPLAY_WIDTH as i32 {
+                let source = self.get(x as u32, y as u32) as u32;
+                if source == 0 {
+                    continue;
+                }
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let (nx, ny) = (x + dx, y + dy);
+                        let d = dx.abs().max(dy.abs()) as u32;
+                        if d == 0
+                            || nx < 0
+                            || ny < 0
+                            || nx >= DISPLAY_WIDTH as i32
+                            || ny >= DISPLAY_HEIGHT as i32
+                        {
+                            continue;
+                        }
+                        let bleed = (source * glow.intensity as u32 * (glow.radius + 1 - d)
+                            / (255 * glow.radius.max(1))) as u8;
+                        if bleed > frame.get(nx as u32, ny as u32) {
+                            frame.set(nx as u32, ny as u32, bleed);
+                        }
+                    }
+                }
+            }
+        }
+        frame
+    }
+
+    /// Convert to an RGBA image with transparent background. Lit pixels get the color returned for their position, with alpha scaled by intensity.
+    pub fn to_image(&self, color: impl Fn(u32, u32) -> Color) -> Image {
+        let mut image = Image::new(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..DISPLAY_WIDTH {
+                let intensity = self.get(x, y) as u32;
+                if intensity > 0 {
+                    let Color { r, g, b, a } = color(x, y);
+                    image.set(x, y, [r, g, b, (a as u32 * intensity / 0xff) as u8]);
+                }
+            }
+        }
+        image
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+fn frame_with(pixels: &[(u32, u32, u8)]) -> Frame {
+    let mut frame = Frame::default();
+    for (x, y, i) in pixels {
+        frame.set(*x, *y, *i);
+    }
+    frame
+}
+
+#[test]
+fn render_empty() {
+    let cpu = Cpu::new(vec![]);
+    assert_eq!(Frame::render(&cpu), Frame::default());
+}
+
+#[test]
+fn get_and_set() {
+    let mut frame = Frame::default();
+    assert_eq!(frame.get(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1), 0);
+    frame.set(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1, 0x80);
+    assert_eq!(frame.get(DISPLAY_WIDTH - 1, DISPLAY_HEIGHT - 1), 0x80);
+    assert_eq!(frame.get(0, 0), 0);
+}
+
+#[test]
+fn blend() {
+    let current = frame_with(&[(0, 0, 0xff), (1, 0, 0xff)]);
+    let previous = frame_with(&[(1, 0, 0xff), (2, 0, 0xff)]);
+
+    let off = current.blend(&previous, Blend::Off);
+    assert_eq!(off, current);
+
+    let or = current.blend(&previous, Blend::Or);
+    assert_eq!(
+        [or.get(0, 0), or.get(1, 0), or.get(2, 0), or.get(3, 0)],
+        [0xff, 0xff, 0xff, 0]
+    );
+
+    let average = current.blend(&previous, Blend::Average);
+    assert_eq!(
+        [
+            average.get(0, 0),
+            average.get(1, 0),
+            average.get(2, 0),
+            average.get(3, 0)
+        ],
+        [0x7f, 0xff, 0x7f, 0]
+    );
+}
+
+#[test]
+fn blend_next() {
+    assert_eq!(Blend::Off.next(), Blend::Or);
+    assert_eq!(Blend::Or.next(), Blend::Average);
+    assert_eq!(Blend::Average.next(), Blend::Off);
+}
+
+#[test]
+fn glow() {
+    let frame = frame_with(&[(10, 10, 0xff)]);
+    let glow = frame.glow(Glow {
+        radius: 2,
+        intensity: 0x80,
+    });
+    assert_eq!(glow.get(10, 10), 0xff);
+    assert_eq!(glow.get(11, 10), 0x80);
+    assert_eq!(glow.get(9, 9), 0x80);
+    assert_eq!(glow.get(12, 10), 0x40);
+    assert_eq!(glow.get(8, 12), 0x40);
+    assert_eq!(glow.get(13, 10), 0);
+
+    // Lit pixels are never dimmed by glow from neighbors
+    let frame = frame_with(&[(0, 0, 0xff), (1, 0, 0xff)]);
+    let glow = frame.glow(Glow {
+        radius: 1,
+        intensity: 0x10,
+    });
+    assert_eq!(glow.get(0, 0), 0xff);
+    assert_eq!(glow.get(1, 0), 0xff);
+    assert_eq!(glow.get(2, 0), 0x10);
+}
+
+#[test]
+fn glow_zero_radius() {
+    let frame = frame_with(&[(0, 0, 0xff)]);
+    let glow = frame.glow(Glow {
+        radius: 0,
+        intensity: 0xff,
+    });
+    assert_eq!(glow, frame);
+}
+
+#[test]
+fn glow_radius_clamped() {
+    assert_eq!(Glow::new(u32::MAX, 0x80).radius, Glow::MAX_RADIUS);
+    let frame = frame_with(&[(100, 100, 0xff)]);
+    let glow = frame.glow(Glow {
+        radius: u32::MAX,
+        intensity: 0xff,
+    });
+    assert_eq!(glow, frame.glow(Glow::new(Glow::MAX_RADIUS, 0xff)));
+    assert!(glow.get(108, 100) > 0);
+    assert_eq!(glow.get(109, 100), 0);
+}
+
+#[test]
+fn to_image() {
+    let frame = frame_with(&[(0, 0, 0xff), (1, 0, 0x80), (0, 1, 0xff)]);
+    let image =
+        frame.to_image(|_, y| Color::from_argb(if y == 0 { 0xffff0000 } else { 0x8000ff00 }));
+    assert_eq!((image.width, image.height), (DISPLAY_WIDTH, DISPLAY_HEIGHT));
+    assert_eq!(image.get(0, 0), [0xff, 0, 0, 0xff]);
+    assert_eq!(image.get(1, 0), [0xff, 0, 0, 0x80]);
+    assert_eq!(image.get(0, 1), [0, 0xff, 0, 0x80]);
+    assert_eq!(image.get(2, 0), [0, 0, 0, 0]);
+}
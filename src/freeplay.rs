@@ -0,0 +1,73 @@
+//! Free play for public installations: pressing start without credits inserts the coins needed, by injecting the coin
+//! input after the inputs of the frame are set rather than patching the rom
+
+use crate::{
+    batch::{credits, in_game},
+    cpu::Cpu,
+    ports::{InputBit, INVADERS},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Frames each injected coin is held and released, the game polls the coin switch in its main loop
+pub const COIN_FRAMES: u32 = 10;
+/// Frames start is held after the coins, long enough for the game to see the credits
+pub const START_FRAMES: u32 = 60;
+
+/// Injection in progress
+#[derive(Debug, Clone, PartialEq)]
+struct Injection {
+    /// Start input pressed
+    start: &'static InputBit,
+    /// Coins to insert, 2 for a 2-player game
+    coins: u32,
+    /// Frames since start was pressed
+    frame: u32,
+}
+
+/// Input filter inserting coins when start is pressed without credits
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FreePlay {
+    injection: Option<Injection>,
+    /// Coins inserted so far
+    pub coins: u32,
+}
+
+impl FreePlay {
+    /// Filter the inputs of the frame, after they have been set from the keys
+    pub fn apply(&mut self, cpu: &mut Cpu) {
+        if self.injection.is_none() && !in_game(cpu) && credits(cpu) == 0 {
+            self.injection = [("p1-start", 1), ("p2-start", 2)]
+                .into_iter()
+                .filter_map(|(name, coins)| Some((INVADERS.find(name)?, coins)))
+                .find(|(start, _)| start.active(cpu))
+                .map(|(start, coins)| Injection {
+                    start,
+                    coins,
+                    frame: 0,
+                });
+        }
+        let Some(injection) = &mut self.injection else {
+            return;
+        };
+        let coin_frames = injection.coins * 2 * COIN_FRAMES;
+        if injection.frame < coin_frames {
+            // Alternately hold and release the coin, with start released until the credits are in
+            let coin = (injection.frame / COIN_FRAMES).is_multiple_of(2);
+            if coin && injection.frame % COIN_FRAMES == 0 {
+                self.coins += 1;
+            }
+            if let Some(credit) = INVADERS.find("credit") {
+                credit.set(cpu, coin);
+            }
+            injection.start.set(cpu, false);
+        } else {
+            injection.start.set(cpu, true);
+        }
+        injection.frame += 1;
+        if in_game(cpu) || injection.frame >= coin_frames + START_FRAMES {
+            self.injection = None;
+        }
+    }
+}
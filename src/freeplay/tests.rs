@@ -0,0 +1,62 @@
+use super::*;
+
+fn input(cpu: &Cpu, name: &str) -> bool {
+    INVADERS.find(name).unwrap().active(cpu)
+}
+
+fn press(cpu: &mut Cpu, name: &str) {
+    INVADERS.find(name).unwrap().set(cpu, true);
+}
+
+#[test]
+fn coin_then_start() {
+    let mut cpu = Cpu::new(vec![]);
+    let mut free_play = FreePlay::default();
+    free_play.apply(&mut cpu);
+    assert_eq!(free_play, FreePlay::default());
+    press(&mut cpu, "p1-start");
+    let mut trace = vec![];
+    for _ in 0..2 * COIN_FRAMES + START_FRAMES {
+        free_play.apply(&mut cpu);
+        trace.push((input(&cpu, "credit"), input(&cpu, "p1-start")));
+    }
+    let expected: Vec<_> = [(true, false); COIN_FRAMES as usize]
+        .into_iter()
+        .chain([(false, false); COIN_FRAMES as usize])
+        .chain([(false, true); START_FRAMES as usize])
+        .collect();
+    assert_eq!(trace, expected);
+    assert_eq!(free_play.coins, 1);
+    assert_eq!(free_play.injection, None);
+}
+
+#[test]
+fn two_coins_for_two_players() {
+    let mut cpu = Cpu::new(vec![]);
+    let mut free_play = FreePlay::default();
+    press(&mut cpu, "p2-start");
+    for _ in 0..4 * COIN_FRAMES {
+        free_play.apply(&mut cpu);
+    }
+    assert_eq!(free_play.coins, 2);
+    // The game starting ends the injection
+    cpu.poke(0x20EF, 1);
+    free_play.apply(&mut cpu);
+    assert!(input(&cpu, "p2-start"));
+    assert_eq!(free_play.injection, None);
+}
+
+#[test]
+fn not_with_credits_or_in_game() {
+    let mut cpu = Cpu::new(vec![]);
+    let mut free_play = FreePlay::default();
+    press(&mut cpu, "p1-start");
+    cpu.poke(0x20EB, 0x01);
+    free_play.apply(&mut cpu);
+    assert_eq!(free_play.coins, 0);
+    cpu.poke(0x20EB, 0);
+    cpu.poke(0x20EF, 1);
+    free_play.apply(&mut cpu);
+    assert_eq!(free_play.coins, 0);
+    assert!(!input(&cpu, "credit"));
+}
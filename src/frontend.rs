@@ -0,0 +1,69 @@
+//! Conventions of launchers like EmulationStation (ES-DE) and LaunchBox: the rom as a plain argument, and no console
+//! output when asked to be quiet. Together with the exit status (0 on quit, 1 on errors, 2 on invalid arguments) and
+//! the graceful shutdown on SIGTERM the emulator can be dropped into a frontend setup.
+
+use std::{fs::File, io};
+
+#[cfg(test)]
+mod tests;
+
+/// An argument that is not an option is the rom to run, e.g. `%ROM%` in a launcher command line
+pub fn positional(arg: &str) -> bool {
+    !arg.is_empty() && !arg.starts_with('-')
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{ffi::c_int, fs::File, io, os::fd::AsRawFd};
+
+    extern "C" {
+        fn dup2(old: c_int, new: c_int) -> c_int;
+    }
+
+    pub fn silence(null: File) -> io::Result<()> {
+        for fd in [1, 2] {
+            if unsafe { dup2(null.as_raw_fd(), fd) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{ffi::c_void, fs::File, io, os::windows::io::IntoRawHandle};
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const STD_ERROR_HANDLE: u32 = -12i32 as u32;
+
+    extern "system" {
+        fn SetStdHandle(which: u32, handle: *mut c_void) -> i32;
+    }
+
+    /// The handle stays open for the rest of the process
+    pub fn silence(null: File) -> io::Result<()> {
+        let handle = null.into_raw_handle();
+        for which in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+            if unsafe { SetStdHandle(which, handle) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::{fs::File, io};
+
+    pub fn silence(_: File) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Send the standard output and error to the null device, for everything printed from here on
+pub fn silence() -> io::Result<()> {
+    let path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    platform::silence(File::options().write(true).open(path)?)
+}
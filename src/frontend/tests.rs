@@ -0,0 +1,10 @@
+use super::*;
+
+#[test]
+fn rom_as_plain_argument() {
+    assert!(positional("roms/invaders.rom"));
+    assert!(positional("C:\\Games\\invaders.rom"));
+    assert!(!positional("--fullscreen"));
+    assert!(!positional("-f"));
+    assert!(!positional(""));
+}
@@ -0,0 +1,30 @@
+//! Exclusive fullscreen: the display is switched to a mode refreshing at the frame rate of the emulation, or at a
+//! multiple of it with every frame shown several times, so frames are shown for even times instead of juddering
+//! against a desktop refreshing at another rate.
+
+#[cfg(test)]
+mod tests;
+
+/// Most refreshes a frame is shown for, 240 Hz at 60 frames per second
+pub const MAX_REPEATS: u32 = 4;
+/// Refresh rates this close to the frame rate match it, in Hz per repeat (59.94 Hz displays match 60)
+const TOLERANCE: f32 = 0.1;
+
+/// A display mode as (width, height, refresh rate in Hz)
+pub type Mode = (i32, i32, f32);
+
+/// Refreshes each frame is shown for at a refresh rate, if it is a multiple of the frame rate
+pub fn repeats(refresh: f32, fps: u32) -> Option<u32> {
+    (1..=MAX_REPEATS).find(|n| (refresh - (fps * n) as f32).abs() <= TOLERANCE * *n as f32)
+}
+
+/// Mode for a frame rate among the modes of a display, with the refreshes each frame is shown for. The size of the
+/// desktop is preferred, then showing each frame once (60 Hz over 120 Hz) and then the largest size.
+pub fn pick(modes: &[Mode], desktop: (i32, i32), fps: u32) -> Option<(usize, u32)> {
+    modes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(w, h, refresh))| Some((i, w, h, repeats(refresh, fps)?)))
+        .max_by_key(|&(_, w, h, repeats)| ((w, h) == desktop, MAX_REPEATS - repeats, w * h))
+        .map(|(i, _, _, repeats)| (i, repeats))
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+#[test]
+fn multiples_of_the_frame_rate() {
+    assert_eq!(repeats(60.0, 60), Some(1));
+    assert_eq!(repeats(59.94, 60), Some(1));
+    assert_eq!(repeats(120.0, 60), Some(2));
+    assert_eq!(repeats(119.88, 60), Some(2));
+    assert_eq!(repeats(75.0, 60), None);
+    assert_eq!(repeats(144.0, 60), None);
+    assert_eq!(repeats(180.0, 60), Some(3));
+    assert_eq!(repeats(239.76, 60), Some(4));
+    assert_eq!(repeats(360.0, 60), None);
+}
+
+#[test]
+fn desktop_size_then_fewer_repeats() {
+    let modes = [
+        (2560, 1440, 144.0),
+        (2560, 1440, 120.0),
+        (1920, 1080, 60.0),
+        (1280, 720, 60.0),
+    ];
+    assert_eq!(pick(&modes, (2560, 1440), 60), Some((1, 2)));
+    assert_eq!(pick(&modes, (3840, 2160), 60), Some((2, 1)));
+    assert_eq!(pick(&modes[..1], (2560, 1440), 60), None);
+    assert_eq!(pick(&[], (2560, 1440), 60), None);
+}
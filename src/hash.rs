@@ -0,0 +1,133 @@
+//! Checksums and hashes behind integrity features: savestate and framebuffer hashes compared by determinism checks and
+//! regression corpora, and rom checksums identifying dumps. Recorded hashes are compared across versions, so the
+//! algorithms and what they are computed over must never change. The tests hold golden vectors for each of them.
+//!
+//! * CRC-32 (ISO 3309, reflected polynomial `0xEDB88320`) as in PNG, zip and MAME rom sets
+//! * Adler-32 as in zlib
+//! * SHA-1 (FIPS 180-4) as in MAME rom sets
+//! * State hash: CRC-32 of the savestate (`Cpu::save_state`)
+//! * Framebuffer hash: CRC-32 of the framebuffer bytes in memory order
+
+use std::fmt::Write;
+
+use crate::{cpu::Cpu, FRAMEBUFFER};
+
+#[cfg(test)]
+mod tests;
+
+/// A hash algorithm
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Algorithm {
+    Crc32,
+    Adler32,
+    Sha1,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Crc32, Algorithm::Adler32, Algorithm::Sha1];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Crc32 => "CRC-32",
+            Algorithm::Adler32 => "Adler-32",
+            Algorithm::Sha1 => "SHA-1",
+        }
+    }
+
+    /// Digest of the data, big endian
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Crc32 => crc32(data).to_be_bytes().to_vec(),
+            Algorithm::Adler32 => adler32(data).to_be_bytes().to_vec(),
+            Algorithm::Sha1 => sha1(data).to_vec(),
+        }
+    }
+
+    /// Digest of the data in lowercase hex
+    pub fn hex(self, data: &[u8]) -> String {
+        self.digest(data).iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+    }
+}
+
+/// CRC-32 (ISO 3309) as used by PNG and zip
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Adler-32 checksum as used by zlib
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for d in data {
+        a = (a + *d as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// SHA-1
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, t);
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+    let mut digest = [0; 20];
+    for (chunk, h) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&h.to_be_bytes());
+    }
+    digest
+}
+
+/// Hash of the whole machine state, as compared by the determinism check
+pub fn state_hash(cpu: &Cpu) -> u32 {
+    crc32(&cpu.save_state())
+}
+
+/// Hash of the framebuffer, as recorded in corpora
+pub fn framebuffer_hash(cpu: &Cpu) -> u32 {
+    crc32(&cpu.memory()[FRAMEBUFFER])
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn golden_checksums() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+}
+
+#[test]
+fn golden_sha1() {
+    let sha1 = |data: &[u8]| Algorithm::Sha1.hex(data);
+    assert_eq!(sha1(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    // Two blocks
+    assert_eq!(
+        sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+    assert_eq!(
+        sha1(&[b'a'; 1000]),
+        "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+    );
+}
+
+#[test]
+fn algorithms() {
+    assert_eq!(Algorithm::Crc32.hex(b"123456789"), "cbf43926");
+    assert_eq!(
+        Algorithm::Adler32.digest(b"Wikipedia"),
+        [0x11, 0xE6, 0x03, 0x98]
+    );
+    assert_eq!(
+        Algorithm::ALL.map(Algorithm::name),
+        ["CRC-32", "Adler-32", "SHA-1"]
+    );
+}
+
+#[test]
+fn golden_machine_hashes() {
+    // Changing these breaks recorded corpora and replays
+    let cpu = Cpu::new(vec![0x3E, 0x42]);
+    assert_eq!(framebuffer_hash(&cpu), crc32(&[0; 0x1E00]));
+    assert_eq!(state_hash(&cpu), 0xcc99b439);
+}
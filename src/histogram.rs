@@ -0,0 +1,171 @@
+//! Frame pacing statistics of a session: histograms of the frame times and of the sleep errors, how late sleeping
+//! until the deadline of a frame woke up. Frame times spread out with small sleep errors point at the emulator, large
+//! sleep errors at the scheduler of the OS. Printed as ASCII charts on quit, or drawn as a PNG for user reports.
+
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::png::Image;
+
+#[cfg(test)]
+mod tests;
+
+/// Buckets of a histogram, the last one counts everything longer
+pub const BUCKETS: usize = 40;
+/// Characters of the longest bar of a chart
+const BAR: usize = 50;
+
+/// Durations counted in buckets of equal width
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub name: &'static str,
+    /// Width of a bucket
+    pub bucket: Duration,
+    counts: [u64; BUCKETS],
+    total: Duration,
+    max: Duration,
+}
+
+impl Histogram {
+    pub fn new(name: &'static str, bucket: Duration) -> Self {
+        Histogram {
+            name,
+            bucket,
+            counts: [0; BUCKETS],
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let i = (duration.as_nanos() / self.bucket.as_nanos().max(1)) as usize;
+        self.counts[i.min(BUCKETS - 1)] += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        (count > 0).then(|| self.total / count as u32)
+    }
+
+    /// Upper end of the bucket below which `percent` of the durations are
+    pub fn percentile(&self, percent: u32) -> Option<Duration> {
+        let count = self.count();
+        let wanted = (count * percent as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        let i = self.counts.iter().position(|c| {
+            seen += c;
+            seen >= wanted
+        })?;
+        Some(self.bucket * (i as u32 + 1))
+    }
+
+    /// Summary and a bar per bucket from the shortest to the longest duration counted
+    pub fn chart(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut s = String::new();
+        let (Some(mean), Some(p99)) = (self.mean(), self.percentile(99)) else {
+            let _ = writeln!(s, "{}: none", self.name);
+            return s;
+        };
+        let _ = writeln!(
+            s,
+            "{}: {} frames, mean {:.2} ms, 99% under {:.2} ms, max {:.2} ms",
+            self.name,
+            self.count(),
+            ms(mean),
+            ms(p99),
+            ms(self.max)
+        );
+        let first = self.counts.iter().position(|c| *c > 0).unwrap_or(0);
+        let last = self.counts.iter().rposition(|c| *c > 0).unwrap_or(0);
+        let most = self.counts.iter().max().copied().unwrap_or(1);
+        for (i, count) in self.counts.iter().enumerate().take(last + 1).skip(first) {
+            let low = ms(self.bucket * i as u32);
+            let range = match i {
+                i if i == BUCKETS - 1 => format!("{low:>6.2}+      "),
+                _ => format!("{low:>6.2}-{:<6.2}", ms(self.bucket * (i as u32 + 1))),
+            };
+            let bar = "#".repeat((count * BAR as u64).div_ceil(most) as usize);
+            let _ = writeln!(s, "  {range} ms |{bar:<BAR$}| {count}");
+        }
+        s
+    }
+
+    /// Draw the bars into a panel of an image, from `top` and `BUCKETS * 4` pixels wide
+    fn draw(&self, image: &mut Image, top: u32, height: u32, rgba: [u8; 4]) {
+        let most = self.counts.iter().max().copied().unwrap_or(0).max(1);
+        for (i, count) in self.counts.iter().enumerate() {
+            let bar = (count * height as u64).div_ceil(most) as u32;
+            for y in top + height - bar..top + height {
+                for x in i as u32 * 4..i as u32 * 4 + 3 {
+                    image.set(x, y, rgba);
+                }
+            }
+        }
+        for x in 0..image.width {
+            image.set(x, top + height, [0x80, 0x80, 0x80, 0xff]);
+        }
+    }
+}
+
+/// Frame times and sleep errors of a session
+#[derive(Clone, Debug, PartialEq)]
+pub struct FramePacing {
+    /// Time from the end of one frame to the end of the next, buckets of 1 ms
+    pub frame_times: Histogram,
+    /// How late sleeping woke up, buckets of 0.25 ms
+    pub sleep_errors: Histogram,
+    last: Option<Instant>,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        FramePacing {
+            frame_times: Histogram::new("Frame times", Duration::from_millis(1)),
+            sleep_errors: Histogram::new("Sleep errors", Duration::from_micros(250)),
+            last: None,
+        }
+    }
+}
+
+impl FramePacing {
+    /// A frame ended
+    pub fn frame(&mut self, now: Instant) {
+        if let Some(last) = self.last.replace(now) {
+            self.frame_times.record(now - last);
+        }
+    }
+
+    /// Sleeping until `deadline` woke up at `now`
+    pub fn slept(&mut self, deadline: Instant, now: Instant) {
+        self.sleep_errors
+            .record(now.saturating_duration_since(deadline));
+    }
+
+    /// Both charts
+    pub fn report(&self) -> String {
+        self.frame_times.chart() + &self.sleep_errors.chart()
+    }
+
+    /// Both histograms as bar charts, frame times above sleep errors, with a bucket 4 pixels wide
+    pub fn to_image(&self) -> Image {
+        const PANEL: u32 = 100;
+        let mut image = Image::new(BUCKETS as u32 * 4, 2 * PANEL + 2);
+        for pixel in image.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0, 0, 0, 0xff]);
+        }
+        self.frame_times
+            .draw(&mut image, 0, PANEL, [0x33, 0xff, 0x66, 0xff]);
+        self.sleep_errors
+            .draw(&mut image, PANEL + 1, PANEL, [0xff, 0xbf, 0x00, 0xff]);
+        image
+    }
+}
@@ -0,0 +1,58 @@
+use super::*;
+
+#[test]
+fn buckets() {
+    let mut histogram = Histogram::new("Frame times", Duration::from_millis(1));
+    assert_eq!(histogram.mean(), None);
+    assert_eq!(histogram.percentile(99), None);
+    assert_eq!(histogram.chart(), "Frame times: none\n");
+    for _ in 0..98 {
+        histogram.record(Duration::from_micros(16_667));
+    }
+    histogram.record(Duration::from_micros(33_333));
+    histogram.record(Duration::from_secs(1));
+    assert_eq!(histogram.count(), 100);
+    assert_eq!(histogram.percentile(50), Some(Duration::from_millis(17)));
+    assert_eq!(histogram.percentile(99), Some(Duration::from_millis(34)));
+    // Longer than the last bucket
+    assert_eq!(histogram.percentile(100), Some(Duration::from_millis(40)));
+    let chart = histogram.chart();
+    assert!(chart.starts_with(
+        "Frame times: 100 frames, mean 26.67 ms, 99% under 34.00 ms, max 1000.00 ms\n"
+    ));
+    assert!(chart.contains(&format!("   16.00-17.00  ms |{}| 98\n", "#".repeat(50))));
+    assert!(chart.contains(&format!("   33.00-34.00  ms |#{}| 1\n", " ".repeat(49))));
+    assert!(chart.contains("   39.00+       ms |#"));
+    // Buckets from the first to the last counted
+    assert_eq!(chart.lines().count(), 1 + 24);
+}
+
+#[test]
+fn frame_pacing() {
+    let mut pacing = FramePacing::default();
+    let start = Instant::now();
+    for i in 0..10 {
+        pacing.frame(start + Duration::from_millis(16) * i);
+    }
+    pacing.slept(start, start + Duration::from_micros(600));
+    pacing.slept(start, start);
+    assert_eq!(pacing.frame_times.count(), 9);
+    assert_eq!(
+        pacing.sleep_errors.percentile(100),
+        Some(Duration::from_micros(750))
+    );
+    let report = pacing.report();
+    assert!(report.contains("Frame times: 9 frames, mean 16.00 ms"));
+    assert!(report.contains("Sleep errors: 2 frames, mean 0.30 ms"));
+
+    let image = pacing.to_image();
+    assert_eq!((image.width, image.height), (160, 202));
+    // Full bar of 16 ms frames, in the bucket at 64 pixels
+    assert_eq!(image.get(64, 0), [0x33, 0xff, 0x66, 0xff]);
+    assert_eq!(image.get(67, 0), [0, 0, 0, 0xff]);
+    assert_eq!(image.get(60, 50), [0, 0, 0, 0xff]);
+    // Sleep errors of 0 and 0.5-0.75 ms
+    assert_eq!(image.get(0, 101), [0xff, 0xbf, 0x00, 0xff]);
+    assert_eq!(image.get(8, 101), [0xff, 0xbf, 0x00, 0xff]);
+    assert_eq!(image.get(4, 101), [0, 0, 0, 0xff]);
+}
@@ -0,0 +1,152 @@
+//! Devices coming and going while running, e.g. headphones plugged in mid-game or controllers of a cabinet powering
+//! up late. SDL reports every audio device present at start as added too, so the devices known are tracked and only
+//! real changes move the sound to the new default device. Controllers are assigned to players by preference.
+
+#[cfg(test)]
+mod tests;
+
+/// Players that controllers are assigned to
+pub const PLAYERS: usize = 2;
+
+/// Connected devices of one kind, by SDL id
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Devices {
+    ids: Vec<u32>,
+}
+
+impl Devices {
+    /// Devices connected at start
+    pub fn new(ids: Vec<u32>) -> Self {
+        Devices { ids }
+    }
+
+    /// A device was added, returns whether it is new
+    pub fn added(&mut self, id: u32) -> bool {
+        if self.ids.contains(&id) {
+            return false;
+        }
+        self.ids.push(id);
+        true
+    }
+
+    /// A device was removed, returns whether it was connected
+    pub fn removed(&mut self, id: u32) -> bool {
+        let count = self.ids.len();
+        self.ids.retain(|i| *i != id);
+        self.ids.len() != count
+    }
+
+    /// Number of devices connected
+    pub fn count(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+/// The samples not played yet with some bytes of them still queued, to continue a sound on another device where it
+/// was. Only the last copy is continued when a sound was queued more than once.
+pub fn unplayed(samples: &[u8], queued: i32) -> &[u8] {
+    let queued = (queued.max(0) as usize).min(samples.len());
+    &samples[samples.len() - queued..]
+}
+
+/// A connected game controller
+#[derive(Debug, Clone, PartialEq)]
+struct Controller {
+    id: u32,
+    /// Model of the controller, the same for controllers of a kind
+    guid: String,
+    name: String,
+}
+
+/// Game controllers assigned to players. A player gets the controller they prefer when it is connected, the other
+/// players get the remaining controllers in the order they were connected. Controllers of the same model share a GUID
+/// and are told apart by that order.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Controllers {
+    connected: Vec<Controller>,
+    /// GUID of the controller each player prefers
+    preferred: [Option<String>; PLAYERS],
+}
+
+impl Controllers {
+    pub fn new(preferred: [Option<String>; PLAYERS]) -> Self {
+        Controllers {
+            connected: vec![],
+            preferred,
+        }
+    }
+
+    pub fn connect(&mut self, id: u32, guid: &str, name: &str) {
+        self.disconnect(id);
+        self.connected.push(Controller {
+            id,
+            guid: guid.to_string(),
+            name: name.to_string(),
+        });
+    }
+
+    /// A controller was removed, returns whether it was connected
+    pub fn disconnect(&mut self, id: u32) -> bool {
+        let count = self.connected.len();
+        self.connected.retain(|c| c.id != id);
+        self.connected.len() != count
+    }
+
+    /// Controller of each player
+    pub fn players(&self) -> [Option<u32>; PLAYERS] {
+        let mut players = [None; PLAYERS];
+        let mut free: Vec<&Controller> = self.connected.iter().collect();
+        for (player, guid) in self.preferred.iter().enumerate() {
+            if let Some(i) = free.iter().position(|c| Some(&c.guid) == guid.as_ref()) {
+                players[player] = Some(free.remove(i).id);
+            }
+        }
+        let mut free = free.into_iter();
+        for player in players.iter_mut().filter(|p| p.is_none()) {
+            *player = free.next().map(|c| c.id);
+        }
+        players
+    }
+
+    /// Player a controller is assigned to
+    pub fn player(&self, id: u32) -> Option<usize> {
+        self.players().iter().position(|p| *p == Some(id))
+    }
+
+    /// Swap the controllers of the players. The preferences and the order are swapped too, so the swap sticks when
+    /// the controllers are of the same model.
+    pub fn swap(&mut self) {
+        let players = self.players();
+        let guid = |id: Option<u32>| Some(self.controller(id?)?.guid.clone());
+        self.preferred = [guid(players[1]), guid(players[0])];
+        let position = |id: Option<u32>| self.connected.iter().position(|c| Some(c.id) == id);
+        if let (Some(a), Some(b)) = (position(players[0]), position(players[1])) {
+            self.connected.swap(a, b);
+        }
+    }
+
+    /// Preferences to remember: the controller of each player, or the one they preferred when they have none
+    pub fn preferred(&self) -> [Option<String>; PLAYERS] {
+        let players = self.players();
+        std::array::from_fn(|player| {
+            let controller = players[player].and_then(|id| self.controller(id));
+            controller.map_or(self.preferred[player].clone(), |c| Some(c.guid.clone()))
+        })
+    }
+
+    fn controller(&self, id: u32) -> Option<&Controller> {
+        self.connected.iter().find(|c| c.id == id)
+    }
+
+    /// Players whose controller changed since the assignment `before`, with the name of their new controller
+    pub fn changes(&self, before: [Option<u32>; PLAYERS]) -> Vec<(usize, Option<&str>)> {
+        let players = self.players();
+        (0..PLAYERS)
+            .filter(|&player| players[player] != before[player])
+            .map(|player| {
+                let controller = players[player].and_then(|id| self.controller(id));
+                (player, controller.map(|c| c.name.as_str()))
+            })
+            .collect()
+    }
+}
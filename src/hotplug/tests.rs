@@ -0,0 +1,74 @@
+use super::*;
+
+#[test]
+fn only_changes_count() {
+    let mut devices = Devices::new(vec![2, 3]);
+    assert!(!devices.added(3));
+    assert!(devices.added(5));
+    assert!(!devices.added(5));
+    assert_eq!(devices.count(), 3);
+    assert!(devices.removed(2));
+    assert!(!devices.removed(2));
+    assert!(!devices.removed(7));
+    assert_eq!(devices.count(), 2);
+}
+
+#[test]
+fn unplayed_tail() {
+    let samples = [1, 2, 3, 4];
+    assert_eq!(unplayed(&samples, 0), &[] as &[u8]);
+    assert_eq!(unplayed(&samples, 3), &[2, 3, 4]);
+    assert_eq!(unplayed(&samples, 9), &samples);
+    assert_eq!(unplayed(&samples, -1), &[] as &[u8]);
+}
+
+#[test]
+fn controllers_in_connection_order() {
+    let mut controllers = Controllers::default();
+    assert_eq!(controllers.players(), [None, None]);
+    controllers.connect(7, "pad", "Pad");
+    controllers.connect(3, "stick", "Stick");
+    controllers.connect(9, "pad", "Pad");
+    assert_eq!(controllers.players(), [Some(7), Some(3)]);
+    assert_eq!(controllers.player(9), None);
+    assert!(controllers.disconnect(7));
+    assert_eq!(controllers.players(), [Some(3), Some(9)]);
+    assert!(!controllers.disconnect(7));
+}
+
+#[test]
+fn preferred_controller_powering_up_late() {
+    let mut controllers = Controllers::new([Some("stick".into()), None]);
+    controllers.connect(7, "pad", "Pad");
+    let before = controllers.players();
+    assert_eq!(before, [Some(7), None]);
+    controllers.connect(3, "stick", "Stick");
+    assert_eq!(controllers.players(), [Some(3), Some(7)]);
+    assert_eq!(
+        controllers.changes(before),
+        [(0, Some("Stick")), (1, Some("Pad"))]
+    );
+    let before = controllers.players();
+    controllers.disconnect(7);
+    assert_eq!(controllers.changes(before), [(1, None)]);
+    assert_eq!(controllers.preferred(), [Some("stick".into()), None]);
+}
+
+#[test]
+fn swap_sticks() {
+    let mut controllers = Controllers::default();
+    controllers.connect(7, "pad", "Pad");
+    controllers.connect(9, "pad", "Pad");
+    controllers.swap();
+    assert_eq!(controllers.players(), [Some(9), Some(7)]);
+    assert_eq!(
+        controllers.preferred(),
+        [Some("pad".into()), Some("pad".into())]
+    );
+
+    let mut controllers = Controllers::new([Some("pad".into()), None]);
+    controllers.connect(7, "pad", "Pad");
+    controllers.swap();
+    assert_eq!(controllers.players(), [None, Some(7)]);
+    assert_eq!(controllers.preferred(), [None, Some("pad".into())]);
+}
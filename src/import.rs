@@ -0,0 +1,79 @@
+//! Import of states from other emulators. Memory dumps of the address space (16 KB or more from `0`, as saved by the
+//! MAME debugger with `save invaders.bin,0,4000` or by `--soak`) or of the RAM alone (8 KB from `0x2000`) are imported
+//! into the game variables and the screen of the running machine. Its registers and stack are kept, so it carries on
+//! in its own main loop with the imported game. MAME savestates (`.sta`) are recognized but not converted: their
+//! contents are only laid out by registration order, which changes between MAME versions.
+
+use std::ops::RangeInclusive;
+
+use crate::{cpu::Cpu, FPS, STACK};
+
+#[cfg(test)]
+mod tests;
+
+/// Tag at the start of MAME savestates
+const MAME_TAG: &[u8] = b"MAMESAVE";
+/// Size of the header of MAME savestates
+const MAME_HEADER: usize = 32;
+/// Smallest memory dump, the 16 KB of address space decoded by the board
+const MEMORY_DUMP: usize = 0x4000;
+/// Size of a RAM dump, the 8 KB of RAM on the board
+const RAM_DUMP: usize = 0x2000;
+
+/// Frames run from power on before importing, so the game is initialized and in its main loop
+pub const BOOT_FRAMES: u32 = 2 * FPS;
+
+/// Format of an imported state
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    /// MAME savestate, with the short name of the game and the version of the format
+    Mame { game: String, version: u8 },
+    /// Memory dump from address 0
+    Memory,
+    /// Dump of the RAM only
+    Ram,
+}
+
+/// Tell the format of a state from its contents, for a machine with the given RAM
+pub fn detect(data: &[u8], ram: &RangeInclusive<usize>) -> Result<Format, String> {
+    if data.starts_with(MAME_TAG) {
+        if data.len() < MAME_HEADER {
+            return Err("Truncated MAME savestate".to_string());
+        }
+        let name = &data[10..28];
+        let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        return Ok(Format::Mame {
+            game: String::from_utf8_lossy(&name[..end]).to_string(),
+            version: data[8],
+        });
+    }
+    if data.len() >= MEMORY_DUMP {
+        Ok(Format::Memory)
+    } else if data.len() == RAM_DUMP || data.len() == ram.end() - ram.start() + 1 {
+        Ok(Format::Ram)
+    } else {
+        Err(format!("Unknown state format ({} bytes)", data.len()))
+    }
+}
+
+/// Import the game variables and the screen of a state into the given RAM, keeping the stack of the machine
+pub fn import(cpu: &mut Cpu, data: &[u8], ram: &RangeInclusive<usize>) -> Result<Format, String> {
+    let format = detect(data, ram)?;
+    let dump = match &format {
+        Format::Mame { game, version } => {
+            return Err(format!(
+                "MAME savestate of {game} (format {version}) cannot be converted, dump the memory in the MAME debugger \
+                 with `save {game}.bin,0,4000` and import that instead"
+            ))
+        }
+        Format::Memory => data.get(*ram.start()..).unwrap_or_default(),
+        Format::Ram => data,
+    };
+    for (addr, value) in ram.clone().zip(dump) {
+        if !STACK.contains(&addr) {
+            cpu.poke(addr, *value);
+        }
+    }
+    cpu.set_display_update(true);
+    Ok(format)
+}
@@ -0,0 +1,49 @@
+use super::*;
+use crate::RAM;
+
+#[test]
+fn detect_formats() {
+    let mut mame = b"MAMESAVE\x02\x00invaders".to_vec();
+    mame.resize(MAME_HEADER + 10, 0);
+    assert_eq!(
+        detect(&mame, &RAM),
+        Ok(Format::Mame {
+            game: "invaders".to_string(),
+            version: 2
+        })
+    );
+    assert!(detect(&mame[..20], &RAM).is_err());
+    assert_eq!(detect(&[0; 0x4000], &RAM), Ok(Format::Memory));
+    assert_eq!(detect(&[0; 0x10000], &RAM), Ok(Format::Memory));
+    assert_eq!(detect(&[0; 0x2000], &RAM), Ok(Format::Ram));
+    assert_eq!(detect(&[0; 0x2200], &RAM), Ok(Format::Ram));
+    assert_eq!(detect(&[0; 0x400], &(0x2000..=0x23FF)), Ok(Format::Ram));
+    assert_eq!(
+        detect(&[0; 100], &RAM),
+        Err("Unknown state format (100 bytes)".to_string())
+    );
+}
+
+#[test]
+fn import_keeps_stack() {
+    let mut cpu = Cpu::new(vec![]);
+    cpu.poke(0x23FF, 0x18);
+    let mut dump = vec![0xAA; 0x4000];
+    dump[0x23FF] = 0x42;
+    assert_eq!(import(&mut cpu, &dump, &RAM), Ok(Format::Memory));
+    assert_eq!(cpu.memory()[0x2000], 0xAA);
+    assert_eq!(cpu.memory()[0x3FFF], 0xAA);
+    assert_eq!(cpu.memory()[0x23FF], 0x18);
+    assert_eq!(cpu.memory()[0x0000], 0);
+
+    let mut ram = vec![0x55; 0x2000];
+    ram[0x1FFF] = 0x66;
+    assert_eq!(import(&mut cpu, &ram, &RAM), Ok(Format::Ram));
+    assert_eq!(cpu.memory()[0x3FFF], 0x66);
+
+    let mut mame = b"MAMESAVE\x02\x00invaders".to_vec();
+    mame.resize(MAME_HEADER, 0);
+    assert!(import(&mut cpu, &mame, &RAM)
+        .unwrap_err()
+        .contains("save invaders.bin,0,4000"));
+}
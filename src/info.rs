@@ -0,0 +1,154 @@
+//! Capabilities of the build as JSON, printed by `--dump-info`, so launchers and frontends can integrate without
+//! parsing the usage text: version, cargo features, subcommands, board presets, the machine loaded with its inputs,
+//! the emulator commands and their default bindings (keys and buttons by SDL name), and the savestate format.
+
+use std::fmt::Write;
+
+use crate::{
+    bindings::{Bindings, Modifier, Trigger},
+    convert::Conversion,
+    cpu::STATE_MAGIC,
+    machine::Machine,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Subcommands, given before any option
+pub const SUBCOMMANDS: [&str; 6] = ["asmrun", "sprites", "batch", "corpus", "diff", "disasm"];
+
+/// Tables understood in machine files
+pub const MACHINE_TABLES: [&str; 10] = [
+    "memory",
+    "banks",
+    "colors",
+    "timer",
+    "serial",
+    "interrupt",
+    "input",
+    "output",
+    "sound",
+    "overlay",
+];
+
+/// A string as a JSON string
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A JSON array of strings
+fn strings<'a>(items: impl IntoIterator<Item = &'a str>) -> String {
+    let items: Vec<String> = items.into_iter().map(quote).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// The machine loaded: name, input and output bits and the peripherals described
+fn machine(machine: &Machine) -> String {
+    let inputs: Vec<String> = machine
+        .board
+        .inputs
+        .iter()
+        .map(|i| {
+            format!(
+                "{{\"name\": {}, \"port\": {}, \"bit\": {}, \"description\": {}}}",
+                quote(i.name),
+                i.port,
+                i.bit,
+                quote(i.description)
+            )
+        })
+        .collect();
+    let outputs: Vec<String> = machine
+        .board
+        .outputs
+        .iter()
+        .map(|o| {
+            format!(
+                "{{\"name\": {}, \"port\": {}, \"bit\": {}, \"description\": {}}}",
+                quote(o.name),
+                o.port,
+                o.bit,
+                quote(o.description)
+            )
+        })
+        .collect();
+    let peripherals = [
+        ("banks", machine.banks.is_some()),
+        ("colors", machine.colors.is_some()),
+        ("timer", machine.timer.is_some()),
+        ("serial", machine.serial.is_some()),
+    ];
+    let peripherals = peripherals.iter().filter(|(_, on)| *on).map(|(p, _)| *p);
+    format!(
+        "{{\"name\": {}, \"inputs\": [{}], \"outputs\": [{}], \"peripherals\": {}}}",
+        quote(machine.board.name),
+        inputs.join(", "),
+        outputs.join(", "),
+        strings(peripherals)
+    )
+}
+
+/// Features of the crate compiled in
+fn features() -> Vec<&'static str> {
+    let mut features = vec![];
+    if cfg!(feature = "test-api") {
+        features.push("test-api");
+    }
+    features
+}
+
+/// Capabilities of the build with a machine loaded, as a JSON object
+pub fn to_json(loaded: &Machine, bindings: &Bindings) -> String {
+    let mut actions: Vec<&str> = vec![];
+    for (_, action) in &bindings.0 {
+        if !actions.contains(&action.name()) {
+            actions.push(action.name());
+        }
+    }
+    let bindings: Vec<String> = bindings
+        .0
+        .iter()
+        .map(|(trigger, action)| {
+            let trigger = match trigger {
+                Trigger::Key(name, Modifier::None) => format!("\"key\": {}", quote(name)),
+                Trigger::Key(name, Modifier::Ctrl) => {
+                    format!("\"key\": {}, \"modifier\": \"ctrl\"", quote(name))
+                }
+                Trigger::Key(name, Modifier::Shift) => {
+                    format!("\"key\": {}, \"modifier\": \"shift\"", quote(name))
+                }
+                Trigger::Button(name) => format!("\"button\": {}", quote(name)),
+            };
+            format!("{{{trigger}, \"action\": {}}}", quote(action.name()))
+        })
+        .collect();
+    let presets = [Machine::invaders().board.name];
+    let mut s = String::from("{\n");
+    let _ = writeln!(s, "  \"name\": {},", quote(env!("CARGO_PKG_NAME")));
+    let _ = writeln!(s, "  \"version\": {},", quote(env!("CARGO_PKG_VERSION")));
+    let _ = writeln!(s, "  \"features\": {},", strings(features()));
+    let _ = writeln!(s, "  \"subcommands\": {},", strings(SUBCOMMANDS));
+    let _ = writeln!(s, "  \"presets\": {},", strings(presets));
+    let _ = writeln!(s, "  \"machine_tables\": {},", strings(MACHINE_TABLES));
+    let _ = writeln!(s, "  \"machine\": {},", machine(loaded));
+    let _ = writeln!(s, "  \"actions\": {},", strings(actions));
+    let _ = writeln!(s, "  \"bindings\": [{}],", bindings.join(", "));
+    let conversions = Conversion::ALL.map(Conversion::name);
+    let _ = writeln!(s, "  \"conversions\": {},", strings(conversions));
+    let magic = String::from_utf8_lossy(STATE_MAGIC);
+    let _ = writeln!(s, "  \"state_format\": {}", quote(&magic));
+    s += "}\n";
+    s
+}
@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn quoted() {
+    assert_eq!(quote("Fire \"1\"\\\n"), "\"Fire \\\"1\\\"\\\\\\u000a\"");
+    assert_eq!(strings(["a", "b"]), "[\"a\", \"b\"]");
+    assert_eq!(strings([]), "[]");
+}
+
+#[test]
+fn capabilities() {
+    let json = to_json(&Machine::invaders(), &Bindings::default());
+    assert!(json.starts_with("{\n  \"name\": \"inv8080rs\",\n"));
+    assert!(json.ends_with("  \"state_format\": \"INV8080S\"\n}\n"));
+    assert!(json.contains("  \"presets\": [\"invaders\"],\n"));
+    assert!(json.contains(
+        "{\"name\": \"credit\", \"port\": 1, \"bit\": 0, \"description\": \"Coin deposited\"}"
+    ));
+    assert!(json.contains("\"peripherals\": []"));
+    assert!(json.contains("{\"key\": \"F1\", \"action\": \"MEMORY EDITOR\"}"));
+    assert!(json.contains("{\"key\": \"F\", \"modifier\": \"ctrl\", \"action\": \"FREE PLAY\"}"));
+    assert!(json.contains("{\"button\": \"guide\", \"action\": \"PAUSE\"}"));
+    assert!(json.contains("\"conversions\": [\"points\", \"buffer\", \"lut\"],"));
+    // Each action once, though bound to several keys
+    assert_eq!(json.matches("\"PAUSE\"").count(), 4);
+}
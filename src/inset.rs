@@ -0,0 +1,116 @@
+//! Picture-in-picture: a small RGBA image from outside the emulator, e.g. a webcam feed or a logo, composited into a
+//! corner of the output every frame, for streamers capturing the emulator window. Independent of SDL: the frontend
+//! sends images from any thread and the latest one sent is shown.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+#[cfg(test)]
+mod tests;
+
+/// Gap between the inset and the edges of the output, in output pixels
+pub const MARGIN: u32 = 8;
+
+/// Image in RGBA, 4 bytes a pixel, row by row from the top
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4));
+        if width == 0 || height == 0 || size != Some(pixels.len()) {
+            return Err(match size {
+                Some(size) => format!(
+                    "Inset of {width}x{height} needs {size} bytes, got {}",
+                    pixels.len()
+                ),
+                None => format!("Inset of {width}x{height} is too large"),
+            });
+        }
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Corner of the output the inset goes in
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    /// Parse `top-left`, `top-right`, `bottom-left` or `bottom-right`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "top-left" => Some(Corner::TopLeft),
+            "top-right" => Some(Corner::TopRight),
+            "bottom-left" => Some(Corner::BottomLeft),
+            "bottom-right" => Some(Corner::BottomRight),
+            _ => None,
+        }
+    }
+}
+
+/// Where an image of `size` goes in an output of `output` size, as (x, y, width, height). Images larger than half the
+/// output either way are scaled down to fit, keeping their aspect.
+pub fn place(corner: Corner, size: (u32, u32), output: (u32, u32)) -> (i32, i32, u32, u32) {
+    let (w, h) = (size.0.max(1) as u64, size.1.max(1) as u64);
+    let (max_w, max_h) = (output.0 as u64 / 2, output.1 as u64 / 2);
+    // Scale by the tighter limit, never up
+    let (w, h) = if w * max_h > h * max_w {
+        (w.min(max_w), h * w.min(max_w) / w)
+    } else {
+        (w * h.min(max_h) / h, h.min(max_h))
+    };
+    let (w, h) = (w as u32, h as u32);
+    let left = MARGIN as i32;
+    let top = MARGIN as i32;
+    let right = output.0 as i32 - MARGIN as i32 - w as i32;
+    let bottom = output.1 as i32 - MARGIN as i32 - h as i32;
+    let (x, y) = match corner {
+        Corner::TopLeft => (left, top),
+        Corner::TopRight => (right, top),
+        Corner::BottomLeft => (left, bottom),
+        Corner::BottomRight => (right, bottom),
+    };
+    (x, y, w, h)
+}
+
+/// Images sent by the frontend, shown from the frame after they arrive. Sending `None` removes the inset.
+#[derive(Debug)]
+pub struct Feed {
+    sender: Sender<Option<Image>>,
+    receiver: Receiver<Option<Image>>,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Feed { sender, receiver }
+    }
+}
+
+impl Feed {
+    /// Sending end for the frontend or another thread
+    pub fn sender(&self) -> Sender<Option<Image>> {
+        self.sender.clone()
+    }
+
+    /// The latest image sent since the last call, skipping older ones a slow frame left behind. The outer None means
+    /// nothing was sent.
+    pub fn latest(&self) -> Option<Option<Image>> {
+        self.receiver.try_iter().last()
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn image_size() {
+    assert!(Image::new(2, 2, vec![0; 16]).is_ok());
+    assert!(Image::new(2, 2, vec![0; 15]).is_err());
+    assert!(Image::new(0, 2, vec![]).is_err());
+    assert_eq!(
+        Image::new(0x8000_0000, 2, vec![0; 16]),
+        Err("Inset of 2147483648x2 needs 17179869184 bytes, got 16".into())
+    );
+    assert_eq!(
+        Image::new(u32::MAX, u32::MAX, vec![0; 16]),
+        Err("Inset of 4294967295x4294967295 is too large".into())
+    );
+}
+
+#[test]
+fn corners() {
+    assert_eq!(Corner::parse("top-right"), Some(Corner::TopRight));
+    assert_eq!(Corner::parse("middle"), None);
+    let output = (672, 768);
+    assert_eq!(place(Corner::TopLeft, (160, 120), output), (8, 8, 160, 120));
+    assert_eq!(
+        place(Corner::TopRight, (160, 120), output),
+        (504, 8, 160, 120)
+    );
+    assert_eq!(
+        place(Corner::BottomLeft, (160, 120), output),
+        (8, 640, 160, 120)
+    );
+    assert_eq!(
+        place(Corner::BottomRight, (160, 120), output),
+        (504, 640, 160, 120)
+    );
+}
+
+#[test]
+fn large_images_shrink() {
+    // 1280x720 into at most 336x384, keeping 16:9
+    assert_eq!(
+        place(Corner::TopLeft, (1280, 720), (672, 768)),
+        (8, 8, 336, 189)
+    );
+    // Tall images are limited by the height
+    assert_eq!(
+        place(Corner::TopLeft, (100, 800), (672, 768)),
+        (8, 8, 48, 384)
+    );
+}
+
+#[test]
+fn latest_image_wins() {
+    let feed = Feed::default();
+    assert_eq!(feed.latest(), None);
+    let sender = feed.sender();
+    let image = |v| Image::new(1, 1, vec![v; 4]).unwrap();
+    sender.send(Some(image(1))).unwrap();
+    sender.send(Some(image(2))).unwrap();
+    assert_eq!(feed.latest(), Some(Some(image(2))));
+    assert_eq!(feed.latest(), None);
+    sender.send(None).unwrap();
+    assert_eq!(feed.latest(), Some(None));
+}
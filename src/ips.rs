@@ -0,0 +1,59 @@
+//! IPS patches (International Patching System)
+
+#[cfg(test)]
+mod tests;
+
+/// Create an IPS patch turning `original` into `patched` (of the same length)
+pub fn create(original: &[u8], patched: &[u8]) -> Vec<u8> {
+    let mut ips = b"PATCH".to_vec();
+    let mut i = 0;
+    while i < patched.len() {
+        if original.get(i) == Some(&patched[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < patched.len() && original.get(i) != Some(&patched[i]) && i - start < 0xFFFF {
+            i += 1;
+        }
+        ips.extend(&(start as u32).to_be_bytes()[1..]);
+        ips.extend(((i - start) as u16).to_be_bytes());
+        ips.extend(&patched[start..i]);
+    }
+    ips.extend(b"EOF");
+    ips
+}
+
+/// Apply an IPS patch, growing the data if needed
+pub fn apply(data: &mut Vec<u8>, ips: &[u8]) -> Result<(), String> {
+    let mut rest = ips.strip_prefix(b"PATCH").ok_or("Not an IPS patch")?;
+    loop {
+        if rest.starts_with(b"EOF") {
+            return Ok(());
+        }
+        if rest.len() < 5 {
+            return Err("Truncated IPS patch".into());
+        }
+        let offset = u32::from_be_bytes([0, rest[0], rest[1], rest[2]]) as usize;
+        let size = u16::from_be_bytes([rest[3], rest[4]]) as usize;
+        rest = &rest[5..];
+        let bytes: Vec<u8> = if size == 0 {
+            // Run-length encoded record
+            if rest.len() < 3 {
+                return Err("Truncated IPS patch".into());
+            }
+            let count = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+            let value = rest[2];
+            rest = &rest[3..];
+            vec![value; count]
+        } else {
+            let bytes = rest.get(..size).ok_or("Truncated IPS patch")?.to_vec();
+            rest = &rest[size..];
+            bytes
+        };
+        if data.len() < offset + bytes.len() {
+            data.resize(offset + bytes.len(), 0);
+        }
+        data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn create_and_apply() {
+    let original = vec![0, 1, 2, 3, 4, 5, 6, 7];
+    let patched = vec![0, 9, 9, 3, 4, 5, 6, 8];
+    let ips = create(&original, &patched);
+    assert_eq!(
+        ips,
+        b"PATCH\x00\x00\x01\x00\x02\x09\x09\x00\x00\x07\x00\x01\x08EOF"
+    );
+
+    let mut data = original.clone();
+    assert_eq!(apply(&mut data, &ips), Ok(()));
+    assert_eq!(data, patched);
+}
+
+#[test]
+fn create_unchanged() {
+    assert_eq!(create(&[1, 2, 3], &[1, 2, 3]), b"PATCHEOF");
+}
+
+#[test]
+fn apply_rle_and_grow() {
+    let mut data = vec![0; 2];
+    assert_eq!(
+        apply(&mut data, b"PATCH\x00\x00\x01\x00\x00\x00\x03\xAAEOF"),
+        Ok(())
+    );
+    assert_eq!(data, [0, 0xAA, 0xAA, 0xAA]);
+}
+
+#[test]
+fn apply_errors() {
+    let mut data = vec![];
+    assert!(apply(&mut data, b"NOTIPS").is_err());
+    assert!(apply(&mut data, b"PATCH\x00\x00\x01\x00\x05\x01").is_err());
+}
@@ -0,0 +1,216 @@
+//! Input latency measurement. End to end: a white flash is shown at unpredictable times and the time from presenting
+//! it to the response is measured, the response being a key press by the user or a photodiode taped to the screen
+//! reporting through a hook (e.g. a microcontroller on a serial port). Internal: the time from a key press event to
+//! its poll, when the input port bit is set, and on to the next frame presented, which shows how run-ahead and input
+//! polling changes pay off.
+
+use std::{
+    fmt::Write,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use crate::{batch::Noise, FPS};
+
+#[cfg(test)]
+mod tests;
+
+/// Frames the flash is shown
+pub const FLASH_FRAMES: u32 = 6;
+/// Shortest and longest wait between flashes, in frames, so the flash cannot be anticipated
+pub const MIN_GAP: u32 = FPS;
+pub const MAX_GAP: u32 = 3 * FPS;
+/// Seed of the waits between flashes
+const SEED: u64 = 8080;
+
+/// Durations measured, summarized as min, median, average and max
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Samples(Vec<Duration>);
+
+impl Samples {
+    pub fn record(&mut self, sample: Duration) {
+        self.0.push(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Min, median, average and max, None without samples
+    pub fn summary(&self) -> Option<[Duration; 4]> {
+        let mut sorted = self.0.clone();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        Some([
+            *sorted.first()?,
+            sorted[sorted.len() / 2],
+            total / sorted.len() as u32,
+            *sorted.last()?,
+        ])
+    }
+
+    /// One line: `<name>: min .. ms, median .. ms, avg .. ms, max .. ms (n samples)`
+    pub fn report(&self, name: &str) -> String {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        match self.summary() {
+            Some([min, median, average, max]) => format!(
+                "{name}: min {:.1} ms, median {:.1} ms, avg {:.1} ms, max {:.1} ms ({} samples)\n",
+                ms(min),
+                ms(median),
+                ms(average),
+                ms(max),
+                self.len()
+            ),
+            None => format!("{name}: no samples\n"),
+        }
+    }
+}
+
+/// Key press to port bit to presented frame
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Pipeline {
+    /// Poll of the press waiting for the next present
+    pending: Option<Instant>,
+    /// Event to poll, when the port bit is set
+    pub queued: Samples,
+    /// Poll to the next frame presented
+    pub to_present: Samples,
+    /// Event to the next frame presented
+    pub total: Samples,
+}
+
+impl Pipeline {
+    /// A key press made at `event` was polled and its port bit set at `polled`. Presses before the next present go
+    /// with the first.
+    pub fn press(&mut self, event: Instant, polled: Instant) {
+        if self.pending.is_none() {
+            let queued = polled.saturating_duration_since(event);
+            self.queued.record(queued);
+            self.total.record(queued);
+            self.pending = Some(polled);
+        }
+    }
+
+    /// A frame was presented
+    pub fn presented(&mut self, at: Instant) {
+        if let Some(polled) = self.pending.take() {
+            let to_present = at.saturating_duration_since(polled);
+            self.to_present.record(to_present);
+            if let Some(total) = self.total.0.last_mut() {
+                *total += to_present;
+            }
+        }
+    }
+
+    pub fn report(&self) -> String {
+        self.queued.report("Event to poll (port bit)")
+            + &self.to_present.report("Poll to present")
+            + &self.total.report("Event to present")
+    }
+}
+
+/// Hook for a sensor seeing the flash, e.g. a photodiode read by a thread on a serial port, which sends when it saw it
+#[derive(Debug)]
+pub struct Sensor {
+    sender: Sender<Instant>,
+    receiver: Receiver<Instant>,
+}
+
+impl Default for Sensor {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Sensor { sender, receiver }
+    }
+}
+
+impl Sensor {
+    /// Sending end for the thread reading the sensor
+    pub fn sender(&self) -> Sender<Instant> {
+        self.sender.clone()
+    }
+
+    /// Responses sent so far
+    pub fn take(&self) -> Vec<Instant> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+/// Flashes and responses, for the end to end latency
+#[derive(Debug, Clone)]
+pub struct FlashTest {
+    noise: Noise,
+    /// Frames until the next flash, or left of the current one
+    countdown: u32,
+    flashing: bool,
+    /// When the current flash was first presented, until the response
+    shown: Option<Instant>,
+    /// Presentation to response
+    pub samples: Samples,
+    /// Responses without a flash to respond to
+    pub early: u32,
+}
+
+impl Default for FlashTest {
+    fn default() -> Self {
+        let mut test = FlashTest {
+            noise: Noise::new(SEED),
+            countdown: 0,
+            flashing: false,
+            shown: None,
+            samples: Samples::default(),
+            early: 0,
+        };
+        test.countdown = test.gap();
+        test
+    }
+}
+
+impl FlashTest {
+    fn gap(&mut self) -> u32 {
+        MIN_GAP + (self.noise.next_u64() % (MAX_GAP - MIN_GAP + 1) as u64) as u32
+    }
+
+    /// Advance a frame, true when the flash is to be drawn in it
+    pub fn frame(&mut self) -> bool {
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown == 0 {
+            self.flashing = !self.flashing;
+            self.countdown = if self.flashing {
+                FLASH_FRAMES
+            } else {
+                self.gap()
+            };
+        }
+        self.flashing
+    }
+
+    /// A frame was presented, the flash is timed from the first frame showing it
+    pub fn presented(&mut self, at: Instant) {
+        if self.flashing && self.countdown == FLASH_FRAMES {
+            self.shown = Some(at);
+        }
+    }
+
+    /// The user or sensor responded. Returns the latency, None if there was no flash to respond to.
+    pub fn respond(&mut self, at: Instant) -> Option<Duration> {
+        let Some(shown) = self.shown.take() else {
+            self.early += 1;
+            return None;
+        };
+        let latency = at.saturating_duration_since(shown);
+        self.samples.record(latency);
+        Some(latency)
+    }
+
+    pub fn report(&self) -> String {
+        let mut s = self.samples.report("Flash to response");
+        if self.early > 0 {
+            let _ = writeln!(s, "Responses without a flash: {}", self.early);
+        }
+        s
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn summary() {
+    let mut samples = Samples::default();
+    assert_eq!(samples.summary(), None);
+    assert_eq!(samples.report("Test"), "Test: no samples\n");
+    for n in [30, 10, 20, 60] {
+        samples.record(ms(n));
+    }
+    assert_eq!(samples.summary(), Some([ms(10), ms(30), ms(30), ms(60)]));
+    assert_eq!(
+        samples.report("Test"),
+        "Test: min 10.0 ms, median 30.0 ms, avg 30.0 ms, max 60.0 ms (4 samples)\n"
+    );
+}
+
+#[test]
+fn pipeline() {
+    let start = Instant::now();
+    let mut pipeline = Pipeline::default();
+    pipeline.press(start, start + ms(3));
+    // A second press before the present goes with the first
+    pipeline.press(start + ms(4), start + ms(5));
+    pipeline.presented(start + ms(20));
+    // Presents without a press are not counted
+    pipeline.presented(start + ms(36));
+    assert_eq!(pipeline.queued.summary().unwrap()[0], ms(3));
+    assert_eq!(pipeline.to_present.summary().unwrap()[0], ms(17));
+    assert_eq!(pipeline.total.summary().unwrap()[0], ms(20));
+    assert_eq!(pipeline.total.len(), 1);
+}
+
+#[test]
+fn flashes() {
+    let mut test = FlashTest::default();
+    let frames: Vec<bool> = (0..10 * FPS).map(|_| test.frame()).collect();
+    let starts: Vec<usize> = (1..frames.len())
+        .filter(|i| frames[*i] && !frames[i - 1])
+        .collect();
+    assert!(starts.len() >= 3);
+    for pair in starts.windows(2) {
+        let gap = (pair[1] - pair[0]) as u32;
+        assert!((MIN_GAP + FLASH_FRAMES..=MAX_GAP + FLASH_FRAMES).contains(&gap));
+    }
+    // Every flash lasts the same
+    let lit = frames.iter().filter(|f| **f).count();
+    assert!(lit.abs_diff(starts.len() * FLASH_FRAMES as usize) <= FLASH_FRAMES as usize);
+}
+
+#[test]
+fn responses() {
+    let start = Instant::now();
+    let mut test = FlashTest::default();
+    assert_eq!(test.respond(start), None);
+    while !test.frame() {}
+    test.presented(start + ms(100));
+    test.frame();
+    // Only the first frame of a flash times it
+    test.presented(start + ms(116));
+    assert_eq!(test.respond(start + ms(350)), Some(ms(250)));
+    assert_eq!(test.respond(start + ms(400)), None);
+    assert_eq!(
+        test.report(),
+        "Flash to response: min 250.0 ms, median 250.0 ms, avg 250.0 ms, max 250.0 ms (1 samples)\n\
+         Responses without a flash: 2\n"
+    );
+}
@@ -1,4 +1,25 @@
 //! # Intel 8080 Space Invaders Emulator
+//!
+//! ## Stable API
+//!
+//! The names re-exported at the crate root follow semver: [`Machine`] describes the board and loads roms on it into a
+//! [`Cpu`], [`Frontend`] runs a loaded machine in a window as configured by [`Options`], and [`State`] is a savestate
+//! of it.
+//! The modules behind them are internal and may be split or moved between minor versions: they are private, or hidden
+//! from the documentation where the binary or the types of the stable API need them. A root name whose item moves
+//! keeps working, and one that is renamed stays as a deprecated alias until the next major version, as do the old
+//! names in the modules, like `emu::Emu` for [`Frontend`].
+//!
+//! ```
+//! use inv8080rs::{Cpu, Machine, State};
+//!
+//! // MVI A,42 ; HLT
+//! let mut cpu: Cpu = Machine::invaders().load(&[0x3E, 42, 0x76]);
+//! let state = State::save(&cpu);
+//! cpu.step();
+//! state.restore(&mut cpu).unwrap();
+//! assert_eq!(State::save(&cpu), state);
+//! ```
 
 use std::ops::RangeInclusive;
 
@@ -28,6 +49,133 @@ pub const DISPLAY_WIDTH: u32 = 224;
 /// Height of display in pixels
 pub const DISPLAY_HEIGHT: u32 = 256;
 
+#[doc(hidden)]
+pub mod assets;
+pub(crate) mod avsync;
+#[doc(hidden)]
+pub mod banks;
+#[doc(hidden)]
+pub mod batch;
+#[doc(hidden)]
+pub mod bench;
+#[doc(hidden)]
+pub mod bindings;
+pub(crate) mod cheats;
+#[doc(hidden)]
+pub mod colors;
+#[doc(hidden)]
+pub mod commands;
+#[doc(hidden)]
+pub mod compare;
+#[doc(hidden)]
+pub mod convert;
+#[doc(hidden)]
+pub mod corpus;
+// Lints newer than the CPU model and its tests, which are kept as written
+#[allow(
+    clippy::manual_is_multiple_of,
+    clippy::explicit_counter_loop,
+    clippy::unnecessary_cast
+)]
+#[doc(hidden)]
 pub mod cpu;
+#[doc(hidden)]
+pub mod determinism;
+#[doc(hidden)]
+pub mod diagnose;
+#[doc(hidden)]
+pub mod diff;
+#[doc(hidden)]
+pub mod disasm;
+pub(crate) mod editor;
+#[doc(hidden)]
+pub mod effects;
+#[doc(hidden)]
 pub mod emu;
+#[doc(hidden)]
+pub mod env;
+#[doc(hidden)]
+pub mod expr;
+#[doc(hidden)]
+pub mod fastboot;
+pub(crate) mod ffi;
+#[doc(hidden)]
+pub mod frame;
+pub(crate) mod freeplay;
+#[doc(hidden)]
+pub mod frontend;
+pub(crate) mod fullscreen;
+#[doc(hidden)]
+pub mod hash;
+pub(crate) mod histogram;
+pub(crate) mod hotplug;
+#[doc(hidden)]
+pub mod import;
+#[doc(hidden)]
+pub mod info;
+#[doc(hidden)]
+pub mod inset;
+#[doc(hidden)]
+pub mod ips;
+#[doc(hidden)]
+pub mod latency;
+#[doc(hidden)]
+pub mod machine;
+pub(crate) mod masking;
+#[doc(hidden)]
+pub mod menu;
+#[doc(hidden)]
+pub mod palette;
+pub(crate) mod pbm;
+#[doc(hidden)]
+pub mod png;
+#[doc(hidden)]
+pub mod ports;
+#[doc(hidden)]
+pub mod present;
+#[doc(hidden)]
+pub mod profile;
+#[doc(hidden)]
+pub mod ram_map;
+#[doc(hidden)]
+pub mod remote;
+#[doc(hidden)]
+pub mod rewind;
+pub(crate) mod runahead;
+#[doc(hidden)]
+pub mod scaling;
+#[doc(hidden)]
+pub mod serial;
+#[doc(hidden)]
+pub mod shutdown;
+#[doc(hidden)]
+pub mod soak;
+#[doc(hidden)]
+pub mod sprites;
+#[doc(hidden)]
+pub mod stack;
+#[doc(hidden)]
+pub mod state;
+pub(crate) mod stats;
+#[doc(hidden)]
+pub mod synth;
+pub(crate) mod text;
+#[doc(hidden)]
+pub mod timer;
+#[doc(hidden)]
+pub mod timing;
+#[doc(hidden)]
+pub mod tournament;
+#[doc(hidden)]
+pub mod user;
+#[doc(hidden)]
 pub mod utils;
+#[doc(hidden)]
+pub mod view;
+pub(crate) mod watchdog;
+pub(crate) mod wav;
+
+pub use cpu::Cpu;
+pub use emu::{Frontend, Options};
+pub use machine::Machine;
+pub use state::State;
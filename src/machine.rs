@@ -0,0 +1,516 @@
+//! Machine descriptions: memory map, interrupts, ports, sounds and overlays of a board in a declarative file, so new
+//! 8080 boards can be described without code changes. The Space Invaders board is embedded, others are loaded with
+//! `--machine`. The files are a small subset of TOML: `key = value` pairs with strings, integers (decimal or `0x` hex),
+//! booleans and arrays of integers, in tables (`[memory]`) and arrays of tables (`[[input]]`). With `base = "invaders"`
+//! a file is read on top of the Space Invaders board, replacing its tables and adding to its arrays of tables, so
+//! variants of the board don't repeat it. An optional `[banks]` table switches banks of memory over a window, selected
+//! by an output port. An optional `[colors]` table adds the color RAM of the color boards, which then colors the
+//! display instead of the overlays. An optional `[timer]` table adds a timer for homebrew programs on an unused port,
+//! an optional `[serial]` table a serial console. A sound may describe a warble that is synthesized when its sample is
+//! missing.
+
+use std::ops::RangeInclusive;
+
+use crate::{
+    banks::Banks,
+    colors::ColorRam,
+    cpu::Cpu,
+    ports::{Board, InputBit, OutputBit, INVADERS},
+    serial::Serial,
+    synth::Warble,
+    timer::Timer,
+    timing::LINES,
+    utils::parse_number,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, FRAMEBUFFER, FREQ, MEMORY_SIZE, NPORTS, RAM, ROM,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Description of the Space Invaders board
+pub const INVADERS_MACHINE: &str = include_str!("../machines/invaders.toml");
+
+/// Value in a machine file
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Int(usize),
+    Bool(bool),
+    Array(Vec<usize>),
+}
+
+/// Table of a machine file, the root table has an empty name
+#[derive(Clone, Debug, PartialEq)]
+struct Table {
+    name: String,
+    /// Line of the header, for errors
+    line: usize,
+    /// Table of an array of tables (`[[input]]`)
+    array: bool,
+    entries: Vec<(String, Value)>,
+}
+
+/// Parse a value: a string, integer, boolean or array of integers
+fn value(s: &str) -> Option<Value> {
+    if let Some(s) = s.strip_prefix('"') {
+        return Some(Value::Str(s.strip_suffix('"')?.to_string()));
+    }
+    if let Some(s) = s.strip_prefix('[') {
+        let items = s.strip_suffix(']')?.split(',').map(str::trim);
+        let items = items.filter(|i| !i.is_empty()).map(parse_number);
+        return items.collect::<Option<_>>().map(Value::Array);
+    }
+    match s {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => parse_number(s).map(Value::Int),
+    }
+}
+
+/// Split a file into tables, `#` starts a comment (outside strings)
+fn tables(text: &str) -> Result<Vec<Table>, String> {
+    let mut tables = vec![Table {
+        name: String::new(),
+        line: 0,
+        array: false,
+        entries: vec![],
+    }];
+    for (i, line) in text.lines().enumerate() {
+        let mut in_string = false;
+        let end = line
+            .find(|c| {
+                in_string ^= c == '"';
+                c == '#' && !in_string
+            })
+            .unwrap_or(line.len());
+        let line = line[..end].trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = || format!("Line {}: invalid entry `{line}`", i + 1);
+        let header = line
+            .strip_prefix("[[")
+            .and_then(|l| l.strip_suffix("]]"))
+            .or_else(|| line.strip_prefix('[').and_then(|l| l.strip_suffix(']')));
+        if let Some(name) = header {
+            tables.push(Table {
+                name: name.trim().to_string(),
+                line: i + 1,
+                array: line.starts_with("[["),
+                entries: vec![],
+            });
+            continue;
+        }
+        let (key, v) = line.split_once('=').ok_or_else(error)?;
+        let v = value(v.trim()).ok_or_else(error)?;
+        if let Some(table) = tables.last_mut() {
+            table.entries.push((key.trim().to_string(), v));
+        }
+    }
+    Ok(tables)
+}
+
+/// Tables of a file on top of those of its base: the tables of the file replace those of the base, its arrays of
+/// tables are added after those of the base
+fn rebase(base: Vec<Table>, file: Vec<Table>) -> Vec<Table> {
+    let mut file = file.into_iter();
+    let mut tables: Vec<Table> = file.next().into_iter().collect();
+    let file: Vec<Table> = file.collect();
+    tables.extend(
+        base.into_iter()
+            .skip(1)
+            .filter(|b| b.array || !file.iter().any(|t| t.name == b.name)),
+    );
+    tables.extend(file);
+    tables
+}
+
+/// Lives for the rest of the program, like the strings of the built-in boards. Machines are loaded once at start.
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+impl Table {
+    /// Error about this table
+    fn error(&self, message: &str) -> String {
+        if self.name.is_empty() {
+            message.to_string()
+        } else {
+            format!("Line {}: [{}] {message}", self.line, self.name)
+        }
+    }
+
+    /// Check that there are only known keys
+    fn keys(&self, known: &[&str]) -> Result<(), String> {
+        match self
+            .entries
+            .iter()
+            .find(|(k, _)| !known.contains(&k.as_str()))
+        {
+            Some((key, _)) => Err(self.error(&format!("unknown key {key}"))),
+            None => Ok(()),
+        }
+    }
+
+    fn has(&self, key: &str) -> bool {
+        self.entries.iter().any(|(k, _)| k == key)
+    }
+
+    fn get(&self, key: &str) -> Result<&Value, String> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .ok_or_else(|| self.error(&format!("missing {key}")))
+    }
+
+    fn string(&self, key: &str) -> Result<&'static str, String> {
+        match self.get(key)? {
+            Value::Str(s) => Ok(leak(s)),
+            _ => Err(self.error(&format!("{key} should be a string"))),
+        }
+    }
+
+    /// Integer no larger than max
+    fn int(&self, key: &str, max: usize) -> Result<usize, String> {
+        match self.get(key)? {
+            Value::Int(n) if *n <= max => Ok(*n),
+            _ => Err(self.error(&format!("{key} should be an integer up to {max}"))),
+        }
+    }
+
+    fn bool(&self, key: &str) -> Result<bool, String> {
+        match self.get(key)? {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(self.error(&format!("{key} should be true or false"))),
+        }
+    }
+
+    /// Integers no larger than max
+    fn array<const N: usize>(&self, key: &str, max: usize) -> Result<[usize; N], String> {
+        match self.get(key)? {
+            Value::Array(a) if a.iter().all(|n| *n <= max) => a
+                .clone()
+                .try_into()
+                .map_err(|_| self.error(&format!("{key} should have {N} numbers"))),
+            _ => Err(self.error(&format!("{key} should be numbers up to {max}"))),
+        }
+    }
+
+    /// Inclusive address range within the emulated memory
+    fn range(&self, key: &str) -> Result<RangeInclusive<usize>, String> {
+        let [start, end] = self.array(key, MEMORY_SIZE - 1)?;
+        if start > end {
+            return Err(self.error(&format!("{key} should start before it ends")));
+        }
+        Ok(start..=end)
+    }
+
+    /// Port and bit of an input or output
+    fn port_bit(&self) -> Result<(usize, u8), String> {
+        Ok((self.int("port", NPORTS - 1)?, self.int("bit", 7)? as u8))
+    }
+}
+
+/// Samples played while an output bit is set
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sound {
+    pub port: usize,
+    pub bit: u8,
+    /// Name of the sample, played from `<sample>.wav` in the assets
+    pub sample: &'static str,
+    /// Synthesized when the sample is missing
+    pub synth: Option<Warble>,
+}
+
+/// Color of an overlay
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Layer {
+    Top,
+    Bottom,
+}
+
+/// Colored cellophane on the monitor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Overlay {
+    /// Area as (x, y, width, height) in display pixels
+    pub rect: (i32, i32, u32, u32),
+    pub layer: Layer,
+}
+
+/// A board described by a machine file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Machine {
+    /// Name and ports
+    pub board: Board,
+    pub rom: RangeInclusive<usize>,
+    pub ram: RangeInclusive<usize>,
+    pub framebuffer: RangeInclusive<usize>,
+    /// Interrupts requested each frame as (RST number, scan line), in order
+    pub interrupts: Vec<(u8, u32)>,
+    pub sounds: Vec<Sound>,
+    pub overlays: Vec<Overlay>,
+    /// Bank switching, the banks follow the rom in the rom file
+    pub banks: Option<Banks>,
+    /// Color RAM, on color boards
+    pub colors: Option<ColorRam>,
+    /// Timer, for homebrew programs
+    pub timer: Option<Timer>,
+    /// Serial console, for homebrew programs
+    pub serial: Option<Serial>,
+}
+
+impl Machine {
+    /// The embedded Space Invaders board
+    pub fn invaders() -> Self {
+        Machine::parse(INVADERS_MACHINE).expect("Invalid embedded machine")
+    }
+
+    /// Split a rom file into the program and the banks following it
+    pub fn split_rom<'a>(&self, data: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        match &self.banks {
+            Some(_) => data.split_at((self.rom.end() + 1).min(data.len())),
+            None => (data, &[]),
+        }
+    }
+
+    /// Check the size of a rom file against the ROM region, and the banks if any. A shorter rom is padded with zeros,
+    /// an empty or longer one is rejected.
+    pub fn check_rom(&self, data: &[u8]) -> Result<(), String> {
+        let size = self.rom.end() + 1;
+        if data.is_empty() {
+            return Err("The rom is empty".to_string());
+        }
+        let capacity = match &self.banks {
+            Some(banks) => size + banks.count() * banks.size(),
+            None => size,
+        };
+        if data.len() > capacity {
+            let region = match &self.banks {
+                Some(banks) => format!(
+                    "{size} bytes of ROM and {} banks of {} bytes",
+                    banks.count(),
+                    banks.size()
+                ),
+                None => format!("{size} bytes of ROM (0000-{:04X})", self.rom.end()),
+            };
+            return Err(format!(
+                "The rom is {} bytes, more than the {region} of {}",
+                data.len(),
+                self.board.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// The board with a rom file loaded, banks filled from the rest of the file. The file should pass `check_rom`.
+    pub fn load(&self, data: &[u8]) -> Cpu {
+        let (program, banked) = self.split_rom(data);
+        let mut cpu = Cpu::new(program.to_vec());
+        if let Some(banks) = self.banks.clone() {
+            cpu.set_banks(banks, banked);
+        }
+        if let Some(colors) = self.colors.clone() {
+            cpu.set_colors(colors);
+        }
+        if let Some(timer) = self.timer.clone() {
+            cpu.set_timer(timer);
+        }
+        if let Some(serial) = self.serial.clone() {
+            cpu.set_serial(serial);
+        }
+        // The variables of the RAM map are the ones of the Space Invaders program
+        if self.board.name != INVADERS.name {
+            cpu.set_ram_map(&[]);
+        }
+        cpu
+    }
+
+    /// Parse a machine file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tables = tables(text)?;
+        let root = &tables[0];
+        root.keys(&["name", "base"])?;
+        let name = root.string("name")?;
+        if root.has("base") {
+            if root.string("base")? != "invaders" {
+                return Err(root.error("base should be invaders, the embedded machine"));
+            }
+            tables = rebase(self::tables(INVADERS_MACHINE)?, tables);
+        }
+        let memory = tables
+            .iter()
+            .find(|t| t.name == "memory")
+            .ok_or("Missing [memory]")?;
+        memory.keys(&["rom", "ram", "framebuffer"])?;
+        let framebuffer = memory.range("framebuffer")?;
+        if framebuffer.end() - framebuffer.start() + 1
+            < (DISPLAY_WIDTH * DISPLAY_HEIGHT / 8) as usize
+        {
+            return Err(memory.error("framebuffer should hold 1 bit per pixel of the display"));
+        }
+        // Only the memory layout of Space Invaders is emulated, so other layouts are refused rather than ignored
+        for (key, emulated) in [("rom", ROM), ("ram", RAM), ("framebuffer", FRAMEBUFFER)] {
+            if memory.range(key)? != emulated {
+                return Err(memory.error(&format!(
+                    "{key} should be [{:#06X}, {:#06X}], the only memory layout emulated",
+                    emulated.start(),
+                    emulated.end()
+                )));
+            }
+        }
+        let (mut inputs, mut outputs) = (vec![], vec![]);
+        let mut machine = Machine {
+            board: Board {
+                name,
+                inputs: &[],
+                outputs: &[],
+            },
+            rom: memory.range("rom")?,
+            ram: memory.range("ram")?,
+            framebuffer,
+            interrupts: vec![],
+            sounds: vec![],
+            overlays: vec![],
+            banks: None,
+            colors: None,
+            timer: None,
+            serial: None,
+        };
+        for table in &tables[1..] {
+            match table.name.as_str() {
+                "memory" => {}
+                "banks" => {
+                    table.keys(&["window", "port", "count"])?;
+                    let window = table.range("window")?;
+                    let port = table.int("port", NPORTS - 1)?;
+                    let count = table.int("count", 256)?;
+                    if count < 2 {
+                        return Err(table.error("count should be at least 2"));
+                    }
+                    machine.banks = Some(Banks::new(window, port, count));
+                }
+                "colors" => {
+                    table.keys(&["window", "mask"])?;
+                    let [start, end] = table.array("window", 0xFFFF)?;
+                    if start < MEMORY_SIZE || start > end {
+                        return Err(table.error("window should be above the emulated memory"));
+                    }
+                    let mask = table.int("mask", 0xFFFF)?;
+                    machine.colors = Some(ColorRam::new(start..=end, mask));
+                }
+                "timer" => {
+                    table.keys(&["port", "hz"])?;
+                    let port = table.int("port", NPORTS - 1)?;
+                    if port == 3 {
+                        return Err(table.error("port 3 reads the shift register"));
+                    }
+                    let hz = table.int("hz", FREQ as usize)?;
+                    if hz == 0 {
+                        return Err(table.error("hz should be at least 1"));
+                    }
+                    machine.timer = Some(Timer::new(port, hz as u32, FREQ));
+                }
+                "serial" => {
+                    table.keys(&["data", "status"])?;
+                    let data = table.int("data", NPORTS - 1)?;
+                    let status = table.int("status", NPORTS - 1)?;
+                    if data == status {
+                        return Err(table.error("data and status should be different ports"));
+                    }
+                    if data == 3 || status == 3 {
+                        return Err(table.error("port 3 reads the shift register"));
+                    }
+                    machine.serial = Some(Serial::new(data, status));
+                }
+                "interrupt" => {
+                    table.keys(&["rst", "line"])?;
+                    let rst = table.int("rst", 7)? as u8;
+                    let line = table.int("line", LINES as usize - 1)? as u32;
+                    if machine
+                        .interrupts
+                        .last()
+                        .is_some_and(|(_, last)| *last >= line)
+                    {
+                        return Err(
+                            table.error("line should be after the line of the interrupt before")
+                        );
+                    }
+                    machine.interrupts.push((rst, line));
+                }
+                "input" => {
+                    table.keys(&[
+                        "name",
+                        "port",
+                        "bit",
+                        "active_low",
+                        "default",
+                        "description",
+                    ])?;
+                    let (port, bit) = table.port_bit()?;
+                    inputs.push(InputBit {
+                        name: table.string("name")?,
+                        port,
+                        bit,
+                        active_low: table.bool("active_low")?,
+                        default: table.bool("default")?,
+                        description: table.string("description")?,
+                    });
+                }
+                "output" => {
+                    table.keys(&["name", "port", "bit", "description"])?;
+                    let (port, bit) = table.port_bit()?;
+                    outputs.push(OutputBit {
+                        name: table.string("name")?,
+                        port,
+                        bit,
+                        description: table.string("description")?,
+                    });
+                }
+                "sound" => {
+                    table.keys(&["port", "bit", "sample", "vco", "slf"])?;
+                    let (port, bit) = table.port_bit()?;
+                    let sample = table.string("sample")?;
+                    let synth = if table.has("vco") || table.has("slf") {
+                        let [low, high] = table.array("vco", 20000)?;
+                        let slf = table.int("slf", 100000)? as u32;
+                        Some(Warble {
+                            vco: (low as u32, high as u32),
+                            slf,
+                        })
+                    } else {
+                        None
+                    };
+                    machine.sounds.push(Sound {
+                        port,
+                        bit,
+                        sample,
+                        synth,
+                    });
+                }
+                "overlay" => {
+                    table.keys(&["rect", "layer"])?;
+                    let [x, y, w, h] = table.array("rect", DISPLAY_HEIGHT as usize)?;
+                    let layer = match table.string("layer")? {
+                        "top" => Layer::Top,
+                        "bottom" => Layer::Bottom,
+                        _ => return Err(table.error("layer should be top or bottom")),
+                    };
+                    let rect = (x as i32, y as i32, w as u32, h as u32);
+                    machine.overlays.push(Overlay { rect, layer });
+                }
+                _ => return Err(table.error("unknown table")),
+            }
+        }
+        if machine.interrupts.is_empty() {
+            return Err("Missing [[interrupt]]".to_string());
+        }
+        // The color RAM colors the display instead of the overlays, e.g. those of the base
+        if machine.colors.is_some() {
+            machine.overlays.clear();
+        }
+        machine.board.inputs = Box::leak(inputs.into_boxed_slice());
+        machine.board.outputs = Box::leak(outputs.into_boxed_slice());
+        Ok(machine)
+    }
+}
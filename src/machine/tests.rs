@@ -0,0 +1,270 @@
+use super::*;
+use crate::{ports::INVADERS, stack::StackWarning, timing::INTERRUPT_LINES, FRAMEBUFFER, RAM, ROM};
+
+#[test]
+fn embedded_invaders() {
+    let machine = Machine::invaders();
+    assert_eq!(machine.board, INVADERS);
+    assert_eq!(
+        (machine.rom, machine.ram, machine.framebuffer),
+        (ROM, RAM, FRAMEBUFFER)
+    );
+    assert_eq!(machine.interrupts, INTERRUPT_LINES);
+    let samples: Vec<_> = machine.sounds.iter().map(|s| s.sample).collect();
+    assert_eq!(
+        samples,
+        ["ufo", "shot", "die", "hit", "xp", "fleet1", "fleet2", "fleet1", "fleet2", "ufo_hit"]
+    );
+    assert_eq!(
+        machine.sounds[0].synth,
+        Some(Warble {
+            vco: (360, 1180),
+            slf: 650
+        })
+    );
+    assert!(machine.sounds[1..].iter().all(|s| s.synth.is_none()));
+    assert_eq!(
+        machine.overlays[0],
+        Overlay {
+            rect: (0, 32, 224, 32),
+            layer: Layer::Top
+        }
+    );
+    assert_eq!(machine.overlays.len(), 3);
+}
+
+const MINIMAL: &str = r##"name = "tiny" # a "board"
+[memory]
+rom = [0, 0x1FFF]
+ram = [0x2000, 0x41FF]
+framebuffer = [0x2400, 0x41FF]
+[[interrupt]]
+rst = 2
+line = 224
+[[input]]
+name = "fire"
+port = 1
+bit = 4
+active_low = true
+default = false
+description = "Fire # not a comment"
+"##;
+
+#[test]
+fn minimal() {
+    let machine = Machine::parse(MINIMAL).unwrap();
+    assert_eq!(machine.board.name, "tiny");
+    assert_eq!(machine.interrupts, vec![(2, 224)]);
+    let fire = machine.board.find("fire").unwrap();
+    assert_eq!((fire.port, fire.bit, fire.active_low), (1, 4, true));
+    assert_eq!(fire.description, "Fire # not a comment");
+    assert!(machine.sounds.is_empty() && machine.overlays.is_empty());
+}
+
+#[test]
+fn errors() {
+    let error = |from: &str, to: &str| Machine::parse(&MINIMAL.replace(from, to)).unwrap_err();
+    assert_eq!(
+        error("bit = 4", "bit = 8"),
+        "Line 9: [input] bit should be an integer up to 7"
+    );
+    assert_eq!(
+        error("bit = 4", "bits = 4"),
+        "Line 9: [input] unknown key bits"
+    );
+    assert_eq!(error("rst = 2\n", ""), "Line 6: [interrupt] missing rst");
+    assert_eq!(
+        error("[[input]]", "[[inputs]]"),
+        "Line 9: [inputs] unknown table"
+    );
+    assert_eq!(
+        error("line = 224", "line = 224 224"),
+        "Line 8: invalid entry `line = 224 224`"
+    );
+    assert_eq!(
+        error(
+            "framebuffer = [0x2400, 0x41FF]",
+            "framebuffer = [0x2400, 0x3FFE]"
+        ),
+        "Line 2: [memory] framebuffer should hold 1 bit per pixel of the display"
+    );
+    assert_eq!(
+        error("[0, 0x1FFF]", "[0x1FFF, 0]"),
+        "Line 2: [memory] rom should start before it ends"
+    );
+    assert_eq!(
+        error("rom = [0, 0x1FFF]", "rom = [0, 0x2FFF]"),
+        "Line 2: [memory] rom should be [0x0000, 0x1FFF], the only memory layout emulated"
+    );
+    assert_eq!(
+        error("ram = [0x2000, 0x41FF]", "ram = [0x2000, 0x3FFF]"),
+        "Line 2: [memory] ram should be [0x2000, 0x41FF], the only memory layout emulated"
+    );
+    let twice = MINIMAL.replace("[[input]]", "[[interrupt]]\nrst = 1\nline = 96\n[[input]]");
+    assert_eq!(
+        Machine::parse(&twice).unwrap_err(),
+        "Line 9: [interrupt] line should be after the line of the interrupt before"
+    );
+    assert_eq!(error("name = \"tiny\"", ""), "missing name");
+}
+
+#[test]
+fn banks() {
+    let banked = format!("{MINIMAL}[banks]\nwindow = [0x1000, 0x1FFF]\nport = 7\ncount = 4\n");
+    let banks = Machine::parse(&banked).unwrap().banks.unwrap();
+    assert_eq!(
+        (banks.window.clone(), banks.port, banks.count()),
+        (0x1000..=0x1FFF, 7, 4)
+    );
+    assert!(Machine::invaders().banks.is_none());
+    assert_eq!(
+        Machine::parse(&banked.replace("count = 4", "count = 1")).unwrap_err(),
+        "Line 16: [banks] count should be at least 2"
+    );
+}
+
+#[test]
+fn rom_sizes() {
+    let invaders = Machine::invaders();
+    assert_eq!(invaders.check_rom(&[]).unwrap_err(), "The rom is empty");
+    // Truncated roms are padded
+    assert!(invaders.check_rom(&[0xC3]).is_ok());
+    assert!(invaders.check_rom(&[0; 0x2000]).is_ok());
+    assert_eq!(
+        invaders.check_rom(&[0; 0x2001]).unwrap_err(),
+        "The rom is 8193 bytes, more than the 8192 bytes of ROM (0000-1FFF) of invaders"
+    );
+    // Even past the memory the machine loads
+    assert_eq!(invaders.load(&[0xFF; 0x5000]).memory()[0x3FFF], 0xFF);
+
+    let banked = format!("{MINIMAL}[banks]\nwindow = [0x1000, 0x1FFF]\nport = 7\ncount = 2\n");
+    let machine = Machine::parse(&banked).unwrap();
+    assert!(machine.check_rom(&[0; 0x4000]).is_ok());
+    assert_eq!(
+        machine.check_rom(&[0; 0x4001]).unwrap_err(),
+        "The rom is 16385 bytes, more than the 8192 bytes of ROM and 2 banks of 4096 bytes of tiny"
+    );
+}
+
+#[test]
+fn load_rom_with_banks() {
+    let banked = format!("{MINIMAL}[banks]\nwindow = [0x1000, 0x1FFF]\nport = 7\ncount = 2\n");
+    let machine = Machine::parse(&banked).unwrap();
+    let mut rom = vec![1; 0x2000];
+    rom.extend(vec![2; 0x1000]);
+    assert_eq!(machine.split_rom(&rom), (&rom[..0x2000], &rom[0x2000..]));
+    let cpu = machine.load(&rom);
+    assert_eq!((cpu.memory()[0x0FFF], cpu.memory()[0x1000]), (1, 2));
+    assert_eq!(Machine::invaders().split_rom(&rom).1, &[] as &[u8]);
+}
+
+#[test]
+fn synthesized_sound() {
+    let sound = "[[sound]]\nport = 3\nbit = 0\nsample = \"ufo\"\nvco = [100, 200]\nslf = 50\n";
+    let machine = Machine::parse(&format!("{MINIMAL}{sound}")).unwrap();
+    let warble = Warble {
+        vco: (100, 200),
+        slf: 50,
+    };
+    assert_eq!(machine.sounds[0].synth, Some(warble));
+    let error =
+        Machine::parse(&format!("{MINIMAL}{}", sound.replace("slf = 50\n", ""))).unwrap_err();
+    assert_eq!(error, "Line 16: [sound] missing slf");
+}
+
+#[test]
+fn color_ram() {
+    let colored = format!("{MINIMAL}[colors]\nwindow = [0xC000, 0xDFFF]\nmask = 0x1F1F\n");
+    let colors = Machine::parse(&colored).unwrap().colors.unwrap();
+    assert_eq!(
+        (colors.window.clone(), colors.mask),
+        (0xC000..=0xDFFF, 0x1F1F)
+    );
+    assert!(Machine::invaders().colors.is_none());
+    assert_eq!(
+        Machine::parse(&colored.replace("0xC000", "0x2000")).unwrap_err(),
+        "Line 16: [colors] window should be above the emulated memory"
+    );
+    let cv = Machine::parse(include_str!("../../machines/invaders-cv.toml")).unwrap();
+    assert!(cv.colors.is_some() && cv.overlays.is_empty());
+    assert!(cv.load(&[]).colors().is_some());
+}
+
+#[test]
+fn based_on_invaders() {
+    let cv = Machine::parse(include_str!("../../machines/invaders-cv.toml")).unwrap();
+    let invaders = Machine::invaders();
+    assert_eq!(cv.board.name, "invaders-cv");
+    assert_eq!(cv.board.inputs, invaders.board.inputs);
+    assert_eq!(cv.board.outputs, invaders.board.outputs);
+    assert_eq!(
+        (cv.interrupts, cv.sounds, cv.rom),
+        (invaders.interrupts, invaders.sounds, invaders.rom)
+    );
+    let moved = "name = \"moved\"\nbase = \"invaders\"\n[memory]\nrom = [0, 0x1000]\n";
+    assert_eq!(
+        Machine::parse(moved).unwrap_err(),
+        "Line 3: [memory] missing framebuffer"
+    );
+    let timed = "name = \"timed\"\nbase = \"invaders\"\n[[interrupt]]\nrst = 3\nline = 250\n";
+    assert_eq!(
+        Machine::parse(timed).unwrap().interrupts.last(),
+        Some(&(3, 250))
+    );
+    assert_eq!(
+        Machine::parse("name = \"x\"\nbase = \"galaxian\"").unwrap_err(),
+        "base should be invaders, the embedded machine"
+    );
+}
+
+#[test]
+fn timer() {
+    let timed = format!("{MINIMAL}[timer]\nport = 7\nhz = 1000\n");
+    let machine = Machine::parse(&timed).unwrap();
+    let timer = machine.timer.as_ref().unwrap();
+    assert_eq!((timer.port, timer.period), (7, FREQ as u64 / 1000));
+    assert!(machine.load(&[]).timer().is_some());
+    assert!(Machine::invaders().timer.is_none());
+    assert_eq!(
+        Machine::parse(&timed.replace("port = 7", "port = 3")).unwrap_err(),
+        "Line 16: [timer] port 3 reads the shift register"
+    );
+    assert_eq!(
+        Machine::parse(&timed.replace("hz = 1000", "hz = 0")).unwrap_err(),
+        "Line 16: [timer] hz should be at least 1"
+    );
+}
+
+#[test]
+fn serial() {
+    let console = format!("{MINIMAL}[serial]\ndata = 6\nstatus = 7\n");
+    let machine = Machine::parse(&console).unwrap();
+    assert_eq!(machine.serial, Some(Serial::new(6, 7)));
+    assert!(machine.load(&[]).serial_mut().is_some());
+    assert!(Machine::invaders().serial.is_none());
+    assert_eq!(
+        Machine::parse(&console.replace("data = 6", "data = 7")).unwrap_err(),
+        "Line 16: [serial] data and status should be different ports"
+    );
+    assert_eq!(
+        Machine::parse(&console.replace("status = 7", "status = 3")).unwrap_err(),
+        "Line 16: [serial] port 3 reads the shift register"
+    );
+}
+
+#[test]
+fn ram_map_of_invaders_only() {
+    // LXI SP,2300h; PUSH B, over p2ShipsRem of Space Invaders
+    let rom = [0x31, 0x00, 0x23, 0xC5];
+    let pushes = |machine: Machine| {
+        let mut cpu = machine.load(&rom);
+        cpu.step();
+        cpu.step();
+        cpu.take_stack_warnings()
+            .iter()
+            .filter(|w| matches!(w, StackWarning::Clobber { .. }))
+            .count()
+    };
+    assert_eq!(pushes(Machine::invaders()), 1);
+    assert_eq!(pushes(Machine::parse(MINIMAL).unwrap()), 0);
+}
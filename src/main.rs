@@ -1,20 +1,974 @@
 use inv8080rs::{
-    cpu::Cpu,
-    emu::{Emu, Options},
+    assets::{self, Locator},
+    batch, bench,
+    bindings::Bindings,
+    convert::Conversion,
+    corpus::Corpus,
+    cpu::{Cpu, RamInit},
+    determinism,
+    diagnose::{self, CHIPS},
+    diff, disasm,
+    effects::Effects,
+    emu::{Bezel, FocusLoss, Frontend, Options, ScreenshotFormat},
+    expr::{Condition, Expr, Trigger, Watch},
+    fastboot::{self, Boot},
+    frame::{Blend, Glow},
+    frontend, hash, import, info,
+    inset::Corner,
+    ips,
+    machine::Machine,
+    palette::Palette,
+    png,
+    present::Present,
+    profile::Profile,
+    scaling::Scaling,
+    serial::{Console, Serial},
+    shutdown, soak,
+    sprites::SPRITES,
+    tournament::{self, Tournament},
+    user::User,
+    utils::parse_number,
+    FPS, MEMORY_SIZE, NPORTS, ROM,
 };
+use std::io::Write;
 
 fn main() {
-    let program = std::fs::read("assets/invaders.rom").expect("could not read file");
-    let mut emu = Emu::new(
-        Cpu::new(program),
-        Options {
-            scale: 3, // scale width and height by
-            color: 0xffffffff,
-            background: 0xff000000,
-            top: 0xffff0000,
-            bottom: 0xff00ff00,
+    match std::env::args().nth(1).as_deref() {
+        Some("asmrun") => return asmrun(std::env::args().skip(2)),
+        Some("sprites") => return sprites(std::env::args().skip(2)),
+        Some("batch") => return batch(std::env::args().skip(2)),
+        Some("corpus") => return corpus(std::env::args().skip(2)),
+        Some("diff") => return diff(std::env::args().skip(2)),
+        Some("disasm") => return disasm(std::env::args().skip(2)),
+        _ => {}
+    }
+
+    // Input names are looked up on the board, so the machine is loaded before the other options
+    let machine_file = std::env::args().skip_while(|a| a != "--machine").nth(1);
+    let machine = match &machine_file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Machine::parse(&text))
+            .unwrap_or_else(|e| {
+                eprintln!("{path}: {e}");
+                usage("--machine")
+            }),
+        None => Machine::invaders(),
+    };
+    let board = machine.board;
+
+    let mut options = Options {
+        machine,
+        ..Default::default()
+    };
+
+    let mut ram_init = RamInit::Zeros;
+    let mut inputs = vec![];
+    let mut soak_hours: Option<f64> = None;
+    let mut verify_frames: Option<u64> = None;
+    let mut verify_threads = false;
+    let mut fast_boot = false;
+    let mut tournament: Option<String> = None;
+    let mut tournament_games = tournament::GAMES;
+    let mut bench_core = false;
+    let mut diagnose = false;
+    let mut import_state = None;
+    let mut rom = None;
+    let mut asset_dirs = vec![];
+    let mut bezel_opacity = None;
+    let mut bezel_playfield = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&arg));
+        match arg.as_str() {
+            "--bezel" => {
+                options.bezel = Some(Bezel {
+                    path: value(),
+                    opacity: 0xff,
+                    playfield: (0, 0, 224, 256),
+                })
+            }
+            "--bezel-opacity" => {
+                bezel_opacity = Some(value().parse().unwrap_or_else(|_| usage(&arg)))
+            }
+            "--bezel-playfield" => {
+                let v: Vec<u32> = value()
+                    .split(',')
+                    .map(|n| n.parse().unwrap_or_else(|_| usage(&arg)))
+                    .collect();
+                if v.len() != 4 || v[2] == 0 || v[3] == 0 {
+                    usage(&arg);
+                }
+                bezel_playfield = Some((v[0] as i32, v[1] as i32, v[2], v[3]));
+            }
+            "--display" => options.display = Some(value().parse().unwrap_or_else(|_| usage(&arg))),
+            "--fullscreen" | "-f" => options.fullscreen = true,
+            // Nowhere to report a failure, the run goes on printing as usual
+            "--quiet" => {
+                let _ = frontend::silence();
+            }
+            "--present" => options
+                .present
+                .push(Present::parse(&value()).unwrap_or_else(|| usage(&arg))),
+            "--exclusive" => {
+                options.fullscreen = true;
+                options.exclusive = true;
+            }
+            "--fullscreen-display" => {
+                options.display = Some(value().parse().unwrap_or_else(|_| usage(&arg)));
+                options.fullscreen = true;
+            }
+            "--blend" => {
+                options.blend = match value().as_str() {
+                    "off" => Blend::Off,
+                    "or" => Blend::Or,
+                    "average" => Blend::Average,
+                    _ => usage(&arg),
+                }
+            }
+            "--palette" => {
+                options.palette = Palette::parse(&value()).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage(&arg)
+                })
+            }
+            "--glow" => {
+                let v: Vec<u32> = value()
+                    .split(',')
+                    .map(|n| n.parse().unwrap_or_else(|_| usage(&arg)))
+                    .collect();
+                if v.len() != 2 || v[0] > Glow::MAX_RADIUS || v[1] > 0xff {
+                    usage(&arg);
+                }
+                options.glow = Some(Glow::new(v[0], v[1] as u8));
+            }
+            "--ram-init" => {
+                let v = value();
+                ram_init = match v.split_once(':') {
+                    None if v == "zeros" => RamInit::Zeros,
+                    None if v == "ff" => RamInit::Ones,
+                    None if v == "aa55" => RamInit::Alternating,
+                    Some(("random", seed)) => {
+                        RamInit::Random(seed.parse().unwrap_or_else(|_| usage(&arg)))
+                    }
+                    _ => usage(&arg),
+                }
+            }
+            "--input" => {
+                let v = value();
+                let input = match v.split_once('=') {
+                    Some((name, "on")) => board.find(name).map(|i| (i, true)),
+                    Some((name, "off")) => board.find(name).map(|i| (i, false)),
+                    _ => None,
+                };
+                inputs.push(input.unwrap_or_else(|| usage(&arg)));
+            }
+            "--dev" => {
+                if value() != "watch" {
+                    usage(&arg);
+                }
+                let path = value();
+                rom = Some(path.clone());
+                options.watch_rom = Some(path);
+            }
+            "--watch" => {
+                let expr = Expr::parse(&value()).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage(&arg)
+                });
+                options.watches.push(Watch::new(expr));
+            }
+            "--inset" => options.inset = Some(value()),
+            "--inset-corner" => {
+                options.inset_corner = Corner::parse(&value()).unwrap_or_else(|| usage(&arg))
+            }
+            "--screenshot-format" => {
+                options.screenshot_format = match value().as_str() {
+                    "png" => ScreenshotFormat::Png,
+                    "pbm" => ScreenshotFormat::Pbm,
+                    "xbm" => ScreenshotFormat::Xbm,
+                    _ => usage(&arg),
+                }
+            }
+            "--screenshot-when" => {
+                let condition = Condition::parse(&value()).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage(&arg)
+                });
+                options.screenshot_triggers.push(Trigger::new(condition));
+            }
+            "--user" => {
+                let dir = assets::config_dir(std::env::consts::OS, |v| std::env::var(v).ok())
+                    .unwrap_or_else(|| {
+                        eprintln!("No config directory for user profiles");
+                        usage(&arg)
+                    });
+                let user = User::load(&dir, &value()).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage(&arg)
+                });
+                if let Some(theme) = user.profile.theme {
+                    options.palette = theme;
+                }
+                for (name, on) in &user.profile.inputs {
+                    inputs.push(
+                        board
+                            .find(name)
+                            .map(|i| (i, *on))
+                            .unwrap_or_else(|| usage(name)),
+                    );
+                }
+                options.user = Some(user);
+            }
+            "--machine" => {
+                value();
+            }
+            "--dump-info" => {
+                print!("{}", info::to_json(&options.machine, &Bindings::default()));
+                return;
+            }
+            "--mirror-p2" => options.mirror_p2 = true,
+            "--free-play" => options.free_play = true,
+            "--tournament" => tournament = Some(value()),
+            "--tournament-games" => {
+                tournament_games = value()
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .unwrap_or_else(|| usage(&arg))
+            }
+            "--remote" => options.remote = Some(value()),
+            "--remote-token" => options.remote_token = Some(value()),
+            "--break-when" => {
+                let condition = Condition::parse(&value()).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage(&arg)
+                });
+                options.break_triggers.push(Trigger::new(condition));
+            }
+            "--bot" => options.bot = Some(value().parse().unwrap_or_else(|_| usage(&arg))),
+            "--bench-core" => bench_core = true,
+            "--diagnose" => diagnose = true,
+            "--import-state" => import_state = Some(value()),
+            "--reference" => {
+                let path = value();
+                let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+                    eprintln!("Could not read {path}: {e}");
+                    usage(&arg)
+                });
+                let corpus = Corpus::parse(&text).unwrap_or_else(|e| {
+                    eprintln!("{path}: {e}");
+                    usage(&arg)
+                });
+                options.reference = Some(corpus);
+            }
+            "--soak" => soak_hours = Some(value().parse().unwrap_or_else(|_| usage(&arg))),
+            "--verify-determinism" => {
+                verify_frames = Some(value().parse().unwrap_or_else(|_| usage(&arg)))
+            }
+            "--verify-threads" => verify_threads = true,
+            "--fast-boot" => fast_boot = true,
+            "--cycle-bar" => options.cycle_bar = true,
+            "--free-run" => options.free_run = true,
+            "--latency-test" => options.latency_test = true,
+            "--watchdog" => options.watchdog = true,
+            "--rewind" => options.rewind = true,
+            "--masked-frames" => {
+                let frames: u32 = value().parse().unwrap_or_else(|_| usage(&arg));
+                options.masked_frames = (frames > 0).then_some(frames);
+            }
+            "--scaling" => {
+                options.scaling = Scaling::parse(&value()).unwrap_or_else(|| usage(&arg))
+            }
+            "--conversion" => {
+                options.conversion = Conversion::parse(&value()).unwrap_or_else(|| usage(&arg))
+            }
+            "--bench-conversion" => options.bench_conversion = true,
+            "--speed" => {
+                options.speed = value().parse().unwrap_or_else(|_| usage(&arg));
+                if !(1..=1000).contains(&options.speed) {
+                    usage(&arg);
+                }
+            }
+            "--cycle-steal" => {
+                options.cycle_steal = value().parse().unwrap_or_else(|_| usage(&arg));
+                if options.cycle_steal > 100 {
+                    usage(&arg);
+                }
+            }
+            "--rumble" => {
+                let v: Vec<u32> = value()
+                    .split(',')
+                    .map(|n| n.parse().unwrap_or_else(|_| usage(&arg)))
+                    .collect();
+                if v.len() != 2 || v[0] > 0xffff {
+                    usage(&arg);
+                }
+                options.rumble = Some((v[0] as u16, v[1]));
+            }
+            "--effects" => {
+                let path = value();
+                let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+                    eprintln!("Could not read {path}: {e}");
+                    usage(&arg)
+                });
+                let sounds: Vec<&str> = options.machine.sounds.iter().map(|s| s.sample).collect();
+                options.effects = Effects::parse(&text, &sounds).unwrap_or_else(|e| {
+                    eprintln!("{path}: {e}");
+                    usage(&arg)
+                });
+            }
+            "--assets" => asset_dirs.push(value().into()),
+            "--ambience" => options.ambience = Some(value()),
+            "--record-audio" => options.record_audio = Some(value()),
+            "--stats-json" => options.stats_json = Some(value()),
+            "--pacing-histogram" => options.pacing_histogram = true,
+            "--pacing-png" => options.pacing_png = Some(value()),
+            "--profile" => options.profile = Some(value()),
+            "--interrupt-log" => options.interrupt_log = Some(value()),
+            "--stack-report" => options.stack_report = true,
+            "--mute" => options.mute = true,
+            "--focus-loss" => {
+                options.focus_loss = match value().as_str() {
+                    "run" => FocusLoss::Run,
+                    "pause" => FocusLoss::Pause,
+                    "throttle" => FocusLoss::Throttle,
+                    _ => usage(&arg),
+                }
+            }
+            "--av-sync" => options.av_sync = true,
+            "--run-ahead" => options.run_ahead = true,
+            "--announce" => options.announce = Some(|line| println!("{line}")),
+            "--teach" => options.teach = Some(value().parse().unwrap_or_else(|_| usage(&arg))),
+            _ if frontend::positional(&arg) && rom.is_none() => rom = Some(arg.clone()),
+            _ => usage(&arg),
+        }
+    }
+
+    // The profile keeps the settings in effect, including options given after --user
+    if let Some(user) = &mut options.user {
+        user.profile.theme = Some(options.palette);
+        user.profile.inputs = inputs
+            .iter()
+            .map(|(input, on)| (input.name.to_string(), *on))
+            .collect();
+    }
+    // The bezel options apply whether given before or after the bezel
+    match &mut options.bezel {
+        Some(bezel) => {
+            bezel.opacity = bezel_opacity.unwrap_or(bezel.opacity);
+            bezel.playfield = bezel_playfield.unwrap_or(bezel.playfield);
+        }
+        None if bezel_opacity.is_some() || bezel_playfield.is_some() => {
+            eprintln!("--bezel-opacity and --bezel-playfield need --bezel");
+            usage("--bezel");
+        }
+        None => {}
+    }
+    if options.remote.is_some() && options.remote_token.is_none() {
+        eprintln!("--remote needs --remote-token");
+        usage("--remote");
+    }
+    if let Some(display) = options.display {
+        let count = Frontend::display_count().unwrap_or_else(|e| {
+            eprintln!("Could not count the displays: {e}");
+            usage("--display")
+        });
+        if display >= count {
+            eprintln!("No display {display}, there are {count} (counting from 0)");
+            usage("--display");
+        }
+    }
+    options.assets = Locator::new(asset_dirs);
+    if let Some(names) = tournament {
+        let dir = assets::config_dir(std::env::consts::OS, |v| std::env::var(v).ok())
+            .unwrap_or_else(|| {
+                eprintln!("No config directory for user profiles");
+                usage("--tournament")
+            });
+        let players = names
+            .split(',')
+            .map(|name| {
+                User::load(&dir, name).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    usage("--tournament")
+                })
+            })
+            .collect();
+        options.tournament = Some(Tournament::new(players, tournament_games));
+    }
+    let rom = rom.map_or_else(|| options.assets.resolve("invaders.rom"), Into::into);
+    let data = std::fs::read(&rom).unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {e}", rom.display());
+        std::process::exit(1);
+    });
+    if let Err(e) = options.machine.check_rom(&data) {
+        eprintln!("{}: {e}", rom.display());
+        // E.g. a header or chips in the wrong order, the chips are the ones of Space Invaders
+        if !data.is_empty() && machine_file.is_none() {
+            troubleshoot(&data, None);
+        }
+        std::process::exit(1);
+    }
+    options.rom = Some(rom.clone());
+    let program = options.machine.split_rom(&data).0;
+    if let Some(hours) = soak_hours {
+        return soak(program, hours);
+    }
+    if let Some(frames) = verify_frames {
+        return verify_determinism(program, frames, verify_threads);
+    }
+    if bench_core {
+        return print!("{}", bench::run(program, bench::SECONDS).report());
+    }
+    if options
+        .reference
+        .as_ref()
+        .is_some_and(|r| r.rom_crc != hash::crc32(program))
+    {
+        eprintln!("The reference was recorded with another rom");
+        std::process::exit(1);
+    }
+    if diagnose {
+        let misbehavior = diagnose::misbehaves(program, diagnose::EARLY_FRAMES);
+        if misbehavior.is_none() && diagnose::identify(program, &CHIPS) {
+            return println!("The rom is recognized and runs");
+        }
+        troubleshoot(program, misbehavior);
+        std::process::exit(1);
+    }
+    let mut cpu = options.machine.load(&data);
+    cpu.set_ram_init(ram_init);
+    if fast_boot {
+        let booted = match assets::config_dir(std::env::consts::OS, |v| std::env::var(v).ok()) {
+            Some(dir) => {
+                let (booted, boot) =
+                    fastboot::load_or_create(&options.machine, &data, ram_init, &dir);
+                cpu = booted;
+                boot
+            }
+            None => Err("No config directory for the boot state".to_string()),
+        };
+        match booted {
+            Ok(Boot::Cached(path)) => println!("Fast boot from {}", path.display()),
+            Ok(Boot::Created(path, frames)) => {
+                println!("Booted in {frames} frames, saved {}", path.display())
+            }
+            Err(e) => eprintln!("Could not fast boot: {e}"),
+        }
+    }
+    for (input, active) in inputs {
+        input.set(&mut cpu, active);
+    }
+    if options.profile.is_some() {
+        cpu.enable_profile();
+    }
+    if options.stack_report {
+        cpu.enable_stack_usage();
+    }
+    if let Some(path) = import_state {
+        let data = std::fs::read(&path).unwrap_or_else(|e| {
+            eprintln!("Could not read {path}: {e}");
+            std::process::exit(1);
+        });
+        for _ in 0..import::BOOT_FRAMES {
+            batch::run_frame(&mut cpu);
+        }
+        match import::import(&mut cpu, &data, &options.machine.ram) {
+            Ok(format) => println!("Imported {path} ({format:?})"),
+            Err(e) => eprintln!("Could not import {path}: {e}"),
+        }
+    }
+    shutdown::install();
+    let mut emu = Frontend::new(cpu, options);
+
+    emu.run();
+}
+
+/// Print a troubleshooting report for a rom that is not recognized or misbehaves early
+fn troubleshoot(program: &[u8], misbehavior: Option<(u64, soak::Violation)>) {
+    let report = diagnose::report(program, &CHIPS, misbehavior.as_ref());
+    eprintln!("The rom is not recognized or misbehaves, it may not run correctly");
+    eprint!("{report}");
+}
+
+/// Run attract mode headless at full speed for hours of machine time, logging invariant violations with a dump of the memory
+fn soak(program: &[u8], hours: f64) {
+    let frames = (hours * 3600.0 * FPS as f64) as u64;
+    let violations = soak::run(
+        program,
+        frames,
+        soak::FROZEN_FRAMES,
+        |frame, violation, cpu| {
+            let path = format!("soak-frame{frame}.bin");
+            println!("Frame {frame}: {violation}");
+            println!("{}", cpu.format_registers());
+            print!("{}", cpu.format_call_stack());
+            match std::fs::write(&path, cpu.memory()) {
+                Ok(_) => println!("Saved {path}"),
+                Err(e) => eprintln!("Could not save {path}: {e}"),
+            }
         },
     );
+    println!("Frames={frames} Violations={violations}");
+    if violations > 0 {
+        std::process::exit(1);
+    }
+}
 
-    emu.run();
+/// Replay a seeded game twice and compare the machine state every frame, exits with 1 at the first mismatch
+fn verify_determinism(program: &[u8], frames: u64, threads: bool) {
+    match determinism::verify(program, frames, threads) {
+        Some(mismatch) => {
+            print!("{mismatch}");
+            std::process::exit(1);
+        }
+        None => println!("Frames={frames} Deterministic"),
+    }
+}
+
+/// Print usage and exit
+fn usage(arg: &str) -> ! {
+    eprintln!("Invalid argument: {arg}");
+    eprintln!("Usage: inv8080rs [options] [rom]");
+    eprintln!("       inv8080rs asmrun <program.bin> [options]");
+    eprintln!("       inv8080rs sprites <export|import> ...");
+    eprintln!("       inv8080rs batch <runs> [frames] [first-seed]");
+    eprintln!("       inv8080rs corpus <record|replay> ...");
+    eprintln!("       inv8080rs diff <expected> <actual> <diff.png>");
+    eprintln!("  --assets <dir>                  Look for the rom and sounds in dir first (may be repeated)");
+    eprintln!("  --bezel <file.bmp>              Cabinet artwork drawn around the playfield");
+    eprintln!("  --bezel-opacity <0-255>         Opacity of the artwork");
+    eprintln!("  --bezel-playfield <x,y,w,h>     Playfield placement within the artwork");
+    eprintln!("  --inset <file.bmp>              Picture-in-picture image in a corner of the output, e.g. a logo");
+    eprintln!("  --inset-corner <corner>         top-left, top-right, bottom-left or bottom-right (default)");
+    eprintln!("  --display <n>                   Open the window on display n (0 is the first)");
+    eprintln!("  --fullscreen, -f                Start in fullscreen");
+    eprintln!("  --fullscreen-display <n>        Start in fullscreen on display n");
+    eprintln!("  --exclusive                     Exclusive fullscreen at 60 Hz (or 120 Hz)");
+    eprintln!("  --scaling <nearest|linear|sharp>  Scaling of the picture to the window (default nearest)");
+    eprintln!(
+        "  --present [<n>=]<auto|sleep|vsync|vrr>  Pace frames by sleeping or by the display"
+    );
+    eprintln!("  --blend <off|or|average>        Blend consecutive frames to reduce flicker");
+    eprintln!("  --glow <radius,intensity>       Let lit pixels glow into their neighbors");
+    eprintln!("  --palette <fg,bg,top,bottom>    Colors of the display and overlays, hex or names");
+    eprintln!("  --dev watch <rom>               Run rom and reload it whenever the file changes");
+    eprintln!("  --watch <expr>                  Print expr whenever it changes (may be repeated)");
+    eprintln!(
+        "  --screenshot-when <condition>   Save a screenshot when condition first becomes true"
+    );
+    eprintln!("  --screenshot-format <png|pbm|xbm>  Image format of screenshots (default png)");
+    eprintln!("  --machine <file>                Emulate the board described in a machine file");
+    eprintln!(
+        "  --dump-info                     Print the capabilities of the build as JSON and exit"
+    );
+    eprintln!("  --quiet                         Print nothing to the console, e.g. in a launcher");
+    eprintln!(
+        "  --free-play                     Insert a coin when start is pressed without credits"
+    );
+    eprintln!(
+        "  --tournament <name,name...>     Tournament of one-credit games between the players"
+    );
+    eprintln!(
+        "  --tournament-games <n>          Games each player plays in the tournament (default 3)"
+    );
+    eprintln!("  --user <name>                   Use and save the settings and leaderboard of a user profile");
+    eprintln!("  --remote <addr>                 Receive input from a control panel over UDP, e.g. 0.0.0.0:9000");
+    eprintln!("  --remote-token <token>          Token the remote input messages must carry");
+    eprintln!(
+        "  --mirror-p2                     Player 1 keys also control player 2 in alternate play"
+    );
+    eprintln!("  --break-when <condition>        Pause and print the machine state when condition becomes true");
+    eprintln!(
+        "  --teach <n>                     Run n instructions per second and explain each one"
+    );
+    eprintln!("  --bot <seed>                    Let a bot play with random input from seed");
+    eprintln!(
+        "  --cycle-bar                     Show cycles spent in interrupt handlers vs main loop"
+    );
+    eprintln!(
+        "  --announce                      Print new text appearing on screen (for screen readers)"
+    );
+    eprintln!(
+        "  --speed <percent>               Emulation speed, change with F9 and F10 while running"
+    );
+    eprintln!("  --cycle-steal <percent>         Let the video circuitry steal cycles during active display");
+    eprintln!("  --rumble <strength,ms>          Rumble game controllers when the player dies");
+    eprintln!("  --effects <file>                Rumble and LED colors of game controllers on game events");
+    eprintln!(
+        "  --ambience <dir>                Mix in ambient cabinet sounds from dir at low volume"
+    );
+    eprintln!("  --record-audio <file.wav>       Record the game sounds to file.wav");
+    eprintln!(
+        "  --stats-json <file.json>        Write the session summary printed on quit to file.json"
+    );
+    eprintln!("  --pacing-histogram              Print histograms of frame times and sleep errors on quit");
+    eprintln!(
+        "  --pacing-png <file.png>         Draw the histograms of frame pacing to file.png on quit"
+    );
+    eprintln!(
+        "  --profile <file>                Add the rom bytes executed and read as data to file"
+    );
+    eprintln!("  --interrupt-log <file.csv>      Log when interrupts came vs their scan lines");
+    eprintln!(
+        "  --stack-report                  Print the stack usage and deepest call chain on quit"
+    );
+    eprintln!("  --mute                          Do not play sound");
+    eprintln!(
+        "  --focus-loss <run|pause|throttle>  Pause, or mute and slow down, in the background"
+    );
+    eprintln!("  --av-sync                       Keep the video in sync with the audio clock");
+    eprintln!(
+        "  --run-ahead                     Cut a frame of input lag by running a frame ahead"
+    );
+    eprintln!("  --soak <hours>                  Run attract mode headless, checking invariants");
+    eprintln!(
+        "  --verify-determinism <frames>   Replay a game twice headless, comparing every frame"
+    );
+    eprintln!("  --verify-threads                Run the two replays on two threads at once");
+    eprintln!(
+        "  --free-run                      Run unthrottled, reporting the spare cycles per frame"
+    );
+    eprintln!(
+        "  --latency-test                  Flash the playfield and time the responses to it and to key presses"
+    );
+    eprintln!(
+        "  --watchdog                      Dump the state and ask for a reset when no frame completes for 2 seconds"
+    );
+    eprintln!("  --rewind                        Keep the state of every frame, Backspace goes back a second");
+    eprintln!(
+        "  --masked-frames <n>             Tell when no interrupt is delivered for n frames (default 180, 0 is off)"
+    );
+    eprintln!(
+        "  --fast-boot                     Start from the state after the self-test of the rom, cached per rom"
+    );
+    eprintln!("  --bench-core                    Benchmark the emulation and rendering headless");
+    eprintln!("  --diagnose                      Check the rom against Space Invaders and run it briefly, then exit");
+    eprintln!(
+        "  --conversion <points|buffer|lut>  Conversion of frames to pixels (default points)"
+    );
+    eprintln!(
+        "  --bench-conversion              Benchmark the conversions in the window, then quit"
+    );
+    eprintln!("  --import-state <file>           Import a memory dump from another emulator");
+    eprintln!(
+        "  --reference <file>              Pause where the attract mode diverges from a corpus"
+    );
+    eprintln!("  --ram-init <zeros|ff|aa55|random:seed>  Initial content of RAM at power on");
+    eprintln!(
+        "  --input <name=on|off>           Set an input, e.g. a DIP switch (may be repeated)"
+    );
+    std::process::exit(2);
+}
+
+/// Load a program at an origin, run it headless for a number of cycles (or until it halts) and print the state
+fn asmrun(mut args: impl Iterator<Item = String>) {
+    let usage = |arg: &str| -> ! {
+        eprintln!("Invalid argument: {arg}");
+        eprintln!("Usage: inv8080rs asmrun <program.bin> [options]");
+        eprintln!(
+            "  --origin <addr>                 Load and start the program at addr in rom (default 0)"
+        );
+        eprintln!("  --cycles <n>                    Run at most n cycles (default 1000000)");
+        eprintln!("  --dump <start-end>              Print memory range (may be repeated)");
+        eprintln!("  --stack                         Print the stack usage and deepest call chain");
+        eprintln!(
+            "  --serial <data>,<status>        Serial console on the ports, printing and reading the terminal"
+        );
+        std::process::exit(2);
+    };
+
+    let file = args.next().unwrap_or_else(|| usage("<program.bin>"));
+    if file.ends_with(".asm") {
+        eprintln!("Assembling is not supported, assemble {file} to a binary first");
+        std::process::exit(2);
+    }
+    let mut origin = 0;
+    let mut cycles = 1_000_000;
+    let mut dumps = vec![];
+    let mut stack = false;
+    let mut serial = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&arg));
+        match arg.as_str() {
+            "--origin" => {
+                origin = parse_number(&value())
+                    .filter(|o| ROM.contains(o))
+                    .unwrap_or_else(|| usage(&arg))
+            }
+            "--cycles" => cycles = parse_number(&value()).unwrap_or_else(|| usage(&arg)),
+            "--dump" => {
+                let v = value();
+                let range = v
+                    .split_once('-')
+                    .and_then(|(a, b)| Some(parse_number(a)?..=parse_number(b)?))
+                    .filter(|r| r.end() < &MEMORY_SIZE)
+                    .unwrap_or_else(|| usage(&arg));
+                dumps.push(range);
+            }
+            "--stack" => stack = true,
+            "--serial" => {
+                let v = value();
+                let ports = v
+                    .split_once(',')
+                    .and_then(|(d, s)| Some((parse_number(d)?, parse_number(s)?)))
+                    .filter(|&(d, s)| d < NPORTS && s < NPORTS && d != s)
+                    .unwrap_or_else(|| usage(&arg));
+                serial = Some(ports);
+            }
+            _ => usage(&arg),
+        }
+    }
+
+    let program = std::fs::read(&file).expect("could not read file");
+    if program.is_empty() {
+        eprintln!("{file} is empty");
+        std::process::exit(1);
+    }
+    if origin + program.len() > ROM.end() + 1 {
+        eprintln!(
+            "{file}: {} bytes at {origin:04X} do not fit the rom, which ends at {:04X}",
+            program.len(),
+            ROM.end()
+        );
+        usage("--origin");
+    }
+    let mut image = vec![0; origin];
+    image.extend(program);
+    let mut cpu = Cpu::new(image);
+    cpu.jump(origin);
+    if stack {
+        cpu.enable_stack_usage();
+    }
+    let console = serial.map(|(data, status)| {
+        cpu.set_serial(Serial::new(data, status));
+        Console::stdin()
+    });
+
+    let mut taken = 0;
+    while taken < cycles && !cpu.is_halted() {
+        taken += cpu.step() as usize;
+        if let (Some(console), Some(serial)) = (&console, cpu.serial_mut()) {
+            serial.receive(&console.take());
+            let output = serial.take_output();
+            if !output.is_empty() {
+                let mut stdout = std::io::stdout();
+                let _ = stdout.write_all(&output).and_then(|_| stdout.flush());
+            }
+        }
+    }
+
+    println!("{}", cpu.format_registers());
+    println!(
+        "Cycles={taken}{}",
+        if cpu.is_halted() { " (halted)" } else { "" }
+    );
+    for range in dumps {
+        for (i, chunk) in cpu.memory()[range.clone()].chunks(16).enumerate() {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("{b:02X}")).collect();
+            println!("{:04X}: {}", range.start() + i * 16, bytes.join(" "));
+        }
+    }
+    for warning in cpu.take_stack_warnings() {
+        println!("Stack warning: {warning}");
+    }
+    if let Some(usage) = cpu.stack_usage() {
+        print!("{}", usage.report());
+    }
+}
+
+/// Read the game rom from the default asset directories
+fn read_rom() -> Vec<u8> {
+    let path = Locator::default().resolve("invaders.rom");
+    std::fs::read(&path).unwrap_or_else(|e| panic!("could not read {}: {e}", path.display()))
+}
+
+/// Simulate games headless with a random bot in parallel and print statistics
+fn batch(mut args: impl Iterator<Item = String>) {
+    let usage = || -> ! {
+        eprintln!("Usage: inv8080rs batch <runs> [frames] [first-seed]");
+        std::process::exit(2);
+    };
+    let runs: u64 = args
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| usage());
+    let frames = match args.next() {
+        Some(n) => n.parse().unwrap_or_else(|_| usage()),
+        None => 60 * 60 * 10,
+    };
+    let first: u64 = match args.next() {
+        Some(n) => n.parse().unwrap_or_else(|_| usage()),
+        None => 0,
+    };
+    let rom = read_rom();
+
+    let seeds: Vec<u64> = (first..first + runs).collect();
+    let outcomes = batch::run(&rom, &seeds, frames, &batch::random_policy);
+    for o in &outcomes {
+        println!(
+            "Seed={} Frames={} Score={}{}",
+            o.seed,
+            o.frames,
+            o.score,
+            if o.game_over { "" } else { " (frame limit)" }
+        );
+    }
+    if let Some(s) = batch::summarize(&outcomes) {
+        println!(
+            "Runs={} Frames min={} median={} max={} mean={:.1} Score mean={:.1}",
+            s.runs, s.min_frames, s.median_frames, s.max_frames, s.mean_frames, s.mean_score
+        );
+    }
+}
+
+/// Record framebuffer hashes of the attract mode to a corpus file, or replay the rom against one
+fn corpus(mut args: impl Iterator<Item = String>) {
+    let usage = || -> ! {
+        eprintln!("Usage: inv8080rs corpus record <file> [minutes] [interval-frames]");
+        eprintln!("       inv8080rs corpus replay <file>");
+        std::process::exit(2);
+    };
+    let rom = read_rom();
+    let command = args.next();
+    let path = args.next().unwrap_or_else(|| usage());
+    let mut number = |default: u64| match args.next() {
+        Some(n) => n.parse().unwrap_or_else(|_| usage()),
+        None => default,
+    };
+
+    match command.as_deref() {
+        Some("record") => {
+            let frames = number(10) * 60 * FPS as u64;
+            let corpus = Corpus::record(&rom, frames, number(FPS as u64));
+            std::fs::write(&path, corpus.to_string()).expect("could not write file");
+            println!("Saved {} hashes to {path}", corpus.hashes.len());
+        }
+        Some("replay") => {
+            let text = std::fs::read_to_string(&path).expect("could not read file");
+            let corpus = Corpus::parse(&text).unwrap_or_else(|e| panic!("{path}: {e}"));
+            if corpus.rom_crc != hash::crc32(&rom) {
+                eprintln!("The corpus was recorded with another rom");
+                std::process::exit(1);
+            }
+            match corpus.replay(&rom) {
+                None => println!("Replayed {} hashes, no drift", corpus.hashes.len()),
+                Some(mismatch) => {
+                    println!("{mismatch}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
+    }
+}
+
+/// Compare two frames (screenshots or memory/framebuffer dumps) and save a diff image
+fn diff(mut args: impl Iterator<Item = String>) {
+    let (Some(expected), Some(actual), Some(output), None) =
+        (args.next(), args.next(), args.next(), args.next())
+    else {
+        eprintln!("Usage: inv8080rs diff <expected> <actual> <diff.png>");
+        std::process::exit(2);
+    };
+    let load = |path: &str| {
+        let data = std::fs::read(path).expect("could not read file");
+        diff::load(&data).unwrap_or_else(|e| panic!("{path}: {e}"))
+    };
+    let (image, differing) = diff::diff(&load(&expected), &load(&actual));
+    std::fs::write(&output, png::encode(&image)).expect("could not write file");
+    println!("Pixels differing={differing}, saved {output}");
+    if differing > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Write a labeled listing of a rom, telling code from data by following the code and tracing the attract mode
+fn disasm(mut args: impl Iterator<Item = String>) {
+    let usage = |arg: &str| -> ! {
+        eprintln!("Invalid argument: {arg}");
+        eprintln!("Usage: inv8080rs disasm [options]");
+        eprintln!("  --rom <file>                    Rom to disassemble (default invaders.rom)");
+        eprintln!("  --out <listing.asm>             Write the listing to file (default stdout)");
+        eprintln!(
+            "  --trace <frames>                Frames of attract mode traced for code (default 3600)"
+        );
+        eprintln!(
+            "  --profile <file>                Use a profile recorded with --profile while playing"
+        );
+        std::process::exit(2);
+    };
+    let mut rom = None;
+    let mut out = None;
+    let mut frames = 60 * FPS as u64;
+    let mut profiles = vec![];
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&arg));
+        match arg.as_str() {
+            "--rom" => rom = Some(value()),
+            "--out" => out = Some(value()),
+            "--trace" => frames = value().parse().unwrap_or_else(|_| usage(&arg)),
+            "--profile" => profiles.push(value()),
+            _ => usage(&arg),
+        }
+    }
+
+    let rom = match rom {
+        Some(path) => std::fs::read(&path).unwrap_or_else(|e| panic!("could not read {path}: {e}")),
+        None => read_rom(),
+    };
+    let mut profile = disasm::trace(&rom, frames);
+    for path in profiles {
+        let data = std::fs::read(&path).expect("could not read file");
+        profile.merge(&Profile::from_bytes(&data).unwrap_or_else(|e| panic!("{path}: {e}")));
+    }
+    let listing = disasm::listing(&rom, &profile);
+    match out {
+        Some(path) => {
+            std::fs::write(&path, listing).expect("could not write file");
+            println!("Saved {path}");
+        }
+        None => print!("{listing}"),
+    }
+}
+
+/// Export sprites from the rom as PNG files, or import edited ones and create an IPS patch for the rom
+fn sprites(mut args: impl Iterator<Item = String>) {
+    let usage = || -> ! {
+        eprintln!("Usage: inv8080rs sprites export <dir>");
+        eprintln!("       inv8080rs sprites import <dir> <patch.ips>");
+        std::process::exit(2);
+    };
+    let rom = read_rom();
+
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (Some("export"), Some(dir), None) => {
+            std::fs::create_dir_all(&dir).expect("could not create directory");
+            for sprite in SPRITES {
+                let path = format!("{dir}/{}.png", sprite.name);
+                let image = sprite.export(&rom).unwrap_or_else(|e| panic!("{e}"));
+                std::fs::write(&path, png::encode(&image)).expect("could not write file");
+                println!("{path}");
+            }
+        }
+        (Some("import"), Some(dir), Some(patch)) => {
+            let mut patched = rom.clone();
+            for sprite in SPRITES {
+                let path = format!("{dir}/{}.png", sprite.name);
+                let Ok(file) = std::fs::read(&path) else {
+                    continue;
+                };
+                let image = png::decode(&file).unwrap_or_else(|e| panic!("{path}: {e}"));
+                sprite
+                    .import(&image, &mut patched)
+                    .unwrap_or_else(|e| panic!("{path}: {e}"));
+            }
+            std::fs::write(&patch, ips::create(&rom, &patched)).expect("could not write file");
+            println!("{patch}");
+        }
+        _ => usage(),
+    }
 }
@@ -0,0 +1,99 @@
+//! Diagnostics of interrupts lost because the CPU keeps them disabled (DI without EI) frame after frame, a common
+//! symptom of a bug in the CPU model or of a bad patch, which otherwise shows as a black or frozen screen. The
+//! addresses the CPU was at when the interrupts were lost tell where it is spinning.
+
+use std::{fmt, ops::RangeInclusive};
+
+#[cfg(test)]
+mod tests;
+
+/// Frames without an interrupt before telling, 3 seconds. Longer than the self-test of Space Invaders keeps them
+/// disabled.
+pub const FRAMES: u32 = 180;
+
+/// What the interrupts did
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnosis {
+    /// No interrupt delivered for `frames` frames, with the addresses of the CPU when they were lost
+    Masked {
+        frames: u32,
+        lost: u32,
+        pcs: RangeInclusive<usize>,
+    },
+    /// Interrupts delivered again after `frames` frames
+    Recovered { frames: u32 },
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::Masked { frames, lost, pcs } => write!(
+                f,
+                "Interrupts disabled for {frames} frames, {lost} lost, the CPU spinning at {:04X}-{:04X}",
+                pcs.start(),
+                pcs.end()
+            ),
+            Diagnosis::Recovered { frames } => {
+                write!(f, "Interrupts enabled again after {frames} frames")
+            }
+        }
+    }
+}
+
+/// Counts the frames without an interrupt delivered
+#[derive(Clone, Debug)]
+pub struct Masking {
+    /// Frames without an interrupt before telling
+    pub threshold: u32,
+    /// Frames in a row without an interrupt
+    frames: u32,
+    /// Interrupts lost in those frames
+    lost: u32,
+    delivered: bool,
+    pcs: Option<RangeInclusive<usize>>,
+}
+
+impl Masking {
+    pub fn new(threshold: u32) -> Self {
+        Masking {
+            threshold: threshold.max(1),
+            frames: 0,
+            lost: 0,
+            delivered: false,
+            pcs: None,
+        }
+    }
+
+    /// An interrupt requested at the end of a section of the frame, lost with the CPU at `pc` when not delivered
+    pub fn interrupt(&mut self, delivered: bool, pc: usize) {
+        if delivered {
+            self.delivered = true;
+        } else {
+            self.lost += 1;
+            self.pcs = Some(match self.pcs.take() {
+                Some(pcs) => (*pcs.start()).min(pc)..=(*pcs.end()).max(pc),
+                None => pc..=pc,
+            });
+        }
+    }
+
+    /// End of a frame: tells when the interrupts have been disabled for the threshold, and when they come back after
+    pub fn frame(&mut self) -> Option<Diagnosis> {
+        if std::mem::take(&mut self.delivered) {
+            let frames = self.frames;
+            self.frames = 0;
+            self.lost = 0;
+            self.pcs = None;
+            return (frames >= self.threshold).then_some(Diagnosis::Recovered { frames });
+        }
+        self.frames += 1;
+        match &self.pcs {
+            Some(pcs) if self.frames == self.threshold => Some(Diagnosis::Masked {
+                frames: self.frames,
+                lost: self.lost,
+                pcs: pcs.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn masked_and_recovered() {
+    let mut masking = Masking::new(3);
+    masking.interrupt(true, 0x100);
+    assert_eq!(masking.frame(), None);
+    for pc in [0x0ADF, 0x0AE2, 0x0AE4] {
+        masking.interrupt(false, pc);
+        masking.interrupt(false, pc);
+        if pc != 0x0AE4 {
+            assert_eq!(masking.frame(), None);
+        }
+    }
+    let masked = masking.frame().unwrap();
+    assert_eq!(
+        masked,
+        Diagnosis::Masked {
+            frames: 3,
+            lost: 6,
+            pcs: 0x0ADF..=0x0AE4
+        }
+    );
+    assert_eq!(
+        masked.to_string(),
+        "Interrupts disabled for 3 frames, 6 lost, the CPU spinning at 0ADF-0AE4"
+    );
+    // Told once
+    masking.interrupt(false, 0x0AE0);
+    assert_eq!(masking.frame(), None);
+
+    masking.interrupt(false, 0x0AE0);
+    masking.interrupt(true, 0x0AE0);
+    let recovered = masking.frame().unwrap();
+    assert_eq!(recovered, Diagnosis::Recovered { frames: 4 });
+    assert_eq!(
+        recovered.to_string(),
+        "Interrupts enabled again after 4 frames"
+    );
+}
+
+#[test]
+fn short_masking() {
+    let mut masking = Masking::new(FRAMES);
+    for _ in 0..FRAMES - 1 {
+        masking.interrupt(false, 0);
+        assert_eq!(masking.frame(), None);
+    }
+    masking.interrupt(true, 0);
+    assert_eq!(masking.frame(), None);
+    // A board without interrupts is never told about
+    let mut masking = Masking::new(1);
+    assert_eq!(masking.frame(), None);
+}
@@ -0,0 +1,108 @@
+//! Command palette: a drop-down list of the emulator commands, narrowed down by fuzzy search as you type, so the
+//! commands can be found without knowing their keys. Independent of SDL, the commands are whatever the frontend
+//! runs when one is chosen.
+
+use crate::DISPLAY_WIDTH;
+
+#[cfg(test)]
+mod tests;
+
+/// Commands listed below the search
+pub const VISIBLE: usize = 8;
+/// Characters in a line across the display
+const COLUMNS: usize = (DISPLAY_WIDTH / 8) as usize;
+
+/// A command as listed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry<T> {
+    /// Name searched, in capitals
+    pub name: &'static str,
+    /// Keys running the command directly
+    pub keys: String,
+    pub command: T,
+}
+
+/// Search text and selection over a list of commands
+#[derive(Clone, Debug)]
+pub struct Menu<T> {
+    entries: Vec<Entry<T>>,
+    query: String,
+    /// Index of the selected match
+    selected: usize,
+}
+
+/// How well a query matches a name, lower is better: its characters must appear in order, every character skipped
+/// between them counts. None when it does not match.
+pub fn score(query: &str, name: &str) -> Option<usize> {
+    let mut name = name.chars();
+    let mut skipped = 0;
+    for q in query.chars() {
+        skipped += name.position(|c| c.eq_ignore_ascii_case(&q))?;
+    }
+    Some(skipped)
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(entries: Vec<Entry<T>>) -> Self {
+        Menu {
+            entries,
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    /// Commands matching the search, best first, in the order given when equally good
+    pub fn matches(&self) -> Vec<&Entry<T>> {
+        let mut matches: Vec<(usize, &Entry<T>)> = self
+            .entries
+            .iter()
+            .filter_map(|e| Some((score(&self.query, e.name)?, e)))
+            .collect();
+        matches.sort_by_key(|(score, _)| *score);
+        matches.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// Add a character to the search, starting over at the best match
+    pub fn type_char(&mut self, c: char) {
+        self.query.push(c.to_ascii_uppercase());
+        self.selected = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Move the selection up or down, staying within the matches
+    pub fn move_by(&mut self, delta: isize) {
+        let last = self.matches().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// The selected command, clearing the search for next time
+    pub fn choose(&mut self) -> Option<T> {
+        let command = self.matches().get(self.selected).map(|e| e.command);
+        self.query.clear();
+        self.selected = 0;
+        command
+    }
+
+    /// Lines to show: the search, then the matches around the selected one with their keys, a full line each
+    pub fn lines(&self) -> Vec<String> {
+        let matches = self.matches();
+        let first = self.selected.saturating_sub(VISIBLE - 1);
+        let mut lines = vec![format!("> {}", self.query)];
+        for (i, entry) in matches.iter().enumerate().skip(first).take(VISIBLE) {
+            let marker = if i == self.selected { '*' } else { ' ' };
+            let width = COLUMNS - 2 - entry.keys.len();
+            lines.push(format!("{marker} {:<width$}{}", entry.name, entry.keys));
+        }
+        if matches.is_empty() {
+            lines.push("  NO MATCHES".to_string());
+        }
+        for line in &mut lines {
+            *line = format!("{line:<COLUMNS$}");
+        }
+        lines
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+
+fn menu() -> Menu<u8> {
+    let entry = |name, keys: &str, command| Entry {
+        name,
+        keys: keys.to_string(),
+        command,
+    };
+    Menu::new(vec![
+        entry("PAUSE", "SPACE", 0),
+        entry("SCREENSHOT", "F6", 1),
+        entry("STORE STATE A", "F11", 2),
+        entry("STORE STATE B", "F12", 3),
+        entry("SLOWER", "F9", 4),
+    ])
+}
+
+#[test]
+fn fuzzy_scores() {
+    assert_eq!(score("", "PAUSE"), Some(0));
+    assert_eq!(score("pause", "PAUSE"), Some(0));
+    assert_eq!(score("SS", "SCREENSHOT"), Some(5));
+    assert_eq!(score("SSA", "STORE STATE A"), Some(6));
+    assert_eq!(score("PA", "SLOWER"), None);
+    assert_eq!(score("EP", "PAUSE"), None);
+}
+
+#[test]
+fn search_and_choose() {
+    let mut menu = menu();
+    assert_eq!(menu.matches().len(), 5);
+    for c in "stb".chars() {
+        menu.type_char(c);
+    }
+    let names: Vec<&str> = menu.matches().iter().map(|e| e.name).collect();
+    assert_eq!(names, ["STORE STATE B"]);
+    menu.backspace();
+    let names: Vec<&str> = menu.matches().iter().map(|e| e.name).collect();
+    assert_eq!(names, ["STORE STATE A", "STORE STATE B", "SCREENSHOT"]);
+    menu.move_by(1);
+    menu.move_by(5);
+    assert_eq!(menu.choose(), Some(1));
+    // The search starts over
+    assert_eq!(menu.matches().len(), 5);
+    menu.type_char('x');
+    assert_eq!(menu.choose(), None);
+}
+
+#[test]
+fn lines_fill_the_display_width() {
+    let mut menu = menu();
+    menu.type_char('s');
+    menu.type_char('l');
+    let lines = menu.lines();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], format!("{:<28}", "> SL"));
+    assert_eq!(lines[1], "* SLOWER                  F9");
+    menu.type_char('q');
+    assert_eq!(menu.lines()[1].trim_end(), "  NO MATCHES");
+}
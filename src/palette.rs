@@ -0,0 +1,150 @@
+//! Colors of the display: the foreground (the phosphor), the background and the top and bottom overlays of the
+//! cabinet. Colors are given as hex (`#rrggbb`, `#aarrggbb`, with `0x` or bare) or by name, e.g. `amber`.
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// A color with alpha
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// Named colors, all opaque
+const NAMES: [(&str, Color); 12] = [
+    ("black", Color::rgb(0x00, 0x00, 0x00)),
+    ("white", Color::rgb(0xff, 0xff, 0xff)),
+    ("red", Color::rgb(0xff, 0x00, 0x00)),
+    ("green", Color::rgb(0x00, 0xff, 0x00)),
+    ("blue", Color::rgb(0x00, 0x00, 0xff)),
+    ("yellow", Color::rgb(0xff, 0xff, 0x00)),
+    ("cyan", Color::rgb(0x00, 0xff, 0xff)),
+    ("magenta", Color::rgb(0xff, 0x00, 0xff)),
+    ("orange", Color::rgb(0xff, 0x80, 0x00)),
+    ("amber", Color::rgb(0xff, 0xbf, 0x00)),
+    ("gray", Color::rgb(0x80, 0x80, 0x80)),
+    ("phosphor", Color::rgb(0x33, 0xff, 0x66)),
+];
+
+impl Color {
+    /// Opaque color
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 0xff }
+    }
+
+    /// Color of an ARGB8888 value
+    pub const fn from_argb(argb: u32) -> Self {
+        let [a, r, g, b] = argb.to_be_bytes();
+        Color { r, g, b, a }
+    }
+
+    /// ARGB8888 value, as in the textures
+    pub const fn argb(self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+
+    /// Multiply blend with a color seen through, like the overlays of the cabinet. The alpha is kept.
+    pub fn multiply(self, through: Color) -> Self {
+        let mul = |c: u8, o: u8| (c as u32 * o as u32 / 0xff) as u8;
+        Color {
+            r: mul(self.r, through.r),
+            g: mul(self.g, through.g),
+            b: mul(self.b, through.b),
+            a: self.a,
+        }
+    }
+
+    /// Parse a color by name or as hex: 6 digits are opaque, 8 digits have the alpha first
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let lower = s.trim().to_ascii_lowercase();
+        if let Some((_, color)) = NAMES.iter().find(|(name, _)| *name == lower) {
+            return Ok(*color);
+        }
+        let hex = lower
+            .strip_prefix('#')
+            .or_else(|| lower.strip_prefix("0x"))
+            .unwrap_or(&lower);
+        let digits = hex.chars().all(|c| c.is_ascii_hexdigit());
+        let value = u32::from_str_radix(hex, 16).ok().filter(|_| digits);
+        match (hex.len(), value) {
+            (6, Some(rgb)) => Ok(Color::from_argb(0xff000000 | rgb)),
+            (8, Some(argb)) => Ok(Color::from_argb(argb)),
+            _ => Err(format!(
+                "Invalid color {s}, expected #rrggbb, #aarrggbb or a name ({})",
+                NAMES.map(|(name, _)| name).join(", ")
+            )),
+        }
+    }
+}
+
+/// As 8 hex digits, alpha first, which `parse` reads back
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.argb())
+    }
+}
+
+/// Colors of the display
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub foreground: Color,
+    pub background: Color,
+    /// Overlay at the top of the screen
+    pub top: Color,
+    /// Overlay at the bottom of the screen
+    pub bottom: Color,
+}
+
+/// White on black with a red top and a green bottom overlay, like the cabinet
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            foreground: Color::rgb(0xff, 0xff, 0xff),
+            background: Color::rgb(0x00, 0x00, 0x00),
+            top: Color::rgb(0xff, 0x00, 0x00),
+            bottom: Color::rgb(0x00, 0xff, 0x00),
+        }
+    }
+}
+
+impl Palette {
+    /// Parse the foreground, background, top and bottom colors separated by commas or spaces
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let colors = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|c| !c.is_empty())
+            .map(Color::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        match colors[..] {
+            [foreground, background, top, bottom] => Ok(Palette {
+                foreground,
+                background,
+                top,
+                bottom,
+            }),
+            _ => Err(format!(
+                "Invalid palette {s}, expected 4 colors: foreground, background, top and bottom"
+            )),
+        }
+    }
+}
+
+/// The four colors separated by spaces, which `parse` reads back
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.foreground, self.background, self.top, self.bottom
+        )
+    }
+}
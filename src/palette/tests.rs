@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn parse_colors() {
+    assert_eq!(Color::parse("#ff8000"), Ok(Color::rgb(0xff, 0x80, 0x00)));
+    assert_eq!(
+        Color::parse("0x80FF0000"),
+        Ok(Color::rgb(0xff, 0, 0).with_alpha(0x80))
+    );
+    assert_eq!(Color::parse("ffffffff"), Ok(Color::rgb(0xff, 0xff, 0xff)));
+    assert_eq!(Color::parse(" Amber "), Ok(Color::rgb(0xff, 0xbf, 0x00)));
+    assert!(Color::parse("#fff").is_err());
+    assert!(Color::parse("+0ffffff").is_err());
+    assert!(Color::parse("chartreuse").unwrap_err().starts_with(
+        "Invalid color chartreuse, expected #rrggbb, #aarrggbb or a name (black, white"
+    ));
+}
+
+#[test]
+fn argb() {
+    let color = Color::from_argb(0x80102030);
+    assert_eq!(
+        (color.a, color.r, color.g, color.b),
+        (0x80, 0x10, 0x20, 0x30)
+    );
+    assert_eq!(color.argb(), 0x80102030);
+    assert_eq!(color.to_string(), "80102030");
+    assert_eq!(Color::parse(&color.to_string()), Ok(color));
+}
+
+#[test]
+fn multiply() {
+    let white = Color::rgb(0xff, 0xff, 0xff).with_alpha(0x40);
+    assert_eq!(
+        white.multiply(Color::rgb(0xff, 0x80, 0x00)),
+        Color::rgb(0xff, 0x80, 0x00).with_alpha(0x40)
+    );
+    assert_eq!(
+        Color::rgb(0x80, 0x80, 0x80).multiply(Color::rgb(0x80, 0xff, 0)),
+        Color::rgb(0x40, 0x80, 0)
+    );
+}
+
+#[test]
+fn palette() {
+    let palette = Palette::default();
+    assert_eq!(palette.to_string(), "ffffffff ff000000 ffff0000 ff00ff00");
+    assert_eq!(Palette::parse(&palette.to_string()), Ok(palette));
+    let amber = Palette::parse("amber,black, red ,#00ff00").unwrap();
+    assert_eq!(amber.foreground, Color::rgb(0xff, 0xbf, 0x00));
+    assert_eq!(amber.bottom, palette.bottom);
+    assert!(Palette::parse("amber,black")
+        .unwrap_err()
+        .starts_with("Invalid palette"));
+    assert!(Palette::parse("amber,black,red,teal").is_err());
+}
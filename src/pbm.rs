@@ -0,0 +1,74 @@
+//! 1-bit text images of the framebuffer: plain PBM (`P1`) and XBM. No image library needed, they can be looked at in a
+//! terminal and diffed line by line, so frames can be kept as fixtures in tests and documentation. A pixel is lit when
+//! its intensity is not 0. Both formats write lit pixels as 1, shown black by most viewers.
+
+use std::fmt::Write;
+
+use crate::{frame::Frame, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+#[cfg(test)]
+mod tests;
+
+/// Plain PBM with a row of the display per line, wider than the 70 characters netpbm writes but still read by it
+pub fn pbm(frame: &Frame) -> String {
+    let mut s = format!("P1\n{DISPLAY_WIDTH} {DISPLAY_HEIGHT}\n");
+    for y in 0..DISPLAY_HEIGHT {
+        s.extend((0..DISPLAY_WIDTH).map(|x| if frame.get(x, y) > 0 { '1' } else { '0' }));
+        s.push('\n');
+    }
+    s
+}
+
+/// Read a plain PBM of the display size, comments and white space allowed anywhere between tokens
+pub fn parse_pbm(text: &str) -> Result<Frame, String> {
+    let mut tokens = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace);
+    if tokens.next() != Some("P1") {
+        return Err("Not a plain PBM (P1)".into());
+    }
+    let mut size = || tokens.next().and_then(|t| t.parse::<u32>().ok());
+    let (width, height) = (size(), size());
+    if (width, height) != (Some(DISPLAY_WIDTH), Some(DISPLAY_HEIGHT)) {
+        return Err(format!(
+            "Size is {width:?}x{height:?}, expected {DISPLAY_WIDTH}x{DISPLAY_HEIGHT}"
+        ));
+    }
+    let mut frame = Frame::default();
+    let mut pixels = tokens.flat_map(str::chars);
+    for y in 0..DISPLAY_HEIGHT {
+        for x in 0..DISPLAY_WIDTH {
+            match pixels.next() {
+                Some('1') => frame.set(x, y, 0xff),
+                Some('0') => {}
+                Some(c) => return Err(format!("Unexpected {c:?} at pixel {x},{y}")),
+                None => return Err("Too few pixels".into()),
+            }
+        }
+    }
+    Ok(frame)
+}
+
+/// XBM as C source, the arrays are called `<name>_width`, `<name>_height` and `<name>_bits`
+pub fn xbm(frame: &Frame, name: &str) -> String {
+    let mut s = format!(
+        "#define {name}_width {DISPLAY_WIDTH}\n#define {name}_height {DISPLAY_HEIGHT}\nstatic unsigned char {name}_bits[] = {{\n"
+    );
+    let mut bytes = vec![];
+    for y in 0..DISPLAY_HEIGHT {
+        // Rows are padded to whole bytes, least significant bit first
+        for x in (0..DISPLAY_WIDTH).step_by(8) {
+            let byte = (0..8)
+                .filter(|bit| x + bit < DISPLAY_WIDTH && frame.get(x + bit, y) > 0)
+                .fold(0u8, |byte, bit| byte | 1 << bit);
+            bytes.push(format!("0x{byte:02x}"));
+        }
+    }
+    for line in bytes.chunks(12) {
+        let _ = writeln!(s, "   {},", line.join(", "));
+    }
+    s.truncate(s.len() - 2);
+    s.push_str(" };\n");
+    s
+}
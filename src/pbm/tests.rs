@@ -0,0 +1,50 @@
+use super::*;
+
+fn frame() -> Frame {
+    let mut frame = Frame::default();
+    frame.set(0, 0, 0xff);
+    frame.set(9, 0, 0x40);
+    frame.set(223, 255, 0xff);
+    frame
+}
+
+#[test]
+fn plain_pbm() {
+    let pbm = pbm(&frame());
+    let lines: Vec<&str> = pbm.lines().collect();
+    assert_eq!(lines.len(), 2 + DISPLAY_HEIGHT as usize);
+    assert_eq!(lines[..2], ["P1", "224 256"]);
+    assert!(lines[2].starts_with("1000000001000"));
+    assert_eq!(lines[3], "0".repeat(224));
+    assert!(lines[257].ends_with("01"));
+}
+
+#[test]
+fn pbm_round_trip() {
+    let frame = frame();
+    let read = parse_pbm(&pbm(&frame)).unwrap();
+    assert_eq!(
+        (read.get(0, 0), read.get(9, 0), read.get(1, 0)),
+        (0xff, 0xff, 0)
+    );
+    // Netpbm style: comments and spaced pixels
+    let spaced = pbm(&frame)
+        .replacen("P1\n", "P1\n# fixture\n", 1)
+        .replace("10000", "1 0 0 0 0");
+    assert_eq!(parse_pbm(&spaced), Ok(read));
+    assert!(parse_pbm("P4\n224 256\n").is_err());
+    assert!(parse_pbm("P1\n8 8\n")
+        .unwrap_err()
+        .contains("expected 224x256"));
+    assert_eq!(parse_pbm("P1 224 256 10"), Err("Too few pixels".into()));
+}
+
+#[test]
+fn xbm_bits() {
+    let xbm = xbm(&frame(), "frame");
+    assert!(xbm.starts_with(
+        "#define frame_width 224\n#define frame_height 256\nstatic unsigned char frame_bits[] = {\n   0x01, 0x02, 0x00,"
+    ));
+    assert!(xbm.ends_with("0x00, 0x80 };\n"));
+    assert_eq!(xbm.matches("0x").count(), 28 * 256);
+}
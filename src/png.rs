@@ -0,0 +1,416 @@
+//! Minimal PNG encoding and decoding, enough for sprites and screenshots
+
+use crate::hash::{adler32, crc32};
+
+#[cfg(test)]
+mod tests;
+
+/// Largest width or height decoded
+const MAX_SIZE: u32 = 8192;
+
+/// An image with 8-bit RGBA pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Pixels row by row, four bytes (RGBA) each
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Create a fully transparent image
+    pub fn new(width: u32, height: u32) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    /// Get RGBA of pixel at (x, y)
+    pub fn get(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Set RGBA of pixel at (x, y)
+    pub fn set(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = ((y * self.width + x) * 4) as usize;
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    /// Pixel is considered lit (bright and opaque), used when converting back to 1-bit graphics
+    pub fn is_lit(&self, x: u32, y: u32) -> bool {
+        let [r, g, b, a] = self.get(x, y);
+        a >= 0x80 && (r as u32 + g as u32 + b as u32) >= 3 * 0x80
+    }
+}
+
+/// PNG file signature
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Encode image as PNG (RGBA, not compressed)
+pub fn encode(image: &Image) -> Vec<u8> {
+    let mut ihdr = vec![];
+    ihdr.extend(image.width.to_be_bytes());
+    ihdr.extend(image.height.to_be_bytes());
+    ihdr.extend([8, 6, 0, 0, 0]); // 8-bit RGBA, deflate, adaptive filtering, no interlace
+
+    let mut raw = vec![];
+    for row in image.pixels.chunks((image.width * 4) as usize) {
+        raw.push(0); // Filter type none
+        raw.extend(row);
+    }
+
+    // Zlib stream with stored (uncompressed) deflate blocks
+    let mut idat = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        idat.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        idat.push(blocks.peek().is_none() as u8);
+        idat.extend(len.to_le_bytes());
+        idat.extend((!len).to_le_bytes());
+        idat.extend(block);
+    }
+    idat.extend(adler32(&raw).to_be_bytes());
+
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &ihdr);
+    chunk(&mut png, b"IDAT", &idat);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Decode PNG (8-bit grayscale, RGB, palette, grayscale with alpha or RGBA, not interlaced)
+pub fn decode(png: &[u8]) -> Result<Image, String> {
+    if png.len() < 8 || png[..8] != SIGNATURE {
+        return Err("Not a PNG file".into());
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut idat = vec![];
+    let mut pos = 8;
+    while pos + 8 <= png.len() {
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &png[pos + 4..pos + 8];
+        let data = png
+            .get(pos + 8..pos + 8 + len)
+            .ok_or("Truncated PNG chunk")?;
+        match kind {
+            b"IHDR" if len == 13 => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => idat.extend(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += len + 12;
+    }
+
+    let header = header.ok_or("Missing PNG header")?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let (depth, color, interlace) = (header[8], header[9], header[12]);
+    let channels = match color {
+        0 => 1,
+        2 => 3,
+        3 => 1,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("Unsupported PNG color type {color}")),
+    };
+    if depth != 8 || interlace != 0 {
+        return Err("Only 8-bit, non-interlaced PNG files are supported".into());
+    }
+    if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return Err(format!(
+            "PNG size {width}x{height} not supported, at most {MAX_SIZE}x{MAX_SIZE}"
+        ));
+    }
+
+    if idat.len() < 2 {
+        return Err("Missing PNG image data".into());
+    }
+    let raw = inflate(&idat[2..])?;
+    let stride = (width as usize)
+        .checked_mul(channels)
+        .ok_or("PNG image too large")?;
+    let size = (stride + 1)
+        .checked_mul(height as usize)
+        .ok_or("PNG image too large")?;
+    if raw.len() < size {
+        return Err("PNG image data too short".into());
+    }
+
+    let mut image = Image::new(width, height);
+    let mut previous = vec![0u8; stride];
+    for y in 0..height as usize {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let row = unfilter(line[0], &line[1..], &previous, channels)?;
+        for x in 0..width as usize {
+            let p = &row[x * channels..(x + 1) * channels];
+            let rgba = match color {
+                0 => [p[0], p[0], p[0], 0xFF],
+                2 => [p[0], p[1], p[2], 0xFF],
+                3 => {
+                    let i = p[0] as usize;
+                    let rgb = palette.get(i * 3..i * 3 + 3).ok_or("Bad palette index")?;
+                    [
+                        rgb[0],
+                        rgb[1],
+                        rgb[2],
+                        *transparency.get(i).unwrap_or(&0xFF),
+                    ]
+                }
+                4 => [p[0], p[0], p[0], p[1]],
+                _ => [p[0], p[1], p[2], p[3]],
+            };
+            image.set(x as u32, y as u32, rgba);
+        }
+        previous = row;
+    }
+    Ok(image)
+}
+
+/// Reverse the PNG filter of one row
+fn unfilter(filter: u8, line: &[u8], previous: &[u8], bpp: usize) -> Result<Vec<u8>, String> {
+    let mut row = line.to_vec();
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = previous[i];
+        let c = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => {
+                let p = a as i16 + b as i16 - c as i16;
+                let (pa, pb, pc) = (
+                    (p - a as i16).abs(),
+                    (p - b as i16).abs(),
+                    (p - c as i16).abs(),
+                );
+                if pa <= pb && pa <= pc {
+                    a
+                } else if pb <= pc {
+                    b
+                } else {
+                    c
+                }
+            }
+            _ => return Err(format!("Unknown PNG filter {filter}")),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(row)
+}
+
+/// Append a chunk with length and CRC
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Bit reader for deflate streams (least significant bit first)
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> Result<u32, String> {
+        let byte = self
+            .data
+            .get(self.pos / 8)
+            .ok_or("Truncated deflate stream")?;
+        let bit = (byte >> (self.pos % 8)) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..n {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+}
+
+/// Canonical Huffman code as (count of codes per length, symbols ordered by code)
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for l in lengths {
+            counts[*l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for i in 1..16 {
+            offsets[i] = offsets[i - 1] + counts[i - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, l) in lengths.iter().enumerate() {
+            if *l != 0 {
+                symbols[offsets[*l as usize] as usize] = symbol as u16;
+                offsets[*l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.bit()? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Bad Huffman code".into())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Decompress a raw deflate stream (RFC 1951)
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut bits = Bits { data, pos: 0 };
+    let mut out = vec![];
+
+    loop {
+        let last = bits.bit()?;
+        match bits.bits(2)? {
+            0 => {
+                bits.pos = bits.pos.div_ceil(8) * 8;
+                let len = bits.bits(16)? as usize;
+                bits.bits(16)?;
+                let start = bits.pos / 8;
+                out.extend(
+                    data.get(start..start + len)
+                        .ok_or("Truncated stored block")?,
+                );
+                bits.pos += len * 8;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut bits, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let hlit = bits.bits(5)? as usize + 257;
+                let hdist = bits.bits(5)? as usize + 1;
+                let hclen = bits.bits(4)? as usize + 4;
+                const ORDER: [usize; 19] = [
+                    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+                ];
+                let mut code_lengths = [0u8; 19];
+                for i in ORDER.iter().take(hclen) {
+                    code_lengths[*i] = bits.bits(3)? as u8;
+                }
+                let code = Huffman::new(&code_lengths);
+
+                let mut lengths = vec![];
+                while lengths.len() < hlit + hdist {
+                    match code.decode(&mut bits)? {
+                        symbol @ 0..=15 => lengths.push(symbol as u8),
+                        16 => {
+                            let previous = *lengths.last().ok_or("Bad code lengths")?;
+                            let repeat = 3 + bits.bits(2)?;
+                            lengths.extend((0..repeat).map(|_| previous));
+                        }
+                        17 => {
+                            let repeat = 3 + bits.bits(3)?;
+                            lengths.extend((0..repeat).map(|_| 0));
+                        }
+                        _ => {
+                            let repeat = 11 + bits.bits(7)?;
+                            lengths.extend((0..repeat).map(|_| 0));
+                        }
+                    }
+                }
+                let literals = Huffman::new(&lengths[..hlit]);
+                let distances = Huffman::new(&lengths[hlit..hlit + hdist]);
+                inflate_block(&mut bits, &mut out, &literals, &distances)?;
+            }
+            _ => return Err("Bad deflate block type".into()),
+        }
+        if last == 1 {
+            return Ok(out);
+        }
+    }
+}
+
+/// Decompress one Huffman coded block
+fn inflate_block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                let base = *LENGTH_BASE.get(i).ok_or("Bad length symbol")?;
+                let len = base as usize + bits.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = distances.decode(bits)? as usize;
+                let base = *DISTANCE_BASE.get(d).ok_or("Bad distance symbol")?;
+                let distance = base as usize + bits.bits(DISTANCE_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("Bad distance".into());
+                }
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
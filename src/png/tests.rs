@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn image_get_set_and_lit() {
+    let mut image = Image::new(2, 2);
+    assert_eq!(image.get(1, 1), [0, 0, 0, 0]);
+    image.set(1, 1, [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(image.get(1, 1), [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert!(image.is_lit(1, 1));
+    image.set(0, 1, [0xFF, 0xFF, 0xFF, 0x00]);
+    assert!(!image.is_lit(0, 1));
+    image.set(1, 0, [0x20, 0x20, 0x20, 0xFF]);
+    assert!(!image.is_lit(1, 0));
+}
+
+#[test]
+fn roundtrip() {
+    let mut image = Image::new(17, 5);
+    for y in 0..5 {
+        for x in 0..17 {
+            image.set(x, y, [x as u8 * 15, y as u8 * 50, 0x80, (x * y) as u8]);
+        }
+    }
+    assert_eq!(decode(&encode(&image)), Ok(image));
+}
+
+#[test]
+fn roundtrip_large() {
+    // More than one stored deflate block
+    let mut image = Image::new(200, 100);
+    image.set(199, 99, [1, 2, 3, 4]);
+    assert_eq!(decode(&encode(&image)), Ok(image));
+}
+
+#[test]
+fn decode_compressed() {
+    // Compressed with dynamic Huffman codes and all filter types
+    let image = decode(include_bytes!("../../screenshot.png")).unwrap();
+    assert_eq!((image.width, image.height), (336, 384));
+    assert_eq!(
+        image.pixels.iter().map(|p| *p as u64).sum::<u64>(),
+        44664985
+    );
+    assert_eq!(image.get(0, 0), [16, 16, 16, 255]);
+    assert_eq!(image.get(335, 383), [17, 17, 17, 255]);
+}
+
+#[test]
+fn decode_errors() {
+    assert!(decode(b"GIF89a").is_err());
+    let mut png = encode(&Image::new(1, 1));
+    png.truncate(40);
+    assert!(decode(&png).is_err());
+}
+
+#[test]
+fn decode_huge_size() {
+    let mut png = encode(&Image::new(1, 1));
+    png[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+    png[20..24].copy_from_slice(&0x4000_0000u32.to_be_bytes());
+    assert_eq!(
+        decode(&png),
+        Err("PNG size 4294967295x1073741824 not supported, at most 8192x8192".into())
+    );
+    png[16..20].copy_from_slice(&0u32.to_be_bytes());
+    assert!(decode(&png).is_err());
+}
@@ -0,0 +1,277 @@
+//! Port definitions of the board in the style of MAME: named input bits with their polarity and default value, and
+//! named output bits. Also a monitor of the port activity of the running program.
+
+use crate::{
+    cpu::Cpu,
+    utils::{get_bit, set_bit},
+    NPORTS,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A bit of an input port: a button, switch, DIP switch or a line tied to a fixed level
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InputBit {
+    /// Name, used on the command line
+    pub name: &'static str,
+    /// Input port
+    pub port: usize,
+    /// Bit in the port
+    pub bit: u8,
+    /// The bit reads 0 when the input is active
+    pub active_low: bool,
+    /// The input is active by default (e.g. a DIP switch that is on)
+    pub default: bool,
+    /// What the input does
+    pub description: &'static str,
+}
+
+const fn input(
+    name: &'static str,
+    port: usize,
+    bit: u8,
+    active_low: bool,
+    default: bool,
+    description: &'static str,
+) -> InputBit {
+    InputBit {
+        name,
+        port,
+        bit,
+        active_low,
+        default,
+        description,
+    }
+}
+
+/// A bit of an output port, e.g. a sound trigger
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OutputBit {
+    /// Name
+    pub name: &'static str,
+    /// Output port
+    pub port: usize,
+    /// Bit in the port
+    pub bit: u8,
+    /// What the output does
+    pub description: &'static str,
+}
+
+const fn output(name: &'static str, port: usize, bit: u8, description: &'static str) -> OutputBit {
+    OutputBit {
+        name,
+        port,
+        bit,
+        description,
+    }
+}
+
+impl InputBit {
+    /// Level of the bit when the input is active or not
+    pub fn level(&self, active: bool) -> bool {
+        active != self.active_low
+    }
+
+    /// The input is active on the machine
+    pub fn active(&self, cpu: &Cpu) -> bool {
+        (cpu.get_bus_in(self.port) >> self.bit & 1 == 1) == self.level(true)
+    }
+
+    /// Activate or deactivate the input
+    pub fn set(&self, cpu: &mut Cpu, active: bool) {
+        cpu.set_bus_in_bit(self.port, self.bit, self.level(active));
+    }
+}
+
+/// Ports of a board
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Board {
+    /// Name of the board
+    pub name: &'static str,
+    /// Defined bits, bits not listed read 0
+    pub inputs: &'static [InputBit],
+    /// Output bits with a meaning of their own, ports written as a whole (e.g. shift data) are not listed
+    pub outputs: &'static [OutputBit],
+}
+
+impl Board {
+    /// Values of the input ports with every input at its default
+    pub fn defaults(&self) -> [u8; NPORTS] {
+        let mut ports = [0; NPORTS];
+        for input in self.inputs {
+            set_bit(
+                &mut ports[input.port],
+                input.bit,
+                input.level(input.default),
+            );
+        }
+        ports
+    }
+
+    /// Input by name (case insensitive)
+    pub fn find(&self, name: &str) -> Option<&'static InputBit> {
+        self.inputs
+            .iter()
+            .find(|i| i.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Names of the bits set in a value of a port, inputs counting as set when active
+    pub fn labels(&self, direction: Direction, port: usize, value: u8) -> Vec<&'static str> {
+        match direction {
+            Direction::In => self
+                .inputs
+                .iter()
+                .filter(|i| i.port == port && get_bit(value, i.bit) == i.level(true))
+                .map(|i| i.name)
+                .collect(),
+            Direction::Out => self
+                .outputs
+                .iter()
+                .filter(|o| o.port == port && get_bit(value, o.bit))
+                .map(|o| o.name)
+                .collect(),
+        }
+    }
+}
+
+/// Direction of a port access
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    /// IN instruction
+    In,
+    /// OUT instruction
+    Out,
+}
+
+/// Activity of a port
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Activity {
+    /// Last value read or written
+    pub last: u8,
+    /// Number of accesses
+    pub accesses: u64,
+    /// Number of accesses with another value than the one before
+    pub changes: u64,
+    /// Changes during the last second
+    pub rate: u64,
+    /// Changes when the last second started
+    changes_before: u64,
+}
+
+/// Monitor of the IN and OUT activity of each port
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PortMonitor {
+    pub inputs: [Activity; NPORTS],
+    pub outputs: [Activity; NPORTS],
+}
+
+impl PortMonitor {
+    /// Record an access
+    pub fn access(&mut self, direction: Direction, port: usize, value: u8) {
+        let activity = match direction {
+            Direction::In => &mut self.inputs[port],
+            Direction::Out => &mut self.outputs[port],
+        };
+        if activity.accesses > 0 && activity.last != value {
+            activity.changes += 1;
+        }
+        activity.accesses += 1;
+        activity.last = value;
+    }
+
+    /// Start a new second, updating the rates of change
+    pub fn second(&mut self) {
+        for activity in self.inputs.iter_mut().chain(&mut self.outputs) {
+            activity.rate = activity.changes - activity.changes_before;
+            activity.changes_before = activity.changes;
+        }
+    }
+
+    /// Panel text in the characters of the font: for each port accessed a line with the direction, port, last value in
+    /// hex and binary and changes per second, followed by the names of the bits set
+    pub fn lines(&self, board: &Board) -> Vec<String> {
+        let mut lines = vec![];
+        let ports = [
+            (Direction::In, &self.inputs),
+            (Direction::Out, &self.outputs),
+        ];
+        for (direction, activities) in ports {
+            for (port, activity) in activities.iter().enumerate() {
+                if activity.accesses == 0 {
+                    continue;
+                }
+                let name = match direction {
+                    Direction::In => format!("IN{port}"),
+                    Direction::Out => format!("OUT{port}"),
+                };
+                lines.push(format!(
+                    "{name:<4} {:02X} {:08b} {}",
+                    activity.last, activity.last, activity.rate
+                ));
+                let labels = board.labels(direction, port, activity.last);
+                if !labels.is_empty() {
+                    lines.push(format!("  {}", labels.join(" ").to_uppercase()));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Space Invaders (Midway 8080 black and white), according to Computer Archeology
+pub const INVADERS: Board = Board {
+    name: "invaders",
+    inputs: &[
+        input("dip4", 0, 0, false, false, "Self-test request at power on"),
+        input("p0-bit1", 0, 1, true, false, "Unused, always 1"),
+        input("p0-bit2", 0, 2, true, false, "Unused, always 1"),
+        input("p0-bit3", 0, 3, true, false, "Unused, always 1"),
+        input("credit", 1, 0, false, false, "Coin deposited"),
+        input("p2-start", 1, 1, false, false, "Player 2 start"),
+        input("p1-start", 1, 2, false, false, "Player 1 start"),
+        input("p1-bit3", 1, 3, true, false, "Unused, always 1"),
+        input("p1-fire", 1, 4, false, false, "Player 1 fire"),
+        input("p1-left", 1, 5, false, false, "Player 1 left"),
+        input("p1-right", 1, 6, false, false, "Player 1 right"),
+        input(
+            "dip3",
+            2,
+            0,
+            false,
+            false,
+            "Ships, low bit (3 + dip3 + 2 * dip5)",
+        ),
+        input("dip5", 2, 1, false, false, "Ships, high bit"),
+        input("tilt", 2, 2, false, false, "Tilt"),
+        input(
+            "dip6",
+            2,
+            3,
+            false,
+            false,
+            "Extra ship at 1000 points instead of 1500",
+        ),
+        input("p2-fire", 2, 4, false, false, "Player 2 fire"),
+        input("p2-left", 2, 5, false, false, "Player 2 left"),
+        input("p2-right", 2, 6, false, false, "Player 2 right"),
+        input("dip7", 2, 7, true, true, "Coin info shown in attract mode"),
+    ],
+    outputs: &[
+        output("shift0", 2, 0, "Shift amount, low bit"),
+        output("shift1", 2, 1, "Shift amount, middle bit"),
+        output("shift2", 2, 2, "Shift amount, high bit"),
+        output("ufo", 3, 0, "Ufo sound, repeating"),
+        output("shot", 3, 1, "Player shot sound"),
+        output("die", 3, 2, "Player dies sound"),
+        output("hit", 3, 3, "Invader hit sound"),
+        output("xp", 3, 4, "Extended play sound"),
+        output("amp", 3, 5, "Sound amplifier enable"),
+        output("fleet1", 5, 0, "Fleet movement sound 1"),
+        output("fleet2", 5, 1, "Fleet movement sound 2"),
+        output("fleet3", 5, 2, "Fleet movement sound 3"),
+        output("fleet4", 5, 3, "Fleet movement sound 4"),
+        output("ufo-hit", 5, 4, "Ufo hit sound"),
+        output("flip", 5, 5, "Flip screen (cocktail cabinet)"),
+    ],
+};
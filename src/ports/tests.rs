@@ -0,0 +1,112 @@
+use super::*;
+
+#[test]
+fn invaders_defaults() {
+    assert_eq!(
+        INVADERS.defaults(),
+        [0b0000_1110, 0b0000_1000, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn unique_and_in_range() {
+    for (i, a) in INVADERS.inputs.iter().enumerate() {
+        assert!(a.port < NPORTS && a.bit < 8, "{:?}", a);
+        for b in &INVADERS.inputs[i + 1..] {
+            assert!(!a.name.eq_ignore_ascii_case(b.name), "{:?}", a);
+            assert!((a.port, a.bit) != (b.port, b.bit), "{:?} {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn find_name() {
+    let fire = INVADERS.find("P1-Fire").unwrap();
+    assert_eq!((fire.port, fire.bit), (1, 4));
+    assert_eq!(INVADERS.find("nothing"), None);
+}
+
+#[test]
+fn set_polarity() {
+    let mut cpu = Cpu::new(vec![]);
+    cpu.set_bus_in(2, 0);
+    let tilt = INVADERS.find("tilt").unwrap();
+    tilt.set(&mut cpu, true);
+    assert_eq!(cpu.get_bus_in(2), 0b0000_0100);
+    tilt.set(&mut cpu, false);
+    assert_eq!(cpu.get_bus_in(2), 0);
+
+    let dip7 = INVADERS.find("dip7").unwrap();
+    dip7.set(&mut cpu, false);
+    assert_eq!(cpu.get_bus_in(2), 0b1000_0000);
+    dip7.set(&mut cpu, true);
+    assert_eq!(cpu.get_bus_in(2), 0);
+    assert!(dip7.active(&cpu));
+    assert!(!tilt.active(&cpu));
+}
+
+#[test]
+fn new_cpu_uses_defaults() {
+    let cpu = Cpu::new(vec![]);
+    for port in [0, 1, 2] {
+        assert_eq!(cpu.get_bus_in(port), INVADERS.defaults()[port]);
+    }
+}
+
+#[test]
+fn output_bits() {
+    for (i, a) in INVADERS.outputs.iter().enumerate() {
+        assert!(a.port < NPORTS && a.bit < 8, "{:?}", a);
+        for b in &INVADERS.outputs[i + 1..] {
+            assert!((a.port, a.bit) != (b.port, b.bit), "{:?} {:?}", a, b);
+        }
+    }
+    assert_eq!(
+        INVADERS.labels(Direction::Out, 3, 0b0010_0001),
+        ["ufo", "amp"]
+    );
+    assert_eq!(INVADERS.labels(Direction::Out, 4, 0xFF), [] as [&str; 0]);
+    // Active low inputs count as set when 0
+    assert_eq!(
+        INVADERS.labels(Direction::In, 2, 0b0000_0100),
+        ["tilt", "dip7"]
+    );
+}
+
+#[test]
+fn monitor_activity() {
+    let mut monitor = PortMonitor::default();
+    monitor.access(Direction::Out, 3, 1);
+    monitor.access(Direction::Out, 3, 1);
+    monitor.access(Direction::Out, 3, 0);
+    monitor.access(Direction::In, 1, 0x08);
+    monitor.second();
+    monitor.access(Direction::Out, 3, 2);
+    let out = monitor.outputs[3];
+    assert_eq!(
+        (out.last, out.accesses, out.changes, out.rate),
+        (2, 4, 2, 1)
+    );
+    monitor.second();
+    assert_eq!(monitor.outputs[3].rate, 1);
+    monitor.second();
+    assert_eq!(monitor.outputs[3].rate, 0);
+    assert_eq!(
+        monitor.lines(&INVADERS),
+        ["IN1  08 00001000 0", "OUT3 02 00000010 0", "  SHOT"]
+    );
+}
+
+#[test]
+fn cpu_monitors_ports() {
+    // MVI A,21h; OUT 3; IN 1
+    let mut cpu = Cpu::new(vec![0x3E, 0x21, 0xD3, 0x03, 0xDB, 0x01]);
+    cpu.step();
+    cpu.enable_port_monitor();
+    cpu.step();
+    cpu.step();
+    let monitor = cpu.port_monitor().unwrap();
+    assert_eq!(monitor.outputs[3].last, 0x21);
+    assert_eq!(monitor.inputs[1].last, INVADERS.defaults()[1]);
+    assert_eq!(monitor.inputs[0].accesses, 0);
+}
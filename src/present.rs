@@ -0,0 +1,99 @@
+//! Presentation of frames on the display. At 60 Hz frames are paced by sleeping until it is time for the next one. On
+//! displays refreshing at a multiple of the frame rate (120, 180 or 240 Hz) each frame is presented with vsync for that
+//! many refreshes, so the display clock paces the frames and every frame is shown for the same time. Displays with
+//! variable refresh rate (VRR: FreeSync, G-Sync) show a frame when it is presented, so frames are paced by sleeping
+//! and presented at once without vsync. The way of presenting can be chosen for each display.
+
+use std::time::{Duration, Instant};
+
+use crate::fullscreen;
+
+#[cfg(test)]
+mod tests;
+
+/// Frames in a row presented in less than half the frame time before vsync is taken as not waiting (some drivers
+/// accept vsync and ignore it)
+pub const FAST_FRAMES: u32 = 30;
+
+/// Way of presenting frames chosen on the command line
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Present {
+    /// Vsync when the refresh rate is a multiple of the frame rate, otherwise sleep
+    Auto,
+    /// Sleep until the next frame, leaving vsync as the driver has it
+    Sleep,
+    /// Vsync, falling back to sleep when the refresh rate is not a multiple of the frame rate
+    Vsync,
+    /// Sleep and present without vsync, for variable refresh rate displays
+    Vrr,
+}
+
+/// How frames are paced
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pacing {
+    /// Sleeping until the next frame
+    Sleep,
+    /// Waiting for this many refreshes of the display when presenting
+    Vsync(u32),
+    /// Sleeping, and presenting without vsync
+    Vrr,
+}
+
+impl Present {
+    /// Parse `<mode>` or `<display>=<mode>`, for one display counting from 0
+    pub fn parse(s: &str) -> Option<(Option<usize>, Present)> {
+        let (display, mode) = match s.split_once('=') {
+            Some((display, mode)) => (Some(display.parse().ok()?), mode),
+            None => (None, s),
+        };
+        let present = match mode {
+            "auto" => Present::Auto,
+            "sleep" => Present::Sleep,
+            "vsync" => Present::Vsync,
+            "vrr" => Present::Vrr,
+            _ => return None,
+        };
+        Some((display, present))
+    }
+
+    /// The way chosen for a display: the last one given for it, or for any display
+    pub fn for_display(choices: &[(Option<usize>, Present)], display: usize) -> Present {
+        let chosen = |d: Option<usize>| choices.iter().rev().find(|(c, _)| *c == d);
+        chosen(Some(display))
+            .or_else(|| chosen(None))
+            .map_or(Present::Auto, |(_, present)| *present)
+    }
+
+    /// Pacing on a display refreshing at `refresh` Hz
+    pub fn pacing(self, refresh: f32, fps: u32) -> Pacing {
+        match (self, fullscreen::repeats(refresh, fps)) {
+            (Present::Auto | Present::Vsync, Some(repeats)) => Pacing::Vsync(repeats),
+            (Present::Vrr, _) => Pacing::Vrr,
+            _ => Pacing::Sleep,
+        }
+    }
+}
+
+/// Checks that presenting with vsync really waits for the display
+#[derive(Debug, Clone, PartialEq)]
+pub struct VsyncCheck {
+    last: Instant,
+    fast: u32,
+}
+
+impl VsyncCheck {
+    pub fn new(now: Instant) -> Self {
+        VsyncCheck { last: now, fast: 0 }
+    }
+
+    /// A frame was presented, returns whether vsync does not wait. `frame` is the time a frame should take.
+    pub fn presented(&mut self, now: Instant, frame: Duration) -> bool {
+        if now.duration_since(self.last) < frame / 2 {
+            self.fast += 1;
+        } else {
+            self.fast = 0;
+        }
+        self.last = now;
+        self.fast >= FAST_FRAMES
+    }
+}
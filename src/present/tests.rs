@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn parse() {
+    assert_eq!(Present::parse("vrr"), Some((None, Present::Vrr)));
+    assert_eq!(Present::parse("1=vsync"), Some((Some(1), Present::Vsync)));
+    assert_eq!(Present::parse("0=sleep"), Some((Some(0), Present::Sleep)));
+    assert_eq!(Present::parse("auto"), Some((None, Present::Auto)));
+    assert_eq!(Present::parse("fast"), None);
+    assert_eq!(Present::parse("x=vrr"), None);
+}
+
+#[test]
+fn chosen_per_display() {
+    let choices = [
+        (None, Present::Sleep),
+        (Some(1), Present::Vrr),
+        (Some(1), Present::Vsync),
+    ];
+    assert_eq!(Present::for_display(&choices, 1), Present::Vsync);
+    assert_eq!(Present::for_display(&choices, 0), Present::Sleep);
+    assert_eq!(Present::for_display(&[], 0), Present::Auto);
+}
+
+#[test]
+fn pacing_by_refresh_rate() {
+    assert_eq!(Present::Auto.pacing(60.0, 60), Pacing::Vsync(1));
+    assert_eq!(Present::Auto.pacing(120.0, 60), Pacing::Vsync(2));
+    assert_eq!(Present::Auto.pacing(179.9, 60), Pacing::Vsync(3));
+    assert_eq!(Present::Auto.pacing(144.0, 60), Pacing::Sleep);
+    assert_eq!(Present::Vsync.pacing(75.0, 60), Pacing::Sleep);
+    assert_eq!(Present::Sleep.pacing(120.0, 60), Pacing::Sleep);
+    assert_eq!(Present::Vrr.pacing(144.0, 60), Pacing::Vrr);
+}
+
+#[test]
+fn vsync_not_waiting() {
+    let start = Instant::now();
+    let frame = Duration::from_millis(16);
+    let mut check = VsyncCheck::new(start);
+    let mut now = start;
+    for _ in 0..FAST_FRAMES * 2 {
+        now += frame;
+        assert!(!check.presented(now, frame));
+    }
+    for i in 1..=FAST_FRAMES {
+        now += Duration::from_millis(1);
+        assert_eq!(check.presented(now, frame), i == FAST_FRAMES);
+    }
+}
@@ -0,0 +1,74 @@
+//! Code/data profile of the rom: which bytes were executed and which were read as data while running, for telling code
+//! from data when disassembling
+
+use crate::ROM;
+
+#[cfg(test)]
+mod tests;
+
+/// Flag of a byte executed as the start of an instruction
+pub const EXECUTED: u8 = 1;
+/// Flag of a byte read as data
+pub const READ: u8 = 2;
+
+/// Flags of each byte of the rom
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    flags: Vec<u8>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            flags: vec![0; ROM.count()],
+        }
+    }
+}
+
+impl Profile {
+    /// Set a flag of an address, addresses outside the rom are ignored
+    pub fn mark(&mut self, addr: usize, flag: u8) {
+        if let Some(f) = self.flags.get_mut(addr) {
+            *f |= flag;
+        }
+    }
+
+    /// The address was executed as the start of an instruction
+    pub fn executed(&self, addr: usize) -> bool {
+        self.flags.get(addr).is_some_and(|f| f & EXECUTED != 0)
+    }
+
+    /// The address was read as data and never executed, so it is data for sure
+    pub fn data(&self, addr: usize) -> bool {
+        self.flags.get(addr) == Some(&READ)
+    }
+
+    /// Add the flags of another profile, e.g. of an earlier session
+    pub fn merge(&mut self, other: &Profile) {
+        for (f, o) in self.flags.iter_mut().zip(&other.flags) {
+            *f |= o;
+        }
+    }
+
+    /// Profile file content, the flags of each byte of the rom
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.flags
+    }
+
+    /// Read a profile file
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != ROM.count() {
+            return Err(format!(
+                "Profile is {} bytes, expected {}",
+                data.len(),
+                ROM.count()
+            ));
+        }
+        if data.iter().any(|f| f & !(EXECUTED | READ) != 0) {
+            return Err("Not a profile, unknown flags".into());
+        }
+        Ok(Profile {
+            flags: data.to_vec(),
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn mark_and_query() {
+    let mut profile = Profile::default();
+    profile.mark(0x10, EXECUTED);
+    profile.mark(0x20, READ);
+    profile.mark(0x30, READ);
+    profile.mark(0x30, EXECUTED);
+    profile.mark(0x4000, READ);
+    assert!(profile.executed(0x10) && !profile.data(0x10));
+    assert!(!profile.executed(0x20) && profile.data(0x20));
+    assert!(profile.executed(0x30) && !profile.data(0x30));
+    assert!(!profile.executed(0x4000) && !profile.data(0x4000));
+}
+
+#[test]
+fn merge_and_bytes() {
+    let mut a = Profile::default();
+    a.mark(1, EXECUTED);
+    let mut b = Profile::default();
+    b.mark(1, READ);
+    b.mark(2, READ);
+    a.merge(&b);
+    assert_eq!(&a.to_bytes()[..3], [0, EXECUTED | READ, READ]);
+    assert_eq!(Profile::from_bytes(a.to_bytes()), Ok(a));
+    assert!(Profile::from_bytes(&[0; 10]).is_err());
+    assert!(Profile::from_bytes(&[4; 0x2000]).is_err());
+}
@@ -0,0 +1,154 @@
+//! Annotated RAM map of the game according to Computer Archeology
+
+#[cfg(test)]
+mod tests;
+
+/// A named region of RAM
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Region {
+    /// Name, as in the Computer Archeology disassembly
+    pub name: &'static str,
+    /// Start address
+    pub addr: usize,
+    /// Length in bytes
+    pub len: usize,
+    /// What the region holds
+    pub description: &'static str,
+}
+
+const fn region(name: &'static str, addr: usize, len: usize, description: &'static str) -> Region {
+    Region {
+        name,
+        addr,
+        len,
+        description,
+    }
+}
+
+/// Known variables and tables of the game, in address order
+pub const RAM_MAP: [Region; 39] = [
+    region(
+        "waitOnDraw",
+        0x2000,
+        1,
+        "Cleared by alien drawing to signal the ISR",
+    ),
+    region(
+        "alienIsExploding",
+        0x2002,
+        1,
+        "Not 0 while an alien is exploding",
+    ),
+    region(
+        "expAlienTimer",
+        0x2003,
+        1,
+        "Time left on an exploding alien",
+    ),
+    region("alienRow", 0x2004, 1, "Row of the alien being drawn"),
+    region("alienFrame", 0x2005, 1, "Animation frame of the aliens"),
+    region("alienCurIndex", 0x2006, 1, "Index of the alien being drawn"),
+    region("refAlienDYr", 0x2007, 1, "Reference alien delta Y"),
+    region("refAlienDXr", 0x2008, 1, "Reference alien delta X"),
+    region("refAlienYr", 0x2009, 1, "Reference alien Y"),
+    region("refAlienXr", 0x200A, 1, "Reference alien X"),
+    region(
+        "alienPos",
+        0x200B,
+        2,
+        "Screen address of the alien being drawn",
+    ),
+    region(
+        "rackDirection",
+        0x200D,
+        1,
+        "Direction of the fleet, 0 right and 1 left",
+    ),
+    region(
+        "rackDownDelta",
+        0x200E,
+        1,
+        "Delta X when the fleet drops down",
+    ),
+    region("playerAlive", 0x2015, 1, "0xFF while the player is alive"),
+    region(
+        "expAnimateTimer",
+        0x2016,
+        1,
+        "Time between frames of the player blowup",
+    ),
+    region(
+        "expAnimateCnt",
+        0x2017,
+        1,
+        "Frames left of the player blowup",
+    ),
+    region("playerYr", 0x201A, 1, "Player Y"),
+    region("playerXr", 0x201B, 1, "Player X"),
+    region("plyrShotStatus", 0x2025, 1, "State of the player shot"),
+    region("obj1CoorYr", 0x2029, 1, "Player shot Y"),
+    region("obj1CoorXr", 0x202A, 1, "Player shot X"),
+    region(
+        "playerDataMSB",
+        0x2067,
+        1,
+        "0x21 in player 1's turn, 0x22 in player 2's turn",
+    ),
+    region(
+        "playerOK",
+        0x2068,
+        1,
+        "1 when the player is ok, 0 while blowing up",
+    ),
+    region("enableAlienFire", 0x2069, 1, "1 when the aliens may fire"),
+    region("invaded", 0x206D, 1, "Set when the aliens reach the bottom"),
+    region("numAliens", 0x2082, 1, "Number of aliens left"),
+    region("saucerActive", 0x2084, 1, "The saucer is on screen"),
+    region("saucerHit", 0x2085, 1, "The saucer has been hit"),
+    region(
+        "shotCount",
+        0x208F,
+        2,
+        "Player shots fired, picks the saucer score",
+    ),
+    region(
+        "tillSaucer",
+        0x2091,
+        2,
+        "Count down until the saucer appears",
+    ),
+    region("tilt", 0x209A, 1, "The machine has been tilted"),
+    region("numCoins", 0x20EB, 1, "Number of credits (BCD)"),
+    region("gameMode", 0x20EF, 1, "1 in game play, 0 in attract mode"),
+    region("HiScor", 0x20F4, 2, "High score (BCD, little endian)"),
+    region("P1Scor", 0x20F8, 2, "Player 1 score (BCD, little endian)"),
+    region("P2Scor", 0x20FC, 2, "Player 2 score (BCD, little endian)"),
+    region(
+        "p1Aliens",
+        0x2100,
+        55,
+        "Player 1 alien table, 1 for each living alien",
+    ),
+    region("p1ShipsRem", 0x21FF, 1, "Player 1 ships remaining"),
+    region("p2ShipsRem", 0x22FF, 1, "Player 2 ships remaining"),
+];
+
+/// Region containing an address
+pub fn lookup(addr: usize) -> Option<&'static Region> {
+    RAM_MAP
+        .iter()
+        .find(|r| (r.addr..r.addr + r.len).contains(&addr))
+}
+
+/// Region by name (case insensitive)
+pub fn find(name: &str) -> Option<&'static Region> {
+    RAM_MAP.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+}
+
+/// Name of an address, with an offset if it is inside a region (e.g. `P1Scor+1`)
+pub fn annotate(addr: usize) -> Option<String> {
+    lookup(addr).map(|r| match addr - r.addr {
+        0 => r.name.to_string(),
+        offset => format!("{}+{offset}", r.name),
+    })
+}
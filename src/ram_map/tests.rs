@@ -0,0 +1,36 @@
+use super::*;
+use crate::RAM;
+
+#[test]
+fn sorted_and_disjoint() {
+    for pair in RAM_MAP.windows(2) {
+        assert!(pair[0].addr + pair[0].len <= pair[1].addr, "{:?}", pair);
+    }
+    for region in RAM_MAP {
+        assert!(RAM.contains(&region.addr));
+        assert!(RAM.contains(&(region.addr + region.len - 1)));
+    }
+}
+
+#[test]
+fn lookup_address() {
+    assert_eq!(lookup(0x2082).unwrap().name, "numAliens");
+    assert_eq!(lookup(0x20F9).unwrap().name, "P1Scor");
+    assert_eq!(lookup(0x2136).unwrap().name, "p1Aliens");
+    assert_eq!(lookup(0x2137), None);
+    assert_eq!(lookup(0x0000), None);
+}
+
+#[test]
+fn find_name() {
+    assert_eq!(find("numaliens").unwrap().addr, 0x2082);
+    assert_eq!(find("P1Scor").unwrap().len, 2);
+    assert_eq!(find("nothing"), None);
+}
+
+#[test]
+fn annotate_address() {
+    assert_eq!(annotate(0x2082).as_deref(), Some("numAliens"));
+    assert_eq!(annotate(0x20F9).as_deref(), Some("P1Scor+1"));
+    assert_eq!(annotate(0x2001), None);
+}
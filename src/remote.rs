@@ -0,0 +1,261 @@
+//! Remote input over UDP, for external controller hardware like a microcontroller scanning a real control panel.
+//!
+//! Each datagram is an OSC message with the address `/input/<name>` (an input of the board, e.g. `/input/p1-fire`)
+//! and the arguments: the token (string), the state (int32, nonzero when active, or `T`/`F`) and optionally the time
+//! it was sent (int64, microseconds since the Unix epoch) for latency statistics. Messages with the wrong token are
+//! rejected. DIP switches can't be set remotely.
+
+use std::{
+    fmt::Write,
+    io::ErrorKind,
+    net::UdpSocket,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::ports::INVADERS;
+
+#[cfg(test)]
+mod tests;
+
+/// Address prefix of input messages
+const PREFIX: &str = "/input/";
+
+/// Largest datagram read
+const MAX_PACKET: usize = 512;
+
+/// Most messages handled per poll, the rest wait for the next frame
+const MAX_MESSAGES: usize = 64;
+
+/// Decoded input message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// Input name
+    pub name: String,
+    /// Authentication token
+    pub token: String,
+    /// Input active
+    pub active: bool,
+    /// Time sent, in microseconds since the Unix epoch
+    pub sent: Option<i64>,
+}
+
+/// OSC string, null terminated and padded to a multiple of 4 bytes. Returns the string and the rest of the packet.
+fn string(data: &[u8]) -> Result<(&str, &[u8]), String> {
+    let end = data
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or("Unterminated string")?;
+    let s = std::str::from_utf8(&data[..end]).map_err(|_| "String is not UTF-8")?;
+    let next = (end / 4 + 1) * 4;
+    Ok((s, data.get(next..).ok_or("String padding missing")?))
+}
+
+/// Big-endian OSC number of N bytes. Returns the bytes and the rest of the packet.
+fn number<const N: usize>(data: &[u8]) -> Result<([u8; N], &[u8]), String> {
+    let bytes = data.get(..N).ok_or("Argument missing")?;
+    Ok((bytes.try_into().unwrap(), &data[N..]))
+}
+
+/// Append an OSC string
+fn put_string(packet: &mut Vec<u8>, s: &str) {
+    packet.extend_from_slice(s.as_bytes());
+    packet.resize((packet.len() / 4 + 1) * 4, 0);
+}
+
+impl Message {
+    /// Decode an OSC message
+    pub fn parse(packet: &[u8]) -> Result<Self, String> {
+        let (address, rest) = string(packet)?;
+        let name = address
+            .strip_prefix(PREFIX)
+            .ok_or(format!("Unknown address {address}"))?;
+        let (tags, mut rest) = string(rest)?;
+        let tags = tags.strip_prefix(',').ok_or("Type tags missing")?;
+        let mut tags = tags.chars();
+        if tags.next() != Some('s') {
+            return Err("Token missing".to_string());
+        }
+        let (token, next) = string(rest)?;
+        rest = next;
+        let active = match tags.next() {
+            Some('i') => {
+                let (bytes, next) = number::<4>(rest)?;
+                rest = next;
+                i32::from_be_bytes(bytes) != 0
+            }
+            Some('T') => true,
+            Some('F') => false,
+            _ => return Err("State missing".to_string()),
+        };
+        let sent = match tags.next() {
+            Some('h') => Some(i64::from_be_bytes(number::<8>(rest)?.0)),
+            None => None,
+            Some(tag) => return Err(format!("Unexpected argument type {tag}")),
+        };
+        Ok(Message {
+            name: name.to_string(),
+            token: token.to_string(),
+            active,
+            sent,
+        })
+    }
+
+    /// Encode as an OSC message
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = vec![];
+        put_string(&mut packet, &format!("{PREFIX}{}", self.name));
+        put_string(
+            &mut packet,
+            if self.sent.is_some() { ",sih" } else { ",si" },
+        );
+        put_string(&mut packet, &self.token);
+        packet.extend_from_slice(&(self.active as i32).to_be_bytes());
+        if let Some(sent) = self.sent {
+            packet.extend_from_slice(&sent.to_be_bytes());
+        }
+        packet
+    }
+}
+
+/// Latency of the messages with a send time, in microseconds. Only meaningful with synchronized clocks.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Latency {
+    pub count: u64,
+    pub total: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Latency {
+    /// Add a message received at `now`, ignored if sent after it
+    pub fn record(&mut self, sent: i64, now: i64) {
+        let latency = now.saturating_sub(sent);
+        if latency < 0 {
+            return;
+        }
+        if self.count == 0 {
+            (self.min, self.max) = (latency, latency);
+        }
+        self.count += 1;
+        self.total = self.total.saturating_add(latency);
+        self.min = self.min.min(latency);
+        self.max = self.max.max(latency);
+    }
+
+    /// Average latency
+    pub fn average(&self) -> i64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.total / self.count as i64
+        }
+    }
+}
+
+/// Remote input backend receiving messages on a UDP socket
+#[derive(Debug)]
+pub struct Remote {
+    socket: Option<UdpSocket>,
+    token: String,
+    /// Messages accepted
+    pub accepted: u64,
+    /// Messages with the wrong token
+    pub rejected: u64,
+    /// Messages that could not be decoded or name no input, or a DIP switch (not settable remotely)
+    pub malformed: u64,
+    pub latency: Latency,
+}
+
+impl Remote {
+    /// Receive on `addr`, e.g. `0.0.0.0:9000`, accepting messages with `token`
+    pub fn bind(addr: &str, token: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind(addr).map_err(|e| format!("Could not bind {addr}: {e}"))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("Could not bind {addr}: {e}"))?;
+        Ok(Remote {
+            socket: Some(socket),
+            ..Remote::new(token)
+        })
+    }
+
+    /// Without a socket, packets are only handled when given to `handle`
+    pub fn new(token: &str) -> Self {
+        Remote {
+            socket: None,
+            token: token.to_string(),
+            accepted: 0,
+            rejected: 0,
+            malformed: 0,
+            latency: Latency::default(),
+        }
+    }
+
+    /// Handle a packet received at `now` (microseconds since the Unix epoch). Returns the input and its new state.
+    pub fn handle(&mut self, packet: &[u8], now: i64) -> Option<(&'static str, bool)> {
+        let Ok(message) = Message::parse(packet) else {
+            self.malformed += 1;
+            return None;
+        };
+        if message.token != self.token {
+            self.rejected += 1;
+            return None;
+        }
+        let Some(input) = INVADERS
+            .find(&message.name)
+            .filter(|i| !i.name.starts_with("dip"))
+        else {
+            self.malformed += 1;
+            return None;
+        };
+        self.accepted += 1;
+        if let Some(sent) = message.sent {
+            self.latency.record(sent, now);
+        }
+        Some((input.name, message.active))
+    }
+
+    /// Input updates received since the last poll, in order, from at most `MAX_MESSAGES` messages
+    pub fn poll(&mut self) -> Vec<(&'static str, bool)> {
+        let mut updates = vec![];
+        let mut buf = [0; MAX_PACKET];
+        for _ in 0..MAX_MESSAGES {
+            let Some(socket) = &self.socket else {
+                break;
+            };
+            match socket.recv_from(&mut buf) {
+                Ok((len, _)) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_micros() as i64);
+                    updates.extend(self.handle(&buf[..len], now));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("Remote input: {e}");
+                    break;
+                }
+            }
+        }
+        updates
+    }
+
+    /// Message counts and latency
+    pub fn report(&self) -> String {
+        let mut s = format!(
+            "Remote input: {} accepted, {} rejected, {} malformed\n",
+            self.accepted, self.rejected, self.malformed
+        );
+        if self.latency.count > 0 {
+            let _ = writeln!(
+                s,
+                "Latency: min {} us, avg {} us, max {} us ({} messages)",
+                self.latency.min,
+                self.latency.average(),
+                self.latency.max,
+                self.latency.count
+            );
+        }
+        s
+    }
+}
@@ -0,0 +1,140 @@
+use super::*;
+
+fn message(name: &str, token: &str, active: bool, sent: Option<i64>) -> Message {
+    Message {
+        name: name.to_string(),
+        token: token.to_string(),
+        active,
+        sent,
+    }
+}
+
+#[test]
+fn encode_and_parse() {
+    let m = message("p1-fire", "secret", true, Some(1_000_000));
+    let packet = m.encode();
+    assert_eq!(&packet[..16], b"/input/p1-fire\0\0");
+    assert_eq!(&packet[16..24], b",sih\0\0\0\0");
+    assert_eq!(packet.len() % 4, 0);
+    assert_eq!(Message::parse(&packet), Ok(m));
+    let m = message("credit", "abc", false, None);
+    assert_eq!(Message::parse(&m.encode()), Ok(m));
+}
+
+#[test]
+fn boolean_state() {
+    let mut packet = vec![];
+    put_string(&mut packet, "/input/tilt");
+    put_string(&mut packet, ",sT");
+    put_string(&mut packet, "abcd");
+    assert_eq!(
+        Message::parse(&packet),
+        Ok(message("tilt", "abcd", true, None))
+    );
+}
+
+#[test]
+fn malformed() {
+    assert!(Message::parse(b"").is_err());
+    assert!(Message::parse(b"/input/p1-fire\0\0").is_err());
+    let mut m = message("p1-fire", "t", true, None).encode();
+    m.truncate(m.len() - 2);
+    assert!(Message::parse(&m).is_err());
+    let mut packet = vec![];
+    put_string(&mut packet, "/other");
+    put_string(&mut packet, ",si");
+    assert_eq!(
+        Message::parse(&packet),
+        Err("Unknown address /other".to_string())
+    );
+}
+
+#[test]
+fn handle_counts_and_latency() {
+    let mut remote = Remote::new("secret");
+    let packet = message("p2-left", "secret", true, Some(100)).encode();
+    assert_eq!(remote.handle(&packet, 350), Some(("p2-left", true)));
+    let packet = message("p2-left", "secret", false, Some(200)).encode();
+    assert_eq!(remote.handle(&packet, 250), Some(("p2-left", false)));
+    let packet = message("p2-left", "wrong", true, None).encode();
+    assert_eq!(remote.handle(&packet, 0), None);
+    let packet = message("nothing", "secret", true, None).encode();
+    assert_eq!(remote.handle(&packet, 0), None);
+    assert_eq!(remote.handle(b"garbage", 0), None);
+    assert_eq!(
+        (remote.accepted, remote.rejected, remote.malformed),
+        (2, 1, 2)
+    );
+    assert_eq!(
+        remote.latency,
+        Latency {
+            count: 2,
+            total: 300,
+            min: 50,
+            max: 250
+        }
+    );
+    assert_eq!(
+        remote.report(),
+        "Remote input: 2 accepted, 1 rejected, 2 malformed\nLatency: min 50 us, avg 150 us, max 250 us (2 messages)\n"
+    );
+}
+
+#[test]
+fn poll_socket() {
+    let mut remote = Remote::bind("127.0.0.1:0", "t").unwrap();
+    let addr = remote.socket.as_ref().unwrap().local_addr().unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender
+        .send_to(&message("p1-right", "t", true, None).encode(), addr)
+        .unwrap();
+    let mut updates = vec![];
+    for _ in 0..100 {
+        updates.extend(remote.poll());
+        if !updates.is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(updates, vec![("p1-right", true)]);
+}
+
+#[test]
+fn latency_extremes() {
+    let mut latency = Latency::default();
+    latency.record(i64::MIN, i64::MAX);
+    latency.record(0, i64::MAX);
+    assert_eq!(latency.count, 2);
+    assert_eq!(
+        (latency.total, latency.min, latency.max),
+        (i64::MAX, i64::MAX, i64::MAX)
+    );
+    latency.record(i64::MAX, i64::MIN);
+    latency.record(500, 100);
+    assert_eq!(latency.count, 2);
+}
+
+#[test]
+fn dip_switches_rejected() {
+    let mut remote = Remote::new("secret");
+    let packet = message("dip4", "secret", true, None).encode();
+    assert_eq!(remote.handle(&packet, 0), None);
+    assert_eq!(
+        (remote.accepted, remote.rejected, remote.malformed),
+        (0, 0, 1)
+    );
+}
+
+#[test]
+fn poll_is_bounded() {
+    let mut remote = Remote::bind("127.0.0.1:0", "secret").unwrap();
+    let addr = remote.socket.as_ref().unwrap().local_addr().unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let packet = message("credit", "secret", true, None).encode();
+    for _ in 0..MAX_MESSAGES + 10 {
+        sender.send_to(&packet, addr).unwrap();
+    }
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(remote.poll().len(), MAX_MESSAGES);
+    assert_eq!(remote.poll().len(), 10);
+}
@@ -0,0 +1,156 @@
+//! Rewind history: a machine state per frame, kept compressed. Between frames little of the 16 KB changes outside the
+//! framebuffer, so each older state is kept as the XOR with the state after it, run-length encoded: a run of
+//! unchanged bytes, then a count of changed bytes and the changed bytes XORed, the lengths as LEB128 numbers. The
+//! newest state is kept whole, and rewinding undoes the deltas from it, dropping the oldest when over a budget.
+
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod tests;
+
+/// Bytes of history kept by default, minutes of play at the rate measured by `--bench-core`
+pub const BUDGET: usize = 32 << 20;
+
+/// Append a number as LEB128: 7 bits a byte, low first, the high bit set on all but the last byte
+fn put_number(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Read a LEB128 number at `pos`, advancing it
+fn number(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut n = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        n |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
+
+/// Run-length encoded XOR of two states of the same length
+pub fn delta(a: &[u8], b: &[u8]) -> Vec<u8> {
+    assert_eq!(a.len(), b.len(), "States of different lengths");
+    let mut out = vec![];
+    let mut i = 0;
+    while i < a.len() {
+        let same = a[i..]
+            .iter()
+            .zip(&b[i..])
+            .take_while(|(x, y)| x == y)
+            .count();
+        let changed = a[i + same..]
+            .iter()
+            .zip(&b[i + same..])
+            .take_while(|(x, y)| x != y)
+            .count();
+        put_number(&mut out, same);
+        put_number(&mut out, changed);
+        let start = i + same;
+        out.extend((start..start + changed).map(|j| a[j] ^ b[j]));
+        i = start + changed;
+    }
+    out
+}
+
+/// Apply a delta to a state, giving the other state of the delta. None if the delta does not fit the state.
+pub fn apply(state: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut out = state.to_vec();
+    let (mut i, mut pos) = (0, 0);
+    while pos < delta.len() {
+        i += number(delta, &mut pos)?;
+        let changed = number(delta, &mut pos)?;
+        let xor = delta.get(pos..pos.checked_add(changed)?)?;
+        for (byte, x) in out.get_mut(i..i + changed)?.iter_mut().zip(xor) {
+            *byte ^= x;
+        }
+        i += changed;
+        pos += changed;
+    }
+    Some(out)
+}
+
+/// States of the frames played, newest last
+#[derive(Clone, Debug)]
+pub struct History {
+    /// Bytes kept at most, the newest state always is
+    pub budget: usize,
+    newest: Option<Vec<u8>>,
+    /// Each older state as the delta to the state after it, oldest first
+    deltas: VecDeque<Vec<u8>>,
+    /// Bytes of the deltas
+    bytes: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(BUDGET)
+    }
+}
+
+impl History {
+    pub fn new(budget: usize) -> Self {
+        History {
+            budget,
+            newest: None,
+            deltas: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Keep the state of a frame. The history starts over when the length of the states changes, like on loading a
+    /// machine with other peripherals.
+    pub fn push(&mut self, state: Vec<u8>) {
+        match self.newest.take() {
+            Some(newest) if newest.len() == state.len() => {
+                let delta = delta(&newest, &state);
+                self.bytes += delta.len();
+                self.deltas.push_back(delta);
+            }
+            _ => self.clear(),
+        }
+        self.newest = Some(state);
+        while self.bytes() > self.budget {
+            match self.deltas.pop_front() {
+                Some(oldest) => self.bytes -= oldest.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Take the newest state, the one before it becomes the newest
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let newest = self.newest.take()?;
+        if let Some(delta) = self.deltas.pop_back() {
+            self.bytes -= delta.len();
+            self.newest = apply(&newest, &delta);
+        }
+        Some(newest)
+    }
+
+    /// States kept
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.newest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    /// Bytes kept, the newest state whole and the others compressed
+    pub fn bytes(&self) -> usize {
+        self.bytes + self.newest.as_ref().map_or(0, Vec::len)
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.bytes = 0;
+    }
+}
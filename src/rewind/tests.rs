@@ -0,0 +1,87 @@
+use super::*;
+use crate::{cpu::Cpu, FPS, FREQ};
+
+#[test]
+fn numbers() {
+    let mut out = vec![];
+    for n in [0, 0x7f, 0x80, 300, 0x4200] {
+        put_number(&mut out, n);
+    }
+    assert_eq!(out[..5], [0x00, 0x7f, 0x80, 0x01, 0xac]);
+    let mut pos = 0;
+    let read: Vec<_> = (0..5).map(|_| number(&out, &mut pos).unwrap()).collect();
+    assert_eq!(read, [0, 0x7f, 0x80, 300, 0x4200]);
+    assert_eq!(number(&[0x80], &mut 0), None);
+}
+
+#[test]
+fn delta_and_apply() {
+    let a = [1, 2, 3, 4, 5, 6, 7, 8];
+    let b = [1, 2, 0, 0, 5, 6, 7, 9];
+    let d = delta(&a, &b);
+    // 2 unchanged, 2 changed, 3 unchanged, 1 changed
+    assert_eq!(d, [2, 2, 3, 4, 3, 1, 1]);
+    assert_eq!(apply(&a, &d).unwrap(), b);
+    assert_eq!(apply(&b, &d).unwrap(), a);
+    assert_eq!(delta(&a, &a), [8, 0]);
+    assert!(delta(&[], &[]).is_empty());
+    // Runs past the end of the state or of the delta
+    assert_eq!(apply(&a[..4], &d), None);
+    assert_eq!(apply(&a, &d[..3]), None);
+}
+
+#[test]
+fn rewind_states() {
+    let mut history = History::default();
+    assert_eq!(history.pop(), None);
+    for i in 0..10u8 {
+        history.push(vec![i, 0, 0, i / 2]);
+    }
+    assert_eq!(history.len(), 10);
+    for i in (0..10u8).rev() {
+        assert_eq!(history.pop(), Some(vec![i, 0, 0, i / 2]));
+    }
+    assert!(history.is_empty());
+    assert_eq!(history.bytes(), 0);
+
+    // Another length starts over
+    history.push(vec![1; 4]);
+    history.push(vec![2; 5]);
+    assert_eq!(history.len(), 1);
+}
+
+#[test]
+fn over_budget() {
+    let mut history = History::new(20);
+    for i in 0..10u8 {
+        history.push(vec![i; 8]);
+    }
+    // The newest state of 8 bytes and one delta of 10 bytes fit
+    assert!(history.bytes() <= 20);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.pop(), Some(vec![9; 8]));
+    assert_eq!(history.pop(), Some(vec![8; 8]));
+    assert_eq!(history.pop(), None);
+}
+
+#[test]
+fn frames_of_a_program() {
+    // Counts up the bytes of a line of the framebuffer: LXI H,2400 ; INR M ; INR L ; JMP 0003
+    let mut cpu = Cpu::new(vec![0x21, 0x00, 0x24, 0x34, 0x2C, 0xC3, 0x03, 0x00]);
+    let mut history = History::default();
+    let mut states = vec![];
+    for _ in 0..30 {
+        let mut cycles = 0;
+        while cycles < FREQ / FPS {
+            cycles += cpu.step();
+        }
+        states.push(cpu.save_state());
+        history.push(cpu.save_state());
+    }
+    // Each frame changes the line and a few registers of the 17 KB state
+    assert!(history.bytes() < states[0].len() + 30 * 300);
+    while let Some(state) = history.pop() {
+        assert_eq!(Some(state), states.pop());
+    }
+    assert!(states.is_empty());
+}
@@ -0,0 +1,92 @@
+//! Run-ahead to cut a frame of input lag: the machine runs a frame ahead of the real one, predicting that the inputs
+//! stay the same, and that frame is presented. When the real inputs turn out different the machine is rolled back to
+//! the savestate taken before the speculative frame, and the frame is run again with them.
+//!
+//! Changes made every frame (cheats) are to be made after `resume` and again before the speculative frame, so they
+//! don't count as changes from outside, and sounds are to be played from the real frames only.
+
+use crate::{cpu::Cpu, NPORTS};
+
+/// Emulator state carried from frame to frame besides the machine: the cycles run past the end of the frame before,
+/// and the cycles of the frame in interrupt handlers and in the main loop
+pub type Timing = (u32, (u32, u32));
+
+#[cfg(test)]
+mod tests;
+
+/// Frame run ahead of the real one
+#[derive(Debug, Clone, PartialEq)]
+struct Speculation {
+    /// Savestate before the frame
+    before: Vec<u8>,
+    /// Inputs the frame was run with
+    inputs: [u8; NPORTS],
+    /// Savestate after the frame, to tell if the machine was changed since (poke, savestate load)
+    after: Vec<u8>,
+    /// Timing before the frame
+    timing: Timing,
+}
+
+/// Run-ahead state and counts
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RunAhead {
+    speculation: Option<Speculation>,
+    /// Real frames run
+    pub frames: u64,
+    /// Speculative frames rolled back
+    pub rollbacks: u64,
+}
+
+/// Inputs of the machine
+fn inputs(cpu: &Cpu) -> [u8; NPORTS] {
+    std::array::from_fn(|port| cpu.get_bus_in(port))
+}
+
+impl RunAhead {
+    /// Start a real frame with the inputs set. Returns whether the frame must be run: not when the speculative frame
+    /// ran with the same inputs (it is the real frame), but after rolling back the machine and the timing when the
+    /// inputs differ.
+    pub fn resume(&mut self, cpu: &mut Cpu, timing: &mut Timing) -> bool {
+        self.frames += 1;
+        let Some(speculation) = self.speculation.take() else {
+            return true;
+        };
+        // Changed from outside, the machine is taken as it is
+        if cpu.save_state() != speculation.after {
+            return false;
+        }
+        if inputs(cpu) == speculation.inputs {
+            return false;
+        }
+        self.rollbacks += 1;
+        cpu.load_state(&speculation.before)
+            .expect("Savestate of the same machine");
+        *timing = speculation.timing;
+        true
+    }
+
+    /// Take a savestate and the timing before running the speculative frame
+    pub fn speculate(&mut self, cpu: &Cpu, timing: Timing) {
+        self.speculation = Some(Speculation {
+            before: cpu.save_state(),
+            inputs: inputs(cpu),
+            after: vec![],
+            timing,
+        });
+    }
+
+    /// The speculative frame has been run
+    pub fn speculated(&mut self, cpu: &Cpu) {
+        if let Some(speculation) = &mut self.speculation {
+            speculation.after = cpu.save_state();
+        }
+    }
+
+    /// Summary printed on quit
+    pub fn report(&self) -> String {
+        format!(
+            "Run-ahead: {} frames, {} rolled back\n",
+            self.frames, self.rollbacks
+        )
+    }
+}
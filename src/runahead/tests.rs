@@ -0,0 +1,110 @@
+use super::*;
+
+/// Copies input port 1 to 0x2000 and counts frames in 0x2001
+fn setup() -> Cpu {
+    Cpu::new(vec![
+        0xDB, 0x01, 0x32, 0x00, 0x20, 0x3A, 0x01, 0x20, 0x3C, 0x32, 0x01, 0x20, 0xC3, 0x00, 0x00,
+    ])
+}
+
+fn run_frame(cpu: &mut Cpu) {
+    for _ in 0..6 {
+        cpu.step();
+    }
+}
+
+/// Real frame and speculative frame, as in the main loop, with the cheats
+fn frame_with(run_ahead: &mut RunAhead, cpu: &mut Cpu, cheats: &[(usize, u8)]) {
+    let mut timing = (0, (0, 0));
+    if run_ahead.resume(cpu, &mut timing) {
+        cheats
+            .iter()
+            .for_each(|&(addr, value)| cpu.poke(addr, value));
+        run_frame(cpu);
+    }
+    run_ahead.speculate(cpu, timing);
+    cheats
+        .iter()
+        .for_each(|&(addr, value)| cpu.poke(addr, value));
+    run_frame(cpu);
+    run_ahead.speculated(cpu);
+}
+
+fn frame(run_ahead: &mut RunAhead, cpu: &mut Cpu) {
+    frame_with(run_ahead, cpu, &[]);
+}
+
+#[test]
+fn keeps_predicted_frame() {
+    let mut cpu = setup();
+    let mut run_ahead = RunAhead::default();
+    cpu.set_bus_in(1, 0x10);
+    frame(&mut run_ahead, &mut cpu);
+    frame(&mut run_ahead, &mut cpu);
+    assert_eq!(cpu.memory()[0x2000..0x2002], [0x10, 3]);
+    assert_eq!((run_ahead.frames, run_ahead.rollbacks), (2, 0));
+}
+
+#[test]
+fn rolls_back_on_new_input() {
+    let mut cpu = setup();
+    let mut run_ahead = RunAhead::default();
+    cpu.set_bus_in(1, 0x10);
+    frame(&mut run_ahead, &mut cpu);
+    cpu.set_bus_in(1, 0x20);
+    assert!(run_ahead.resume(&mut cpu, &mut (0, (0, 0))));
+    assert_eq!(cpu.memory()[0x2000..0x2002], [0x10, 1]);
+    run_frame(&mut cpu);
+    assert_eq!(cpu.memory()[0x2000..0x2002], [0x20, 2]);
+    assert_eq!(run_ahead.rollbacks, 1);
+}
+
+#[test]
+fn keeps_changes_from_outside() {
+    let mut cpu = setup();
+    let mut run_ahead = RunAhead::default();
+    frame(&mut run_ahead, &mut cpu);
+    cpu.poke(0x2001, 0x42);
+    cpu.set_bus_in(1, 0x20);
+    assert!(!run_ahead.resume(&mut cpu, &mut (0, (0, 0))));
+    assert_eq!(cpu.memory()[0x2001], 0x42);
+    assert_eq!(run_ahead.rollbacks, 0);
+}
+
+#[test]
+fn cheats_are_not_changes_from_outside() {
+    let mut cpu = setup();
+    let mut run_ahead = RunAhead::default();
+    // Keeps the frame count at 0x50 before each frame
+    let cheats = [(0x2001, 0x50)];
+    cpu.set_bus_in(1, 0x10);
+    frame_with(&mut run_ahead, &mut cpu, &cheats);
+    frame_with(&mut run_ahead, &mut cpu, &cheats);
+    cpu.set_bus_in(1, 0x20);
+    frame_with(&mut run_ahead, &mut cpu, &cheats);
+    assert_eq!(cpu.memory()[0x2000..0x2002], [0x20, 0x51]);
+    assert_eq!((run_ahead.frames, run_ahead.rollbacks), (3, 1));
+}
+
+#[test]
+fn rolls_back_the_timing() {
+    let mut cpu = setup();
+    let mut run_ahead = RunAhead::default();
+    cpu.set_bus_in(1, 0x10);
+    run_ahead.speculate(&cpu, (7, (100, 200)));
+    run_frame(&mut cpu);
+    run_ahead.speculated(&cpu);
+
+    let mut timing = (3, (300, 400));
+    cpu.set_bus_in(1, 0x10);
+    assert!(!run_ahead.resume(&mut cpu, &mut timing));
+    assert_eq!(timing, (3, (300, 400)));
+
+    run_ahead.speculate(&cpu, timing);
+    run_frame(&mut cpu);
+    run_ahead.speculated(&cpu);
+    let mut timing = (9, (0, 0));
+    cpu.set_bus_in(1, 0x20);
+    assert!(run_ahead.resume(&mut cpu, &mut timing));
+    assert_eq!(timing, (3, (300, 400)));
+}
@@ -0,0 +1,42 @@
+//! Scaling of the game picture to the window: `nearest` keeps the pixels sharp but makes them uneven at window sizes
+//! that are not a multiple of the display, shimmering as things move, and `linear` is even but blurry. `sharp`
+//! (sharp bilinear) first scales by the largest whole factor that fits with nearest, then the rest of the way with
+//! linear, so only the edges between pixels are blended.
+
+#[cfg(test)]
+mod tests;
+
+/// Way of scaling the game picture
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Scaling {
+    #[default]
+    Nearest,
+    Linear,
+    /// Whole factor with nearest, the rest with linear
+    Sharp,
+}
+
+impl Scaling {
+    pub const ALL: [Scaling; 3] = [Scaling::Nearest, Scaling::Linear, Scaling::Sharp];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Scaling::ALL.into_iter().find(|c| c.name() == s)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scaling::Nearest => "nearest",
+            Scaling::Linear => "linear",
+            Scaling::Sharp => "sharp",
+        }
+    }
+}
+
+/// Whole factor of the first step of sharp scaling of a picture to its size on the output, both as (width, height)
+/// in pixels. Never less than 1, pictures shown smaller than they are only get the linear step.
+pub fn prescale(output: (u32, u32), picture: (u32, u32)) -> u32 {
+    let factor = |o: u32, p: u32| o / p.max(1);
+    factor(output.0, picture.0)
+        .min(factor(output.1, picture.1))
+        .max(1)
+}
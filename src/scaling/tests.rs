@@ -0,0 +1,20 @@
+use super::*;
+
+#[test]
+fn parse_scalings() {
+    for scaling in Scaling::ALL {
+        assert_eq!(Scaling::parse(scaling.name()), Some(scaling));
+    }
+    assert_eq!(Scaling::parse("bilinear"), None);
+}
+
+#[test]
+fn prescale_factor() {
+    // 2.5 times in a window of 560x640
+    assert_eq!(prescale((560, 640), (224, 256)), 2);
+    assert_eq!(prescale((672, 768), (224, 256)), 3);
+    // Limited by the height in a wide window
+    assert_eq!(prescale((1920, 1080), (224, 256)), 4);
+    assert_eq!(prescale((100, 100), (224, 256)), 1);
+    assert_eq!(prescale((0, 0), (0, 0)), 1);
+}
@@ -0,0 +1,115 @@
+//! Serial console for homebrew and test programs, in the style of a simple UART: bytes written to the data port go to
+//! the terminal, bytes typed (or sent by the frontend) are read from it, and a status port tells whether a byte is
+//! waiting. Lets a program print diagnostics through the emulator.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Status bit set when a byte is waiting to be read
+pub const RX_READY: u8 = 0x01;
+/// Status bit set when a byte can be written, always
+pub const TX_READY: u8 = 0x02;
+
+/// The serial port of the machine
+#[derive(Clone, Debug, PartialEq)]
+pub struct Serial {
+    /// Port reading the next byte received and writing a byte to send
+    pub data: usize,
+    /// Port reading the status bits
+    pub status: usize,
+    /// Bytes received, not read yet
+    input: VecDeque<u8>,
+    /// Bytes sent, not taken yet
+    output: Vec<u8>,
+}
+
+impl Serial {
+    pub fn new(data: usize, status: usize) -> Self {
+        Serial {
+            data,
+            status,
+            input: VecDeque::new(),
+            output: vec![],
+        }
+    }
+
+    /// Read from the data port: the next byte received, 0 when none is waiting
+    pub fn read(&mut self) -> u8 {
+        self.input.pop_front().unwrap_or(0)
+    }
+
+    /// Written to the data port
+    pub fn write(&mut self, data: u8) {
+        self.output.push(data);
+    }
+
+    /// Read from the status port
+    pub fn status(&self) -> u8 {
+        TX_READY | if self.input.is_empty() { 0 } else { RX_READY }
+    }
+
+    /// Bytes received from outside, read by the program in order
+    pub fn receive(&mut self, data: &[u8]) {
+        self.input.extend(data);
+    }
+
+    /// Bytes sent by the program since last taken
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Drop the bytes in flight, as at power on
+    pub fn clear(&mut self) {
+        self.input.clear();
+        self.output.clear();
+    }
+}
+
+/// Bytes for the serial port from another thread, e.g. the terminal or the frontend
+#[derive(Debug)]
+pub struct Console {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Console { sender, receiver }
+    }
+}
+
+impl Console {
+    /// Console fed by a thread reading the standard input
+    pub fn stdin() -> Self {
+        let console = Console::default();
+        let sender = console.sender();
+        thread::spawn(move || {
+            let mut buffer = [0; 256];
+            let mut stdin = io::stdin();
+            while let Ok(n @ 1..) = stdin.read(&mut buffer) {
+                if sender.send(buffer[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        console
+    }
+
+    /// Sending end for the frontend or another thread
+    pub fn sender(&self) -> Sender<Vec<u8>> {
+        self.sender.clone()
+    }
+
+    /// All bytes sent since the last call
+    pub fn take(&self) -> Vec<u8> {
+        self.receiver.try_iter().flatten().collect()
+    }
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn data_and_status() {
+    let mut serial = Serial::new(6, 7);
+    assert_eq!(serial.status(), TX_READY);
+    assert_eq!(serial.read(), 0);
+    serial.receive(b"hi");
+    assert_eq!(serial.status(), TX_READY | RX_READY);
+    assert_eq!((serial.read(), serial.read()), (b'h', b'i'));
+    assert_eq!(serial.status(), TX_READY);
+    serial.write(b'o');
+    serial.write(b'k');
+    assert_eq!(serial.take_output(), b"ok");
+    assert!(serial.take_output().is_empty());
+    serial.receive(b"x");
+    serial.clear();
+    assert_eq!(serial.status(), TX_READY);
+}
+
+#[test]
+fn console() {
+    let console = Console::default();
+    let sender = console.sender();
+    assert!(console.take().is_empty());
+    sender.send(b"ab".to_vec()).unwrap();
+    sender.send(b"c".to_vec()).unwrap();
+    assert_eq!(console.take(), b"abc");
+}
@@ -0,0 +1,114 @@
+//! Graceful shutdown: Ctrl+C, SIGTERM and closing the console on Windows ask the emulator to quit at the end of the
+//! frame, so the user profile, recordings, logs and statistics are saved as on a normal quit instead of the process
+//! dying in the middle of writing them. A second Ctrl+C while shutting down ends the process at once.
+
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Quit requested by a signal or the console
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Everything is saved, the console may close
+static DONE: AtomicBool = AtomicBool::new(false);
+
+/// Ask the emulator to quit at the end of the frame
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Quit has been requested
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Shutdown is complete
+pub fn done() {
+    DONE.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{ffi::c_int, sync::atomic::Ordering};
+
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    /// Only async-signal-safe calls here
+    extern "C" fn handle(signum: c_int) {
+        if super::REQUESTED.swap(true, Ordering::SeqCst) {
+            unsafe { _exit(128 + signum) }
+        }
+    }
+
+    pub fn install() {
+        unsafe {
+            signal(SIGINT, handle);
+            signal(SIGTERM, handle);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{sync::atomic::Ordering, thread::sleep, time::Duration};
+
+    const CTRL_CLOSE_EVENT: u32 = 2;
+
+    extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    /// Runs on a thread of its own. The process ends when the handler returns from a console close, so it waits
+    /// for the shutdown (Windows allows about 5 seconds).
+    unsafe extern "system" fn handle(event: u32) -> i32 {
+        super::request();
+        if event == CTRL_CLOSE_EVENT {
+            for _ in 0..50 {
+                if super::DONE.load(Ordering::SeqCst) {
+                    break;
+                }
+                sleep(Duration::from_millis(100));
+            }
+        }
+        1
+    }
+
+    pub fn install() {
+        unsafe {
+            SetConsoleCtrlHandler(Some(handle), 1);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn install() {}
+}
+
+/// Handle Ctrl+C, SIGTERM and console close by requesting a quit. Installed before SDL, which then leaves the
+/// signals alone.
+pub fn install() {
+    platform::install();
+}
+
+/// Write a file through a temporary file renamed over it, so it is never left half written
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
@@ -0,0 +1,20 @@
+use super::*;
+
+#[test]
+fn write_replaces_file() {
+    let dir = std::env::temp_dir().join(format!("inv8080rs-shutdown-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("profile.txt");
+    write(&path, "first").unwrap();
+    write(&path, "second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    assert!(!dir.join("profile.txt.tmp").exists());
+    assert!(write(dir.join("missing").join("file"), "x").is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn request_quit() {
+    request();
+    assert!(requested());
+}
@@ -0,0 +1,115 @@
+//! Long running soak test of the machine, checking invariants while the game runs in attract mode
+
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{cpu::Cpu, FPS, FRAMEBUFFER, FREQ, ROM, STACK};
+
+#[cfg(test)]
+mod tests;
+
+/// Frames the display may stay unchanged before it counts as frozen
+pub const FROZEN_FRAMES: u32 = 30 * FPS;
+
+/// A broken invariant
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// Program counter outside ROM
+    ProgramCounter(usize),
+    /// Stack pointer outside the stack (once set)
+    StackPointer(usize),
+    /// The display has not changed for this many frames
+    FrozenDisplay(u32),
+    /// The CPU panicked, e.g. on an unimplemented instruction
+    Panic(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::ProgramCounter(pc) => write!(f, "Program counter {pc:04X} outside ROM"),
+            Violation::StackPointer(sp) => write!(f, "Stack pointer {sp:04X} outside stack"),
+            Violation::FrozenDisplay(frames) => {
+                write!(f, "Display unchanged for {frames} frames")
+            }
+            Violation::Panic(message) => write!(f, "Panic: {message}"),
+        }
+    }
+}
+
+/// Check the program counter and stack pointer. The stack pointer is 0 until the program sets it.
+pub fn check(cpu: &Cpu) -> Option<Violation> {
+    let pc = cpu.program_counter();
+    let sp = cpu.register("SP").unwrap_or_default() as usize;
+    if !ROM.contains(&pc) {
+        Some(Violation::ProgramCounter(pc))
+    } else if sp != 0 && !STACK.contains(&sp) {
+        Some(Violation::StackPointer(sp))
+    } else {
+        None
+    }
+}
+
+/// Run one frame checking the invariants after each instruction
+fn run_frame(cpu: &mut Cpu) -> Option<Violation> {
+    for i in [1, 2] {
+        let mut cycles = 0;
+        while cycles < FREQ / FPS / 2 {
+            cycles += cpu.step();
+            if let Some(violation) = check(cpu) {
+                return Some(violation);
+            }
+        }
+        cpu.interrupt(i);
+    }
+    None
+}
+
+/// Run the rom from power on for a number of frames without input. Each violation is reported with the frame and the machine state,
+/// after which the machine is power cycled (a frozen display only restarts the count). Returns the number of violations.
+pub fn run(
+    rom: &[u8],
+    frames: u64,
+    frozen_frames: u32,
+    mut report: impl FnMut(u64, &Violation, &Cpu),
+) -> usize {
+    let mut cpu = Cpu::new(rom.to_vec());
+    let mut framebuffer = cpu.memory()[FRAMEBUFFER].to_vec();
+    let mut unchanged = 0;
+    let mut violations = 0;
+
+    for frame in 0..frames {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| run_frame(&mut cpu)));
+        let violation = match result {
+            Ok(violation) => violation,
+            Err(payload) => Some(Violation::Panic(
+                payload
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or_default(),
+            )),
+        };
+
+        if cpu.memory()[FRAMEBUFFER] == framebuffer[..] {
+            unchanged += 1;
+        } else {
+            unchanged = 0;
+            framebuffer.copy_from_slice(&cpu.memory()[FRAMEBUFFER]);
+        }
+
+        if let Some(violation) = violation {
+            violations += 1;
+            report(frame, &violation, &cpu);
+            cpu.power_cycle();
+            unchanged = 0;
+        } else if unchanged >= frozen_frames {
+            violations += 1;
+            report(frame, &Violation::FrozenDisplay(unchanged), &cpu);
+            unchanged = 0;
+        }
+    }
+    violations
+}
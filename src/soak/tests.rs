@@ -0,0 +1,80 @@
+use super::*;
+
+/// Run a program, collecting the violations
+fn soak(program: Vec<u8>, frames: u64, frozen_frames: u32) -> Vec<(u64, Violation)> {
+    let mut violations = vec![];
+    let count = run(&program, frames, frozen_frames, |frame, violation, _| {
+        violations.push((frame, violation.clone()))
+    });
+    assert_eq!(count, violations.len());
+    violations
+}
+
+#[test]
+fn check_registers() {
+    let mut cpu = Cpu::new(vec![]);
+    assert_eq!(check(&cpu), None);
+    cpu.jump(0x1FFF);
+    assert_eq!(check(&cpu), None);
+}
+
+#[test]
+fn changing_display_ok() {
+    // Increment the first byte of the framebuffer forever
+    let program = vec![
+        0x31, 0x00, 0x24, // LXI SP,2400h
+        0x21, 0x00, 0x24, // LXI H,2400h
+        0x34, // INR M
+        0xC3, 0x06, 0x00, // JMP 0006h
+    ];
+    assert_eq!(soak(program, 10, 5), vec![]);
+}
+
+#[test]
+fn frozen_display() {
+    let program = vec![0xC3, 0x00, 0x00]; // JMP 0000h
+    assert_eq!(
+        soak(program, 10, 4),
+        vec![
+            (3, Violation::FrozenDisplay(4)),
+            (7, Violation::FrozenDisplay(4))
+        ]
+    );
+}
+
+#[test]
+fn unimplemented_instruction() {
+    let violations = soak(vec![0x08], 2, 100); // Unused opcode
+    assert_eq!(violations.len(), 2);
+    assert!(matches!(&violations[0], (0, Violation::Panic(m)) if m.starts_with("Unimplemented")));
+}
+
+#[test]
+fn stack_pointer_outside_stack() {
+    let violations = soak(vec![0x31, 0x00, 0x30, 0x76], 1, 100); // LXI SP,3000h; HLT
+    assert_eq!(violations, [(0, Violation::StackPointer(0x3000))]);
+}
+
+#[test]
+fn program_counter_outside_rom() {
+    let violations = soak(vec![0xC3, 0x00, 0x20], 1, 100); // JMP 2000h
+                                                           // Debug builds assert on the program counter before the check sees it
+    let violation = if cfg!(debug_assertions) {
+        Violation::Panic("Program counter 2000 outside ROM memory!".to_string())
+    } else {
+        Violation::ProgramCounter(0x2000)
+    };
+    assert_eq!(violations, [(0, violation)]);
+}
+
+#[test]
+fn display_violation() {
+    assert_eq!(
+        Violation::ProgramCounter(0x2000).to_string(),
+        "Program counter 2000 outside ROM"
+    );
+    assert_eq!(
+        Violation::FrozenDisplay(1800).to_string(),
+        "Display unchanged for 1800 frames"
+    );
+}
@@ -0,0 +1,127 @@
+//! Sprite tables of the original game, export to images and import back into the rom
+
+use crate::png::Image;
+
+#[cfg(test)]
+mod tests;
+
+/// A sprite in rom. Each byte is a column of 8 pixels with bit 7 at the top (as displayed).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    /// Name, used as file name when exporting
+    pub name: &'static str,
+    /// Address in rom
+    pub addr: usize,
+    /// Width in pixels (number of bytes)
+    pub width: usize,
+}
+
+/// Known sprites of the game according to Computer Archeology
+pub const SPRITES: [Sprite; 11] = [
+    Sprite {
+        name: "alien_a0",
+        addr: 0x1C00,
+        width: 16,
+    },
+    Sprite {
+        name: "alien_b0",
+        addr: 0x1C10,
+        width: 16,
+    },
+    Sprite {
+        name: "alien_c0",
+        addr: 0x1C20,
+        width: 16,
+    },
+    Sprite {
+        name: "alien_a1",
+        addr: 0x1C30,
+        width: 16,
+    },
+    Sprite {
+        name: "alien_b1",
+        addr: 0x1C40,
+        width: 16,
+    },
+    Sprite {
+        name: "alien_c1",
+        addr: 0x1C50,
+        width: 16,
+    },
+    Sprite {
+        name: "player",
+        addr: 0x1C60,
+        width: 16,
+    },
+    Sprite {
+        name: "player_blowup0",
+        addr: 0x1C70,
+        width: 16,
+    },
+    Sprite {
+        name: "player_blowup1",
+        addr: 0x1C80,
+        width: 16,
+    },
+    Sprite {
+        name: "saucer",
+        addr: 0x1D64,
+        width: 24,
+    },
+    Sprite {
+        name: "saucer_explosion",
+        addr: 0x1D7C,
+        width: 24,
+    },
+];
+
+/// Height of all sprites in pixels
+pub const SPRITE_HEIGHT: u32 = 8;
+
+impl Sprite {
+    /// Export sprite from rom as an image with white pixels on a transparent background
+    pub fn export(&self, rom: &[u8]) -> Result<Image, String> {
+        let mut image = Image::new(self.width as u32, SPRITE_HEIGHT);
+        for (x, byte) in self.bytes(rom.len())?.map(|i| rom[i]).enumerate() {
+            for bit in 0..8 {
+                if byte & (1 << bit) != 0 {
+                    image.set(x as u32, 7 - bit, [0xFF, 0xFF, 0xFF, 0xFF]);
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// Addresses of the sprite, if within a rom of `len` bytes
+    fn bytes(&self, len: usize) -> Result<std::ops::Range<usize>, String> {
+        let range = self.addr..self.addr + self.width;
+        if range.end > len {
+            return Err(format!(
+                "Sprite {} at 0x{:04X} is outside the rom of {len} bytes",
+                self.name, self.addr
+            ));
+        }
+        Ok(range)
+    }
+
+    /// Import sprite from an (edited) image into rom
+    pub fn import(&self, image: &Image, rom: &mut [u8]) -> Result<(), String> {
+        if image.width as usize != self.width || image.height != SPRITE_HEIGHT {
+            return Err(format!(
+                "Sprite {} should be {}x{} pixels",
+                self.name, self.width, SPRITE_HEIGHT
+            ));
+        }
+        let addrs = self.bytes(rom.len())?;
+        for (x, addr) in addrs.enumerate() {
+            let mut byte = 0;
+            for bit in 0..8 {
+                if image.is_lit(x as u32, 7 - bit) {
+                    byte |= 1 << bit;
+                }
+            }
+            rom[addr] = byte;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+#[test]
+fn export_and_import() {
+    let mut rom = vec![0; 0x2000];
+    rom[0x1C60..0x1C70].copy_from_slice(&[
+        0x00, 0x00, 0x0F, 0x1F, 0x1F, 0x1F, 0x1F, 0x7F, 0xFF, 0x7F, 0x1F, 0x1F, 0x1F, 0x1F, 0x0F,
+        0x00,
+    ]);
+    let player = SPRITES[6];
+    let image = player.export(&rom).unwrap();
+    assert_eq!((image.width, image.height), (16, 8));
+    assert!(image.is_lit(8, 0));
+    assert!(!image.is_lit(7, 0));
+    assert!(image.is_lit(7, 1));
+    assert!(image.is_lit(2, 7));
+    assert!(!image.is_lit(2, 3));
+    assert!(!image.is_lit(0, 7));
+
+    let mut imported = vec![0; 0x2000];
+    assert_eq!(player.import(&image, &mut imported), Ok(()));
+    assert_eq!(imported, rom);
+}
+
+#[test]
+fn import_wrong_size() {
+    let mut rom = vec![0; 0x2000];
+    assert!(SPRITES[0].import(&Image::new(8, 8), &mut rom).is_err());
+}
+
+#[test]
+fn sprites_in_rom() {
+    for sprite in SPRITES {
+        assert!(sprite.addr + sprite.width <= 0x2000);
+    }
+}
+
+#[test]
+fn outside_rom() {
+    let mut rom = vec![0; 0x1000];
+    assert_eq!(
+        SPRITES[6].export(&rom),
+        Err("Sprite player at 0x1C60 is outside the rom of 4096 bytes".into())
+    );
+    let image = Image::new(SPRITES[6].width as u32, SPRITE_HEIGHT);
+    assert!(SPRITES[6].import(&image, &mut rom).is_err());
+}
@@ -0,0 +1,142 @@
+//! Stack usage analysis: the lowest stack pointer reached, stack overflows and the deepest call chain observed. Also
+//! the warnings about the stack red zone given by the CPU while running: the stack pointer leaving the stack, and
+//! pushes overwriting variables of the RAM map.
+
+use std::fmt::{self, Write};
+
+use crate::{
+    cpu::{Cpu, StackFrame},
+    ram_map, STACK,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Where the stack pointer went astray
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Overflow {
+    /// Address of the instruction
+    pub pc: usize,
+    /// Stack pointer after it
+    pub sp: usize,
+}
+
+/// What to do when the stack leaves its region
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum StackPolicy {
+    /// Keep a warning and continue, for slightly nonconforming roms
+    #[default]
+    Warn,
+    /// Panic, for tests of roms that must keep to the stack
+    Panic,
+}
+
+/// The stack left its region, at the instruction at `pc`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StackWarning {
+    /// The stack pointer was set outside the stack
+    Outside { pc: usize, sp: usize },
+    /// A push overwrote a variable of the RAM map
+    Clobber { pc: usize, addr: usize },
+}
+
+impl fmt::Display for StackWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StackWarning::Outside { pc, sp } => write!(
+                f,
+                "PC {pc:04X}: stack pointer {sp:04X} outside the stack {:04X}-{:04X}",
+                STACK.start(),
+                STACK.end()
+            ),
+            StackWarning::Clobber { pc, addr } => write!(
+                f,
+                "PC {pc:04X}: push overwrote {addr:04X} {}",
+                ram_map::annotate(addr).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Stack usage observed while running
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StackUsage {
+    /// Lowest stack pointer once set by the program
+    pub min_sp: Option<usize>,
+    /// Times the stack pointer went below the stack into the variables, with the first time
+    pub overflows: (u64, Option<Overflow>),
+    /// Times the stack pointer went above the stack, so pushes write into the framebuffer, with the first time
+    pub framebuffer: (u64, Option<Overflow>),
+    /// Deepest call stack, outermost call first
+    pub deepest: Vec<StackFrame>,
+    /// Stack pointer after the previous instruction
+    last_sp: usize,
+}
+
+impl StackUsage {
+    /// Update after the instruction at `pc` was executed. The stack pointer is 0 until the program sets it.
+    pub fn update(&mut self, cpu: &Cpu, pc: usize) {
+        self.track_sp(pc, cpu.register("SP").unwrap_or_default() as usize);
+        if cpu.call_stack().len() > self.deepest.len() {
+            self.deepest = cpu.call_stack().to_vec();
+        }
+    }
+
+    fn track_sp(&mut self, pc: usize, sp: usize) {
+        if sp != 0 {
+            self.min_sp = Some(self.min_sp.map_or(sp, |min| min.min(sp)));
+            let overflow = Overflow { pc, sp };
+            let (below, above) = (sp < *STACK.start(), sp > *STACK.end());
+            if below && self.last_sp >= *STACK.start() {
+                self.overflows.0 += 1;
+                self.overflows.1.get_or_insert(overflow);
+            }
+            if above && self.last_sp <= *STACK.end() {
+                self.framebuffer.0 += 1;
+                self.framebuffer.1.get_or_insert(overflow);
+            }
+        }
+        self.last_sp = sp;
+    }
+
+    /// Human readable report
+    pub fn report(&self) -> String {
+        let mut r = String::new();
+        match self.min_sp {
+            Some(sp) => {
+                let used = STACK.end().saturating_sub(sp);
+                let size = STACK.count();
+                let _ = writeln!(
+                    r,
+                    "Lowest stack pointer: {sp:04X} ({used} of {size} bytes used)"
+                );
+            }
+            None => {
+                let _ = writeln!(r, "Stack pointer never set");
+            }
+        }
+        let astray = [
+            (self.overflows, "below the stack"),
+            (self.framebuffer, "into the framebuffer"),
+        ];
+        for ((times, first), place) in astray {
+            let _ = write!(r, "Stack overflows {place}: {times}");
+            match first {
+                Some(Overflow { pc, sp }) => {
+                    let _ = writeln!(r, ", first at {pc:04X} with SP {sp:04X}");
+                }
+                None => r.push('\n'),
+            }
+        }
+        let _ = writeln!(r, "Deepest call chain ({} calls):", self.deepest.len());
+        for frame in &self.deepest {
+            let kind = if frame.interrupt { " interrupt" } else { "" };
+            let _ = writeln!(
+                r,
+                "  {:04X}{kind} (return to {:04X}, SP {:04X})",
+                frame.target, frame.return_address, frame.sp
+            );
+        }
+        r
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn min_sp_and_deepest_calls() {
+    // LXI SP,2400h; CALL 0008h; HLT; CALL 000Ch; RET
+    let mut cpu = Cpu::new(vec![
+        0x31, 0x00, 0x24, 0xCD, 0x08, 0x00, 0x76, 0x00, 0xCD, 0x0C, 0x00, 0x00, 0xC9,
+    ]);
+    cpu.enable_stack_usage();
+    for _ in 0..6 {
+        cpu.step();
+    }
+    let usage = cpu.stack_usage().unwrap();
+    assert_eq!(usage.min_sp, Some(0x23FC));
+    assert_eq!(
+        usage.deepest.iter().map(|f| f.target).collect::<Vec<_>>(),
+        [0x0008, 0x000C]
+    );
+    assert_eq!(usage.overflows, (0, None));
+    let report = usage.report();
+    assert!(report.starts_with("Lowest stack pointer: 23FC (4 of 256 bytes used)\n"));
+    assert!(report.contains("Stack overflows below the stack: 0\n"));
+    assert!(report.contains("Deepest call chain (2 calls):\n  0008 (return to 0006, SP 23FE)\n"));
+}
+
+#[test]
+fn overflows() {
+    let mut usage = StackUsage::default();
+    usage.update(&Cpu::new(vec![]), 0);
+    assert_eq!(usage.min_sp, None);
+    // Debug builds of the cpu panic on a stack pointer outside the stack
+    for (pc, sp) in [
+        (1, 0x2400),
+        (2, 0x22FF),
+        (3, 0x22FE),
+        (4, 0x2301),
+        (5, 0x2200),
+        (6, 0x2402),
+    ] {
+        usage.track_sp(pc, sp);
+    }
+    assert_eq!(usage.min_sp, Some(0x2200));
+    assert_eq!(usage.overflows, (2, Some(Overflow { pc: 2, sp: 0x22FF })));
+    assert_eq!(usage.framebuffer, (1, Some(Overflow { pc: 6, sp: 0x2402 })));
+    assert!(usage
+        .report()
+        .contains("Stack overflows into the framebuffer: 1, first at 0006 with SP 2402\n"));
+}
@@ -0,0 +1,36 @@
+//! Savestates as a type of their own, for the stable API at the crate root: the bytes of `Cpu::save_state`, checked
+//! to be a savestate when read back from storage.
+
+use crate::cpu::{Cpu, STATE_MAGIC};
+
+#[cfg(test)]
+mod tests;
+
+/// The state of a machine: memory, registers, output ports and the state of the peripherals of the board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State(Vec<u8>);
+
+impl State {
+    /// The state of the machine now
+    pub fn save(cpu: &Cpu) -> Self {
+        State(cpu.save_state())
+    }
+
+    /// Put the machine back in the state, which should be saved from the same board
+    pub fn restore(&self, cpu: &mut Cpu) -> Result<(), String> {
+        cpu.load_state(&self.0)
+    }
+
+    /// A state read back from storage
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        if !bytes.starts_with(STATE_MAGIC) {
+            return Err("Not a savestate".to_string());
+        }
+        Ok(State(bytes))
+    }
+
+    /// The bytes to store
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
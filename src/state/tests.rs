@@ -0,0 +1,21 @@
+use super::*;
+
+#[test]
+fn save_and_restore() {
+    // MVI A,42
+    let mut cpu = Cpu::new(vec![0x3E, 42]);
+    let state = State::save(&cpu);
+    cpu.step();
+    assert_ne!(State::save(&cpu), state);
+    let stored = State::from_bytes(state.as_bytes().to_vec()).unwrap();
+    assert_eq!(stored, state);
+    stored.restore(&mut cpu).unwrap();
+    assert_eq!(State::save(&cpu), state);
+
+    assert_eq!(
+        State::from_bytes(vec![1, 2, 3]),
+        Err("Not a savestate".to_string())
+    );
+    let truncated = State::from_bytes(state.as_bytes()[..100].to_vec()).unwrap();
+    assert!(truncated.restore(&mut cpu).is_err());
+}
@@ -0,0 +1,85 @@
+//! Statistics of a play session: play time, frames, instructions, credits and best score, summarized on quit
+
+use std::{fmt::Write, time::Duration};
+
+use crate::{batch::player_score, cpu::Cpu, ports::INVADERS};
+
+#[cfg(test)]
+mod tests;
+
+/// Counters updated every frame
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    /// Frames run
+    pub frames: u64,
+    /// Instructions executed
+    pub instructions: u64,
+    /// Coins inserted
+    pub credits: u32,
+    /// Highest score reached by either player
+    pub best_score: u32,
+    /// Instruction counter of the cpu last frame
+    last_instructions: u64,
+    /// Coin input was active last frame
+    coin: bool,
+}
+
+impl Stats {
+    /// Count a frame that has just been run
+    pub fn frame(&mut self, cpu: &Cpu) {
+        self.frames += 1;
+        // The counter starts over when the machine is replaced, e.g. by a reloaded rom
+        let now = cpu.instructions();
+        self.instructions += now.checked_sub(self.last_instructions).unwrap_or(now);
+        self.last_instructions = now;
+        let coin = INVADERS.find("credit").is_some_and(|i| i.active(cpu));
+        if coin && !self.coin {
+            self.credits += 1;
+        }
+        self.coin = coin;
+        self.best_score = self
+            .best_score
+            .max(player_score(cpu, 1))
+            .max(player_score(cpu, 2));
+    }
+
+    /// Average frames per second over the play time
+    pub fn fps(&self, play_time: Duration) -> f64 {
+        match play_time.as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.frames as f64 / secs,
+        }
+    }
+
+    /// Human readable summary
+    pub fn summary(&self, play_time: Duration) -> String {
+        let secs = play_time.as_secs();
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "Play time: {}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+        let _ = writeln!(s, "Frames: {}", self.frames);
+        let _ = writeln!(s, "Average FPS: {:.1}", self.fps(play_time));
+        let _ = writeln!(s, "Instructions: {}", self.instructions);
+        let _ = writeln!(s, "Credits inserted: {}", self.credits);
+        let _ = writeln!(s, "Best score: {:04}", self.best_score);
+        s
+    }
+
+    /// Summary as a JSON object
+    pub fn to_json(&self, play_time: Duration) -> String {
+        format!(
+            "{{\"play_time\": {:.3}, \"frames\": {}, \"average_fps\": {:.3}, \"instructions\": {}, \"credits\": {}, \"best_score\": {}}}\n",
+            play_time.as_secs_f64(),
+            self.frames,
+            self.fps(play_time),
+            self.instructions,
+            self.credits,
+            self.best_score
+        )
+    }
+}
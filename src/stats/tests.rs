@@ -0,0 +1,53 @@
+use super::*;
+
+#[test]
+fn count_frames_credits_and_scores() {
+    let mut cpu = Cpu::new(vec![0; 0x10]);
+    cpu.step();
+    let mut stats = Stats::default();
+    let coin = INVADERS.find("credit").unwrap();
+
+    stats.frame(&cpu);
+    coin.set(&mut cpu, true);
+    cpu.step();
+    cpu.step();
+    stats.frame(&cpu);
+    stats.frame(&cpu);
+    coin.set(&mut cpu, false);
+    stats.frame(&cpu);
+    coin.set(&mut cpu, true);
+    stats.frame(&cpu);
+    assert_eq!(stats.frames, 5);
+    assert_eq!(stats.instructions, 3);
+    assert_eq!(stats.credits, 2);
+
+    // Player 2 score 1250, then a lower player 1 score
+    let mut memory = vec![0; 0x2100];
+    memory[0x20FC..0x20FE].copy_from_slice(&[0x50, 0x12]);
+    stats.frame(&Cpu::new(memory.clone()));
+    memory[0x20F8..0x20FE].copy_from_slice(&[0x00, 0x10, 0, 0, 0, 0]);
+    stats.frame(&Cpu::new(memory));
+    assert_eq!(stats.best_score, 1250);
+    assert_eq!(stats.instructions, 3);
+}
+
+#[test]
+fn summary_and_json() {
+    let stats = Stats {
+        frames: 600,
+        instructions: 1234,
+        credits: 1,
+        best_score: 990,
+        ..Default::default()
+    };
+    let play_time = Duration::from_secs(3725);
+    let summary = stats.summary(play_time);
+    assert!(summary.starts_with("Play time: 1:02:05\nFrames: 600\n"));
+    assert!(summary.contains("Average FPS: 0.2\n"));
+    assert!(summary.contains("Best score: 0990\n"));
+    assert_eq!(
+        stats.to_json(Duration::from_secs(10)),
+        "{\"play_time\": 10.000, \"frames\": 600, \"average_fps\": 60.000, \"instructions\": 1234, \"credits\": 1, \"best_score\": 990}\n"
+    );
+    assert_eq!(stats.fps(Duration::ZERO), 0.0);
+}
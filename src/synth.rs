@@ -0,0 +1,53 @@
+//! Synthesized sounds, played when a sample is missing. The ufo of Space Invaders comes from an SN76477 sound chip: a
+//! voltage controlled oscillator (VCO) swept up and down by its super low frequency oscillator (SLF), which makes the
+//! warble. It is modeled with integer phase accumulators, so the same samples come out on every platform.
+
+#[cfg(test)]
+mod tests;
+
+/// Deviation of the square wave from silence, the samples are about as loud
+pub const AMPLITUDE: u8 = 0x30;
+
+/// Parameters of a warble, from the board description
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Warble {
+    /// Lowest and highest frequency of the VCO in Hz
+    pub vco: (u32, u32),
+    /// Frequency of the SLF sweeping the VCO, in hundredths of a Hz
+    pub slf: u32,
+}
+
+/// Position in a cycle as a fraction of 2^32, advanced by `hz / 100` per sample at `rate`
+fn step(hundredths_hz: u64, rate: u32) -> u32 {
+    ((hundredths_hz << 32) / (rate as u64 * 100)) as u32
+}
+
+impl Warble {
+    /// 8-bit unsigned samples of the warble at `rate`, whole SLF cycles lasting about a second
+    pub fn samples(&self, rate: u32) -> Vec<u8> {
+        let slf = self.slf.max(1);
+        let cycles = (slf / 100).max(1) as u64;
+        let len = rate as u64 * 100 * cycles / slf as u64;
+        let (low, high) = self.vco;
+        let slf_step = step(slf as u64, rate);
+        let (mut slf_phase, mut vco_phase) = (0u32, 0u32);
+        (0..len)
+            .map(|_| {
+                // Triangle from 0 to 0xFFFF and back
+                let triangle = if slf_phase < 1 << 31 {
+                    slf_phase >> 15
+                } else {
+                    (u32::MAX - slf_phase) >> 15
+                };
+                let hz = low as u64 + ((high.saturating_sub(low) as u64 * triangle as u64) >> 16);
+                slf_phase = slf_phase.wrapping_add(slf_step);
+                vco_phase = vco_phase.wrapping_add(step(hz * 100, rate));
+                if vco_phase < 1 << 31 {
+                    0x80 + AMPLITUDE
+                } else {
+                    0x80 - AMPLITUDE
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,35 @@
+use super::*;
+
+const UFO: Warble = Warble {
+    vco: (400, 1200),
+    slf: 600,
+};
+
+/// Changes between the two levels of the square wave
+fn edges(samples: &[u8]) -> usize {
+    samples.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+#[test]
+fn whole_cycles_of_a_second() {
+    let samples = UFO.samples(11025);
+    assert_eq!(samples.len(), 11025);
+    assert!(samples
+        .iter()
+        .all(|s| *s == 0x80 + AMPLITUDE || *s == 0x80 - AMPLITUDE));
+    assert_eq!(samples, UFO.samples(11025));
+    let slow = Warble { slf: 150, ..UFO };
+    assert_eq!(slow.samples(11025).len(), 7350);
+}
+
+#[test]
+fn swept_up_and_down() {
+    let samples = UFO.samples(11025);
+    // A sixth of a second per SLF cycle, low at its start and high in the middle
+    let cycle = 11025 / 6;
+    let start = edges(&samples[..cycle / 8]);
+    let middle = edges(&samples[cycle / 2 - cycle / 16..cycle / 2 + cycle / 16]);
+    assert!(middle > start * 2, "{start} {middle}");
+    // About 2 edges per VCO cycle, 400 Hz for the first 1/48 s
+    assert!((14..=24).contains(&start), "{start}");
+}
@@ -0,0 +1,96 @@
+//! Decoding of the characters drawn in the framebuffer into text, using the font of the rom
+
+use crate::{frame::Frame, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+#[cfg(test)]
+mod tests;
+
+/// Address of the font (character tiles) in rom
+pub const FONT: usize = 0x1E00;
+/// Characters of the font in order, `?` for tiles without a text equivalent
+pub const CHARACTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789<> =*Y??????????????????????-";
+
+/// Start of framebuffer in memory
+const VRAM: usize = 0x2400;
+/// Bytes per column of the display
+const ROW: usize = (DISPLAY_HEIGHT / 8) as usize;
+
+/// Decode text drawn in the framebuffer. Characters are found on every row of 8 pixels at any horizontal position.
+/// Returns one line per row containing text, from top to bottom.
+pub fn decode(memory: &[u8]) -> Vec<String> {
+    let tile = |c: usize| &memory[FONT + c * 8..FONT + c * 8 + 8];
+    let width = DISPLAY_WIDTH as usize;
+    let mut lines = vec![];
+
+    for row in (0..ROW).rev() {
+        let column = |x: usize| memory[VRAM + x * ROW + row];
+        let mut line = String::new();
+        let mut blank = 0;
+        let mut x = 0;
+
+        while x < width {
+            let bytes: Vec<u8> = (x..(x + 8).min(width)).map(column).collect();
+            let found = CHARACTERS
+                .chars()
+                .enumerate()
+                .find(|(c, ch)| *ch != ' ' && *ch != '?' && bytes == tile(*c));
+            match found {
+                Some((_, ch)) => {
+                    if !line.is_empty() {
+                        line.extend(std::iter::repeat_n(' ', blank / 8));
+                    }
+                    line.push(ch);
+                    blank = 0;
+                    x += 8;
+                }
+                None => {
+                    blank = if bytes[0] == 0 { blank + 1 } else { 0 };
+                    x += 1;
+                }
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Draw text in the font of the rom into a frame with the top left corner at (x, y), on a cleared background.
+/// Characters missing from the font are left blank, text outside the display is clipped.
+pub fn draw(frame: &mut Frame, memory: &[u8], x: u32, y: u32, text: &str) {
+    for (i, ch) in text.chars().enumerate() {
+        let tile = match CHARACTERS.find(ch) {
+            Some(c) if ch != '?' => &memory[FONT + c * 8..FONT + c * 8 + 8],
+            _ => &[0; 8],
+        };
+        for (col, byte) in tile.iter().enumerate() {
+            for bit in 0..8 {
+                let (px, py) = (x + i as u32 * 8 + col as u32, y + 7 - bit);
+                if px < DISPLAY_WIDTH && py < DISPLAY_HEIGHT {
+                    frame.set(px, py, if byte & (1 << bit) != 0 { 0xff } else { 0 });
+                }
+            }
+        }
+    }
+}
+
+/// Tracks on-screen text and reports lines that appeared since the last update, for screen readers
+#[derive(Debug, Default)]
+pub struct Announcer {
+    previous: Vec<String>,
+}
+
+impl Announcer {
+    /// Update with the current lines and return the lines that are new
+    pub fn update(&mut self, lines: Vec<String>) -> Vec<String> {
+        let new = lines
+            .iter()
+            .filter(|line| !self.previous.contains(line))
+            .cloned()
+            .collect();
+        self.previous = lines;
+        new
+    }
+}
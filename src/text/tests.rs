@@ -0,0 +1,80 @@
+use super::*;
+use crate::MEMORY_SIZE;
+
+/// Memory with a made-up font where each character tile is unique
+fn setup() -> Vec<u8> {
+    let mut memory = vec![0; MEMORY_SIZE];
+    for (c, ch) in CHARACTERS.chars().enumerate() {
+        if ch == ' ' {
+            continue;
+        }
+        let tile = [0x81, c as u8 + 1, 0x42, 0x24, 0x18, 0x24, 0x42, 0x81];
+        memory[FONT + c * 8..FONT + c * 8 + 8].copy_from_slice(&tile);
+    }
+    memory
+}
+
+/// Draw text with the top left corner of the first character at display position (x, row * 8)
+fn draw(memory: &mut [u8], x: usize, row: usize, text: &str) {
+    for (i, ch) in text.chars().enumerate() {
+        let c = CHARACTERS.find(ch).unwrap();
+        for col in 0..8 {
+            memory[VRAM + (x + i * 8 + col) * ROW + (ROW - 1 - row)] = memory[FONT + c * 8 + col];
+        }
+    }
+}
+
+#[test]
+fn decode_empty() {
+    assert!(decode(&setup()).is_empty());
+}
+
+#[test]
+fn decode_text() {
+    let mut memory = setup();
+    draw(&mut memory, 8, 1, "SCORE<1>");
+    draw(&mut memory, 3, 3, "0120");
+    draw(&mut memory, 100, 3, "HI");
+    draw(&mut memory, 0, 31, "CREDIT 05");
+    assert_eq!(decode(&memory), ["SCORE<1>", "0120        HI", "CREDIT 05"]);
+}
+
+#[test]
+fn announcer() {
+    let mut announcer = Announcer::default();
+    let lines = |l: &[&str]| l.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        announcer.update(lines(&["SCORE<1>", "0000", "CREDIT 00"])),
+        ["SCORE<1>", "0000", "CREDIT 00"]
+    );
+    assert!(announcer
+        .update(lines(&["SCORE<1>", "0000", "CREDIT 00"]))
+        .is_empty());
+    assert_eq!(
+        announcer.update(lines(&["SCORE<1>", "0000", "CREDIT 01"])),
+        ["CREDIT 01"]
+    );
+    assert_eq!(
+        announcer.update(lines(&["GAME OVER", "CREDIT 01"])),
+        ["GAME OVER"]
+    );
+}
+
+#[test]
+fn draw_matches_framebuffer() {
+    let mut memory = setup();
+    draw(&mut memory, 5, 2, "SOUNDS MISSING");
+    let expected = Frame::render(&crate::cpu::Cpu::new(memory.clone()));
+
+    let mut frame = Frame::default();
+    super::draw(&mut frame, &memory, 5, 16, "SOUNDS MISSING");
+    assert_eq!(frame, expected);
+
+    // Drawing clears the background, unknown characters are blank and the text is clipped at the edge
+    super::draw(&mut frame, &memory, 5, 16, "??????????????");
+    assert_eq!(frame, Frame::default());
+    super::draw(&mut frame, &memory, 220, 252, "AB");
+    assert_ne!(frame, Frame::default());
+    super::draw(&mut frame, &memory, 220, 252, " ");
+    assert_eq!(frame, Frame::default());
+}
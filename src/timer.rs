@@ -0,0 +1,87 @@
+//! Timer peripheral for homebrew programs: a 32-bit count of ticks since power on, read a byte at a time through a
+//! port. The ticks are derived from the cycles executed, not the host clock, so the count is the same under replay and
+//! restored with savestates. With 1 tick a second it is a real-time clock, with more it times periodic work.
+
+#[cfg(test)]
+mod tests;
+
+/// Bytes in the count
+const BYTES: usize = 4;
+
+/// Timer counting ticks of a fixed number of cycles
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timer {
+    /// Port reading the selected byte of the latched count and, written, selecting a byte
+    pub port: usize,
+    /// Cycles per tick
+    pub period: u64,
+    /// Cycles executed since power on
+    cycles: u64,
+    /// Count as of the last latch
+    latched: u32,
+    /// Byte of the latched count read, 0 is the lowest
+    selected: u8,
+}
+
+impl Timer {
+    /// Timer at power on ticking `hz` times a second of a cpu running at `freq`
+    pub fn new(port: usize, hz: u32, freq: u32) -> Self {
+        Timer {
+            port,
+            period: (freq / hz.clamp(1, freq)) as u64,
+            cycles: 0,
+            latched: 0,
+            selected: 0,
+        }
+    }
+
+    /// Cycles have been executed
+    pub fn run(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+    }
+
+    /// Ticks since power on, wrapping at 32 bits
+    pub fn count(&self) -> u32 {
+        (self.cycles / self.period) as u32
+    }
+
+    /// Written to the port: select byte `data` (0 to 3) of the count, selecting byte 0 latches the current count first
+    /// so the bytes read next belong together
+    pub fn write(&mut self, data: u8) {
+        self.selected = data % BYTES as u8;
+        if self.selected == 0 {
+            self.latched = self.count();
+        }
+    }
+
+    /// Read from the port: the selected byte of the latched count
+    pub fn read(&self) -> u8 {
+        self.latched.to_le_bytes()[self.selected as usize]
+    }
+
+    /// Count and latch, for savestates
+    pub fn contents(&self) -> Vec<u8> {
+        let mut contents = self.cycles.to_le_bytes().to_vec();
+        contents.extend_from_slice(&self.latched.to_le_bytes());
+        contents.push(self.selected);
+        contents
+    }
+
+    /// Restore the count and latch as returned by `contents`
+    pub fn restore(&mut self, contents: &[u8]) -> Result<(), String> {
+        let [c0, c1, c2, c3, c4, c5, c6, c7, l0, l1, l2, l3, selected] = *contents else {
+            return Err("Timer does not match".to_string());
+        };
+        self.cycles = u64::from_le_bytes([c0, c1, c2, c3, c4, c5, c6, c7]);
+        self.latched = u32::from_le_bytes([l0, l1, l2, l3]);
+        self.selected = selected % BYTES as u8;
+        Ok(())
+    }
+
+    /// Back to zero, as at power on
+    pub fn clear(&mut self) {
+        self.cycles = 0;
+        self.latched = 0;
+        self.selected = 0;
+    }
+}
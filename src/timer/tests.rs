@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn ticks_from_cycles() {
+    let mut timer = Timer::new(7, 1000, 2_000_000);
+    assert_eq!(timer.period, 2000);
+    timer.run(1999);
+    assert_eq!(timer.count(), 0);
+    timer.run(1);
+    assert_eq!(timer.count(), 1);
+    timer.run(2000 * 0x1233);
+    assert_eq!(timer.count(), 0x1234);
+    timer.clear();
+    assert_eq!(timer.count(), 0);
+    assert_eq!(Timer::new(7, 0, 100).period, 100);
+}
+
+#[test]
+fn bytes_of_the_latched_count() {
+    let mut timer = Timer::new(7, 1, 10);
+    timer.run(10 * 0x010203);
+    assert_eq!(timer.read(), 0);
+    let bytes: Vec<u8> = (0..4)
+        .map(|n| {
+            timer.write(n);
+            timer.read()
+        })
+        .collect();
+    assert_eq!(bytes, [0x03, 0x02, 0x01, 0x00]);
+    timer.run(10 * 0x40);
+    timer.write(1);
+    assert_eq!(timer.read(), 0x02);
+    // Selecting wraps around the bytes, selecting byte 0 latches again
+    timer.write(4);
+    assert_eq!(timer.read(), 0x43);
+}
+
+#[test]
+fn restore() {
+    let mut timer = Timer::new(7, 1, 10);
+    timer.run(25);
+    timer.write(0);
+    timer.write(1);
+    let contents = timer.contents();
+    let mut other = Timer::new(7, 1, 10);
+    assert!(other.restore(&contents[1..]).is_err());
+    other.restore(&contents).unwrap();
+    assert_eq!(other, timer);
+}
@@ -0,0 +1,311 @@
+//! Video timing of the board: optional cycle stealing by the video circuitry and measurement of the effective clock
+//!
+//! The timing model is integer only, so nothing drifts however long the emulator runs:
+//! * Frame `n` gets `freq * (n + 1) / fps - freq * n / fps` cycles, which add up to exactly `freq` cycles a second
+//!   even when a frame does not get a whole number of cycles.
+//! * Instructions run past the end of a frame are taken from the next one, the interrupts are requested when the
+//!   cycles counted from the start of the frame reach their point in it.
+//! * Frames are paced against deadlines in real time, each a frame time after the previous one with the fraction of
+//!   a nanosecond carried (1/60 s is 16,666,666⅔ ns). Sleeping too long delays a frame, not the ones after it. On
+//!   displays refreshing at a multiple of the frame rate the display paces the frames instead (see `present`).
+//! * Audio positions are computed from the number of frames since the start (`freq * frames / fps`), or carry the
+//!   fraction of a sample to the next frame.
+//! * Time is read and slept through a `Clock`, the wall clock or a virtual one that only moves when slept, so the
+//!   pacing can be tested deterministically and run faster than real time.
+
+use std::{
+    cell::Cell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Scan lines per frame
+pub const LINES: u32 = 262;
+/// Scan lines of active display (the width of the rotated display)
+pub const ACTIVE_LINES: u32 = 224;
+
+/// Cycles the CPU gets per frame when the video circuitry steals `steal` percent of the cycles during active display.
+/// No cycles are stolen during vertical blanking.
+pub fn effective_cycles(cycles_per_frame: u32, steal: u32) -> u32 {
+    let stolen = cycles_per_frame as u64 * ACTIVE_LINES as u64 * steal.min(100) as u64
+        / (LINES as u64 * 100);
+    cycles_per_frame - stolen as u32
+}
+
+/// Measures the effective clock, cycles run per second of real time
+#[derive(Debug)]
+pub struct ClockMeter {
+    /// Start of the current measurement
+    since: Instant,
+    /// Cycles run since then
+    cycles: u64,
+    /// Last measured clock in Hz
+    hz: Option<u32>,
+}
+
+impl ClockMeter {
+    /// How often the clock is measured
+    pub const PERIOD: Duration = Duration::from_secs(1);
+
+    pub fn new(now: Instant) -> Self {
+        ClockMeter {
+            since: now,
+            cycles: 0,
+            hz: None,
+        }
+    }
+
+    /// Count cycles run, at time `now`
+    pub fn update(&mut self, cycles: u32, now: Instant) {
+        self.cycles += cycles as u64;
+        let elapsed = now.saturating_duration_since(self.since);
+        if elapsed >= Self::PERIOD {
+            self.hz = Some((self.cycles as u128 * 1_000_000_000 / elapsed.as_nanos()) as u32);
+            self.since = now;
+            self.cycles = 0;
+        }
+    }
+
+    /// Last measured clock in Hz, none until a full period has passed
+    pub fn hz(&self) -> Option<u32> {
+        self.hz
+    }
+}
+
+/// Emulation speeds in percent of normal, selectable while running
+pub const SPEEDS: [u32; 5] = [25, 50, 100, 200, 400];
+
+/// Next speed in `SPEEDS`, faster or slower than `speed`, staying at the ends
+pub fn next_speed(speed: u32, faster: bool) -> u32 {
+    let next = if faster {
+        SPEEDS.iter().find(|s| **s > speed)
+    } else {
+        SPEEDS.iter().rev().find(|s| **s < speed)
+    };
+    next.copied().unwrap_or(speed)
+}
+
+/// Real time of a frame at a speed in percent of normal
+pub fn frame_duration(fps: u32, speed: u32) -> Duration {
+    Duration::from_secs(1) * 100 / (fps * speed.max(1))
+}
+
+/// Cycles of frame number `frame`, the fractions of a cycle add up over the frames of a second
+pub fn frame_cycles(freq: u32, fps: u32, frame: u64) -> u32 {
+    let (freq, fps) = (freq as u64, fps as u64);
+    (freq * (frame + 1) / fps - freq * frame / fps) as u32
+}
+
+/// Source of time for frame pacing and telemetry
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The wall clock
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Time that only moves when slept or advanced. Clones share the time, keep one to inspect a clock given away.
+#[derive(Clone, Debug)]
+pub struct VirtualClock {
+    start: Instant,
+    elapsed: Rc<Cell<Duration>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        VirtualClock {
+            start: Instant::now(),
+            elapsed: Rc::new(Cell::new(Duration::ZERO)),
+        }
+    }
+
+    /// Move time forward, as if the host spent that long
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+
+    /// Time passed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// Paces frames against deadlines in real time, carrying the fraction of a nanosecond of the frame time
+#[derive(Debug)]
+pub struct Pacer {
+    deadline: Instant,
+    /// Fraction of a nanosecond carried to the next frame, in units of the divisor of the frame time
+    remainder: u64,
+    divisor: u64,
+}
+
+impl Pacer {
+    pub fn new(now: Instant) -> Self {
+        Pacer {
+            deadline: now,
+            remainder: 0,
+            divisor: 1,
+        }
+    }
+
+    /// Deadline of the next frame at a speed in percent of normal, with the frame time corrected by `correction` per
+    /// mille. More than a frame behind (a slow host, stopped in a debugger) it starts over from `now` rather than
+    /// running frames back to back to catch up.
+    pub fn next(&mut self, fps: u32, speed: u32, correction: i64, now: Instant) -> Instant {
+        let divisor = fps as u64 * speed.max(1) as u64 * 1000;
+        if divisor != self.divisor {
+            self.divisor = divisor;
+            self.remainder = 0;
+        }
+        let numerator = 100_000_000_000 * (1000 + correction) as u64 + self.remainder;
+        self.remainder = numerator % divisor;
+        let frame = Duration::from_nanos(numerator / divisor);
+        self.deadline += frame;
+        if self.deadline + frame < now {
+            self.deadline = now;
+            self.remainder = 0;
+        }
+        self.deadline
+    }
+
+    /// Sleep until the deadline of the next frame, see `next`. Returns the deadline when it slept, none when the
+    /// deadline had already passed.
+    pub fn wait(
+        &mut self,
+        clock: &dyn Clock,
+        fps: u32,
+        speed: u32,
+        correction: i64,
+    ) -> Option<Instant> {
+        let now = clock.now();
+        let deadline = self.next(fps, speed, correction, now);
+        let sleep_duration = deadline.saturating_duration_since(now);
+        if sleep_duration.is_zero() {
+            return None;
+        }
+        clock.sleep(sleep_duration);
+        Some(deadline)
+    }
+}
+
+/// Scan lines where the board requests the interrupts: RST 1 with the beam in the middle of the screen and RST 2 at the
+/// end of active display
+pub const INTERRUPT_LINES: [(u8, u32); 2] = [(1, 96), (2, ACTIVE_LINES)];
+
+/// Cycle within a frame where the beam reaches a scan line
+pub fn line_cycle(line: u32, cycles_per_frame: u32) -> u32 {
+    (cycles_per_frame as u64 * line as u64 / LINES as u64) as u32
+}
+
+/// An interrupt requested by the emulator, with the cycle within the frame it should have come at (from its scan line)
+/// and the cycle it actually came at
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Delivery {
+    pub rst: u8,
+    pub ideal: u32,
+    pub cycle: u32,
+    /// Interrupts were enabled, otherwise the request was lost
+    pub delivered: bool,
+}
+
+impl Delivery {
+    pub const CSV_HEADER: &str = "frame,rst,ideal,cycle,latency,delivered";
+
+    /// Cycles late (negative when early)
+    pub fn latency(&self) -> i64 {
+        self.cycle as i64 - self.ideal as i64
+    }
+
+    /// CSV line for the frame, as in `Delivery::CSV_HEADER`
+    pub fn csv(&self, frame: u64) -> String {
+        format!(
+            "{frame},{},{},{},{},{}",
+            self.rst,
+            self.ideal,
+            self.cycle,
+            self.latency(),
+            self.delivered as u8
+        )
+    }
+}
+
+/// Interrupt deliveries of the last frame and the range of latencies of each interrupt so far
+#[derive(Debug, Default)]
+pub struct InterruptTiming {
+    /// Deliveries during the last frame
+    pub frame: Vec<Delivery>,
+    /// Lowest and highest latency of RST 1 and 2
+    pub range: [Option<(i64, i64)>; 2],
+    /// Requests lost with interrupts disabled, of RST 1 and 2
+    pub missed: [u64; 2],
+}
+
+impl InterruptTiming {
+    /// Start a new frame
+    pub fn new_frame(&mut self) {
+        self.frame.clear();
+    }
+
+    /// Record a delivery of RST 1 or 2
+    pub fn record(&mut self, delivery: Delivery) {
+        let i = (delivery.rst as usize).clamp(1, 2) - 1;
+        if delivery.delivered {
+            let latency = delivery.latency();
+            let (min, max) = self.range[i].get_or_insert((latency, latency));
+            *min = latency.min(*min);
+            *max = latency.max(*max);
+        } else {
+            self.missed[i] += 1;
+        }
+        self.frame.push(delivery);
+    }
+
+    /// Text in the characters of the font: latency of each interrupt in the last frame, the range so far and requests
+    /// lost
+    pub fn lines(&self) -> Vec<String> {
+        self.frame
+            .iter()
+            .map(|d| {
+                let i = (d.rst as usize).clamp(1, 2) - 1;
+                let (min, max) = self.range[i].unwrap_or_default();
+                let mut line = format!("RST{} {} {min} {max}", d.rst, d.latency());
+                if self.missed[i] > 0 {
+                    line += &format!(" MISSED {}", self.missed[i]);
+                }
+                line
+            })
+            .collect()
+    }
+}
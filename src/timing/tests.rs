@@ -0,0 +1,155 @@
+use super::*;
+use crate::{FPS, FREQ};
+
+#[test]
+fn no_stealing() {
+    assert_eq!(effective_cycles(FREQ / FPS, 0), FREQ / FPS);
+}
+
+#[test]
+fn stealing_during_active_display() {
+    assert_eq!(effective_cycles(26200, 100), 26200 - 22400);
+    assert_eq!(effective_cycles(26200, 10), 26200 - 2240);
+    assert_eq!(effective_cycles(26200, 1000), 26200 - 22400);
+    assert!(effective_cycles(FREQ / FPS, 5) < FREQ / FPS);
+}
+
+#[test]
+fn clock_meter() {
+    let start = Instant::now();
+    let mut meter = ClockMeter::new(start);
+    meter.update(1_000_000, start + Duration::from_millis(500));
+    assert_eq!(meter.hz(), None);
+    meter.update(1_000_000, start + Duration::from_millis(1000));
+    assert_eq!(meter.hz(), Some(2_000_000));
+    meter.update(500_000, start + Duration::from_millis(1500));
+    assert_eq!(meter.hz(), Some(2_000_000));
+    meter.update(500_000, start + Duration::from_millis(3000));
+    assert_eq!(meter.hz(), Some(500_000));
+}
+
+#[test]
+fn speed_steps() {
+    assert_eq!(next_speed(100, true), 200);
+    assert_eq!(next_speed(100, false), 50);
+    assert_eq!(next_speed(400, true), 400);
+    assert_eq!(next_speed(25, false), 25);
+    // Speeds between the steps go to the nearest step in the direction
+    assert_eq!(next_speed(150, true), 200);
+    assert_eq!(next_speed(150, false), 100);
+}
+
+#[test]
+fn frame_durations() {
+    assert_eq!(frame_duration(60, 100), Duration::from_nanos(16_666_666));
+    assert_eq!(frame_duration(60, 25), Duration::from_nanos(66_666_666));
+    assert_eq!(frame_duration(50, 200), Duration::from_millis(10));
+}
+
+#[test]
+fn interrupt_lines() {
+    assert_eq!(line_cycle(0, 26200), 0);
+    assert_eq!(line_cycle(96, 26200), 9600);
+    assert_eq!(line_cycle(LINES, 26200), 26200);
+}
+
+#[test]
+fn interrupt_timing() {
+    let mut timing = InterruptTiming::default();
+    let delivery = |rst, cycle, delivered| Delivery {
+        rst,
+        ideal: if rst == 1 { 9600 } else { 22400 },
+        cycle,
+        delivered,
+    };
+    timing.record(delivery(1, 13100, true));
+    timing.record(delivery(2, 26210, true));
+    assert_eq!(
+        timing.lines(),
+        ["RST1 3500 3500 3500", "RST2 3810 3810 3810"]
+    );
+    assert_eq!(delivery(2, 26210, true).csv(7), "7,2,22400,26210,3810,1");
+
+    timing.new_frame();
+    timing.record(delivery(1, 9590, true));
+    timing.record(delivery(2, 26200, false));
+    assert_eq!(
+        timing.lines(),
+        ["RST1 -10 -10 3500", "RST2 3800 3810 3810 MISSED 1"]
+    );
+    assert_eq!(timing.range[1], Some((3810, 3810)));
+}
+
+#[test]
+fn frame_cycles_add_up() {
+    assert_eq!(frame_cycles(FREQ, FPS, 0), 33280);
+    let second: u32 = (0..7).map(|n| frame_cycles(1000, 7, n)).sum();
+    assert_eq!(second, 1000);
+    assert_eq!(frame_cycles(1000, 7, 0), 142);
+    assert_eq!(frame_cycles(1000, 7, 1), 143);
+}
+
+#[test]
+fn pacing_carries_fractions() {
+    let start = Instant::now();
+    let mut pacer = Pacer::new(start);
+    let mut deadline = start;
+    for _ in 0..60 {
+        deadline = pacer.next(60, 100, 0, start);
+    }
+    assert_eq!(deadline - start, Duration::from_secs(1));
+    // Half speed, and 0.5% longer frames
+    assert_eq!(
+        pacer.next(60, 50, 0, start) - deadline,
+        Duration::from_nanos(33_333_333)
+    );
+    let deadline = pacer.next(60, 100, 0, start);
+    assert_eq!(
+        pacer.next(50, 100, 5, start) - deadline,
+        Duration::from_micros(20_100)
+    );
+}
+
+#[test]
+fn pacing_starts_over_when_behind() {
+    let start = Instant::now();
+    let mut pacer = Pacer::new(start);
+    let late = start + Duration::from_secs(1);
+    assert_eq!(pacer.next(60, 100, 0, late), late);
+    assert_eq!(
+        pacer.next(60, 100, 0, late) - late,
+        Duration::from_nanos(16_666_666)
+    );
+}
+
+#[test]
+fn virtual_clock() {
+    let clock = VirtualClock::new();
+    let shared = clock.clone();
+    let start = clock.now();
+    clock.sleep(Duration::from_millis(5));
+    shared.advance(Duration::from_millis(10));
+    assert_eq!(clock.now() - start, Duration::from_millis(15));
+    assert_eq!(shared.elapsed(), Duration::from_millis(15));
+}
+
+#[test]
+fn pacing_by_a_virtual_clock() {
+    let clock = VirtualClock::new();
+    let mut pacer = Pacer::new(clock.now());
+    for _ in 0..60 {
+        pacer.wait(&clock, 60, 100, 0);
+    }
+    assert_eq!(clock.elapsed(), Duration::from_secs(1));
+    // Time spent emulating a frame is taken from the sleep
+    clock.advance(Duration::from_millis(10));
+    pacer.wait(&clock, 60, 100, 0);
+    assert_eq!(clock.elapsed(), Duration::from_nanos(1_016_666_666));
+    // A slow frame delays the next one, not the ones after it
+    clock.advance(Duration::from_millis(40));
+    assert_eq!(pacer.wait(&clock, 60, 100, 0), None);
+    assert_eq!(clock.elapsed(), Duration::from_nanos(1_056_666_666));
+    let deadline = pacer.wait(&clock, 60, 100, 0);
+    assert_eq!(deadline, Some(clock.now()));
+    assert_eq!(clock.elapsed(), Duration::from_nanos(1_073_333_332));
+}
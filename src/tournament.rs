@@ -0,0 +1,174 @@
+//! Tournament for game nights: each registered player plays a fixed number of one-credit games, taking turns. The
+//! coin for each game is inserted for the player whose turn it is and 2-player games are blocked. Each result goes to
+//! the leaderboard of the player's user profile, and the players are ranked by their total score at the end.
+
+use std::fmt::Write;
+
+use crate::{
+    batch::{credits, in_game, player_score},
+    cpu::Cpu,
+    freeplay::COIN_FRAMES,
+    ports::INVADERS,
+    user::User,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Games each player plays unless given
+pub const GAMES: u32 = 3;
+
+/// What happened in a frame
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The game of the player whose turn it is started
+    Started(usize),
+    /// The game of a player ended with a score, and the place of the score on their leaderboard
+    Finished {
+        player: usize,
+        score: u32,
+        place: Option<usize>,
+    },
+}
+
+/// Players, their scores and whose turn it is
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    /// Players with their profiles, whose leaderboards get the scores
+    pub players: Vec<User>,
+    /// Games each player plays
+    pub games: u32,
+    /// Scores of the games played so far, by player
+    scores: Vec<Vec<u32>>,
+    /// Games played so far, all players
+    turn: u32,
+    /// Frames the coin of the turn has been held, None until the turn starts
+    coin: Option<u32>,
+    playing: bool,
+}
+
+impl Tournament {
+    pub fn new(players: Vec<User>, games: u32) -> Self {
+        let scores = vec![vec![]; players.len()];
+        Tournament {
+            players,
+            games,
+            scores,
+            turn: 0,
+            coin: None,
+            playing: false,
+        }
+    }
+
+    /// All games played
+    pub fn over(&self) -> bool {
+        self.turn >= self.players.len() as u32 * self.games
+    }
+
+    /// Player whose turn it is
+    pub fn player(&self) -> usize {
+        self.turn as usize % self.players.len().max(1)
+    }
+
+    /// Name of a player for the display, which has no small letters or underscores
+    pub fn display_name(&self, player: usize) -> String {
+        self.players[player].name.to_uppercase().replace('_', "-")
+    }
+
+    /// Message telling whose turn it is
+    pub fn prompt(&self) -> String {
+        let round = self.turn / self.players.len().max(1) as u32 + 1;
+        format!(
+            "{} GAME {round} OF {} PRESS 1",
+            self.display_name(self.player()),
+            self.games
+        )
+    }
+
+    /// Filter the inputs of the frame, after they have been set from the keys: insert the one coin of the turn and
+    /// block 2-player games
+    pub fn apply(&mut self, cpu: &mut Cpu) {
+        if let Some(p2) = INVADERS.find("p2-start") {
+            p2.set(cpu, false);
+        }
+        if self.over() || self.playing {
+            return;
+        }
+        if self.coin.is_none() && credits(cpu) == 0 {
+            self.coin = Some(0);
+        }
+        if let Some(frames) = &mut self.coin {
+            if *frames < COIN_FRAMES {
+                if let Some(credit) = INVADERS.find("credit") {
+                    credit.set(cpu, true);
+                }
+                *frames += 1;
+            }
+        }
+    }
+
+    /// Follow the game after a frame has run, recording the score on the leaderboard of the player when it ends
+    pub fn update(&mut self, cpu: &Cpu) -> Option<Event> {
+        if self.over() {
+            return None;
+        }
+        let player = self.player();
+        match (self.playing, in_game(cpu)) {
+            (false, true) => {
+                self.playing = true;
+                Some(Event::Started(player))
+            }
+            (true, false) => {
+                self.playing = false;
+                self.coin = None;
+                self.turn += 1;
+                let score = player_score(cpu, 1);
+                self.scores[player].push(score);
+                let place = self.players[player].profile.add_score(score);
+                Some(Event::Finished {
+                    player,
+                    score,
+                    place,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Players by total score, highest first, as player, total and best game. Ties keep the order of registration.
+    pub fn ranking(&self) -> Vec<(usize, u32, u32)> {
+        let mut ranking: Vec<(usize, u32, u32)> = self
+            .scores
+            .iter()
+            .enumerate()
+            .map(|(player, scores)| {
+                let total = scores.iter().sum();
+                (player, total, scores.iter().copied().max().unwrap_or(0))
+            })
+            .collect();
+        ranking.sort_by_key(|(_, total, _)| std::cmp::Reverse(*total));
+        ranking
+    }
+
+    /// The ranking as a table, with the scores of every game
+    pub fn report(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "Tournament ranking ({} of {} games played):",
+            self.turn,
+            self.players.len() as u32 * self.games
+        );
+        for (rank, (player, total, best)) in self.ranking().into_iter().enumerate() {
+            let games: Vec<String> = self.scores[player].iter().map(u32::to_string).collect();
+            let _ = writeln!(
+                s,
+                "{:>2}. {:<16} {total:>6} (best {best}, games {})",
+                rank + 1,
+                self.players[player].name,
+                games.join(" ")
+            );
+        }
+        s
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use super::*;
+
+fn user(name: &str) -> User {
+    User {
+        name: name.to_string(),
+        path: PathBuf::from(name),
+        profile: Default::default(),
+    }
+}
+
+fn input(cpu: &Cpu, name: &str) -> bool {
+    INVADERS.find(name).unwrap().active(cpu)
+}
+
+/// Play a game to the end with a score, as the rom would keep it
+fn play(tournament: &mut Tournament, cpu: &mut Cpu, score: u16) -> Vec<Event> {
+    let mut events = vec![];
+    cpu.poke(0x20EF, 1);
+    events.extend(tournament.update(cpu));
+    cpu.poke(0x20F8, (score & 0xFF) as u8);
+    cpu.poke(0x20F9, (score >> 8) as u8);
+    cpu.poke(0x20EF, 0);
+    events.extend(tournament.update(cpu));
+    events
+}
+
+#[test]
+fn turns_rotate() {
+    let mut tournament = Tournament::new(vec![user("ann"), user("bob_b")], 2);
+    let mut cpu = Cpu::new(vec![]);
+    assert_eq!(tournament.prompt(), "ANN GAME 1 OF 2 PRESS 1");
+    assert_eq!(
+        play(&mut tournament, &mut cpu, 0x0150),
+        [
+            Event::Started(0),
+            Event::Finished {
+                player: 0,
+                score: 150,
+                place: Some(0)
+            }
+        ]
+    );
+    assert_eq!(tournament.prompt(), "BOB-B GAME 1 OF 2 PRESS 1");
+    play(&mut tournament, &mut cpu, 0x0300);
+    assert_eq!(tournament.prompt(), "ANN GAME 2 OF 2 PRESS 1");
+    play(&mut tournament, &mut cpu, 0x0200);
+    assert!(!tournament.over());
+    play(&mut tournament, &mut cpu, 0x0010);
+    assert!(tournament.over());
+    // No more games are followed
+    assert!(play(&mut tournament, &mut cpu, 0x0990).is_empty());
+    assert_eq!(tournament.ranking(), [(0, 350, 200), (1, 310, 300)]);
+    assert_eq!(tournament.players[0].profile.scores, [200, 150]);
+    assert_eq!(
+        tournament.report(),
+        "Tournament ranking (4 of 4 games played):\n \
+         1. ann                 350 (best 200, games 150 200)\n \
+         2. bob_b               310 (best 300, games 300 10)\n"
+    );
+}
+
+#[test]
+fn one_coin_per_game() {
+    let mut tournament = Tournament::new(vec![user("ann")], 1);
+    let mut cpu = Cpu::new(vec![]);
+    let mut coins = 0;
+    for _ in 0..3 * COIN_FRAMES {
+        INVADERS.find("credit").unwrap().set(&mut cpu, false);
+        INVADERS.find("p2-start").unwrap().set(&mut cpu, true);
+        tournament.apply(&mut cpu);
+        coins += input(&cpu, "credit") as u32;
+        assert!(!input(&cpu, "p2-start"));
+    }
+    assert_eq!(coins, COIN_FRAMES);
+}
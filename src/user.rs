@@ -0,0 +1,184 @@
+//! Named user profiles with personal settings (key bindings, colors, DIP switches) and a leaderboard, so several
+//! people sharing a machine keep their settings and scores separate
+
+use std::{
+    fmt::Write,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{hotplug::PLAYERS, palette::Palette, shutdown};
+
+#[cfg(test)]
+mod tests;
+
+/// Scores kept on the leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Settings and scores of a user
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UserProfile {
+    /// Foreground, background, top and bottom overlay colors
+    pub theme: Option<Palette>,
+    /// Inputs set at start, mostly DIP switches
+    pub inputs: Vec<(String, bool)>,
+    /// Keys bound to inputs, as input name and SDL key name, replacing the default keys of the input
+    pub keys: Vec<(String, String)>,
+    /// GUID of the game controller preferred by each player
+    pub controllers: [Option<String>; PLAYERS],
+    /// Best scores, highest first
+    pub scores: Vec<u32>,
+}
+
+/// A name can be used as a file name on any platform
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// File of the profile in the config directory
+pub fn path(config_dir: &Path, name: &str) -> PathBuf {
+    config_dir.join("users").join(format!("{name}.txt"))
+}
+
+impl UserProfile {
+    /// Parse a profile: `theme`, `input`, `key`, `controller` and `score` lines, `#` starts a comment
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut profile = UserProfile::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = || format!("Line {}: invalid profile entry `{line}`", i + 1);
+            let (kind, rest) = line.split_once(' ').ok_or_else(error)?;
+            match (kind, rest.trim()) {
+                ("theme", colors) => {
+                    profile.theme = Some(Palette::parse(colors).map_err(|_| error())?);
+                }
+                ("input", input) => match input.split_whitespace().collect::<Vec<_>>()[..] {
+                    [name, "on"] => profile.inputs.push((name.to_string(), true)),
+                    [name, "off"] => profile.inputs.push((name.to_string(), false)),
+                    _ => return Err(error()),
+                },
+                // Key names may contain spaces, like `Left Ctrl`
+                ("key", binding) => {
+                    let (input, key) = binding.split_once(' ').ok_or_else(error)?;
+                    profile
+                        .keys
+                        .push((input.to_string(), key.trim().to_string()));
+                }
+                ("controller", controller) => {
+                    match controller.split_whitespace().collect::<Vec<_>>()[..] {
+                        [player, guid] => {
+                            let player = player.parse::<usize>().map_err(|_| error())?;
+                            let preferred = player
+                                .checked_sub(1)
+                                .and_then(|p| profile.controllers.get_mut(p))
+                                .ok_or_else(error)?;
+                            *preferred = Some(guid.to_string());
+                        }
+                        _ => return Err(error()),
+                    }
+                }
+                ("score", score) => profile.scores.push(score.parse().map_err(|_| error())?),
+                _ => return Err(error()),
+            }
+        }
+        profile.scores.sort_unstable_by(|a, b| b.cmp(a));
+        profile.scores.truncate(LEADERBOARD_SIZE);
+        Ok(profile)
+    }
+
+    /// Text of the profile file
+    pub fn to_text(&self) -> String {
+        let mut s = "# inv8080rs user profile\n".to_string();
+        if let Some(theme) = self.theme {
+            let _ = writeln!(s, "theme {theme}");
+        }
+        for (name, on) in &self.inputs {
+            let _ = writeln!(s, "input {name} {}", if *on { "on" } else { "off" });
+        }
+        for (input, key) in &self.keys {
+            let _ = writeln!(s, "key {input} {key}");
+        }
+        for (player, guid) in self.controllers.iter().enumerate() {
+            if let Some(guid) = guid {
+                let _ = writeln!(s, "controller {} {guid}", player + 1);
+            }
+        }
+        for score in &self.scores {
+            let _ = writeln!(s, "score {score}");
+        }
+        s
+    }
+
+    /// Put a score on the leaderboard, returns its place (0 is the best) if it made it
+    pub fn add_score(&mut self, score: u32) -> Option<usize> {
+        let place = self
+            .scores
+            .iter()
+            .position(|s| score > *s)
+            .unwrap_or(self.scores.len());
+        if place >= LEADERBOARD_SIZE {
+            return None;
+        }
+        self.scores.insert(place, score);
+        self.scores.truncate(LEADERBOARD_SIZE);
+        Some(place)
+    }
+
+    /// Leaderboard lines, with the place of a new score marked
+    pub fn leaderboard(&self, name: &str, new: Option<usize>) -> String {
+        let mut s = format!("Leaderboard of {name}:\n");
+        for (i, score) in self.scores.iter().enumerate() {
+            let marker = if new == Some(i) { " <" } else { "" };
+            let _ = writeln!(s, "{:>3}. {score:>5}{marker}", i + 1);
+        }
+        s
+    }
+}
+
+/// A user and the file of the profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub name: String,
+    pub path: PathBuf,
+    pub profile: UserProfile,
+}
+
+impl User {
+    /// Load the profile of a user from the config directory, a new user starts with an empty profile
+    pub fn load(config_dir: &Path, name: &str) -> Result<Self, String> {
+        if !valid_name(name) {
+            return Err(format!(
+                "Invalid user name {name}, use letters, digits, - and _"
+            ));
+        }
+        let path = path(config_dir, name);
+        let profile = match fs::read_to_string(&path) {
+            Ok(text) => {
+                UserProfile::parse(&text).map_err(|e| format!("{}: {e}", path.display()))?
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => UserProfile::default(),
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+        Ok(User {
+            name: name.to_string(),
+            path,
+            profile,
+        })
+    }
+
+    /// Save the profile, creating the directory if needed
+    pub fn save(&self) -> Result<(), String> {
+        let error = |e| format!("Could not save {}: {e}", self.path.display());
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(error)?;
+        }
+        shutdown::write(&self.path, self.profile.to_text()).map_err(error)
+    }
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+const TEXT: &str = "# inv8080rs user profile
+theme ffffffff ff000000 ffff0000 ff00ff00
+input dip3 on
+input dip7 off
+key p1-fire Left Ctrl
+controller 2 03000000de280000ff11000001000000
+score 1230
+score 150
+";
+
+#[test]
+fn parse_and_text() {
+    let profile = UserProfile::parse(TEXT).unwrap();
+    assert_eq!(
+        profile,
+        UserProfile {
+            theme: Some(Palette::default()),
+            inputs: vec![("dip3".into(), true), ("dip7".into(), false)],
+            keys: vec![("p1-fire".into(), "Left Ctrl".into())],
+            controllers: [None, Some("03000000de280000ff11000001000000".into())],
+            scores: vec![1230, 150],
+        }
+    );
+    assert_eq!(profile.to_text(), TEXT);
+    assert_eq!(UserProfile::parse(""), Ok(UserProfile::default()));
+}
+
+#[test]
+fn invalid_entries() {
+    assert_eq!(
+        UserProfile::parse("theme ffffffff"),
+        Err("Line 1: invalid profile entry `theme ffffffff`".to_string())
+    );
+    assert!(UserProfile::parse("input dip3 maybe").is_err());
+    assert!(UserProfile::parse("key p1-fire").is_err());
+    assert!(UserProfile::parse("score lots").is_err());
+    assert!(UserProfile::parse("controller 3 03000000").is_err());
+    assert!(UserProfile::parse("controller 0 03000000").is_err());
+    assert!(UserProfile::parse("controller 1").is_err());
+    assert!(UserProfile::parse("color red").is_err());
+}
+
+#[test]
+fn leaderboard() {
+    let mut profile = UserProfile::parse("score 100\nscore 300").unwrap();
+    assert_eq!(profile.scores, vec![300, 100]);
+    assert_eq!(profile.add_score(200), Some(1));
+    assert_eq!(profile.add_score(50), Some(3));
+    for _ in 0..LEADERBOARD_SIZE {
+        profile.add_score(500);
+    }
+    assert_eq!(profile.scores, vec![500; LEADERBOARD_SIZE]);
+    assert_eq!(profile.add_score(400), None);
+    let profile = UserProfile::parse("score 300\nscore 100").unwrap();
+    assert_eq!(
+        profile.leaderboard("alice", Some(1)),
+        "Leaderboard of alice:\n  1.   300\n  2.   100 <\n"
+    );
+}
+
+#[test]
+fn names() {
+    assert!(valid_name("alice_2-b"));
+    assert!(!valid_name(""));
+    assert!(!valid_name("../bob"));
+    assert_eq!(
+        path(Path::new("/cfg"), "alice"),
+        PathBuf::from("/cfg/users/alice.txt")
+    );
+}
+
+#[test]
+fn load_and_save() {
+    let dir = std::env::temp_dir().join(format!("inv8080rs-user-{}", std::process::id()));
+    let mut user = User::load(&dir, "alice").unwrap();
+    assert_eq!(user.profile, UserProfile::default());
+    user.profile.add_score(990);
+    user.save().unwrap();
+    assert_eq!(User::load(&dir, "alice").unwrap().profile.scores, vec![990]);
+    assert!(User::load(&dir, "a/b").is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
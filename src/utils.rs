@@ -37,3 +37,34 @@ pub fn set_bit(value: &mut u8, n: u8, val: bool) {
         *value &= !(1 << n);
     }
 }
+
+/// Pseudo-random number generator (SplitMix64), deterministic for a given seed
+/// ```
+/// # use inv8080rs::utils::splitmix64;
+/// let mut a = 42;
+/// let mut b = 42;
+///
+/// assert_eq!(splitmix64(&mut a), splitmix64(&mut b));
+/// assert_ne!(splitmix64(&mut a), splitmix64(&mut a));
+/// ```
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Parse decimal or hexadecimal (0x-prefixed) number
+/// ```
+/// # use inv8080rs::utils::parse_number;
+/// assert_eq!(parse_number("42"), Some(42));
+/// assert_eq!(parse_number("0x20EF"), Some(0x20EF));
+/// assert_eq!(parse_number("0xG"), None);
+/// ```
+pub fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
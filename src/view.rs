@@ -0,0 +1,117 @@
+//! Read-only views of the machine for other threads (UI, HTTP, scripting): the emulation publishes a compact view
+//! every frame, and each reader takes the latest one, neither ever waiting for the other. Each reader has a triple
+//! buffer: the slot the emulation writes, the slot the reader reads, and the latest published in between, traded
+//! with an atomic swap. A view is overwritten in place, so publishing allocates nothing.
+
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use crate::{cpu::Cpu, NPORTS, RAM};
+
+#[cfg(test)]
+mod tests;
+
+/// Set on the slot in between when it holds a view not taken by the reader yet
+const FRESH: u8 = 4;
+/// Bits of the index of the slot in between
+const INDEX: u8 = 3;
+
+/// What the machine looked like at the end of a frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct View {
+    /// Frames run since the emulation started, 0 until the first one is published
+    pub frame: u64,
+    pub pc: usize,
+    pub instructions: u64,
+    pub halted: bool,
+    /// Output ports
+    pub ports: [u8; NPORTS],
+    /// RAM, the framebuffer included, from the start of RAM
+    pub ram: Vec<u8>,
+}
+
+impl View {
+    /// Copy the state of the machine, reusing the memory of the view
+    pub fn update(&mut self, cpu: &Cpu, frame: u64) {
+        self.frame = frame;
+        self.pc = cpu.program_counter();
+        self.instructions = cpu.instructions();
+        self.halted = cpu.is_halted();
+        for (port, out) in self.ports.iter_mut().enumerate() {
+            *out = cpu.get_bus_out(port);
+        }
+        self.ram.clear();
+        self.ram.extend_from_slice(&cpu.memory()[RAM]);
+    }
+
+    /// Byte of RAM at an address, none outside RAM
+    pub fn peek(&self, addr: usize) -> Option<u8> {
+        self.ram.get(addr.checked_sub(*RAM.start())?).copied()
+    }
+}
+
+/// The three slots of a reader, each owned by the publisher, the reader or the index in between at any time
+struct Slots {
+    views: [UnsafeCell<View>; 3],
+    middle: AtomicU8,
+}
+
+// A slot is only touched by the side owning its index, which the atomic swaps hand over
+unsafe impl Sync for Slots {}
+
+/// Reading end, to move to another thread
+pub struct Reader {
+    slots: Arc<Slots>,
+    index: u8,
+}
+
+impl Reader {
+    /// The latest view published, the same one until another is
+    pub fn latest(&mut self) -> &View {
+        if self.slots.middle.load(Ordering::Acquire) & FRESH != 0 {
+            self.index = self.slots.middle.swap(self.index, Ordering::AcqRel) & INDEX;
+        }
+        // The reader owns its slot until it swaps it away
+        unsafe { &*self.slots.views[self.index as usize].get() }
+    }
+}
+
+/// Publishing end, kept by the emulation
+#[derive(Default)]
+pub struct Publisher {
+    /// Slots of each reader with the index of the slot being written
+    readers: Vec<(Arc<Slots>, u8)>,
+}
+
+impl Publisher {
+    /// A new reader, seeing views published from now on
+    pub fn reader(&mut self) -> Reader {
+        let slots = Arc::new(Slots {
+            views: Default::default(),
+            middle: AtomicU8::new(1),
+        });
+        self.readers.push((slots.clone(), 0));
+        Reader { slots, index: 2 }
+    }
+
+    /// Publish a view of the machine to every reader still there
+    pub fn publish(&mut self, cpu: &Cpu, frame: u64) {
+        self.readers
+            .retain(|(slots, _)| Arc::strong_count(slots) > 1);
+        for (slots, index) in &mut self.readers {
+            // The publisher owns its slot until it swaps it away
+            let view = unsafe { &mut *slots.views[*index as usize].get() };
+            view.update(cpu, frame);
+            *index = slots.middle.swap(*index | FRESH, Ordering::AcqRel) & INDEX;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readers.is_empty()
+    }
+}
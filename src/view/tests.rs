@@ -0,0 +1,60 @@
+use super::*;
+
+#[test]
+fn latest_view() {
+    let mut publisher = Publisher::default();
+    let mut reader = publisher.reader();
+    assert_eq!(reader.latest(), &View::default());
+
+    // MVI A,42 ; STA 2000 ; HLT
+    let mut cpu = Cpu::new(vec![0x3E, 42, 0x32, 0x00, 0x20, 0x76]);
+    publisher.publish(&cpu, 1);
+    for _ in 0..3 {
+        cpu.step();
+    }
+    assert_eq!(reader.latest().frame, 1);
+    assert_eq!(reader.latest().peek(0x2000), Some(0));
+    publisher.publish(&cpu, 2);
+    publisher.publish(&cpu, 3);
+    let view = reader.latest();
+    assert_eq!((view.frame, view.pc, view.halted), (3, 6, true));
+    assert_eq!(view.instructions, 3);
+    assert_eq!(view.peek(0x2000), Some(42));
+    assert_eq!(view.peek(0x1FFF), None);
+    assert_eq!(view.ram.len(), RAM.count());
+}
+
+#[test]
+fn dropped_readers() {
+    let mut publisher = Publisher::default();
+    let cpu = Cpu::new(vec![]);
+    let reader = publisher.reader();
+    let mut other = publisher.reader();
+    drop(reader);
+    publisher.publish(&cpu, 1);
+    assert_eq!(other.latest().frame, 1);
+    drop(other);
+    publisher.publish(&cpu, 2);
+    assert!(publisher.is_empty());
+}
+
+#[test]
+fn reading_from_another_thread() {
+    let mut publisher = Publisher::default();
+    let mut reader = publisher.reader();
+    let cpu = Cpu::new(vec![]);
+    let thread = std::thread::spawn(move || {
+        let mut last = 0;
+        while last < 1000 {
+            let view = reader.latest();
+            // Frames only go forward and views are whole
+            assert!(view.frame >= last);
+            assert!(view.frame == 0 || view.ram.len() == RAM.count());
+            last = view.frame;
+        }
+    });
+    for frame in 1..=1000 {
+        publisher.publish(&cpu, frame);
+    }
+    thread.join().unwrap();
+}
@@ -0,0 +1,231 @@
+//! Watchdog for kiosks: a thread watching the frame loop for progress. When the loop has not completed a frame for
+//! `TIMEOUT` it dumps the last machine state snapshot (taken every `SNAPSHOT_FRAMES` frames) and the instructions
+//! leading up to it, and asks for a soft reset. If the loop stays stuck it asks for a clean exit (as on `SIGTERM`),
+//! and at last ends the process, so a supervisor can restart it instead of the cabinet freezing silently.
+//!
+//! The reset and the clean exit are only requests the frame loop acts on at the start of its next frame, so they help
+//! when the loop stalls for a while and then recovers (e.g. a blocked audio or video driver). A loop that never
+//! returns is only ended by the last step, `Verdict::Kill`, which exits the process from the watchdog thread.
+
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{cpu::Cpu, shutdown};
+
+#[cfg(test)]
+mod tests;
+
+/// Time without a frame after which the loop is taken as hung
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+/// Time given to each attempt at recovering before the next
+pub const GRACE: Duration = Duration::from_secs(3);
+/// How often the watchdog checks
+const INTERVAL: Duration = Duration::from_millis(250);
+/// Exit status when the process is ended by the watchdog
+pub const EXIT_STATUS: i32 = 3;
+/// Frames between snapshots of the machine, so the savestate isn't taken every frame
+pub const SNAPSHOT_FRAMES: u64 = 60;
+
+/// What to do about a hung loop, in order
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Verdict {
+    /// Dump the last state and ask for a soft reset, done if the loop recovers
+    Dump,
+    /// Ask for a clean exit, done if the loop recovers
+    Exit,
+    /// End the process, the only step that works when the loop never recovers
+    Kill,
+}
+
+/// Progress of the loop as seen by the watchdog
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monitor {
+    frames: u64,
+    since: Instant,
+    /// Verdicts given since the last progress
+    given: usize,
+}
+
+impl Monitor {
+    pub fn new(now: Instant) -> Self {
+        Monitor {
+            frames: 0,
+            since: now,
+            given: 0,
+        }
+    }
+
+    /// Check the frames completed so far, giving each verdict once as the loop stays stuck
+    pub fn check(&mut self, frames: u64, now: Instant) -> Option<Verdict> {
+        if frames != self.frames {
+            *self = Monitor {
+                frames,
+                since: now,
+                given: 0,
+            };
+            return None;
+        }
+        let due = TIMEOUT + GRACE * self.given as u32;
+        let verdict = [Verdict::Dump, Verdict::Exit, Verdict::Kill].get(self.given)?;
+        (now.saturating_duration_since(self.since) >= due).then(|| {
+            self.given += 1;
+            *verdict
+        })
+    }
+}
+
+/// The machine at the end of the last completed frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub frame: u64,
+    /// Savestate
+    pub state: Vec<u8>,
+    /// Addresses of the last instructions executed, oldest first
+    pub history: Vec<usize>,
+}
+
+impl Snapshot {
+    pub fn take(cpu: &Cpu, frame: u64) -> Self {
+        Snapshot {
+            frame,
+            state: cpu.save_state(),
+            history: cpu
+                .history()
+                .map_or(vec![], |h| h.iter().copied().collect()),
+        }
+    }
+
+    /// Registers, call stack and next instructions, and the instructions leading up to them, disassembled from the
+    /// savestate when it loads into the plain board
+    pub fn report(&self, hung: Duration) -> String {
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "No frame completed for {:.1} s, state at the end of frame {}",
+            hung.as_secs_f64(),
+            self.frame
+        );
+        let mut cpu = Cpu::new(vec![]);
+        let loaded = cpu.load_state(&self.state).is_ok();
+        if loaded {
+            s += &cpu.format_state(8);
+        }
+        let _ = writeln!(s, "Last {} instructions:", self.history.len());
+        for &pc in &self.history {
+            if loaded {
+                let instr = cpu.disassemble(pc);
+                let _ = writeln!(s, "  {pc:04X}  {}", instr.mnemonic);
+            } else {
+                let _ = writeln!(s, "  {pc:04X}");
+            }
+        }
+        s
+    }
+}
+
+/// Shared between the frame loop and the watchdog thread
+#[derive(Debug, Default)]
+struct Shared {
+    frames: AtomicU64,
+    snapshot: Mutex<Snapshot>,
+    reset: AtomicBool,
+}
+
+/// Frame loop side of the watchdog
+#[derive(Debug)]
+pub struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+impl Watchdog {
+    /// Start the watchdog thread, which dumps to `watchdog-<time>.txt` and `.state` in the current directory
+    pub fn start() -> Self {
+        let shared = Arc::new(Shared::default());
+        let watched = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("watchdog".to_string())
+            .spawn(move || watch(&watched))
+            .expect("Could not start watchdog");
+        Watchdog { shared }
+    }
+
+    /// A frame has completed, with the machine as it is now. The cpu records its history for the dump.
+    pub fn frame(&self, cpu: &mut Cpu) {
+        cpu.enable_history();
+        let frame = self.shared.frames.load(Ordering::SeqCst) + 1;
+        if snapshot_due(frame) {
+            if let Ok(mut snapshot) = self.shared.snapshot.lock() {
+                *snapshot = Snapshot::take(cpu, frame);
+            }
+        }
+        self.shared.frames.store(frame, Ordering::SeqCst);
+    }
+
+    /// A soft reset has been asked for since last called
+    pub fn take_reset(&self) -> bool {
+        self.shared.reset.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// A snapshot is taken at the end of the frame, the first and every `SNAPSHOT_FRAMES` after it
+fn snapshot_due(frame: u64) -> bool {
+    frame % SNAPSHOT_FRAMES == 1
+}
+
+fn watch(shared: &Shared) {
+    let mut monitor = Monitor::new(Instant::now());
+    loop {
+        thread::sleep(INTERVAL);
+        let now = Instant::now();
+        let Some(verdict) = monitor.check(shared.frames.load(Ordering::SeqCst), now) else {
+            continue;
+        };
+        match verdict {
+            Verdict::Dump => {
+                eprintln!("Watchdog: no frame for {} s", TIMEOUT.as_secs());
+                let snapshot = shared
+                    .snapshot
+                    .lock()
+                    .map(|s| s.clone())
+                    .unwrap_or_default();
+                dump(&snapshot, TIMEOUT);
+                eprintln!("Watchdog: asking for a soft reset");
+                shared.reset.store(true, Ordering::SeqCst);
+            }
+            Verdict::Exit => {
+                eprintln!("Watchdog: still hung, asking to quit");
+                shutdown::request();
+            }
+            Verdict::Kill => {
+                eprintln!("Watchdog: still hung, exiting");
+                std::process::exit(EXIT_STATUS);
+            }
+        }
+    }
+}
+
+/// Save the report and the savestate of a snapshot
+fn dump(snapshot: &Snapshot, hung: Duration) {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let report = snapshot.report(hung);
+    eprint!("{report}");
+    for (path, contents) in [
+        (format!("watchdog-{secs}.txt"), report.into_bytes()),
+        (format!("watchdog-{secs}.state"), snapshot.state.clone()),
+    ] {
+        match shutdown::write(&path, contents) {
+            Ok(_) => eprintln!("Saved {path}"),
+            Err(e) => eprintln!("Could not save {path}: {e}"),
+        }
+    }
+}
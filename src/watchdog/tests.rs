@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn monitor_escalates_until_progress() {
+    let start = Instant::now();
+    let mut monitor = Monitor::new(start);
+    let at = |d: Duration| start + d;
+    assert_eq!(monitor.check(0, at(TIMEOUT / 2)), None);
+    assert_eq!(monitor.check(0, at(TIMEOUT)), Some(Verdict::Dump));
+    assert_eq!(monitor.check(0, at(TIMEOUT + GRACE / 2)), None);
+    assert_eq!(monitor.check(0, at(TIMEOUT + GRACE)), Some(Verdict::Exit));
+    assert_eq!(
+        monitor.check(0, at(TIMEOUT + 2 * GRACE)),
+        Some(Verdict::Kill)
+    );
+    assert_eq!(monitor.check(0, at(TIMEOUT + 3 * GRACE)), None);
+    // A completed frame starts over
+    let later = at(TIMEOUT * 10);
+    assert_eq!(monitor.check(1, later), None);
+    assert_eq!(monitor.check(1, later + TIMEOUT / 2), None);
+    assert_eq!(monitor.check(1, later + TIMEOUT), Some(Verdict::Dump));
+}
+
+#[test]
+fn snapshot_report() {
+    let mut cpu = Cpu::new(vec![0x00, 0x00, 0xC3, 0x00, 0x00]);
+    cpu.enable_history();
+    for _ in 0..3 {
+        cpu.step();
+    }
+    let snapshot = Snapshot::take(&cpu, 7);
+    assert_eq!(snapshot.frame, 7);
+    assert_eq!(snapshot.history, vec![0, 1, 2]);
+    let report = snapshot.report(TIMEOUT);
+    assert!(report.starts_with("No frame completed for 2.0 s, state at the end of frame 7\n"));
+    assert!(report.contains("Last 3 instructions:\n  0000  NOP\n  0001  NOP\n  0002  JMP 0000H\n"));
+
+    let broken = Snapshot {
+        state: vec![],
+        ..snapshot
+    };
+    assert!(broken
+        .report(TIMEOUT)
+        .ends_with("Last 3 instructions:\n  0000\n  0001\n  0002\n"));
+}
+
+#[test]
+fn snapshot_every_second() {
+    let due: Vec<u64> = (1..=200).filter(|f| snapshot_due(*f)).collect();
+    assert_eq!(due, vec![1, 61, 121, 181]);
+}
@@ -0,0 +1,220 @@
+//! Recording of the game sounds to WAV (8-bit unsigned mono, like the sound samples) and loading of the samples
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use crate::assets::Locator;
+
+#[cfg(test)]
+mod tests;
+
+/// Silence in 8-bit unsigned samples
+const SILENCE: u8 = 0x80;
+
+/// Mixes sounds started at frame boundaries into one track
+#[derive(Debug)]
+pub struct Recorder {
+    /// Sample rate
+    freq: u32,
+    /// Frames per second
+    fps: u32,
+    /// Number of frames recorded
+    frames: u64,
+    /// Number of samples taken out with `take`
+    taken: usize,
+    /// Mixed samples after the taken ones, signed around silence
+    mix: Vec<i16>,
+}
+
+impl Recorder {
+    pub fn new(freq: u32, fps: u32) -> Self {
+        Recorder {
+            freq,
+            fps,
+            frames: 0,
+            taken: 0,
+            mix: vec![],
+        }
+    }
+
+    /// Current position in samples
+    fn position(&self) -> usize {
+        (self.frames * self.freq as u64 / self.fps as u64) as usize
+    }
+
+    /// Mix a sound (8-bit unsigned samples) in, starting at the current frame
+    pub fn play(&mut self, samples: &[u8]) {
+        let start = self.position() - self.taken;
+        if self.mix.len() < start + samples.len() {
+            self.mix.resize(start + samples.len(), 0);
+        }
+        for (m, s) in self.mix[start..].iter_mut().zip(samples) {
+            *m += *s as i16 - SILENCE as i16;
+        }
+    }
+
+    /// Advance to the next frame
+    pub fn next_frame(&mut self) {
+        self.frames += 1;
+    }
+
+    /// The recording not taken yet as 8-bit unsigned samples, clipped where sounds add up too loud
+    pub fn samples(&self) -> Vec<u8> {
+        let mut samples: Vec<u8> = self.mix.iter().map(|m| clip(*m)).collect();
+        samples.resize(samples.len().max(self.position() - self.taken), SILENCE);
+        samples
+    }
+
+    /// Take out the samples before the current frame, which later sounds can no longer change
+    pub fn take(&mut self) -> Vec<u8> {
+        let len = self.position() - self.taken;
+        let mut samples: Vec<u8> = self
+            .mix
+            .drain(..len.min(self.mix.len()))
+            .map(clip)
+            .collect();
+        samples.resize(len, SILENCE);
+        self.taken += len;
+        samples
+    }
+}
+
+/// Mixed sample as an 8-bit unsigned sample
+fn clip(m: i16) -> u8 {
+    (m + SILENCE as i16).clamp(0, 0xFF) as u8
+}
+
+/// WAV file written while recording, through a temporary file renamed over it when finished
+#[derive(Debug)]
+pub struct Writer {
+    path: PathBuf,
+    temporary: PathBuf,
+    file: BufWriter<File>,
+    /// Number of samples written
+    len: u32,
+}
+
+impl Writer {
+    /// Start a WAV file, with the sizes filled in by `finish`
+    pub fn create(path: impl AsRef<Path>, freq: u32) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        file.write_all(&encode(&[], freq))?;
+        Ok(Writer {
+            path,
+            temporary,
+            file,
+            len: 0,
+        })
+    }
+
+    /// Append samples, failing when the file would outgrow the 4 GiB a WAV file can hold
+    pub fn write(&mut self, samples: &[u8]) -> io::Result<()> {
+        self.len = u32::try_from(samples.len())
+            .ok()
+            .and_then(|len| self.len.checked_add(len))
+            .filter(|len| *len < u32::MAX - 36)
+            .ok_or_else(|| io::Error::other("WAV file too large"))?;
+        self.file.write_all(samples)
+    }
+
+    /// Pad, fill in the sizes and move the file in place
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.len.is_multiple_of(2) {
+            self.file.write_all(&[0])?; // Chunks are padded to even size
+        }
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&(36 + self.len).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&self.len.to_le_bytes())?;
+        self.file.flush()?;
+        drop(self.file);
+        fs::rename(&self.temporary, &self.path)
+    }
+}
+
+/// Encode 8-bit unsigned mono samples as WAV
+pub fn encode(samples: &[u8], freq: u32) -> Vec<u8> {
+    let mut wav = vec![];
+    wav.extend(b"RIFF");
+    wav.extend((36 + samples.len() as u32).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16u32.to_le_bytes()); // Size of format chunk
+    wav.extend(1u16.to_le_bytes()); // PCM
+    wav.extend(1u16.to_le_bytes()); // Channels
+    wav.extend(freq.to_le_bytes());
+    wav.extend(freq.to_le_bytes()); // Bytes per second
+    wav.extend(1u16.to_le_bytes()); // Bytes per sample
+    wav.extend(8u16.to_le_bytes()); // Bits per sample
+    wav.extend(b"data");
+    wav.extend((samples.len() as u32).to_le_bytes());
+    wav.extend(samples);
+    if !samples.len().is_multiple_of(2) {
+        wav.push(0); // Chunks are padded to even size
+    }
+    wav
+}
+
+/// Decode a WAV file of 8-bit unsigned mono samples
+pub fn decode(wav: &[u8]) -> Result<Vec<u8>, String> {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err("Not a WAV file".into());
+    }
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= wav.len() {
+        let size = u32::from_le_bytes([wav[pos + 4], wav[pos + 5], wav[pos + 6], wav[pos + 7]]);
+        let start = pos + 8;
+        let end = start.saturating_add(size as usize);
+        if end > wav.len() {
+            return Err("WAV chunk too short".into());
+        }
+        let chunk = &wav[start..end];
+        match &wav[pos..pos + 4] {
+            b"fmt " if chunk.len() >= 16 => {
+                let u16_at = |i: usize| u16::from_le_bytes([chunk[i], chunk[i + 1]]);
+                format = Some((u16_at(0), u16_at(2), u16_at(14)));
+            }
+            b"data" => {
+                return match format {
+                    Some((1, 1, 8)) => Ok(chunk.to_vec()),
+                    Some((1, channels, bits)) => Err(format!(
+                        "Unsupported WAV format, {bits}-bit with {channels} channels (8-bit mono needed)"
+                    )),
+                    Some((tag, _, _)) => Err(format!("Unsupported WAV encoding {tag}")),
+                    None => Err("Missing WAV format".into()),
+                };
+            }
+            _ => {}
+        }
+        pos = end + size as usize % 2;
+    }
+    Err("Missing WAV data".into())
+}
+
+/// Samples of a WAV file, or why they could not be loaded, with the index of the file
+pub type Loaded = (usize, Result<Vec<u8>, String>);
+
+/// Load WAV assets in a background thread. Each result is sent as soon as it is loaded.
+pub fn load_in_background(assets: Locator, names: Vec<String>) -> Receiver<Loaded> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for (i, name) in names.iter().enumerate() {
+            let samples = assets
+                .read(name)
+                .and_then(|wav| decode(&wav).map_err(|e| format!("{name}: {e}")));
+            if sender.send((i, samples)).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
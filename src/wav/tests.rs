@@ -0,0 +1,133 @@
+use super::*;
+
+#[test]
+fn encode_header() {
+    let wav = encode(&[0x80, 0xFF, 0x00], 11025);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[4..8], &39u32.to_le_bytes());
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(&wav[24..28], &11025u32.to_le_bytes());
+    assert_eq!(&wav[34..36], &8u16.to_le_bytes());
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(&wav[40..44], &3u32.to_le_bytes());
+    assert_eq!(&wav[44..47], &[0x80, 0xFF, 0x00]);
+    assert_eq!(wav.len(), 48);
+}
+
+#[test]
+fn recorder_silence() {
+    let mut recorder = Recorder::new(60, 60);
+    for _ in 0..3 {
+        recorder.next_frame();
+    }
+    assert_eq!(recorder.samples(), vec![SILENCE; 3]);
+}
+
+#[test]
+fn recorder_mix() {
+    let mut recorder = Recorder::new(120, 60);
+    recorder.play(&[0x90, 0x90, 0x90]);
+    recorder.next_frame();
+    recorder.play(&[0xF0, 0x70, 0x70]);
+    assert_eq!(recorder.samples(), vec![0x90, 0x90, 0xFF, 0x70, 0x70]);
+}
+
+#[test]
+fn recorder_frame_rounding() {
+    let mut recorder = Recorder::new(11025, 60);
+    for _ in 0..60 {
+        recorder.next_frame();
+    }
+    assert_eq!(recorder.samples().len(), 11025);
+}
+
+#[test]
+fn recorder_take() {
+    let mut recorder = Recorder::new(120, 60);
+    recorder.play(&[0x90, 0x90, 0x90]);
+    recorder.next_frame();
+    assert_eq!(recorder.take(), vec![0x90, 0x90]);
+    assert!(recorder.take().is_empty());
+    recorder.play(&[0xF0, 0x70, 0x70]);
+    recorder.next_frame();
+    recorder.next_frame();
+    assert_eq!(recorder.take(), vec![0xFF, 0x70, 0x70, SILENCE]);
+    assert!(recorder.samples().is_empty());
+}
+
+#[test]
+fn writer_streams_samples() {
+    let dir = std::env::temp_dir().join(format!("inv8080rs-wav-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("session.wav");
+    let mut writer = Writer::create(&path, 11025).unwrap();
+    writer.write(&[0x80, 0xFF]).unwrap();
+    writer.write(&[0x00]).unwrap();
+    assert!(!path.exists());
+    writer.finish().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), encode(&[0x80, 0xFF, 0x00], 11025));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn decode_encoded() {
+    let samples = [0x80, 0xFF, 0x00];
+    assert_eq!(decode(&encode(&samples, 11025)), Ok(samples.to_vec()));
+    assert_eq!(decode(&encode(&[], 11025)), Ok(vec![]));
+}
+
+#[test]
+fn decode_skips_chunks() {
+    let mut wav = encode(&[1, 2], 11025);
+    let list = [b"LIST".as_slice(), &3u32.to_le_bytes(), b"abc", &[0]].concat();
+    wav.splice(36..36, list);
+    assert_eq!(decode(&wav), Ok(vec![1, 2]));
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(decode(b"RIFF"), Err("Not a WAV file".into()));
+    let wav = encode(&[1, 2, 3, 4], 11025);
+    assert_eq!(decode(&wav[..46]), Err("WAV chunk too short".into()));
+
+    let mut stereo = wav.clone();
+    stereo[22] = 2;
+    assert!(decode(&stereo)
+        .unwrap_err()
+        .contains("8-bit with 2 channels"));
+    let mut float = wav.clone();
+    float[20] = 3;
+    assert_eq!(decode(&float), Err("Unsupported WAV encoding 3".into()));
+
+    let data_only = [&wav[..12], &wav[36..]].concat();
+    assert_eq!(decode(&data_only), Err("Missing WAV format".into()));
+    assert_eq!(decode(&wav[..36]), Err("Missing WAV data".into()));
+}
+
+#[test]
+fn load_missing_in_background() {
+    let names = vec!["no-such-file.wav".to_string(), "no-such-other.wav".into()];
+    let results: Vec<_> = load_in_background(Locator::memory(vec![]), names)
+        .iter()
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, 0);
+    assert_eq!(results[1].0, 1);
+    assert!(results[1]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .contains("no-such-other.wav: "));
+}
+
+#[test]
+fn load_from_memory_in_background() {
+    let assets = Locator::memory(vec![
+        ("shot.wav".into(), encode(&[1, 2, 3], 11025)),
+        ("hit.wav".into(), b"RIFF".to_vec()),
+    ]);
+    let names = vec!["shot.wav".to_string(), "hit.wav".into()];
+    let results: Vec<_> = load_in_background(assets, names).iter().collect();
+    assert_eq!(results[0], (0, Ok(vec![1, 2, 3])));
+    assert_eq!(results[1], (1, Err("hit.wav: Not a WAV file".into())));
+}